@@ -0,0 +1,98 @@
+//! Petgraph vs CSR backend: build time and the graph analyses.
+//!
+//! Run with `cargo bench --bench graph_backends`. The mappings are synthetic
+//! but shaped like the real feed: a few thousand VERIS capabilities mapped to
+//! a few hundred ATT&CK techniques with skewed popularity.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use mighty_graph_rs::centrality::source_paths;
+use mighty_graph_rs::csr::CsrGraph;
+use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+use mighty_graph_rs::petgraph_full_0x0::{
+    perform_connected_components_analysis, perform_mapping_type_analysis,
+    perform_node_degree_analysis,
+};
+use mighty_graph_rs::utils::create_graph;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const MAPPING_TYPES: [&str; 4] = ["Strong", "Partial", "related-to", "uses"];
+
+fn synthetic_mappings(count: usize, seed: u64) -> Vec<Mapping> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let capability = rng.gen_range(0..count / 4 + 1);
+            // Squaring skews the picks towards the low technique IDs.
+            let technique = (rng.gen::<f64>().powi(2) * 600.0) as usize;
+            Mapping {
+                mapping_framework: "veris".to_string(),
+                mapping_framework_version: "1.3.7".to_string(),
+                capability_group: "action.hacking".to_string(),
+                capability_id: format!("action.hacking.variety.{}", capability),
+                capability_description: "".to_string(),
+                mapping_type: MAPPING_TYPES[rng.gen_range(0..MAPPING_TYPES.len())].to_string(),
+                attack_object_id: format!("T{}", 1000 + technique),
+                attack_object_name: "".to_string(),
+                attack_version: "12.1".to_string(),
+                technology_domain: "enterprise".to_string(),
+                references: "".to_string(),
+                comments: "".to_string(),
+                organization: "Acme".to_string(),
+                creation_date: "01/02/2023".to_string(),
+                last_update: "2023-03-04".to_string(),
+            }
+        })
+        .collect()
+}
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    for count in [1_000, 20_000] {
+        let mappings = synthetic_mappings(count, 42);
+        group.bench_with_input(BenchmarkId::new("petgraph", count), &mappings, |b, m| {
+            b.iter(|| create_graph(black_box(m)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("csr", count), &mappings, |b, m| {
+            b.iter(|| CsrGraph::from_mappings(black_box(m)))
+        });
+    }
+    group.finish();
+}
+
+fn analyses(c: &mut Criterion) {
+    let mappings = synthetic_mappings(20_000, 42);
+    let (graph, _) = create_graph(&mappings).unwrap();
+    let csr = CsrGraph::from_mappings(&mappings);
+
+    let mut group = c.benchmark_group("analyses");
+    group.bench_function("degree/petgraph", |b| b.iter(|| perform_node_degree_analysis(&graph)));
+    group.bench_function("degree/csr", |b| b.iter(|| perform_node_degree_analysis(&csr)));
+    group.bench_function("components/petgraph", |b| {
+        b.iter(|| perform_connected_components_analysis(&graph))
+    });
+    group.bench_function("components/csr", |b| {
+        b.iter(|| perform_connected_components_analysis(&csr))
+    });
+    group.bench_function("mapping_types/petgraph", |b| {
+        b.iter(|| perform_mapping_type_analysis(&graph))
+    });
+    group.bench_function("mapping_types/csr", |b| b.iter(|| perform_mapping_type_analysis(&csr)));
+    group.finish();
+}
+
+fn all_pairs(c: &mut Criterion) {
+    // One Dijkstra per node, so keep the graph small.
+    let mappings = synthetic_mappings(2_000, 42);
+    let (graph, _) = create_graph(&mappings).unwrap();
+    let csr = CsrGraph::from_mappings(&mappings);
+
+    let mut group = c.benchmark_group("source_paths");
+    group.sample_size(10);
+    group.bench_function("petgraph", |b| b.iter(|| source_paths(&graph)));
+    group.bench_function("csr", |b| b.iter(|| source_paths(&csr)));
+    group.finish();
+}
+
+criterion_group!(benches, build, analyses, all_pairs);
+criterion_main!(benches);
@@ -0,0 +1,403 @@
+//! Cache of expensive analysis results.
+//!
+//! Results are stored as JSON under keys made of the crate version, the
+//! analysis name, the [`content_hash`] of the inputs and a hash of the
+//! parameters:
+//!
+//! ```text
+//! mightygraph:0.1.0:shortest_path_analysis:<content hash>:<parameter hash>
+//! ```
+//!
+//! A changed mapping file, catalog enrichment or parameter gives a new key,
+//! so stale results are never read and need no explicit invalidation; they
+//! expire with the TTL.
+//!
+//! With the `redis-cache` feature, [`AnalysisCache::from_env`] uses the Redis
+//! server in `REDIS_URL` (or the `docker-compose.yml` one), so repeated CLI
+//! runs share results. Without it, or when Redis cannot be reached, results
+//! are kept in memory for the life of the process, which still serves
+//! repeated server queries. A backend that fails mid-run is dropped in favour
+//! of the in-memory cache rather than failing the analysis.
+//!
+//! The named analyses go through [`cached_analysis`]; the batch run also
+//! caches its novelty scores. The crate has no community detection, so
+//! there are no communities to cache.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::metrics;
+use crate::petgraph_full_0x0::{perform_analysis_by_name, ANALYSIS_NAMES, RESULT_ANALYSES};
+use crate::petgraph_full_0x0::prelude::*;
+use crate::utils::{graph_hash, sha256_hex};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A key-value store for serialized results.
+pub trait CacheBackend: Send + Sync {
+    /// Short name for logs, e.g. `redis`.
+    fn name(&self) -> &'static str;
+
+    fn get(&self, key: &str) -> Result<Option<String>>;
+
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<()>;
+}
+
+/// Process-local backend.
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, (String, Option<Instant>)>>,
+}
+
+impl MemoryCache {
+    pub fn new() -> MemoryCache {
+        MemoryCache::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CacheBackend for MemoryCache {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .get(key)
+            .filter(|(_, expires)| expires.is_none_or(|at| at > Instant::now()))
+            .map(|(value, _)| value.clone()))
+    }
+
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<()> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (_, expires)| expires.is_none_or(|at| at > now));
+        entries.insert(key.to_string(), (value.to_string(), ttl.map(|ttl| now + ttl)));
+        Ok(())
+    }
+}
+
+/// Redis backend (`redis-cache` feature).
+#[cfg(feature = "redis-cache")]
+pub struct RedisCache {
+    connection: Mutex<redis::Connection>,
+}
+
+#[cfg(feature = "redis-cache")]
+impl RedisCache {
+    /// Connects to a Redis server.
+    ///
+    /// # Arguments
+    ///
+    /// - `url`: A `redis://[user:password@]host:port[/db]` URL.
+    /// - `timeout`: Connect timeout.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<RedisCache>`: The backend, or an error if the server cannot
+    ///   be reached.
+    pub fn connect(url: &str, timeout: Duration) -> Result<RedisCache> {
+        let client = redis::Client::open(url)?;
+        let connection = client.get_connection_with_timeout(timeout)?;
+        connection.set_read_timeout(Some(timeout))?;
+        connection.set_write_timeout(Some(timeout))?;
+        Ok(RedisCache { connection: Mutex::new(connection) })
+    }
+}
+
+#[cfg(feature = "redis-cache")]
+impl CacheBackend for RedisCache {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        let mut connection = self.connection.lock().unwrap();
+        Ok(redis::cmd("GET").arg(key).query(&mut *connection)?)
+    }
+
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<()> {
+        let mut connection = self.connection.lock().unwrap();
+        let mut command = redis::cmd("SET");
+        command.arg(key).arg(value);
+        if let Some(ttl) = ttl {
+            command.arg("EX").arg(ttl.as_secs().max(1));
+        }
+        command.query::<()>(&mut *connection)?;
+        Ok(())
+    }
+}
+
+/// The Redis URL: `REDIS_URL`, or one built from the `docker-compose.yml`
+/// variables `REDIS_USER` and `REDIS_PASSWORD` with `REDIS_HOST` (default
+/// `localhost`).
+pub fn redis_url_from_env() -> Option<String> {
+    if let Ok(url) = std::env::var("REDIS_URL") {
+        return Some(url);
+    }
+    let password = std::env::var("REDIS_PASSWORD").ok()?;
+    let user = std::env::var("REDIS_USER").unwrap_or_default();
+    let host = std::env::var("REDIS_HOST").unwrap_or_else(|_| "localhost".to_string());
+    Some(format!("redis://{}:{}@{}:6379/", user, password, host))
+}
+
+#[cfg(feature = "redis-cache")]
+fn backend_from_env() -> Option<Box<dyn CacheBackend>> {
+    let url = redis_url_from_env()?;
+    match RedisCache::connect(&url, Duration::from_secs(2)) {
+        Ok(redis) => Some(Box::new(redis)),
+        Err(e) => {
+            eprintln!("redis cache unavailable, caching in memory: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "redis-cache"))]
+fn backend_from_env() -> Option<Box<dyn CacheBackend>> {
+    None
+}
+
+/// Hash of everything an analysis reads: the graph (see [`graph_hash`]) and
+/// the mapping rows.
+pub fn content_hash(graph: &MappingGraph, mappings: &[Mapping]) -> String {
+    let rows = serde_json::to_string(mappings).unwrap_or_default();
+    sha256_hex(&format!("{}\n{}", graph_hash(graph), rows))
+}
+
+/// JSON text with object keys sorted at every level.
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<_> = object.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| format!("{}:{}", json!(key), canonical_json(value)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Hit and miss counts of an [`AnalysisCache`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub backend: String,
+    pub hits: usize,
+    pub misses: usize,
+    /// Backend errors; the first one switches to the in-memory cache.
+    pub errors: usize,
+}
+
+/// Results cache in front of a [`CacheBackend`], with an in-memory fallback.
+pub struct AnalysisCache {
+    backend: Box<dyn CacheBackend>,
+    fallback: MemoryCache,
+    degraded: AtomicBool,
+    prefix: String,
+    ttl: Option<Duration>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    errors: AtomicUsize,
+}
+
+impl Default for AnalysisCache {
+    fn default() -> Self {
+        AnalysisCache::new(Box::new(MemoryCache::new()))
+    }
+}
+
+impl AnalysisCache {
+    pub fn new(backend: Box<dyn CacheBackend>) -> AnalysisCache {
+        AnalysisCache {
+            backend,
+            fallback: MemoryCache::new(),
+            degraded: AtomicBool::new(false),
+            prefix: format!("mightygraph:{}", env!("CARGO_PKG_VERSION")),
+            ttl: Some(DEFAULT_TTL),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            errors: AtomicUsize::new(0),
+        }
+    }
+
+    /// Entries expire after `ttl`; `None` keeps them until evicted.
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> AnalysisCache {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Redis when the `redis-cache` feature is on and a server is configured
+    /// and reachable, the in-memory cache otherwise.
+    pub fn from_env() -> AnalysisCache {
+        match backend_from_env() {
+            Some(backend) => AnalysisCache::new(backend),
+            None => AnalysisCache::default(),
+        }
+    }
+
+    /// The cache key of a result.
+    ///
+    /// # Arguments
+    ///
+    /// - `analysis`: The analysis name.
+    /// - `content_hash`: [`content_hash`] of the inputs.
+    /// - `params`: The analysis parameters; objects are hashed with sorted keys.
+    pub fn key(&self, analysis: &str, content_hash: &str, params: &Value) -> String {
+        let params = sha256_hex(&canonical_json(params));
+        format!("{}:{}:{}:{}", self.prefix, analysis, content_hash, &params[..16])
+    }
+
+    fn backend(&self) -> &dyn CacheBackend {
+        if self.degraded.load(Ordering::Relaxed) {
+            &self.fallback
+        } else {
+            self.backend.as_ref()
+        }
+    }
+
+    fn backend_error(&self, error: Box<dyn std::error::Error>) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        if !self.degraded.swap(true, Ordering::Relaxed) {
+            eprintln!("{} cache failed, caching in memory: {}", self.backend.name(), error);
+        }
+    }
+
+    /// Returns the cached result, or computes and caches it.
+    ///
+    /// # Arguments
+    ///
+    /// - `analysis`: The analysis name.
+    /// - `content_hash`: [`content_hash`] of the inputs.
+    /// - `params`: The analysis parameters.
+    /// - `compute`: Computes the result on a miss.
+    ///
+    /// # Returns
+    ///
+    /// - `Value`: The result. Backend errors never fail the call.
+    pub fn get_or_compute<F>(
+        &self,
+        analysis: &str,
+        content_hash: &str,
+        params: &Value,
+        compute: F,
+    ) -> Value
+    where
+        F: FnOnce() -> Value,
+    {
+        self.try_get_or_compute(analysis, content_hash, params, || Some(compute()))
+            .unwrap_or_default()
+    }
+
+    /// Like [`AnalysisCache::get_or_compute`], for a computation that can
+    /// fail. A `None` result is not cached, so the next call computes again.
+    pub fn try_get_or_compute<F>(
+        &self,
+        analysis: &str,
+        content_hash: &str,
+        params: &Value,
+        compute: F,
+    ) -> Option<Value>
+    where
+        F: FnOnce() -> Option<Value>,
+    {
+        let key = self.key(analysis, content_hash, params);
+        let cached = self.backend().get(&key).unwrap_or_else(|e| {
+            self.backend_error(e);
+            None
+        });
+        if let Some(value) = cached.and_then(|text| serde_json::from_str(&text).ok()) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Some(value);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = compute()?;
+        if let Err(e) = self.backend().set(&key, &value.to_string(), self.ttl) {
+            self.backend_error(e);
+            let _ = self.fallback.set(&key, &value.to_string(), self.ttl);
+        }
+        Some(value)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            backend: self.backend().name().to_string(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// [`perform_analysis_by_name`] through the cache. Computations on a miss
+/// are timed in the global [`metrics`] registry.
+///
+/// # Arguments
+///
+/// - `cache`: The results cache.
+/// - `content_hash`: [`content_hash`] of `graph` and `mappings`.
+/// - `name`: One of `ANALYSIS_NAMES`.
+/// - `graph`, `mappings`, `node_indices`: The inputs of the analysis.
+///
+/// # Returns
+///
+/// - `Option<Value>`: The result, or `None` for an unknown analysis or one
+///   without a result; neither is cached.
+pub fn cached_analysis(
+    cache: &AnalysisCache,
+    content_hash: &str,
+    name: &str,
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    node_indices: &HashMap<String, NodeIndex>,
+) -> Option<Value> {
+    if !ANALYSIS_NAMES.contains(&name) {
+        return None;
+    }
+    cache.try_get_or_compute(name, content_hash, &json!({}), || {
+        metrics::global()
+            .time_analysis(name, || perform_analysis_by_name(name, graph, mappings, node_indices))
+            .filter(|value| !value.is_null())
+    })
+}
+
+/// All the `perform_analyses` results, each through the cache. Misses are
+/// computed in parallel, like `perform_analyses`.
+pub fn cached_analyses(
+    cache: &AnalysisCache,
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    node_indices: &HashMap<String, NodeIndex>,
+) -> AnalysisResults {
+    let hash = content_hash(graph, mappings);
+    let mut results: HashMap<&str, Value> = RESULT_ANALYSES
+        .par_iter()
+        .map(|&name| {
+            let value = cached_analysis(cache, &hash, name, graph, mappings, node_indices);
+            (name, value.unwrap_or_default())
+        })
+        .collect();
+    AnalysisResults::from_named(|name| results.remove(name).unwrap_or_default())
+}
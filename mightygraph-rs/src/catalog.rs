@@ -0,0 +1,332 @@
+//! Reference catalogs the mappings are checked against.
+//!
+//! [`AttackCatalog`] is read from an ATT&CK STIX 2.1 bundle such as
+//! `enterprise-attack.json` and knows every technique with its tactics,
+//! platforms and domains, plus the software and groups with their aliases.
+//! [`VerisCatalog`] holds the VERIS enumerations, read from
+//! `verisc-enum.json` (or any flat list of dotted enumeration paths).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::petgraph_full_0x0::prelude::{Mapping, MappingGraph, NodeType};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// An ATT&CK technique or sub-technique.
+#[derive(Debug, Clone, Serialize)]
+pub struct Technique {
+    /// ATT&CK ID, e.g. `T1003.001`.
+    pub id: String,
+    pub stix_id: String,
+    pub name: String,
+    pub description: String,
+    /// Kill chain phase names, e.g. `credential-access`.
+    pub tactics: Vec<String>,
+    pub platforms: Vec<String>,
+    /// e.g. `enterprise-attack`.
+    pub domains: Vec<String>,
+    pub is_subtechnique: bool,
+    pub revoked: bool,
+    pub deprecated: bool,
+}
+
+impl Technique {
+    /// Whether the technique is still part of the current matrix.
+    pub fn is_active(&self) -> bool {
+        !self.revoked && !self.deprecated
+    }
+}
+
+/// An ATT&CK software (malware or tool) or group.
+#[derive(Debug, Clone, Serialize)]
+pub struct AttackEntity {
+    /// ATT&CK ID, e.g. `S0002` or `G0016`.
+    pub id: String,
+    pub stix_id: String,
+    pub name: String,
+    /// Other names, without the name itself.
+    pub aliases: Vec<String>,
+    pub description: String,
+    pub revoked: bool,
+}
+
+/// ATT&CK techniques, software and groups keyed by ATT&CK ID.
+#[derive(Debug, Clone, Default)]
+pub struct AttackCatalog {
+    pub techniques: BTreeMap<String, Technique>,
+    pub software: BTreeMap<String, AttackEntity>,
+    pub groups: BTreeMap<String, AttackEntity>,
+    /// `x_mitre_version` of the bundle's collection object, if present.
+    pub version: Option<String>,
+}
+
+impl AttackCatalog {
+    /// Loads the catalog from an ATT&CK STIX 2.1 bundle file.
+    pub fn from_stix_bundle<P: AsRef<Path>>(path: P) -> Result<AttackCatalog> {
+        let reader = BufReader::new(File::open(path)?);
+        let bundle: Value = serde_json::from_reader(reader)?;
+        Ok(AttackCatalog::from_stix_value(&bundle))
+    }
+
+    /// Builds the catalog from an already parsed STIX bundle.
+    pub fn from_stix_value(bundle: &Value) -> AttackCatalog {
+        let mut catalog = AttackCatalog::default();
+        let objects = bundle["objects"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+
+        for object in objects {
+            match object["type"].as_str() {
+                Some("attack-pattern") => {
+                    if let Some(technique) = technique_from_stix(object) {
+                        catalog.techniques.insert(technique.id.clone(), technique);
+                    }
+                }
+                Some("malware") | Some("tool") => {
+                    if let Some(software) = entity_from_stix(object, "x_mitre_aliases") {
+                        catalog.software.insert(software.id.clone(), software);
+                    }
+                }
+                Some("intrusion-set") => {
+                    if let Some(group) = entity_from_stix(object, "aliases") {
+                        catalog.groups.insert(group.id.clone(), group);
+                    }
+                }
+                Some("x-mitre-collection") => {
+                    catalog.version = object["x_mitre_version"].as_str().map(str::to_string);
+                }
+                _ => {}
+            }
+        }
+
+        catalog
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Technique> {
+        self.techniques.get(id)
+    }
+
+    /// Techniques that are neither revoked nor deprecated.
+    pub fn active_techniques(&self) -> impl Iterator<Item = &Technique> {
+        self.techniques.values().filter(|t| t.is_active())
+    }
+
+    /// The ATT&CK domain (e.g. `enterprise-attack`) most active techniques
+    /// belong to, or `None` if no technique lists one.
+    pub fn domain(&self) -> Option<&str> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for domain in self.active_techniques().flat_map(|t| &t.domains) {
+            *counts.entry(domain.as_str()).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .max_by_key(|&(_, count)| count)
+            .map(|(domain, _)| domain)
+    }
+
+    /// Every tactic used by an active technique, sorted.
+    pub fn tactics(&self) -> BTreeSet<&str> {
+        self.active_techniques()
+            .flat_map(|t| t.tactics.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+/// Reads the ATT&CK ID from the `mitre-attack` external reference.
+pub(crate) fn attack_external_id(object: &Value) -> Option<String> {
+    object["external_references"]
+        .as_array()?
+        .iter()
+        .find(|r| {
+            r["source_name"]
+                .as_str()
+                .is_some_and(|s| s.starts_with("mitre"))
+        })
+        .and_then(|r| r["external_id"].as_str())
+        .map(str::to_string)
+}
+
+/// The graph node of a STIX object: its ID (the ATT&CK ID, or the name of a
+/// vulnerability) and type. `None` for other object types and objects
+/// without an ID.
+pub(crate) fn stix_node(object: &Value) -> Option<(String, NodeType)> {
+    let node_type = match object["type"].as_str()? {
+        "attack-pattern" => NodeType::Mitre,
+        "malware" | "tool" => NodeType::Software,
+        "intrusion-set" => NodeType::Group,
+        "vulnerability" => NodeType::Vulnerability,
+        _ => return None,
+    };
+    let id = match node_type {
+        NodeType::Vulnerability => object["name"].as_str().map(str::to_string),
+        _ => attack_external_id(object),
+    };
+    Some((id?, node_type))
+}
+
+/// Edge strength of a STIX relationship.
+pub(crate) fn relationship_strength(relationship: &Value) -> f32 {
+    // OpenCTI confidence is 0–100; ATT&CK relationships carry none.
+    relationship["confidence"]
+        .as_f64()
+        .map_or(1.0, |c| (c / 100.0) as f32)
+}
+
+pub(crate) fn string_list(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn technique_from_stix(object: &Value) -> Option<Technique> {
+    let id = attack_external_id(object)?;
+    let tactics = object["kill_chain_phases"]
+        .as_array()
+        .map(|phases| {
+            phases
+                .iter()
+                .filter(|p| {
+                    p["kill_chain_name"]
+                        .as_str()
+                        .is_some_and(|n| n.starts_with("mitre"))
+                })
+                .filter_map(|p| p["phase_name"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(Technique {
+        is_subtechnique: object["x_mitre_is_subtechnique"]
+            .as_bool()
+            .unwrap_or(id.contains('.')),
+        id,
+        stix_id: object["id"].as_str().unwrap_or_default().to_string(),
+        name: object["name"].as_str().unwrap_or_default().to_string(),
+        description: object["description"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        tactics,
+        platforms: string_list(&object["x_mitre_platforms"]),
+        domains: string_list(&object["x_mitre_domains"]),
+        revoked: object["revoked"].as_bool().unwrap_or(false),
+        deprecated: object["x_mitre_deprecated"].as_bool().unwrap_or(false),
+    })
+}
+
+fn entity_from_stix(object: &Value, aliases_field: &str) -> Option<AttackEntity> {
+    let id = attack_external_id(object)?;
+    let name = object["name"].as_str().unwrap_or_default().to_string();
+    let aliases = string_list(&object[aliases_field])
+        .into_iter()
+        .filter(|alias| *alias != name)
+        .collect();
+
+    Some(AttackEntity {
+        id,
+        stix_id: object["id"].as_str().unwrap_or_default().to_string(),
+        name,
+        aliases,
+        description: object["description"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        revoked: object["revoked"].as_bool().unwrap_or(false),
+    })
+}
+
+/// The VERIS enumerations, as dotted paths like `action.hacking.variety.Brute force`.
+#[derive(Debug, Clone, Default)]
+pub struct VerisCatalog {
+    pub enumerations: BTreeSet<String>,
+}
+
+impl VerisCatalog {
+    /// Loads `verisc-enum.json`-style nested objects, or a JSON array of paths.
+    pub fn from_enum_file<P: AsRef<Path>>(path: P) -> Result<VerisCatalog> {
+        let reader = BufReader::new(File::open(path)?);
+        let value: Value = serde_json::from_reader(reader)?;
+        Ok(VerisCatalog::from_enum_value(&value))
+    }
+
+    pub fn from_enum_value(value: &Value) -> VerisCatalog {
+        let mut enumerations = BTreeSet::new();
+        flatten_enum("", value, &mut enumerations);
+        VerisCatalog { enumerations }
+    }
+
+    /// Falls back to the enumerations referenced by the mappings themselves.
+    pub fn from_mappings(mappings: &[Mapping]) -> VerisCatalog {
+        VerisCatalog {
+            enumerations: mappings.iter().map(|m| m.capability_id.clone()).collect(),
+        }
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.enumerations.contains(id)
+    }
+}
+
+fn flatten_enum(prefix: &str, value: &Value, out: &mut BTreeSet<String>) {
+    let join = |leaf: &str| {
+        if prefix.is_empty() {
+            leaf.to_string()
+        } else {
+            format!("{}.{}", prefix, leaf)
+        }
+    };
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object {
+                flatten_enum(&join(key), child, out);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                match item {
+                    Value::String(leaf) => {
+                        out.insert(join(leaf));
+                    }
+                    other => flatten_enum(prefix, other, out),
+                }
+            }
+        }
+        Value::String(leaf) => {
+            out.insert(join(leaf));
+        }
+        _ => {}
+    }
+}
+
+/// Copies catalog attributes onto the ATT&CK nodes' metadata: `name`,
+/// `tactics` and `platforms` (comma separated), and `is_subtechnique`.
+pub fn enrich_graph(graph: &mut MappingGraph, catalog: &AttackCatalog) {
+    for node in graph.node_indices() {
+        if graph[node].node_type != NodeType::Mitre {
+            continue;
+        }
+        let Some(technique) = catalog.get(&graph[node].id) else {
+            continue;
+        };
+        let metadata = &mut graph[node].metadata;
+        metadata.insert("name".to_string(), technique.name.clone());
+        metadata.insert("tactics".to_string(), technique.tactics.join(","));
+        metadata.insert("platforms".to_string(), technique.platforms.join(","));
+        metadata.insert(
+            "is_subtechnique".to_string(),
+            technique.is_subtechnique.to_string(),
+        );
+    }
+}
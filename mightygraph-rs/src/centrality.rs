@@ -0,0 +1,128 @@
+//! Closeness centrality and path-length analyses.
+//!
+//! Both need the distances from every node, so each source runs one
+//! [`shortest_distances`] over the undirected, `1 / strength` weighted graph.
+//! The sources are spread over the rayon thread pool (see
+//! [`crate::utils::configure_threads`]); the per-source results come back in
+//! node order and are reduced sequentially, so the output does not depend on
+//! the number of threads.
+
+use std::collections::BTreeMap;
+
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::csr::GraphView;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::traversal::shortest_distances;
+
+/// Shortest-path summary of one source node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourcePaths {
+    pub id: String,
+    /// Other nodes reachable from the source.
+    pub reachable: usize,
+    /// Sum of the distances to them.
+    pub total_distance: f64,
+    /// Distance to the farthest of them.
+    pub eccentricity: f64,
+}
+
+impl SourcePaths {
+    /// Wasserman–Faust closeness: the inverse mean distance, scaled by the
+    /// share of the graph that is reachable, so that the nodes of a small
+    /// component do not all score high.
+    ///
+    /// # Arguments
+    ///
+    /// - `node_count`: Nodes in the whole graph.
+    pub fn closeness(&self, node_count: usize) -> f64 {
+        if self.total_distance <= 0.0 || node_count < 2 {
+            return 0.0;
+        }
+        let reachable = self.reachable as f64;
+        (reachable / self.total_distance) * (reachable / (node_count - 1) as f64)
+    }
+}
+
+/// Runs a single-source shortest path search from every node, in parallel.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph, in either backend.
+///
+/// # Returns
+///
+/// - `Vec<SourcePaths>`: One summary per node, in node index order.
+pub fn source_paths<G: GraphView + Sync>(graph: &G) -> Vec<SourcePaths> {
+    (0..graph.node_count())
+        .into_par_iter()
+        .map(|i| {
+            let node = NodeIndex::new(i);
+            let mut paths = SourcePaths {
+                id: graph.node_id(node).to_string(),
+                reachable: 0,
+                total_distance: 0.0,
+                eccentricity: 0.0,
+            };
+            let distances = shortest_distances(graph, node);
+            for (j, &distance) in distances.iter().enumerate() {
+                if j == i || !distance.is_finite() {
+                    continue;
+                }
+                paths.reachable += 1;
+                paths.total_distance += f64::from(distance);
+                paths.eccentricity = paths.eccentricity.max(f64::from(distance));
+            }
+            paths
+        })
+        .collect()
+}
+
+/// Closeness centrality of every node.
+///
+/// # Returns
+///
+/// - `serde_json::Value`: Closeness by node ID.
+pub fn perform_closeness_analysis<G: GraphView + Sync>(graph: &G) -> Value {
+    let node_count = graph.node_count();
+    let closeness: BTreeMap<String, f64> = source_paths(graph)
+        .into_iter()
+        .map(|paths| {
+            let score = paths.closeness(node_count);
+            (paths.id, score)
+        })
+        .collect();
+    json!(closeness)
+}
+
+/// Path-length statistics over all sources.
+///
+/// # Returns
+///
+/// - `serde_json::Value`: `reachable_pairs` (ordered), `average_path_length`,
+///   `diameter` and `radius` (over nodes that reach anything), and the
+///   `eccentricity` by node ID.
+pub fn perform_path_length_analysis<G: GraphView + Sync>(graph: &G) -> Value {
+    let paths = source_paths(graph);
+    let connected: Vec<&SourcePaths> = paths.iter().filter(|p| p.reachable > 0).collect();
+
+    let reachable_pairs: usize = connected.iter().map(|p| p.reachable).sum();
+    let total_distance: f64 = connected.iter().map(|p| p.total_distance).sum();
+    let eccentricities = connected.iter().map(|p| p.eccentricity);
+    let diameter = eccentricities.clone().reduce(f64::max);
+    let radius = eccentricities.reduce(f64::min);
+    let average = (reachable_pairs > 0).then(|| total_distance / reachable_pairs as f64);
+
+    json!({
+        "reachable_pairs": reachable_pairs,
+        "average_path_length": average,
+        "diameter": diameter,
+        "radius": radius,
+        "eccentricity": connected
+            .iter()
+            .map(|p| (p.id.clone(), p.eccentricity))
+            .collect::<BTreeMap<_, _>>(),
+    })
+}
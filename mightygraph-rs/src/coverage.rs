@@ -0,0 +1,363 @@
+//! Coverage and gap analysis of ATT&CK by the VERIS mappings.
+//!
+//! Answers "which ATT&CK techniques have no VERIS mapping, and which VERIS
+//! enumerations map to nothing?", broken down per tactic, per platform, per
+//! `Mapping::technology_domain` and per ATT&CK domain (the catalog's
+//! `x_mitre_domains`, e.g. `enterprise-attack`).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::path::Path;
+
+use polars::prelude::*;
+use serde::Serialize;
+
+use crate::catalog::{AttackCatalog, VerisCatalog};
+use crate::petgraph_full_0x0::prelude::*;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Options for [`perform_coverage_analysis`].
+#[derive(Debug, Clone)]
+pub struct CoverageOptions {
+    /// Counts sub-techniques in the totals and percentages.
+    pub include_subtechniques: bool,
+    /// Counts revoked and deprecated techniques in the totals.
+    pub include_inactive: bool,
+}
+
+impl Default for CoverageOptions {
+    fn default() -> Self {
+        CoverageOptions {
+            include_subtechniques: true,
+            include_inactive: false,
+        }
+    }
+}
+
+/// Mapped vs. total count for one slice of the catalog.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CoverageBucket {
+    pub total: usize,
+    pub mapped: usize,
+    pub coverage_pct: f64,
+}
+
+impl CoverageBucket {
+    fn add(&mut self, mapped: bool) {
+        self.total += 1;
+        if mapped {
+            self.mapped += 1;
+        }
+        self.coverage_pct = 100.0 * self.mapped as f64 / self.total as f64;
+    }
+}
+
+/// The result of [`perform_coverage_analysis`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CoverageReport {
+    pub techniques: CoverageBucket,
+    pub veris: CoverageBucket,
+    pub by_tactic: BTreeMap<String, CoverageBucket>,
+    pub by_platform: BTreeMap<String, CoverageBucket>,
+    /// Keyed by `Mapping::technology_domain`: the catalog techniques of that
+    /// domain, and those mapped by a row of that domain.
+    pub by_technology_domain: BTreeMap<String, CoverageBucket>,
+    /// Keyed by the catalog's ATT&CK domains (`x_mitre_domains`).
+    pub by_attack_domain: BTreeMap<String, CoverageBucket>,
+    pub unmapped_techniques: Vec<String>,
+    pub unmapped_subtechniques: Vec<String>,
+    pub unmapped_veris: Vec<String>,
+    /// Mapped ATT&CK IDs missing from the catalog (typos, other domains).
+    pub unknown_techniques: Vec<String>,
+    /// VERIS IDs mapped to each covered technique.
+    pub veris_by_technique: BTreeMap<String, Vec<String>>,
+}
+
+/// Computes ATT&CK and VERIS coverage of the mapping graph.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `mappings`: The mapping rows the graph was built from.
+/// - `attack`: The ATT&CK catalog defining the full technique set.
+/// - `veris`: The VERIS enumerations defining the full VERIS set.
+/// - `options`: Which techniques count towards the totals.
+///
+/// # Returns
+///
+/// - `CoverageReport`: Coverage percentages and the gap lists, all sorted by ID.
+pub fn perform_coverage_analysis(
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    attack: &AttackCatalog,
+    veris: &VerisCatalog,
+    options: &CoverageOptions,
+) -> CoverageReport {
+    let mut report = CoverageReport::default();
+
+    let veris_by_technique = veris_by_technique(graph);
+    let mapped_veris: BTreeSet<&String> = veris_by_technique.values().flatten().collect();
+    let mut mapped_by_domain: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for mapping in mappings {
+        mapped_by_domain
+            .entry(mapping.technology_domain.as_str())
+            .or_default()
+            .insert(mapping.attack_object_id.as_str());
+    }
+    for domain in mapped_by_domain.keys() {
+        report.by_technology_domain.insert(domain.to_string(), CoverageBucket::default());
+    }
+
+    for technique in attack.techniques.values() {
+        if (!options.include_inactive && !technique.is_active())
+            || (!options.include_subtechniques && technique.is_subtechnique)
+        {
+            continue;
+        }
+        let mapped = veris_by_technique.contains_key(&technique.id);
+
+        report.techniques.add(mapped);
+        for tactic in &technique.tactics {
+            report
+                .by_tactic
+                .entry(tactic.clone())
+                .or_default()
+                .add(mapped);
+        }
+        for platform in &technique.platforms {
+            report
+                .by_platform
+                .entry(platform.clone())
+                .or_default()
+                .add(mapped);
+        }
+        for (domain, mapped_ids) in &mapped_by_domain {
+            if technique.domains.iter().any(|d| is_attack_domain_of(d, domain)) {
+                report
+                    .by_technology_domain
+                    .entry(domain.to_string())
+                    .or_default()
+                    .add(mapped_ids.contains(technique.id.as_str()));
+            }
+        }
+        for domain in &technique.domains {
+            report
+                .by_attack_domain
+                .entry(domain.clone())
+                .or_default()
+                .add(mapped);
+        }
+
+        if !mapped {
+            if technique.is_subtechnique {
+                report.unmapped_subtechniques.push(technique.id.clone());
+            } else {
+                report.unmapped_techniques.push(technique.id.clone());
+            }
+        }
+    }
+
+    for id in &veris.enumerations {
+        let mapped = mapped_veris.contains(id);
+        report.veris.add(mapped);
+        if !mapped {
+            report.unmapped_veris.push(id.clone());
+        }
+    }
+
+    report.unknown_techniques = veris_by_technique
+        .keys()
+        .filter(|id| attack.get(id).is_none())
+        .cloned()
+        .collect();
+    report.veris_by_technique = veris_by_technique;
+
+    report
+}
+
+/// Whether `attack_domain` (e.g. `enterprise-attack`) is the catalog name of
+/// the mapping domain `technology_domain` (e.g. `enterprise`).
+fn is_attack_domain_of(attack_domain: &str, technology_domain: &str) -> bool {
+    let name = attack_domain.strip_suffix("-attack").unwrap_or(attack_domain);
+    name.eq_ignore_ascii_case(technology_domain)
+        || attack_domain.eq_ignore_ascii_case(technology_domain)
+}
+
+/// Lists the VERIS IDs mapped to every ATT&CK node that has at least one.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+///
+/// # Returns
+///
+/// - `BTreeMap<String, Vec<String>>`: Sorted VERIS IDs by ATT&CK ID.
+pub fn veris_by_technique(graph: &MappingGraph) -> BTreeMap<String, Vec<String>> {
+    let mut by_technique: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for node in graph.node_indices() {
+        if graph[node].node_type != NodeType::Mitre {
+            continue;
+        }
+        let neighbors = graph
+            .neighbors_undirected(node)
+            .filter(|&n| graph[n].node_type == NodeType::Veris)
+            .map(|n| graph[n].id.clone());
+        by_technique
+            .entry(graph[node].id.clone())
+            .or_default()
+            .extend(neighbors);
+    }
+    by_technique
+        .into_iter()
+        .filter(|(_, ids)| !ids.is_empty())
+        .map(|(id, ids)| (id, ids.into_iter().collect()))
+        .collect()
+}
+
+impl CoverageReport {
+    /// Same report as a `serde_json::Value`, for `export_to_json`.
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    /// One row per catalog technique: `technique_id`, `name`, `tactics`,
+    /// `platforms`, `is_subtechnique`, `active`, `mapped`, `mapping_count` and
+    /// `veris_ids` (list columns are `;`-separated).
+    pub fn technique_frame(&self, attack: &AttackCatalog) -> PolarsResult<DataFrame> {
+        let techniques: Vec<_> = attack.techniques.values().collect();
+        let veris_ids = |id: &str| {
+            self.veris_by_technique
+                .get(id)
+                .map(Vec::as_slice)
+                .unwrap_or(&[])
+        };
+
+        DataFrame::new(vec![
+            Column::new(
+                "technique_id".into(),
+                techniques.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "name".into(),
+                techniques
+                    .iter()
+                    .map(|t| t.name.as_str())
+                    .collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "tactics".into(),
+                techniques
+                    .iter()
+                    .map(|t| t.tactics.join(";"))
+                    .collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "platforms".into(),
+                techniques
+                    .iter()
+                    .map(|t| t.platforms.join(";"))
+                    .collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "is_subtechnique".into(),
+                techniques
+                    .iter()
+                    .map(|t| t.is_subtechnique)
+                    .collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "active".into(),
+                techniques.iter().map(|t| t.is_active()).collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "mapped".into(),
+                techniques
+                    .iter()
+                    .map(|t| !veris_ids(&t.id).is_empty())
+                    .collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "mapping_count".into(),
+                techniques
+                    .iter()
+                    .map(|t| veris_ids(&t.id).len() as u32)
+                    .collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "veris_ids".into(),
+                techniques
+                    .iter()
+                    .map(|t| veris_ids(&t.id).join(";"))
+                    .collect::<Vec<_>>(),
+            ),
+        ])
+    }
+
+    /// One row per breakdown bucket: `dimension` (`tactic`, `platform`,
+    /// `technology_domain`, `attack_domain`), `value`, `total`, `mapped` and `coverage_pct`.
+    pub fn summary_frame(&self) -> PolarsResult<DataFrame> {
+        let rows: Vec<(&str, &str, &CoverageBucket)> = [
+            ("tactic", &self.by_tactic),
+            ("platform", &self.by_platform),
+            ("technology_domain", &self.by_technology_domain),
+            ("attack_domain", &self.by_attack_domain),
+        ]
+        .into_iter()
+        .flat_map(|(dimension, buckets)| {
+            buckets
+                .iter()
+                .map(move |(value, bucket)| (dimension, value.as_str(), bucket))
+        })
+        .collect();
+
+        DataFrame::new(vec![
+            Column::new("dimension".into(), rows.iter().map(|r| r.0).collect::<Vec<_>>()),
+            Column::new("value".into(), rows.iter().map(|r| r.1).collect::<Vec<_>>()),
+            Column::new(
+                "total".into(),
+                rows.iter().map(|r| r.2.total as u32).collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "mapped".into(),
+                rows.iter().map(|r| r.2.mapped as u32).collect::<Vec<_>>(),
+            ),
+            Column::new(
+                "coverage_pct".into(),
+                rows.iter().map(|r| r.2.coverage_pct).collect::<Vec<_>>(),
+            ),
+        ])
+    }
+}
+
+/// Writes `coverage_techniques.csv`, `coverage_summary.csv`, `coverage.json`
+/// and the `coverage_layer.json` Navigator layer into `output_dir`.
+///
+/// # Arguments
+///
+/// - `report`: The coverage report.
+/// - `attack`: The catalog the report was computed against.
+/// - `output_dir`: Target directory; created if missing.
+///
+/// # Returns
+///
+/// - `Result<()>`: Success, or the first I/O or Polars error.
+pub fn export_coverage(
+    report: &CoverageReport,
+    attack: &AttackCatalog,
+    output_dir: &Path,
+) -> Result<()> {
+    fs::create_dir_all(output_dir)?;
+
+    let mut techniques = report.technique_frame(attack)?;
+    CsvWriter::new(File::create(output_dir.join("coverage_techniques.csv"))?)
+        .finish(&mut techniques)?;
+    let mut summary = report.summary_frame()?;
+    CsvWriter::new(File::create(output_dir.join("coverage_summary.csv"))?).finish(&mut summary)?;
+
+    serde_json::to_writer_pretty(File::create(output_dir.join("coverage.json"))?, report)?;
+    crate::navigator::coverage_layer(report, attack)
+        .write(output_dir.join("coverage_layer.json"))?;
+
+    Ok(())
+}
@@ -0,0 +1,419 @@
+//! Read-optimized compressed sparse row (CSR) graph backend.
+//!
+//! [`CsrGraph`] is built once (with [`CsrBuilder`]) and then only read. Node
+//! IDs, mapping types and metadata are interned (see [`crate::interner`]), and
+//! node and edge attributes are stored column by column: per node a symbol
+//! and a type byte, per edge two endpoints, a symbol and a strength. The
+//! incident edges of each node are contiguous, in an outgoing and an
+//! incoming edge list indexed by offset arrays.
+//!
+//! [`GraphView`] is the read API both backends implement. The graph-level
+//! `perform_*` analyses, the centrality analyses and
+//! [`shortest_distances`](crate::traversal::shortest_distances) are written
+//! against it, so they run unchanged on either backend. Node and edge
+//! indices are assigned in insertion order, as petgraph does, so
+//! [`CsrGraph::from_mappings`] numbers nodes and edges like `create_graph`.
+
+use std::collections::BTreeMap;
+
+use petgraph::graph::EdgeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use crate::interner::{Interner, Symbol};
+use crate::petgraph_full_0x0::prelude::*;
+use crate::utils::calculate_strength;
+
+/// Read access to a mapping graph, whatever its storage.
+///
+/// Nodes are `0..node_count()` and edges `0..edge_count()`; traversals
+/// ignore edge direction, like those of [`crate::traversal`].
+pub trait GraphView {
+    fn node_count(&self) -> usize;
+
+    fn edge_count(&self) -> usize;
+
+    fn node_id(&self, node: NodeIndex) -> &str;
+
+    fn node_type(&self, node: NodeIndex) -> NodeType;
+
+    /// Edges touching `node` in either direction, with the node at the
+    /// other end.
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+    ) -> impl Iterator<Item = (EdgeIndex, NodeIndex)> + '_;
+
+    /// Source and target of an edge.
+    fn edge_endpoints(&self, edge: EdgeIndex) -> (NodeIndex, NodeIndex);
+
+    fn mapping_type(&self, edge: EdgeIndex) -> &str;
+
+    fn strength(&self, edge: EdgeIndex) -> f32;
+
+    /// Undirected degree, counting parallel edges.
+    fn degree(&self, node: NodeIndex) -> usize {
+        self.incident_edges(node).count()
+    }
+}
+
+impl GraphView for MappingGraph {
+    fn node_count(&self) -> usize {
+        Graph::node_count(self)
+    }
+
+    fn edge_count(&self) -> usize {
+        Graph::edge_count(self)
+    }
+
+    fn node_id(&self, node: NodeIndex) -> &str {
+        &self[node].id
+    }
+
+    fn node_type(&self, node: NodeIndex) -> NodeType {
+        self[node].node_type
+    }
+
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+    ) -> impl Iterator<Item = (EdgeIndex, NodeIndex)> + '_ {
+        self.edges_directed(node, Direction::Outgoing)
+            .map(|e| (e.id(), e.target()))
+            .chain(
+                self.edges_directed(node, Direction::Incoming)
+                    .map(|e| (e.id(), e.source())),
+            )
+    }
+
+    fn edge_endpoints(&self, edge: EdgeIndex) -> (NodeIndex, NodeIndex) {
+        Graph::edge_endpoints(self, edge).expect("edge index out of bounds")
+    }
+
+    fn mapping_type(&self, edge: EdgeIndex) -> &str {
+        &self[edge].mapping_type
+    }
+
+    fn strength(&self, edge: EdgeIndex) -> f32 {
+        self[edge].strength
+    }
+}
+
+/// Number of connected components, ignoring edge direction.
+pub fn connected_component_count<G: GraphView>(graph: &G) -> usize {
+    fn find(parents: &mut [usize], mut node: usize) -> usize {
+        while parents[node] != node {
+            parents[node] = parents[parents[node]];
+            node = parents[node];
+        }
+        node
+    }
+
+    let mut parents: Vec<usize> = (0..graph.node_count()).collect();
+    let mut components = graph.node_count();
+    for edge in 0..graph.edge_count() {
+        let (source, target) = graph.edge_endpoints(EdgeIndex::new(edge));
+        let (a, b) = (find(&mut parents, source.index()), find(&mut parents, target.index()));
+        if a != b {
+            parents[a.max(b)] = a.min(b);
+            components -= 1;
+        }
+    }
+    components
+}
+
+/// Sentinel for "no node" in `CsrGraph::nodes_by_symbol`.
+const NO_NODE: u32 = u32::MAX;
+
+/// A mapping graph in CSR form. Build it with [`CsrBuilder`],
+/// [`CsrGraph::from_mappings`] or [`CsrGraph::from_graph`].
+#[derive(Debug, Clone, Default)]
+pub struct CsrGraph {
+    strings: Interner,
+    node_ids: Vec<Symbol>,
+    node_types: Vec<NodeType>,
+    /// Node of each interned string, by symbol index; `NO_NODE` for strings
+    /// that are not node IDs. Replaces an ID-to-index map.
+    nodes_by_symbol: Vec<u32>,
+    /// One column per metadata key, with a value per node.
+    metadata: BTreeMap<Symbol, Vec<Option<Symbol>>>,
+    /// Outgoing edges of node `n` are `out_edges[out_offsets[n]..out_offsets[n + 1]]`.
+    out_offsets: Vec<u32>,
+    out_edges: Vec<u32>,
+    in_offsets: Vec<u32>,
+    in_edges: Vec<u32>,
+    edge_sources: Vec<u32>,
+    edge_targets: Vec<u32>,
+    edge_types: Vec<Symbol>,
+    edge_strengths: Vec<f32>,
+}
+
+impl CsrGraph {
+    /// Builds the graph straight from mapping rows, like `create_graph`.
+    pub fn from_mappings<'a>(mappings: impl IntoIterator<Item = &'a Mapping>) -> CsrGraph {
+        let mut builder = CsrBuilder::new();
+        for mapping in mappings {
+            builder.add_mapping(mapping);
+        }
+        builder.build()
+    }
+
+    /// Converts a petgraph graph, keeping node and edge indices.
+    pub fn from_graph(graph: &MappingGraph) -> CsrGraph {
+        let mut builder = CsrBuilder::new();
+        for node in graph.node_weights() {
+            let index = builder.add_node(&node.id, node.node_type);
+            let mut metadata: Vec<_> = node.metadata.iter().collect();
+            metadata.sort();
+            for (key, value) in metadata {
+                builder.set_metadata(index, key, value);
+            }
+        }
+        for edge in graph.raw_edges() {
+            builder.add_edge(
+                edge.source(),
+                edge.target(),
+                &edge.weight.mapping_type,
+                edge.weight.strength,
+            );
+        }
+        builder.build()
+    }
+
+    /// The node with this ID.
+    pub fn node_index(&self, id: &str) -> Option<NodeIndex> {
+        let symbol = self.strings.get(id)?;
+        match self.nodes_by_symbol[symbol.index()] {
+            NO_NODE => None,
+            node => Some(NodeIndex::new(node as usize)),
+        }
+    }
+
+    /// A metadata value of a node.
+    pub fn metadata(&self, node: NodeIndex, key: &str) -> Option<&str> {
+        let column = self.metadata.get(&self.strings.get(key)?)?;
+        column[node.index()].map(|value| self.strings.resolve(value))
+    }
+
+    /// Metadata keys, sorted by interning order.
+    pub fn metadata_keys(&self) -> impl Iterator<Item = &str> {
+        self.metadata.keys().map(|&key| self.strings.resolve(key))
+    }
+
+    /// The symbol table shared by IDs, mapping types and metadata.
+    pub fn strings(&self) -> &Interner {
+        &self.strings
+    }
+
+    /// Approximate heap memory in use, in bytes.
+    pub fn heap_bytes(&self) -> usize {
+        fn bytes<T>(column: &Vec<T>) -> usize {
+            column.capacity() * std::mem::size_of::<T>()
+        }
+        self.strings.heap_bytes()
+            + bytes(&self.node_ids)
+            + bytes(&self.node_types)
+            + bytes(&self.nodes_by_symbol)
+            + self.metadata.values().map(bytes).sum::<usize>()
+            + bytes(&self.out_offsets)
+            + bytes(&self.out_edges)
+            + bytes(&self.in_offsets)
+            + bytes(&self.in_edges)
+            + bytes(&self.edge_sources)
+            + bytes(&self.edge_targets)
+            + bytes(&self.edge_types)
+            + bytes(&self.edge_strengths)
+    }
+
+    fn edge_range(offsets: &[u32], node: NodeIndex) -> std::ops::Range<usize> {
+        offsets[node.index()] as usize..offsets[node.index() + 1] as usize
+    }
+}
+
+impl GraphView for CsrGraph {
+    fn node_count(&self) -> usize {
+        self.node_ids.len()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.edge_sources.len()
+    }
+
+    fn node_id(&self, node: NodeIndex) -> &str {
+        self.strings.resolve(self.node_ids[node.index()])
+    }
+
+    fn node_type(&self, node: NodeIndex) -> NodeType {
+        self.node_types[node.index()]
+    }
+
+    fn incident_edges(
+        &self,
+        node: NodeIndex,
+    ) -> impl Iterator<Item = (EdgeIndex, NodeIndex)> + '_ {
+        let other = |ends: &[u32], e: u32| {
+            (EdgeIndex::new(e as usize), NodeIndex::new(ends[e as usize] as usize))
+        };
+        let outgoing = self.out_edges[CsrGraph::edge_range(&self.out_offsets, node)]
+            .iter()
+            .map(move |&e| other(&self.edge_targets, e));
+        let incoming = self.in_edges[CsrGraph::edge_range(&self.in_offsets, node)]
+            .iter()
+            .map(move |&e| other(&self.edge_sources, e));
+        outgoing.chain(incoming)
+    }
+
+    fn edge_endpoints(&self, edge: EdgeIndex) -> (NodeIndex, NodeIndex) {
+        (
+            NodeIndex::new(self.edge_sources[edge.index()] as usize),
+            NodeIndex::new(self.edge_targets[edge.index()] as usize),
+        )
+    }
+
+    fn mapping_type(&self, edge: EdgeIndex) -> &str {
+        self.strings.resolve(self.edge_types[edge.index()])
+    }
+
+    fn strength(&self, edge: EdgeIndex) -> f32 {
+        self.edge_strengths[edge.index()]
+    }
+
+    fn degree(&self, node: NodeIndex) -> usize {
+        CsrGraph::edge_range(&self.out_offsets, node).len()
+            + CsrGraph::edge_range(&self.in_offsets, node).len()
+    }
+}
+
+/// Collects nodes and edges in any order, then lays them out as a
+/// [`CsrGraph`].
+#[derive(Debug, Default)]
+pub struct CsrBuilder {
+    graph: CsrGraph,
+}
+
+impl CsrBuilder {
+    pub fn new() -> CsrBuilder {
+        CsrBuilder::default()
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.graph.node_ids.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_sources.len()
+    }
+
+    /// The node with this ID, added first if needed (like
+    /// `add_node_if_not_exists`; the type of an existing node is kept).
+    pub fn add_node(&mut self, id: &str, node_type: NodeType) -> NodeIndex {
+        let graph = &mut self.graph;
+        let symbol = graph.strings.intern(id);
+        if graph.nodes_by_symbol.len() <= symbol.index() {
+            graph.nodes_by_symbol.resize(symbol.index() + 1, NO_NODE);
+        }
+        match graph.nodes_by_symbol[symbol.index()] {
+            NO_NODE => {
+                let node = graph.node_ids.len();
+                graph.nodes_by_symbol[symbol.index()] = node as u32;
+                graph.node_ids.push(symbol);
+                graph.node_types.push(node_type);
+                NodeIndex::new(node)
+            }
+            node => NodeIndex::new(node as usize),
+        }
+    }
+
+    /// Interns a string into the graph's symbol table, for callers that keep
+    /// their own symbol-based records next to the graph.
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        self.graph.strings.intern(text)
+    }
+
+    pub fn set_metadata(&mut self, node: NodeIndex, key: &str, value: &str) {
+        let key = self.graph.strings.intern(key);
+        let value = self.graph.strings.intern(value);
+        let column = self.graph.metadata.entry(key).or_default();
+        if column.len() <= node.index() {
+            column.resize(node.index() + 1, None);
+        }
+        column[node.index()] = Some(value);
+    }
+
+    pub fn add_edge(
+        &mut self,
+        source: NodeIndex,
+        target: NodeIndex,
+        mapping_type: &str,
+        strength: f32,
+    ) -> EdgeIndex {
+        let mapping_type = self.graph.strings.intern(mapping_type);
+        self.add_interned_edge(source, target, mapping_type, strength)
+    }
+
+    /// [`add_edge`](CsrBuilder::add_edge) with a mapping type already
+    /// interned by [`intern`](CsrBuilder::intern).
+    pub fn add_interned_edge(
+        &mut self,
+        source: NodeIndex,
+        target: NodeIndex,
+        mapping_type: Symbol,
+        strength: f32,
+    ) -> EdgeIndex {
+        let graph = &mut self.graph;
+        graph.edge_sources.push(source.index() as u32);
+        graph.edge_targets.push(target.index() as u32);
+        graph.edge_types.push(mapping_type);
+        graph.edge_strengths.push(strength);
+        EdgeIndex::new(graph.edge_sources.len() - 1)
+    }
+
+    /// Adds the VERIS and ATT&CK nodes of a mapping and the edge between
+    /// them, as `create_graph` does.
+    pub fn add_mapping(&mut self, mapping: &Mapping) -> EdgeIndex {
+        let veris = self.add_node(&mapping.capability_id, NodeType::Veris);
+        let mitre = self.add_node(&mapping.attack_object_id, NodeType::Mitre);
+        self.add_edge(veris, mitre, &mapping.mapping_type, calculate_strength(mapping))
+    }
+
+    /// Sorts the edges into the per-node lists and releases spare capacity.
+    pub fn build(self) -> CsrGraph {
+        let mut graph = self.graph;
+        let node_count = graph.node_ids.len();
+        (graph.out_offsets, graph.out_edges) = adjacency(&graph.edge_sources, node_count);
+        (graph.in_offsets, graph.in_edges) = adjacency(&graph.edge_targets, node_count);
+
+        graph.nodes_by_symbol.resize(graph.strings.len(), NO_NODE);
+        for column in graph.metadata.values_mut() {
+            column.resize(node_count, None);
+            column.shrink_to_fit();
+        }
+        graph.strings.shrink_to_fit();
+        graph.node_ids.shrink_to_fit();
+        graph.node_types.shrink_to_fit();
+        graph.nodes_by_symbol.shrink_to_fit();
+        graph.edge_sources.shrink_to_fit();
+        graph.edge_targets.shrink_to_fit();
+        graph.edge_types.shrink_to_fit();
+        graph.edge_strengths.shrink_to_fit();
+        graph
+    }
+}
+
+/// Counting sort of edge indices by endpoint: offsets and the edge list.
+fn adjacency(endpoints: &[u32], node_count: usize) -> (Vec<u32>, Vec<u32>) {
+    let mut offsets = vec![0u32; node_count + 1];
+    for &node in endpoints {
+        offsets[node as usize + 1] += 1;
+    }
+    for i in 0..node_count {
+        offsets[i + 1] += offsets[i];
+    }
+    let mut next = offsets.clone();
+    let mut edges = vec![0u32; endpoints.len()];
+    for (edge, &node) in endpoints.iter().enumerate() {
+        edges[next[node as usize] as usize] = edge as u32;
+        next[node as usize] += 1;
+    }
+    (offsets, edges)
+}
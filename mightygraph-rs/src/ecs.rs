@@ -0,0 +1,700 @@
+//! Elastic Common Schema (ECS) records for the exported graph.
+//!
+//! Nodes, edges and mapping rows are turned into [`EcsRecord`]s. ATT&CK data
+//! goes into the standard `threat.*` fields (`threat.framework`,
+//! `threat.technique.id`, `threat.technique.subtechnique.id`,
+//! `threat.tactic.name`, `threat.software.*`, `threat.group.*`) and CVEs into
+//! `vulnerability.id`. Everything without an ECS field goes under the custom
+//! `mightygraph.*` field set.
+//!
+//! [`EcsSchema::bundled`] is the field definition bundled with the crate
+//! (`src/ecs/fields.json`). Every record is checked against it for unknown
+//! fields, wrong types, values outside `allowed_values` and missing required
+//! fields. Records are written as NDJSON for the Elasticsearch `_bulk` API,
+//! one `index` action per record. Document IDs are derived from the record
+//! type and key, so re-indexing a graph overwrites each document instead of
+//! adding a duplicate.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map};
+
+use crate::export::NodeMetrics;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::utils::{calculate_strength, parse_mapping_date, sha256_hex};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// The bundled field definition.
+pub const ECS_FIELDS_JSON: &str = include_str!("ecs/fields.json");
+
+pub const ATTACK_FRAMEWORK: &str = "MITRE ATT&CK";
+pub const VERIS_FRAMEWORK: &str = "VERIS";
+const ATTACK_URL: &str = "https://attack.mitre.org";
+
+/// Metadata keys that map onto their own fields rather than
+/// `mightygraph.node.metadata`.
+const MAPPED_METADATA: &[&str] = &["name", "tactics", "summary", "tags", "title"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    Keyword,
+    MatchOnlyText,
+    Date,
+    Long,
+    Float,
+    Boolean,
+    Object,
+    Flattened,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldDefinition {
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    /// `core`, `extended` or `custom`.
+    pub level: String,
+    /// Whether the field may hold several values.
+    #[serde(default)]
+    pub array: bool,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub allowed_values: Option<Vec<String>>,
+    pub description: String,
+}
+
+/// ECS field definitions by dotted field name. Names ending in `.*` define
+/// every field under that prefix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcsSchema {
+    pub ecs_version: String,
+    /// Field set of the fields that are not part of ECS.
+    pub custom_prefix: String,
+    pub fields: BTreeMap<String, FieldDefinition>,
+}
+
+/// A field of a record that does not conform to the schema.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EcsViolation {
+    pub record_id: String,
+    pub field: String,
+    pub problem: String,
+}
+
+impl EcsSchema {
+    /// The field definition bundled with the crate.
+    pub fn bundled() -> EcsSchema {
+        EcsSchema::from_json(ECS_FIELDS_JSON).expect("bundled ECS field definition is valid")
+    }
+
+    pub fn from_json(json: &str) -> Result<EcsSchema> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// The definition of a field, direct or through a `prefix.*` entry.
+    pub fn field(&self, name: &str) -> Option<&FieldDefinition> {
+        self.fields.get(name).or_else(|| {
+            self.fields.iter().find_map(|(pattern, definition)| {
+                let prefix = pattern.strip_suffix('*')?;
+                (name.starts_with(prefix) && name.len() > prefix.len()).then_some(definition)
+            })
+        })
+    }
+
+    /// Checks one record.
+    ///
+    /// # Arguments
+    ///
+    /// - `record`: The record to check.
+    ///
+    /// # Returns
+    ///
+    /// - `Vec<EcsViolation>`: Unknown fields, type and allowed-value
+    ///   mismatches, and missing required fields; empty if the record conforms.
+    pub fn validate(&self, record: &EcsRecord) -> Vec<EcsViolation> {
+        let mut violations = vec![];
+        let mut violation = |field: &str, problem: String| {
+            violations.push(EcsViolation {
+                record_id: record.id.clone(),
+                field: field.to_string(),
+                problem,
+            })
+        };
+
+        for (name, value) in &record.fields {
+            let Some(definition) = self.field(name) else {
+                violation(name, "not defined in the ECS schema".to_string());
+                continue;
+            };
+            let values = match value {
+                Value::Array(values) if definition.array => values.as_slice(),
+                Value::Array(_) => {
+                    violation(name, "expected a single value, found an array".to_string());
+                    continue;
+                }
+                value => std::slice::from_ref(value),
+            };
+            for value in values {
+                if !type_matches(definition.field_type, value) {
+                    let expected = definition.field_type;
+                    violation(name, format!("expected {:?}, found {}", expected, value));
+                    continue;
+                }
+                let allowed = definition.allowed_values.as_ref();
+                if let (Some(allowed), Some(text)) = (allowed, value.as_str()) {
+                    if !allowed.iter().any(|a| a == text) {
+                        violation(name, format!("{:?} is not an allowed value", text));
+                    }
+                }
+            }
+        }
+
+        for (name, definition) in &self.fields {
+            if definition.required && !record.fields.contains_key(name) {
+                violation(name, "required field is missing".to_string());
+            }
+        }
+        violations
+    }
+
+    /// Checks every record.
+    pub fn validate_all(&self, records: &[EcsRecord]) -> Vec<EcsViolation> {
+        records.iter().flat_map(|r| self.validate(r)).collect()
+    }
+
+    /// Checks every record.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<()>`: An error naming the number of violations and the first
+    ///   one, if any record does not conform.
+    pub fn ensure_valid(&self, records: &[EcsRecord]) -> Result<()> {
+        let violations = self.validate_all(records);
+        match violations.first() {
+            Some(first) => Err(format!(
+                "{} ECS violations, first: {} in record {}: {}",
+                violations.len(),
+                first.field,
+                first.record_id,
+                first.problem
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+}
+
+fn type_matches(field_type: FieldType, value: &Value) -> bool {
+    match field_type {
+        FieldType::Keyword | FieldType::MatchOnlyText => value.is_string(),
+        FieldType::Date => value.as_str().is_some_and(|s| {
+            DateTime::parse_from_rfc3339(s).is_ok()
+                || NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok()
+        }),
+        FieldType::Long => value.is_i64() || value.is_u64(),
+        FieldType::Float => value.is_number(),
+        FieldType::Boolean => value.is_boolean(),
+        FieldType::Object | FieldType::Flattened => value.is_object(),
+    }
+}
+
+/// One document, with its target index and ID.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EcsRecord {
+    pub index: String,
+    pub id: String,
+    /// Field values by dotted ECS name.
+    pub fields: BTreeMap<String, Value>,
+}
+
+impl EcsRecord {
+    pub fn new(index: &str, id: String) -> EcsRecord {
+        EcsRecord {
+            index: index.to_string(),
+            id,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Sets a field; null values, empty strings and empty arrays are skipped.
+    pub fn set<V: Into<Value>>(&mut self, field: &str, value: V) {
+        let value = value.into();
+        let empty = match &value {
+            Value::Null => true,
+            Value::String(s) => s.trim().is_empty(),
+            Value::Array(a) => a.is_empty(),
+            _ => false,
+        };
+        if !empty {
+            self.fields.insert(field.to_string(), value);
+        }
+    }
+
+    /// The record as a nested JSON document.
+    pub fn document(&self) -> Value {
+        let mut root = Map::new();
+        for (name, value) in &self.fields {
+            let mut parts: Vec<&str> = name.split('.').collect();
+            let leaf = parts.pop().unwrap_or_default();
+            let mut object = &mut root;
+            for part in parts {
+                let child = object
+                    .entry(part.to_string())
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if !child.is_object() {
+                    *child = Value::Object(Map::new());
+                }
+                object = child.as_object_mut().unwrap();
+            }
+            object.insert(leaf.to_string(), value.clone());
+        }
+        Value::Object(root)
+    }
+}
+
+/// Options for the record builders.
+#[derive(Debug, Clone)]
+pub struct EcsOptions {
+    /// Indices are `{prefix}-nodes`, `{prefix}-edges`, `{prefix}-mappings` and
+    /// `{prefix}-analyses`.
+    pub index_prefix: String,
+    /// `@timestamp` of every record.
+    pub timestamp: DateTime<Utc>,
+    pub ecs_version: String,
+}
+
+impl Default for EcsOptions {
+    fn default() -> Self {
+        EcsOptions {
+            index_prefix: "mightygraph".to_string(),
+            timestamp: Utc::now(),
+            ecs_version: EcsSchema::bundled().ecs_version,
+        }
+    }
+}
+
+impl EcsOptions {
+    pub fn index(&self, record_type: &str) -> String {
+        format!("{}-{}", self.index_prefix, plural(record_type))
+    }
+
+    fn record(&self, record_type: &str, key: &str) -> EcsRecord {
+        let mut record = EcsRecord::new(&self.index(record_type), document_id(record_type, key));
+        record.set(
+            "@timestamp",
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+        );
+        record.set("ecs.version", self.ecs_version.as_str());
+        record.set("event.kind", "enrichment");
+        record.set("event.category", json!(["threat"]));
+        record.set("event.type", json!(["indicator"]));
+        record.set("event.module", "mightygraph");
+        record.set("event.dataset", format!("mightygraph.{}", plural(record_type)));
+        record.set("mightygraph.record_type", record_type);
+        record
+    }
+}
+
+fn plural(record_type: &str) -> String {
+    match record_type {
+        "analysis" => "analyses".to_string(),
+        other => format!("{}s", other),
+    }
+}
+
+/// Deterministic document ID: the first 32 hex digits of the SHA-256 of the
+/// record type and key.
+pub fn document_id(record_type: &str, key: &str) -> String {
+    sha256_hex(&format!("{}\u{0}{}", record_type, key))[..32].to_string()
+}
+
+/// Sets `threat.technique.*`, splitting sub-techniques into
+/// `threat.technique.subtechnique.*` as ECS does.
+fn set_technique(record: &mut EcsRecord, id: &str, name: Option<&str>) {
+    let name = name.filter(|n| !n.trim().is_empty());
+    let (parent_name, child_name) = match name.map(|n| n.split_once(':')) {
+        Some(Some((parent, child))) => (Some(parent.trim()), Some(child.trim())),
+        Some(None) => (None, name),
+        None => (None, None),
+    };
+    record.set("threat.framework", ATTACK_FRAMEWORK);
+    match id.split_once('.') {
+        Some((parent, sub)) => {
+            record.set("threat.technique.id", json!([parent]));
+            record.set("threat.technique.reference", json!([technique_url(parent, None)]));
+            if let Some(parent_name) = parent_name {
+                record.set("threat.technique.name", json!([parent_name]));
+            }
+            record.set("threat.technique.subtechnique.id", json!([id]));
+            record.set(
+                "threat.technique.subtechnique.reference",
+                json!([technique_url(parent, Some(sub))]),
+            );
+            if let Some(name) = child_name {
+                record.set("threat.technique.subtechnique.name", json!([name]));
+            }
+        }
+        None => {
+            record.set("threat.technique.id", json!([id]));
+            record.set("threat.technique.reference", json!([technique_url(id, None)]));
+            if let Some(name) = name {
+                record.set("threat.technique.name", json!([name]));
+            }
+        }
+    }
+}
+
+fn technique_url(id: &str, sub: Option<&str>) -> String {
+    match sub {
+        Some(sub) => format!("{}/techniques/{}/{}/", ATTACK_URL, id, sub),
+        None => format!("{}/techniques/{}/", ATTACK_URL, id),
+    }
+}
+
+fn split_list(value: &str) -> Vec<&str> {
+    value
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// The ECS fields describing one node: technique, software, group, CVE or
+/// VERIS enumeration.
+fn set_entity(record: &mut EcsRecord, data: &NodeData) {
+    let name = data.metadata.get("name").map(String::as_str);
+    match data.node_type {
+        NodeType::Mitre => {
+            set_technique(record, &data.id, name);
+            if let Some(tactics) = data.metadata.get("tactics") {
+                record.set("threat.tactic.name", json!(split_list(tactics)));
+            }
+        }
+        NodeType::Veris => {
+            record.set("threat.framework", VERIS_FRAMEWORK);
+            record.set("mightygraph.veris.id", data.id.as_str());
+        }
+        NodeType::Software => {
+            record.set("threat.framework", ATTACK_FRAMEWORK);
+            record.set("threat.software.id", data.id.as_str());
+            record.set("threat.software.name", name);
+            record.set(
+                "threat.software.reference",
+                format!("{}/software/{}/", ATTACK_URL, data.id),
+            );
+        }
+        NodeType::Group => {
+            record.set("threat.framework", ATTACK_FRAMEWORK);
+            record.set("threat.group.id", data.id.as_str());
+            record.set("threat.group.name", name);
+            record.set("threat.group.reference", format!("{}/groups/{}/", ATTACK_URL, data.id));
+        }
+        NodeType::Vulnerability => {
+            record.set("vulnerability.id", data.id.as_str());
+            record.set(
+                "vulnerability.reference",
+                format!("https://www.cve.org/CVERecord?id={}", data.id),
+            );
+        }
+        NodeType::Report => {}
+    }
+}
+
+/// One record per node, in node index order.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `metrics`: Per-node metrics, written as `mightygraph.metrics.<name>`.
+/// - `options`: Index prefix, timestamp and ECS version.
+///
+/// # Returns
+///
+/// - `Vec<EcsRecord>`: The node records.
+pub fn node_records(
+    graph: &MappingGraph,
+    metrics: &NodeMetrics,
+    options: &EcsOptions,
+) -> Vec<EcsRecord> {
+    graph
+        .node_indices()
+        .map(|node| {
+            let data = &graph[node];
+            let mut record = options.record("node", &data.id);
+            record.set("mightygraph.node.id", data.id.as_str());
+            record.set("mightygraph.node.type", format!("{:?}", data.node_type));
+            record.set(
+                "mightygraph.node.degree",
+                graph.neighbors_undirected(node).count(),
+            );
+            set_entity(&mut record, data);
+
+            let title = data.metadata.get("title").or(data.metadata.get("name"));
+            record.set(
+                "message",
+                match title {
+                    Some(title) => format!("{:?} {}: {}", data.node_type, data.id, title),
+                    None => format!("{:?} {}", data.node_type, data.id),
+                },
+            );
+            record.set("mightygraph.node.summary", data.metadata.get("summary").cloned());
+            if let Some(tags) = data.metadata.get("tags") {
+                record.set("tags", json!(split_list(tags)));
+            }
+            let rest: Map<String, Value> = data
+                .metadata
+                .iter()
+                .filter(|(key, _)| !MAPPED_METADATA.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                .collect();
+            if !rest.is_empty() {
+                record.set("mightygraph.node.metadata", Value::Object(rest));
+            }
+
+            for (metric, values) in metrics {
+                if let Some(&value) = values.get(&data.id).filter(|v| v.is_finite()) {
+                    record.set(&format!("mightygraph.metrics.{}", metric), value);
+                }
+            }
+            record
+        })
+        .collect()
+}
+
+/// One record per edge. Edges with the same endpoints and mapping type share
+/// a document ID, so duplicate edges index as one document.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `options`: Index prefix, timestamp and ECS version.
+///
+/// # Returns
+///
+/// - `Vec<EcsRecord>`: The edge records, in edge index order.
+pub fn edge_records(graph: &MappingGraph, options: &EcsOptions) -> Vec<EcsRecord> {
+    graph
+        .edge_indices()
+        .map(|edge| {
+            let (source, target) = graph.edge_endpoints(edge).unwrap();
+            let (source, target, data) = (&graph[source], &graph[target], &graph[edge]);
+            let key = format!("{}\u{0}{}\u{0}{}", source.id, target.id, data.mapping_type);
+            let mut record = options.record("edge", &key);
+            record.set("mightygraph.edge.source.id", source.id.as_str());
+            record.set("mightygraph.edge.target.id", target.id.as_str());
+            record.set("mightygraph.edge.mapping_type", &*data.mapping_type);
+            record.set("mightygraph.edge.strength", data.strength);
+            record.set(
+                "message",
+                format!("{} {} {}", source.id, data.mapping_type, target.id),
+            );
+            set_entity(&mut record, target);
+            if source.node_type == NodeType::Veris {
+                record.set("mightygraph.veris.id", source.id.as_str());
+            }
+            record
+        })
+        .collect()
+}
+
+/// One record per mapping row, the ECS form of the combined export.
+///
+/// # Arguments
+///
+/// - `mappings`: The mapping rows.
+/// - `options`: Index prefix, timestamp and ECS version.
+///
+/// # Returns
+///
+/// - `Vec<EcsRecord>`: The mapping records, in row order. Rows that repeat
+///   the same pair, type and organization share a document ID.
+pub fn mapping_records(mappings: &[Mapping], options: &EcsOptions) -> Vec<EcsRecord> {
+    mappings
+        .iter()
+        .map(|m| {
+            let key = format!(
+                "{}\u{0}{}\u{0}{}\u{0}{}",
+                m.capability_id, m.attack_object_id, m.mapping_type, m.organization
+            );
+            let mut record = options.record("mapping", &key);
+            set_technique(&mut record, &m.attack_object_id, Some(&m.attack_object_name));
+            record.set(
+                "message",
+                format!("{} {} {}", m.capability_id, m.mapping_type, m.attack_object_id),
+            );
+            record.set("mightygraph.veris.id", m.capability_id.as_str());
+            record.set("mightygraph.veris.group", m.capability_group.as_str());
+            record.set("mightygraph.veris.description", m.capability_description.as_str());
+            record.set("mightygraph.edge.source.id", m.capability_id.as_str());
+            record.set("mightygraph.edge.target.id", m.attack_object_id.as_str());
+            record.set("mightygraph.edge.mapping_type", m.mapping_type.as_str());
+            record.set("mightygraph.edge.strength", calculate_strength(m));
+            record.set("mightygraph.mapping.framework", m.mapping_framework.as_str());
+            record.set(
+                "mightygraph.mapping.framework_version",
+                m.mapping_framework_version.as_str(),
+            );
+            record.set("mightygraph.mapping.attack_version", m.attack_version.as_str());
+            record.set("mightygraph.mapping.technology_domain", m.technology_domain.as_str());
+            record.set("mightygraph.mapping.references", json!(split_list(&m.references)));
+            record.set("mightygraph.mapping.comments", m.comments.as_str());
+            record.set("organization.name", m.organization.as_str());
+            let date = |value: &str| parse_mapping_date(value).map(|d| d.to_string());
+            record.set("event.created", date(&m.creation_date));
+            record.set("mightygraph.mapping.last_update", date(&m.last_update));
+            record
+        })
+        .collect()
+}
+
+/// One record per analysis, with the result under `mightygraph.analysis.result`.
+///
+/// # Arguments
+///
+/// - `analyses`: The analysis results.
+/// - `options`: Index prefix, timestamp and ECS version.
+///
+/// # Returns
+///
+/// - `Vec<EcsRecord>`: The analysis records, keyed by analysis name so a
+///   re-run replaces them.
+pub fn analysis_records(analyses: &AnalysisResults, options: &EcsOptions) -> Vec<EcsRecord> {
+    let Value::Object(results) = json!(analyses) else {
+        return vec![];
+    };
+    results
+        .into_iter()
+        .map(|(name, result)| {
+            let mut record = options.record("analysis", &name);
+            record.set("message", format!("{} analysis", name));
+            record.set("mightygraph.analysis.name", name.as_str());
+            let result = match result {
+                Value::Object(_) => result,
+                other => json!({ "value": other }),
+            };
+            record.set("mightygraph.analysis.result", result);
+            record
+        })
+        .collect()
+}
+
+/// Writes records as `_bulk` NDJSON: an `index` action line, then the
+/// document, per record.
+pub fn write_bulk<W: Write>(records: &[EcsRecord], writer: &mut W) -> Result<()> {
+    for record in records {
+        let action = json!({ "index": { "_index": record.index, "_id": record.id } });
+        serde_json::to_writer(&mut *writer, &action)?;
+        writer.write_all(b"\n")?;
+        serde_json::to_writer(&mut *writer, &record.document())?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Validates the records and writes them to an NDJSON file.
+///
+/// # Arguments
+///
+/// - `schema`: The schema the records must conform to.
+/// - `records`: The records.
+/// - `path`: The NDJSON file to write.
+///
+/// # Returns
+///
+/// - `Result<()>`: An error listing the first violations, without writing,
+///   if any record does not conform.
+pub fn export_bulk<P: AsRef<Path>>(
+    schema: &EcsSchema,
+    records: &[EcsRecord],
+    path: P,
+) -> Result<()> {
+    schema.ensure_valid(records)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_bulk(records, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Outcome of one `_bulk` request.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BulkResponse {
+    pub took: u64,
+    pub indexed: usize,
+    /// `(document ID, error reason)` of the rejected documents.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Sends records to `{endpoint}/_bulk` in one request.
+///
+/// # Arguments
+///
+/// - `endpoint`: Base URL of the cluster, e.g. `http://localhost:9200`.
+/// - `records`: The records to index.
+///
+/// # Returns
+///
+/// - `Result<BulkResponse>`: Indexed and rejected documents. An HTTP error
+///   status is returned as an error.
+pub fn post_bulk(endpoint: &str, records: &[EcsRecord]) -> Result<BulkResponse> {
+    let mut body = vec![];
+    write_bulk(records, &mut body)?;
+    let url = format!("{}/_bulk", endpoint.trim_end_matches('/'));
+    let request = ureq::post(&url).timeout(Duration::from_secs(60));
+    let response = bulk_request(request)
+        .send_bytes(&body)
+        .map_err(|e| format!("bulk request to {} failed: {}", url, e))?;
+    let reply: Value = response.into_json()?;
+    Ok(parse_bulk_reply(&reply))
+}
+
+/// Prepares a request for a `_bulk` NDJSON body.
+pub fn bulk_request(request: ureq::Request) -> ureq::Request {
+    request.set("Content-Type", "application/x-ndjson")
+}
+
+/// Reads the per-item results of a `_bulk` reply.
+pub fn parse_bulk_reply(reply: &Value) -> BulkResponse {
+    let mut response = BulkResponse {
+        took: reply["took"].as_u64().unwrap_or(0),
+        ..BulkResponse::default()
+    };
+    for result in bulk_reply_items(reply).filter(|result| !result.is_null()) {
+        let id = result["_id"].as_str().unwrap_or_default().to_string();
+        match bulk_item_error(result) {
+            None => response.indexed += 1,
+            Some(reason) => response.failed.push((id, reason)),
+        }
+    }
+    response
+}
+
+/// The result of each item of a `_bulk` reply, in request order: the object
+/// under the item's action key, or `Null` for a malformed item.
+pub fn bulk_reply_items(reply: &Value) -> impl Iterator<Item = &Value> {
+    reply["items"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+        .iter()
+        .map(|item| item.as_object().and_then(|o| o.values().next()).unwrap_or(&Value::Null))
+}
+
+/// The error reason of a `_bulk` item result, `None` if it was indexed.
+pub fn bulk_item_error(result: &Value) -> Option<String> {
+    if result["error"].is_null() {
+        return None;
+    }
+    Some(
+        result["error"]["reason"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| result["error"].to_string()),
+    )
+}
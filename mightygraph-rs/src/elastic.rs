@@ -0,0 +1,416 @@
+//! Indexing of ECS records into Elasticsearch or OpenSearch.
+//!
+//! [`index_template`] turns the ECS field definition into a composable index
+//! template for `{prefix}-*`, so the nodes, edges, mappings and analyses
+//! indices get explicit mappings before the first document arrives.
+//! OpenSearch has no `match_only_text` or `flattened` field types; they are
+//! mapped to `text` and `flat_object` there.
+//!
+//! [`BulkIndexer`] sends records through the `_bulk` API. Records are cut
+//! into batches by count and by size, and batches go through a bounded queue
+//! to a few worker threads. Records are serialized as batches are built, so
+//! a slow cluster holds back serialization instead of piling documents up
+//! in memory. A request rejected as a whole (429, 502, 503, 504 or a
+//! transport error) is sent again after a backoff, honouring `Retry-After`;
+//! documents rejected one by one with 429 are sent again on their own. Records carry the deterministic IDs from
+//! [`crate::ecs::document_id`] and use the `index` action, so a re-run
+//! replaces the documents of the earlier run.
+
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Map};
+
+use crate::ecs::{self, EcsRecord, EcsSchema, FieldType};
+use crate::petgraph_full_0x0::prelude::*;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Whole-request statuses that are retried.
+const RETRY_STATUSES: &[u16] = &[429, 502, 503, 504];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterFlavor {
+    Elasticsearch,
+    OpenSearch,
+}
+
+/// Cluster address, index naming, batching and retry settings.
+#[derive(Debug, Clone)]
+pub struct ElasticConfig {
+    /// Base URL of the cluster, e.g. `http://localhost:9200`.
+    pub endpoint: String,
+    pub flavor: ClusterFlavor,
+    /// Must match the `index_prefix` the records were built with.
+    pub index_prefix: String,
+    /// Sent as the `Authorization` header when set, e.g. `ApiKey ...`.
+    pub authorization: Option<String>,
+    /// Documents per `_bulk` request.
+    pub batch_size: usize,
+    /// Upper bound on the NDJSON body of one request.
+    pub max_batch_bytes: usize,
+    /// Requests in flight at once.
+    pub concurrency: usize,
+    /// Batches waiting for a worker before batch building blocks.
+    pub queue_capacity: usize,
+    /// Retries of a request or a rejected document before it counts as failed.
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for ElasticConfig {
+    fn default() -> Self {
+        ElasticConfig {
+            endpoint: "http://localhost:9200".to_string(),
+            flavor: ClusterFlavor::Elasticsearch,
+            index_prefix: "mightygraph".to_string(),
+            authorization: None,
+            batch_size: 500,
+            max_batch_bytes: 5 * 1024 * 1024,
+            concurrency: 2,
+            queue_capacity: 4,
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(30),
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Outcome of [`BulkIndexer::index`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexReport {
+    /// `_bulk` requests sent, retries included.
+    pub requests: usize,
+    pub indexed: usize,
+    /// Requests sent again after a 429, 5xx or transport error, or to resend
+    /// documents rejected with 429.
+    pub retries: usize,
+    /// `(document ID, error reason)` of the documents that were not indexed.
+    pub failed: Vec<(String, String)>,
+}
+
+impl IndexReport {
+    fn merge(&mut self, other: IndexReport) {
+        self.requests += other.requests;
+        self.indexed += other.indexed;
+        self.retries += other.retries;
+        self.failed.extend(other.failed);
+    }
+}
+
+/// A record serialized as its action and document lines.
+struct BulkItem {
+    id: String,
+    ndjson: Vec<u8>,
+}
+
+/// Why a `_bulk` request got no per-item reply.
+enum RequestError {
+    Retryable { reason: String, retry_after: Option<Duration> },
+    Fatal(String),
+}
+
+fn mapping_type(field_type: FieldType, flavor: ClusterFlavor) -> &'static str {
+    match (field_type, flavor) {
+        (FieldType::Keyword, _) => "keyword",
+        (FieldType::MatchOnlyText, ClusterFlavor::Elasticsearch) => "match_only_text",
+        (FieldType::MatchOnlyText, ClusterFlavor::OpenSearch) => "text",
+        (FieldType::Date, _) => "date",
+        (FieldType::Long, _) => "long",
+        (FieldType::Float, _) => "float",
+        (FieldType::Boolean, _) => "boolean",
+        (FieldType::Object, _) => "object",
+        (FieldType::Flattened, ClusterFlavor::Elasticsearch) => "flattened",
+        (FieldType::Flattened, ClusterFlavor::OpenSearch) => "flat_object",
+    }
+}
+
+/// Builds the composable index template for the `{prefix}-*` indices.
+///
+/// # Arguments
+///
+/// - `schema`: The ECS field definition; `prefix.*` entries become dynamic
+///   templates.
+/// - `config`: Index prefix and cluster flavor.
+///
+/// # Returns
+///
+/// - `Value`: The body for `PUT _index_template/{prefix}`.
+pub fn index_template(schema: &EcsSchema, config: &ElasticConfig) -> Value {
+    let mut properties = Map::new();
+    let mut dynamic_templates = vec![];
+    for (name, definition) in &schema.fields {
+        let field_mapping = json!({ "type": mapping_type(definition.field_type, config.flavor) });
+        if let Some(prefix) = name.strip_suffix(".*") {
+            let mut template = Map::new();
+            template.insert(
+                prefix.replace('.', "_"),
+                json!({ "path_match": name, "mapping": field_mapping }),
+            );
+            dynamic_templates.push(Value::Object(template));
+            continue;
+        }
+
+        let mut parts: Vec<&str> = name.split('.').collect();
+        let leaf = parts.pop().unwrap_or_default();
+        let mut object = &mut properties;
+        for part in parts {
+            let parent = object.entry(part.to_string()).or_insert_with(|| json!({}));
+            object = parent
+                .as_object_mut()
+                .unwrap()
+                .entry("properties")
+                .or_insert_with(|| json!({}))
+                .as_object_mut()
+                .unwrap();
+        }
+        // Keeps the `properties` of a parent defined after its children.
+        let entry = object.entry(leaf.to_string()).or_insert_with(|| json!({}));
+        if let (Value::Object(entry), Value::Object(field_mapping)) = (entry, field_mapping) {
+            entry.extend(field_mapping);
+        }
+    }
+
+    json!({
+        "index_patterns": [format!("{}-*", config.index_prefix)],
+        "priority": 200,
+        "_meta": { "managed_by": "mightygraph", "ecs_version": schema.ecs_version },
+        "template": {
+            "mappings": {
+                "dynamic_templates": dynamic_templates,
+                "properties": properties,
+            }
+        }
+    })
+}
+
+/// Splits serialized records into batches by count and body size. A record
+/// larger than `max_bytes` goes into a batch of its own.
+fn batches(
+    items: impl Iterator<Item = BulkItem>,
+    max_items: usize,
+    max_bytes: usize,
+) -> impl Iterator<Item = Vec<BulkItem>> {
+    let mut items = items.peekable();
+    std::iter::from_fn(move || {
+        let mut batch = vec![];
+        let mut bytes = 0;
+        while let Some(item) = items.peek() {
+            let full = batch.len() >= max_items.max(1) || bytes + item.ndjson.len() > max_bytes;
+            if !batch.is_empty() && full {
+                break;
+            }
+            bytes += item.ndjson.len();
+            if let Some(item) = items.next() {
+                batch.push(item);
+            }
+        }
+        (!batch.is_empty()).then_some(batch)
+    })
+}
+
+fn serialize(record: &EcsRecord) -> Result<BulkItem> {
+    let mut ndjson = vec![];
+    ecs::write_bulk(std::slice::from_ref(record), &mut ndjson)?;
+    Ok(BulkItem { id: record.id.clone(), ndjson })
+}
+
+/// Sends records to a cluster through the `_bulk` API.
+pub struct BulkIndexer {
+    config: ElasticConfig,
+    agent: ureq::Agent,
+}
+
+impl BulkIndexer {
+    pub fn new(config: ElasticConfig) -> BulkIndexer {
+        let agent = ureq::AgentBuilder::new().timeout(config.timeout).build();
+        BulkIndexer { config, agent }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.endpoint.trim_end_matches('/'), path)
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let request = self.agent.request(method, &self.url(path));
+        match &self.config.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    /// Creates or replaces the index template of the `{prefix}-*` indices.
+    ///
+    /// # Arguments
+    ///
+    /// - `schema`: The ECS field definition the template is built from.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<()>`: An error if the cluster does not acknowledge the template.
+    pub fn put_template(&self, schema: &EcsSchema) -> Result<()> {
+        let path = format!("_index_template/{}", self.config.index_prefix);
+        let response = self
+            .request("PUT", &path)
+            .send_json(index_template(schema, &self.config))
+            .map_err(|e| match e {
+                ureq::Error::Status(code, response) => format!(
+                    "index template rejected with {}: {}",
+                    code,
+                    response.into_string().unwrap_or_default()
+                ),
+                e => format!("index template request failed: {}", e),
+            })?;
+        let reply: Value = response.into_json()?;
+        if reply["acknowledged"] != json!(true) {
+            return Err(format!("index template not acknowledged: {}", reply).into());
+        }
+        Ok(())
+    }
+
+    /// Indexes records, retrying throttled requests and documents.
+    ///
+    /// # Arguments
+    ///
+    /// - `records`: The records; their `index` and `id` pick the target document.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<IndexReport>`: Requests, retries, and indexed and failed
+    ///   documents. Documents still rejected after the retries, or rejected
+    ///   for other reasons, are reported as failed rather than as an error.
+    ///   A record that cannot be serialized stops the run with an error;
+    ///   the batches before it have been sent by then.
+    pub fn index(&self, records: &[EcsRecord]) -> Result<IndexReport> {
+        let mut error = None;
+        let items = records.iter().map_while(|record| match serialize(record) {
+            Ok(item) => Some(item),
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        });
+        let (sender, receiver) = mpsc::sync_channel::<Vec<BulkItem>>(self.config.queue_capacity);
+        let receiver = Mutex::new(receiver);
+
+        let mut report = IndexReport::default();
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.config.concurrency.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut report = IndexReport::default();
+                        loop {
+                            let batch = receiver.lock().unwrap().recv();
+                            match batch {
+                                Ok(batch) => self.send_batch(batch, &mut report),
+                                Err(_) => return report,
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            let config = &self.config;
+            for batch in batches(items, config.batch_size, config.max_batch_bytes) {
+                // Blocks while every worker is busy and the queue is full.
+                if sender.send(batch).is_err() {
+                    break;
+                }
+            }
+            drop(sender);
+            for worker in workers {
+                report.merge(worker.join().expect("bulk worker panicked"));
+            }
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(report),
+        }
+    }
+
+    /// Sends one batch until every document is indexed, failed, or out of
+    /// retries.
+    fn send_batch(&self, mut pending: Vec<BulkItem>, report: &mut IndexReport) {
+        let mut attempt = 0;
+        loop {
+            report.requests += 1;
+            let body: Vec<u8> = pending.iter().flat_map(|item| &item.ndjson).copied().collect();
+            let (reason, retry_after) = match self.post_bulk(&body) {
+                Ok(reply) => {
+                    let mut throttled = vec![];
+                    let mut items = pending.into_iter();
+                    for (item, result) in items.by_ref().zip(ecs::bulk_reply_items(&reply)) {
+                        match ecs::bulk_item_error(result) {
+                            None => report.indexed += 1,
+                            Some(_) if result["status"] == 429 => throttled.push(item),
+                            Some(reason) => report.failed.push((item.id, reason)),
+                        }
+                    }
+                    for item in items {
+                        report.failed.push((item.id, "missing from the bulk reply".to_string()));
+                    }
+                    if throttled.is_empty() {
+                        return;
+                    }
+                    pending = throttled;
+                    ("rejected with 429".to_string(), None)
+                }
+                Err(RequestError::Retryable { reason, retry_after }) => (reason, retry_after),
+                Err(RequestError::Fatal(reason)) => {
+                    let failed = pending.into_iter().map(|item| (item.id, reason.clone()));
+                    report.failed.extend(failed);
+                    return;
+                }
+            };
+
+            if attempt >= self.config.max_retries {
+                let reason = format!("{} after {} retries", reason, attempt);
+                let failed = pending.into_iter().map(|item| (item.id, reason.clone()));
+                report.failed.extend(failed);
+                return;
+            }
+            attempt += 1;
+            report.retries += 1;
+            thread::sleep(self.backoff(attempt, retry_after));
+        }
+    }
+
+    fn post_bulk(&self, body: &[u8]) -> std::result::Result<Value, RequestError> {
+        match ecs::bulk_request(self.request("POST", "_bulk")).send_bytes(body) {
+            Ok(response) => response.into_json().map_err(|e| RequestError::Retryable {
+                reason: format!("unreadable bulk reply: {}", e),
+                retry_after: None,
+            }),
+            Err(ureq::Error::Status(code, response)) if RETRY_STATUSES.contains(&code) => {
+                let retry_after = response
+                    .header("Retry-After")
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                let reason = format!("cluster returned {}", code);
+                Err(RequestError::Retryable { reason, retry_after })
+            }
+            Err(ureq::Error::Status(code, response)) => {
+                let text = response.into_string().unwrap_or_default();
+                Err(RequestError::Fatal(format!("cluster returned {}: {}", code, text)))
+            }
+            Err(error @ ureq::Error::Transport(_)) => Err(RequestError::Retryable {
+                reason: error.to_string(),
+                retry_after: None,
+            }),
+        }
+    }
+
+    /// Exponential backoff from `initial_backoff`, or the server's
+    /// `Retry-After`, capped at `max_backoff`.
+    fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let factor = 1 << (attempt - 1).min(16);
+        let exponential = self.config.initial_backoff.saturating_mul(factor);
+        retry_after.unwrap_or(exponential).min(self.config.max_backoff)
+    }
+}
@@ -0,0 +1,422 @@
+//! Random-walk node embeddings (DeepWalk and node2vec).
+//!
+//! Walks are generated over the undirected mapping graph and fed to a
+//! skip-gram model trained with negative sampling, as in word2vec. With
+//! `p = q = 1` the walks are uniform (DeepWalk); other values bias them as
+//! in node2vec: a low `p` keeps walks local, a low `q` pushes them outward.
+//!
+//! Everything is seeded, so the same graph and options give the same
+//! vectors. Embeddings are written to Parquet or to a NumPy `.npy` matrix
+//! whose rows follow `Embeddings::ids`.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use polars::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::petgraph_full_0x0::prelude::*;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Options for [`generate_walks`].
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    pub walk_length: usize,
+    pub walks_per_node: usize,
+    /// Return parameter: weight `1 / p` for stepping back to the previous node.
+    pub p: f64,
+    /// In-out parameter: weight `1 / q` for moving away from the previous node.
+    pub q: f64,
+    /// Scales transition weights by mapping strength.
+    pub weighted: bool,
+    pub seed: u64,
+}
+
+impl WalkOptions {
+    /// Uniform DeepWalk walks.
+    pub fn deepwalk() -> Self {
+        WalkOptions {
+            walk_length: 40,
+            walks_per_node: 10,
+            p: 1.0,
+            q: 1.0,
+            weighted: false,
+            seed: 42,
+        }
+    }
+
+    /// Biased node2vec walks.
+    pub fn node2vec(p: f64, q: f64) -> Self {
+        WalkOptions {
+            p,
+            q,
+            ..WalkOptions::deepwalk()
+        }
+    }
+
+    fn is_uniform(&self) -> bool {
+        self.p == 1.0 && self.q == 1.0 && !self.weighted
+    }
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions::deepwalk()
+    }
+}
+
+/// Options for [`train_skip_gram`].
+#[derive(Debug, Clone)]
+pub struct SkipGramOptions {
+    pub dimensions: usize,
+    /// Maximum distance between a node and its context in a walk.
+    pub window: usize,
+    pub negative_samples: usize,
+    pub epochs: usize,
+    /// Learning rate at the start; it decays linearly to `min_learning_rate`.
+    pub learning_rate: f32,
+    pub min_learning_rate: f32,
+    pub seed: u64,
+}
+
+impl Default for SkipGramOptions {
+    fn default() -> Self {
+        SkipGramOptions {
+            dimensions: 64,
+            window: 5,
+            negative_samples: 5,
+            epochs: 5,
+            learning_rate: 0.025,
+            min_learning_rate: 0.0001,
+            seed: 42,
+        }
+    }
+}
+
+/// One vector per node, stored row-major.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Embeddings {
+    /// Node ids, in graph index order.
+    pub ids: Vec<String>,
+    pub node_types: Vec<NodeType>,
+    pub dimensions: usize,
+    pub vectors: Vec<f32>,
+}
+
+impl Embeddings {
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Vector of the `i`-th node.
+    pub fn vector(&self, i: usize) -> &[f32] {
+        &self.vectors[i * self.dimensions..(i + 1) * self.dimensions]
+    }
+
+    /// Vector of the node with this id.
+    pub fn get(&self, id: &str) -> Option<&[f32]> {
+        self.ids.iter().position(|n| n == id).map(|i| self.vector(i))
+    }
+
+    /// Map from node id to row, for repeated lookups.
+    pub fn index(&self) -> HashMap<&str, usize> {
+        self.ids.iter().enumerate().map(|(i, id)| (id.as_str(), i)).collect()
+    }
+
+    /// One row per node: `node_id`, `node_type`, then `dim_0` … `dim_{d-1}`.
+    pub fn to_frame(&self) -> PolarsResult<DataFrame> {
+        let mut columns = vec![
+            Column::new("node_id".into(), self.ids.clone()),
+            Column::new(
+                "node_type".into(),
+                self.node_types.iter().map(|t| format!("{:?}", t)).collect::<Vec<_>>(),
+            ),
+        ];
+        for d in 0..self.dimensions {
+            let column: Vec<f32> = (0..self.len()).map(|i| self.vector(i)[d]).collect();
+            columns.push(Column::new(format!("dim_{}", d).into(), column));
+        }
+        DataFrame::new(columns)
+    }
+
+    pub fn write_parquet<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut df = self.to_frame()?;
+        ParquetWriter::new(File::create(path)?).finish(&mut df)?;
+        Ok(())
+    }
+
+    /// Writes the vectors as a `float32` matrix of shape `(nodes, dimensions)`
+    /// in NumPy's `.npy` format (version 1.0).
+    pub fn write_npy<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut header = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+            self.len(),
+            self.dimensions
+        );
+        // Magic (6) + version (2) + header length (2) + header, padded with
+        // spaces and a final newline to a multiple of 64 bytes.
+        let unpadded = 10 + header.len() + 1;
+        header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+        header.push('\n');
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(b"\x93NUMPY\x01\x00")?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+        for value in &self.vectors {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the node ids, one per line, in the row order of `write_npy`.
+    pub fn write_ids<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for id in &self.ids {
+            writeln!(writer, "{}", id)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Cosine similarity of two vectors; 0 if either is zero.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Neighbors of every node, ignoring direction, with the edge strength.
+fn weighted_adjacency(graph: &MappingGraph) -> Vec<Vec<(usize, f64)>> {
+    let mut adjacency = vec![vec![]; graph.node_count()];
+    for edge in graph.edge_indices() {
+        let (a, b) = graph.edge_endpoints(edge).unwrap();
+        let strength = graph[edge].strength as f64;
+        adjacency[a.index()].push((b.index(), strength));
+        adjacency[b.index()].push((a.index(), strength));
+    }
+    for neighbors in &mut adjacency {
+        neighbors.sort_by_key(|&(n, _)| n);
+    }
+    adjacency
+}
+
+/// Generates random walks from every node.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `options`: Walk length and count, node2vec `p`/`q` and the seed.
+///
+/// # Returns
+///
+/// - `Vec<Vec<usize>>`: Walks as node indices; a walk stops early at a node
+///   without neighbors.
+pub fn generate_walks(graph: &MappingGraph, options: &WalkOptions) -> Vec<Vec<usize>> {
+    let adjacency = weighted_adjacency(graph);
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut starts: Vec<usize> = (0..graph.node_count()).collect();
+    let mut walks = Vec::with_capacity(starts.len() * options.walks_per_node);
+
+    for _ in 0..options.walks_per_node {
+        starts.shuffle(&mut rng);
+        for &start in &starts {
+            let mut walk = vec![start];
+            while walk.len() < options.walk_length {
+                let current = *walk.last().unwrap();
+                let neighbors = &adjacency[current];
+                if neighbors.is_empty() {
+                    break;
+                }
+                let next = if options.is_uniform() {
+                    neighbors[rng.gen_range(0..neighbors.len())].0
+                } else {
+                    let previous = walk.len().checked_sub(2).map(|i| walk[i]);
+                    biased_step(&adjacency, neighbors, previous, options, &mut rng)
+                };
+                walk.push(next);
+            }
+            walks.push(walk);
+        }
+    }
+
+    walks
+}
+
+/// Samples the next node with node2vec's second-order weights.
+fn biased_step(
+    adjacency: &[Vec<(usize, f64)>],
+    neighbors: &[(usize, f64)],
+    previous: Option<usize>,
+    options: &WalkOptions,
+    rng: &mut StdRng,
+) -> usize {
+    let weights: Vec<f64> = neighbors
+        .iter()
+        .map(|&(next, strength)| {
+            let base = if options.weighted { strength.max(f64::EPSILON) } else { 1.0 };
+            let bias = match previous {
+                None => 1.0,
+                Some(prev) if prev == next => 1.0 / options.p,
+                Some(prev) if is_adjacent(adjacency, prev, next) => 1.0,
+                Some(_) => 1.0 / options.q,
+            };
+            base * bias
+        })
+        .collect();
+
+    let mut target = rng.gen::<f64>() * weights.iter().sum::<f64>();
+    for (&(next, _), weight) in neighbors.iter().zip(&weights) {
+        if target < *weight {
+            return next;
+        }
+        target -= weight;
+    }
+    neighbors.last().unwrap().0
+}
+
+fn is_adjacent(adjacency: &[Vec<(usize, f64)>], a: usize, b: usize) -> bool {
+    adjacency[a].binary_search_by_key(&b, |&(n, _)| n).is_ok()
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Trains skip-gram vectors with negative sampling on the walks.
+///
+/// # Arguments
+///
+/// - `walks`: Walks as produced by [`generate_walks`].
+/// - `node_count`: Number of nodes; walk entries must be below it.
+/// - `options`: Model size, window, negatives, schedule and seed.
+///
+/// # Returns
+///
+/// - `Vec<f32>`: The input vectors, `node_count * dimensions` values row-major.
+pub fn train_skip_gram(
+    walks: &[Vec<usize>],
+    node_count: usize,
+    options: &SkipGramOptions,
+) -> Vec<f32> {
+    let dim = options.dimensions;
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut input: Vec<f32> = (0..node_count * dim)
+        .map(|_| (rng.gen::<f32>() - 0.5) / dim as f32)
+        .collect();
+    let mut output = vec![0.0f32; node_count * dim];
+
+    // Negatives are drawn from the walk frequencies raised to 3/4.
+    let mut frequencies = vec![0usize; node_count];
+    for node in walks.iter().flatten() {
+        frequencies[*node] += 1;
+    }
+    let mut cumulative = Vec::with_capacity(node_count);
+    let mut total = 0.0;
+    for &count in &frequencies {
+        total += (count as f64).powf(0.75);
+        cumulative.push(total);
+    }
+    if total == 0.0 {
+        return input;
+    }
+
+    let total_steps = (options.epochs * walks.iter().map(Vec::len).sum::<usize>()).max(1);
+    let mut step = 0;
+    let mut gradient = vec![0.0f32; dim];
+
+    for _ in 0..options.epochs {
+        for walk in walks {
+            for (i, &center) in walk.iter().enumerate() {
+                let progress = step as f32 / total_steps as f32;
+                let rate =
+                    (options.learning_rate * (1.0 - progress)).max(options.min_learning_rate);
+                step += 1;
+
+                // As in word2vec, the effective window is sampled per position.
+                let window = rng.gen_range(1..=options.window.max(1));
+                let from = i.saturating_sub(window);
+                let to = (i + window).min(walk.len() - 1);
+                for (j, &context) in walk.iter().enumerate().take(to + 1).skip(from) {
+                    if j == i {
+                        continue;
+                    }
+                    gradient.iter_mut().for_each(|g| *g = 0.0);
+                    let center_vec = center * dim..(center + 1) * dim;
+
+                    for k in 0..=options.negative_samples {
+                        let (target, label) = if k == 0 {
+                            (context, 1.0)
+                        } else {
+                            let draw = rng.gen::<f64>() * total;
+                            let sample = cumulative.partition_point(|&c| c <= draw);
+                            let sample = sample.min(node_count - 1);
+                            if sample == context {
+                                continue;
+                            }
+                            (sample, 0.0)
+                        };
+                        let center_in = &input[center_vec.clone()];
+                        let target_out = &mut output[target * dim..(target + 1) * dim];
+                        let dot: f32 =
+                            center_in.iter().zip(target_out.iter()).map(|(a, b)| a * b).sum();
+                        let g = (label - sigmoid(dot)) * rate;
+                        let rows = gradient.iter_mut().zip(target_out).zip(center_in);
+                        for ((grad, out), inp) in rows {
+                            *grad += g * *out;
+                            *out += g * inp;
+                        }
+                    }
+                    for (value, g) in input[center_vec].iter_mut().zip(&gradient) {
+                        *value += g;
+                    }
+                }
+            }
+        }
+    }
+
+    input
+}
+
+/// Generates walks and trains embeddings for every node of the graph.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `walk_options`: DeepWalk or node2vec walk settings.
+/// - `skip_gram_options`: Skip-gram training settings.
+///
+/// # Returns
+///
+/// - `Embeddings`: One vector per node, in graph index order.
+pub fn train_embeddings(
+    graph: &MappingGraph,
+    walk_options: &WalkOptions,
+    skip_gram_options: &SkipGramOptions,
+) -> Embeddings {
+    let walks = generate_walks(graph, walk_options);
+    let vectors = train_skip_gram(&walks, graph.node_count(), skip_gram_options);
+
+    Embeddings {
+        ids: graph.node_indices().map(|n| graph[n].id.clone()).collect(),
+        node_types: graph.node_indices().map(|n| graph[n].node_type).collect(),
+        dimensions: skip_gram_options.dimensions,
+        vectors,
+    }
+}
@@ -0,0 +1,381 @@
+//! Columnar export of the combined mapping data.
+//!
+//! The combined table has one row per `Mapping`, enriched with the edge strength,
+//! the ATT&CK object's degree and the derived impact score. Its column types are
+//! fixed by [`combined_schema`] so Parquet, Arrow IPC and CSV outputs all agree:
+//! dates are typed `Date` columns and `mapping_type` is categorical.
+//!
+//! Parquet output can be partitioned Hive-style, e.g.
+//! `technology_domain=enterprise/attack_version=12.1/part-00000.parquet`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use polars::prelude::*;
+
+use crate::petgraph_full_0x0::prelude::{AnalysisResults, Mapping, Value};
+use crate::utils::{calculate_strength, parse_mapping_date};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Per-node metrics keyed by metric name, then by node ID.
+pub type NodeMetrics = BTreeMap<String, HashMap<String, f64>>;
+
+/// Output formats supported by [`export_combined`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Parquet,
+    Csv,
+    Ipc,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ipc => "arrow",
+        }
+    }
+}
+
+/// Columns the Parquet output can be partitioned by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionKey {
+    TechnologyDomain,
+    AttackVersion,
+}
+
+impl PartitionKey {
+    pub fn column_name(self) -> &'static str {
+        match self {
+            PartitionKey::TechnologyDomain => "technology_domain",
+            PartitionKey::AttackVersion => "attack_version",
+        }
+    }
+}
+
+/// Options controlling which columns are written and where.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Directory the files are written to; created if missing.
+    pub output_dir: PathBuf,
+    /// File name without extension, e.g. `combined_analysis`.
+    pub file_stem: String,
+    pub formats: Vec<ExportFormat>,
+    /// Adds every remaining `Mapping` field as a column.
+    pub include_all_mapping_fields: bool,
+    /// Adds one `metric_<name>` column per entry in the node metrics.
+    pub include_node_metrics: bool,
+    /// Hive-style partitioning for Parquet output. Ignored by CSV and IPC.
+    pub partition_by: Vec<PartitionKey>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            output_dir: PathBuf::from("./analysed/data"),
+            file_stem: "combined_analysis".to_string(),
+            formats: vec![ExportFormat::Parquet, ExportFormat::Csv, ExportFormat::Ipc],
+            include_all_mapping_fields: false,
+            include_node_metrics: false,
+            partition_by: vec![],
+        }
+    }
+}
+
+/// Optional `Mapping` fields added by `include_all_mapping_fields`.
+const EXTRA_MAPPING_FIELDS: &[&str] = &[
+    "mapping_framework",
+    "mapping_framework_version",
+    "capability_group",
+    "capability_description",
+    "attack_object_name",
+    "references",
+    "comments",
+    "organization",
+];
+
+/// Collects the per-node metrics computed by `perform_analyses`.
+///
+/// # Arguments
+///
+/// - `analyses`: The analysis results.
+///
+/// # Returns
+///
+/// - `NodeMetrics`: Every analysis that is keyed by node ID, by analysis name.
+pub fn node_metrics_from_analyses(analyses: &AnalysisResults) -> NodeMetrics {
+    let mut metrics = NodeMetrics::new();
+    metrics.insert(
+        "degree".to_string(),
+        node_metric_from_value(&analyses.node_degree_analysis),
+    );
+    metrics.insert(
+        "closeness".to_string(),
+        node_metric_from_value(&analyses.closeness_centrality),
+    );
+    metrics.insert(
+        "eccentricity".to_string(),
+        node_metric_from_value(&analyses.path_length_analysis["eccentricity"]),
+    );
+    metrics
+}
+
+/// Reads a `{ "<node id>": <number> }` analysis result into a metric map.
+pub fn node_metric_from_value(value: &Value) -> HashMap<String, f64> {
+    value
+        .as_object()
+        .map(|object| {
+            object
+                .iter()
+                .filter_map(|(id, v)| v.as_f64().map(|v| (id.clone(), v)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Builds the explicit schema of the combined table.
+///
+/// # Arguments
+///
+/// - `options`: The export options deciding the optional columns.
+/// - `metrics`: The node metrics; only their names are used.
+///
+/// # Returns
+///
+/// - `Schema`: The column names and types, in output order.
+pub fn combined_schema(options: &ExportOptions, metrics: &NodeMetrics) -> Schema {
+    let mut fields = vec![
+        Field::new("veris_id".into(), DataType::String),
+        Field::new("mitre_id".into(), DataType::String),
+        Field::new("mapping_type".into(), DataType::Categorical(None, CategoricalOrdering::Physical)),
+        Field::new("strength".into(), DataType::Float64),
+        Field::new("frequency".into(), DataType::Int64),
+        Field::new("impact_score".into(), DataType::Float64),
+        Field::new("technology_domain".into(), DataType::String),
+        Field::new("attack_version".into(), DataType::String),
+        Field::new("creation_date".into(), DataType::Date),
+        Field::new("last_update".into(), DataType::Date),
+    ];
+    if options.include_all_mapping_fields {
+        fields.extend(
+            EXTRA_MAPPING_FIELDS
+                .iter()
+                .map(|&name| Field::new(name.into(), DataType::String)),
+        );
+    }
+    if options.include_node_metrics {
+        fields.extend(
+            metrics
+                .keys()
+                .map(|name| Field::new(format!("metric_{}", name).into(), DataType::Float64)),
+        );
+    }
+    Schema::from_iter(fields)
+}
+
+/// Builds the combined table, sorted by impact score (highest first).
+///
+/// # Arguments
+///
+/// - `mappings`: The loaded mappings.
+/// - `metrics`: Per-node metrics; the `degree` metric drives `frequency`.
+/// - `options`: The export options deciding the optional columns.
+///
+/// # Returns
+///
+/// - `Result<DataFrame>`: A frame whose columns match [`combined_schema`].
+pub fn build_combined_frame(
+    mappings: &[Mapping],
+    metrics: &NodeMetrics,
+    options: &ExportOptions,
+) -> Result<DataFrame> {
+    let degrees = metrics.get("degree");
+    let mut rows: Vec<(&Mapping, f64, i64, f64)> = mappings
+        .iter()
+        .map(|mapping| {
+            let frequency = degrees
+                .and_then(|d| d.get(&mapping.attack_object_id))
+                .copied()
+                .unwrap_or(0.0) as i64;
+            let strength = calculate_strength(mapping) as f64;
+            let impact_score = (frequency as f64 * strength) / 10.0;
+            (mapping, strength, frequency, impact_score)
+        })
+        .collect();
+    rows.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap_or(std::cmp::Ordering::Equal));
+
+    let text = |name: &str, f: &dyn Fn(&Mapping) -> &str| {
+        Series::new(name.into(), rows.iter().map(|r| f(r.0)).collect::<Vec<_>>())
+    };
+    let date = |name: &str, f: &dyn Fn(&Mapping) -> &str| {
+        Series::new(
+            name.into(),
+            rows.iter()
+                .map(|r| parse_mapping_date(f(r.0)).map(days_since_epoch))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let mut columns = vec![
+        text("veris_id", &|m| m.capability_id.as_str()),
+        text("mitre_id", &|m| m.attack_object_id.as_str()),
+        text("mapping_type", &|m| m.mapping_type.as_str()),
+        Series::new("strength".into(), rows.iter().map(|r| r.1).collect::<Vec<_>>()),
+        Series::new("frequency".into(), rows.iter().map(|r| r.2).collect::<Vec<_>>()),
+        Series::new("impact_score".into(), rows.iter().map(|r| r.3).collect::<Vec<_>>()),
+        text("technology_domain", &|m| m.technology_domain.as_str()),
+        text("attack_version", &|m| m.attack_version.as_str()),
+        date("creation_date", &|m| m.creation_date.as_str()),
+        date("last_update", &|m| m.last_update.as_str()),
+    ];
+
+    if options.include_all_mapping_fields {
+        columns.extend([
+            text("mapping_framework", &|m| m.mapping_framework.as_str()),
+            text("mapping_framework_version", &|m| {
+                m.mapping_framework_version.as_str()
+            }),
+            text("capability_group", &|m| m.capability_group.as_str()),
+            text("capability_description", &|m| {
+                m.capability_description.as_str()
+            }),
+            text("attack_object_name", &|m| m.attack_object_name.as_str()),
+            text("references", &|m| m.references.as_str()),
+            text("comments", &|m| m.comments.as_str()),
+            text("organization", &|m| m.organization.as_str()),
+        ]);
+    }
+
+    if options.include_node_metrics {
+        for (name, values) in metrics {
+            // Metrics are looked up on the ATT&CK side first, then the VERIS side.
+            let column: Vec<Option<f64>> = rows
+                .iter()
+                .map(|r| {
+                    values
+                        .get(&r.0.attack_object_id)
+                        .or_else(|| values.get(&r.0.capability_id))
+                        .copied()
+                })
+                .collect();
+            columns.push(Series::new(format!("metric_{}", name).into(), column));
+        }
+    }
+
+    let schema = combined_schema(options, metrics);
+    let columns = columns
+        .into_iter()
+        .zip(schema.iter_fields())
+        .map(|(series, field)| series.cast(field.dtype()).map(Column::from))
+        .collect::<PolarsResult<Vec<_>>>()?;
+
+    Ok(DataFrame::new(columns)?)
+}
+
+/// Writes the combined table in every format listed in `options`.
+///
+/// # Arguments
+///
+/// - `mappings`: The loaded mappings.
+/// - `metrics`: Per-node metrics, see [`node_metrics_from_analyses`].
+/// - `options`: Output location, formats, optional columns and partitioning.
+///
+/// # Returns
+///
+/// - `Result<Vec<PathBuf>>`: The files that were written.
+pub fn export_combined(
+    mappings: &[Mapping],
+    metrics: &NodeMetrics,
+    options: &ExportOptions,
+) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(&options.output_dir)?;
+    let mut df = build_combined_frame(mappings, metrics, options)?;
+    let mut written = vec![];
+
+    for &format in &options.formats {
+        let path = options
+            .output_dir
+            .join(format!("{}.{}", options.file_stem, format.extension()));
+        match format {
+            ExportFormat::Parquet if !options.partition_by.is_empty() => {
+                let dir = options.output_dir.join(&options.file_stem);
+                written.extend(write_partitioned_parquet(&df, &dir, &options.partition_by)?);
+            }
+            ExportFormat::Parquet => {
+                ParquetWriter::new(File::create(&path)?).finish(&mut df)?;
+                written.push(path);
+            }
+            ExportFormat::Ipc => {
+                IpcWriter::new(File::create(&path)?).finish(&mut df)?;
+                written.push(path);
+            }
+            ExportFormat::Csv => {
+                // CSV has no categorical type, so write the plain string form.
+                let mut csv_df = df.clone();
+                let mapping_type = csv_df.column("mapping_type")?.cast(&DataType::String)?;
+                csv_df.with_column(mapping_type)?;
+                CsvWriter::new(File::create(&path)?).finish(&mut csv_df)?;
+                written.push(path);
+            }
+        }
+    }
+
+    Ok(written)
+}
+
+/// Writes one Parquet file per distinct combination of the partition keys.
+fn write_partitioned_parquet(
+    df: &DataFrame,
+    dir: &Path,
+    keys: &[PartitionKey],
+) -> Result<Vec<PathBuf>> {
+    let names: Vec<String> = keys.iter().map(|k| k.column_name().to_string()).collect();
+    let mut written = vec![];
+
+    for (i, part) in df.partition_by(names.clone(), true)?.into_iter().enumerate() {
+        let mut part_dir = dir.to_path_buf();
+        for name in &names {
+            let value = match part.column(name)?.get(0)? {
+                AnyValue::Null => "__null__".to_string(),
+                other => other.str_value().into_owned(),
+            };
+            part_dir.push(format!("{}={}", name, sanitize_partition_value(&value)));
+        }
+        fs::create_dir_all(&part_dir)?;
+
+        let mut part = part.drop_many(&names);
+        let path = part_dir.join(format!("part-{:05}.parquet", i));
+        ParquetWriter::new(File::create(&path)?).finish(&mut part)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// Keeps partition directory names portable.
+fn sanitize_partition_value(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if value.is_empty() {
+        "__empty__".to_string()
+    } else {
+        value
+    }
+}
+
+pub(crate) fn days_since_epoch(date: NaiveDate) -> i32 {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    (date - epoch).num_days() as i32
+}
@@ -0,0 +1,736 @@
+//! Entity extraction from unstructured threat intelligence reports.
+//!
+//! A report (plain text, Markdown or HTML) is scanned for:
+//!
+//! - ATT&CK IDs such as `T1003.001`, `S0002` or `G0016`;
+//! - technique, software and group names and aliases;
+//! - CVE IDs such as `CVE-2021-44228`;
+//! - VERIS enumerations, either as full paths
+//!   (`action.hacking.variety.Brute force`) or as their last segment in prose
+//!   ("brute force").
+//!
+//! Names are looked up in an [`EntityDictionary`] built from the loaded graph,
+//! the mappings and, optionally, the ATT&CK catalog. Each [`Mention`] carries
+//! byte offsets into the original document and a confidence. Explicit IDs
+//! score highest; a name that several entities of one kind share has its
+//! confidence split between them.
+//!
+//! [`link_report`] adds the report to the graph as a [`NodeType::Report`] node
+//! with a `mentions` edge to every entity it mentions. That way reports take
+//! part in the same analyses as the mappings.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::catalog::AttackCatalog;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::utils::{add_node_if_not_exists, sha256_hex, STOP_WORDS};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Known ATT&CK ID, CVE or full VERIS path.
+pub const ID_CONFIDENCE: f32 = 1.0;
+/// Well-formed ATT&CK ID that the dictionary does not know.
+pub const UNKNOWN_ID_CONFIDENCE: f32 = 0.6;
+/// Technique, software or group name of several words.
+pub const NAME_CONFIDENCE: f32 = 0.9;
+/// One-word software or group name, matched with its exact case.
+pub const SHORT_NAME_CONFIDENCE: f32 = 0.7;
+/// Last segment of a VERIS enumeration used in prose.
+pub const VERIS_TERM_CONFIDENCE: f32 = 0.5;
+
+/// Edge type from a report to what it mentions.
+pub const MENTIONS: &str = "mentions";
+
+/// VERIS values too generic to be matched in prose.
+const GENERIC_VERIS_TERMS: &[&str] = &["other", "unknown", "na", "none", "yes", "no"];
+
+/// Most non-space characters allowed between two words of a name, like the
+/// `:` in `OS Credential Dumping: LSASS Memory`.
+const MAX_WORD_GAP: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Technique,
+    Software,
+    Group,
+    Vulnerability,
+    Veris,
+}
+
+impl EntityKind {
+    /// Node type of the entity in the graph.
+    pub fn node_type(self) -> NodeType {
+        match self {
+            EntityKind::Technique => NodeType::Mitre,
+            EntityKind::Software => NodeType::Software,
+            EntityKind::Group => NodeType::Group,
+            EntityKind::Vulnerability => NodeType::Vulnerability,
+            EntityKind::Veris => NodeType::Veris,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// Guesses the format from the file extension; anything unknown is text.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> ReportFormat {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("html") | Some("htm") => ReportFormat::Html,
+            Some("md") | Some("markdown") => ReportFormat::Markdown,
+            _ => ReportFormat::Text,
+        }
+    }
+}
+
+/// One occurrence of an entity in a report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mention {
+    pub entity_id: String,
+    pub kind: EntityKind,
+    /// The matched text, as written in the report.
+    pub text: String,
+    /// Byte offsets into the original document.
+    pub start: usize,
+    pub end: usize,
+    pub confidence: f32,
+}
+
+/// A report and what it mentions.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    /// Node ID of the report, e.g. `report:apt29-2023`.
+    pub id: String,
+    pub title: Option<String>,
+    /// File path or URL the report was read from.
+    pub source: Option<String>,
+    pub content_hash: String,
+    /// Mentions in document order.
+    pub mentions: Vec<Mention>,
+}
+
+/// Per-entity view of a report's mentions.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntityMentions {
+    pub kind: EntityKind,
+    pub count: usize,
+    pub max_confidence: f32,
+}
+
+impl Report {
+    /// Mentions grouped by entity ID.
+    pub fn entities(&self) -> BTreeMap<&str, EntityMentions> {
+        let mut entities: BTreeMap<&str, EntityMentions> = BTreeMap::new();
+        for mention in &self.mentions {
+            let entry = entities
+                .entry(mention.entity_id.as_str())
+                .or_insert(EntityMentions {
+                    kind: mention.kind,
+                    count: 0,
+                    max_confidence: 0.0,
+                });
+            entry.count += 1;
+            entry.max_confidence = entry.max_confidence.max(mention.confidence);
+        }
+        entities
+    }
+}
+
+/// Options for [`extract`].
+#[derive(Debug, Clone)]
+pub struct ExtractionOptions {
+    /// Drops mentions below this confidence.
+    pub min_confidence: f32,
+    /// Keeps well-formed ATT&CK IDs that the dictionary does not know.
+    pub unknown_attack_ids: bool,
+}
+
+impl Default for ExtractionOptions {
+    fn default() -> Self {
+        ExtractionOptions {
+            min_confidence: 0.3,
+            unknown_attack_ids: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    id: String,
+    kind: EntityKind,
+    /// Exact spelling required, for one-word names like `Net` or `at`.
+    exact: Option<String>,
+    confidence: f32,
+}
+
+/// Names and IDs to look for, built from the graph and reference data.
+#[derive(Debug, Clone, Default)]
+pub struct EntityDictionary {
+    /// ATT&CK IDs, upper case.
+    attack_ids: HashMap<String, EntityKind>,
+    /// VERIS paths, ASCII lower case, to their original spelling.
+    veris_paths: HashMap<String, String>,
+    /// Names as lower case words joined by single spaces.
+    phrases: HashMap<String, Vec<Entry>>,
+    max_phrase_words: usize,
+}
+
+impl EntityDictionary {
+    /// Starts a dictionary from the graph's nodes.
+    ///
+    /// Technique names come from the `name` metadata that
+    /// [`crate::catalog::enrich_graph`] sets; use
+    /// [`EntityDictionary::with_mappings`] for graphs that are not enriched.
+    pub fn from_graph(graph: &MappingGraph) -> EntityDictionary {
+        let mut dictionary = EntityDictionary::default();
+        for node in graph.node_weights() {
+            let kind = match node.node_type {
+                NodeType::Veris => {
+                    dictionary.add_veris(&node.id);
+                    continue;
+                }
+                NodeType::Mitre => EntityKind::Technique,
+                NodeType::Software => EntityKind::Software,
+                NodeType::Group => EntityKind::Group,
+                NodeType::Report | NodeType::Vulnerability => continue,
+            };
+            dictionary.attack_ids.insert(node.id.to_ascii_uppercase(), kind);
+            if let Some(name) = node.metadata.get("name") {
+                dictionary.add_name(name, &node.id, kind);
+            }
+        }
+        dictionary
+    }
+
+    /// Adds the VERIS enumerations and technique names of the mappings.
+    pub fn with_mappings(mut self, mappings: &[Mapping]) -> EntityDictionary {
+        for mapping in mappings {
+            self.add_veris(&mapping.capability_id);
+            self.attack_ids
+                .insert(mapping.attack_object_id.to_ascii_uppercase(), EntityKind::Technique);
+            self.add_technique_name(&mapping.attack_object_name, &mapping.attack_object_id);
+        }
+        self
+    }
+
+    /// Adds the active techniques, software and groups of the catalog.
+    pub fn with_catalog(mut self, catalog: &AttackCatalog) -> EntityDictionary {
+        for technique in catalog.active_techniques() {
+            self.attack_ids.insert(technique.id.clone(), EntityKind::Technique);
+            self.add_technique_name(&technique.name, &technique.id);
+        }
+        for (entities, kind) in [
+            (&catalog.software, EntityKind::Software),
+            (&catalog.groups, EntityKind::Group),
+        ] {
+            for entity in entities.values().filter(|e| !e.revoked) {
+                self.attack_ids.insert(entity.id.clone(), kind);
+                self.add_name(&entity.name, &entity.id, kind);
+                for alias in &entity.aliases {
+                    self.add_name(alias, &entity.id, kind);
+                }
+            }
+        }
+        self
+    }
+
+    /// Number of distinct names.
+    pub fn len(&self) -> usize {
+        self.phrases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phrases.is_empty()
+    }
+
+    /// Adds `Parent: Child` names whole, the child part on its own, and the
+    /// parent part for the parent technique.
+    fn add_technique_name(&mut self, name: &str, id: &str) {
+        self.add_name(name, id, EntityKind::Technique);
+        if let Some((parent, child)) = name.split_once(':') {
+            self.add_name(child, id, EntityKind::Technique);
+            if let Some((parent_id, _)) = id.split_once('.') {
+                self.attack_ids
+                    .insert(parent_id.to_ascii_uppercase(), EntityKind::Technique);
+                self.add_name(parent, parent_id, EntityKind::Technique);
+            }
+        }
+    }
+
+    fn add_name(&mut self, name: &str, id: &str, kind: EntityKind) {
+        let words = phrase_words(name);
+        let (exact, confidence) = match words.as_slice() {
+            [] => return,
+            [word] if word.len() < 3 || STOP_WORDS.contains(&word.as_str()) => return,
+            [_] if kind == EntityKind::Veris => (None, VERIS_TERM_CONFIDENCE),
+            [_] => {
+                let word = name.split(|c: char| !c.is_alphanumeric()).find(|w| !w.is_empty());
+                (word.map(str::to_string), SHORT_NAME_CONFIDENCE)
+            }
+            _ if kind == EntityKind::Veris => (None, VERIS_TERM_CONFIDENCE),
+            _ => (None, NAME_CONFIDENCE),
+        };
+        self.max_phrase_words = self.max_phrase_words.max(words.len());
+
+        let entries = self.phrases.entry(words.join(" ")).or_default();
+        if !entries.iter().any(|e| e.id == id && e.exact == exact) {
+            entries.push(Entry {
+                id: id.to_string(),
+                kind,
+                exact,
+                confidence,
+            });
+        }
+    }
+
+    fn add_veris(&mut self, path: &str) {
+        self.veris_paths
+            .insert(path.to_ascii_lowercase(), path.to_string());
+        if let Some((_, leaf)) = path.rsplit_once('.') {
+            if !GENERIC_VERIS_TERMS.contains(&leaf.trim().to_ascii_lowercase().as_str()) {
+                self.add_name(leaf, path, EntityKind::Veris);
+            }
+        }
+    }
+}
+
+fn phrase_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Blanks out markup with spaces, keeping every other byte where it was, so
+/// offsets into the result are offsets into the original document.
+fn visible_text(document: &str, format: ReportFormat) -> String {
+    let mut bytes = document.as_bytes().to_vec();
+    match format {
+        ReportFormat::Text => {}
+        ReportFormat::Markdown => {
+            for byte in bytes.iter_mut() {
+                if matches!(byte, b'*' | b'`' | b'#' | b'>' | b'[' | b']') {
+                    *byte = b' ';
+                }
+            }
+        }
+        ReportFormat::Html => {
+            let lower = document.to_ascii_lowercase();
+            let mut i = 0;
+            while let Some(offset) = lower[i..].find('<') {
+                let start = i + offset;
+                let mut end = lower[start..].find('>').map_or(lower.len(), |e| start + e + 1);
+                // Script and style bodies are not text either.
+                for (open, close) in [("<script", "</script"), ("<style", "</style")] {
+                    if lower[start..].starts_with(open) {
+                        if let Some(offset) = lower[end..].find(close) {
+                            let close = end + offset;
+                            end = lower[close..].find('>').map_or(lower.len(), |e| close + e + 1);
+                        }
+                    }
+                }
+                bytes[start..end].fill(b' ');
+                i = end;
+            }
+            for (entity, replacement) in [
+                ("&amp;", b'&'),
+                ("&lt;", b'<'),
+                ("&gt;", b'>'),
+                ("&quot;", b'"'),
+                ("&#39;", b'\''),
+                ("&nbsp;", b' '),
+            ] {
+                for (start, _) in document.match_indices(entity) {
+                    bytes[start..start + entity.len()].fill(b' ');
+                    bytes[start] = replacement;
+                }
+            }
+        }
+    }
+    // Only whole ASCII runs or whole tags were replaced with ASCII.
+    String::from_utf8(bytes).expect("blanking keeps UTF-8 valid")
+}
+
+/// First heading, `<title>` or non-empty line.
+fn find_title(document: &str, format: ReportFormat) -> Option<String> {
+    let title = match format {
+        ReportFormat::Html => {
+            let lower = document.to_ascii_lowercase();
+            ["<title", "<h1"].iter().find_map(|tag| {
+                let start = lower.find(tag)?;
+                let start = start + lower[start..].find('>')? + 1;
+                let end = start + lower[start..].find('<')?;
+                Some(visible_text(&document[start..end], ReportFormat::Html))
+            })
+        }
+        ReportFormat::Markdown => document
+            .lines()
+            .find(|l| l.trim_start().starts_with('#'))
+            .map(|l| l.trim_start_matches(|c: char| c == '#' || c.is_whitespace()).to_string()),
+        ReportFormat::Text => document.lines().find(|l| !l.trim().is_empty()).map(str::to_string),
+    };
+    title
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty())
+}
+
+fn is_boundary(text: &[u8], index: usize) -> bool {
+    text.get(index).is_none_or(|b| !b.is_ascii_alphanumeric())
+}
+
+fn is_word_start(text: &[u8], index: usize) -> bool {
+    index == 0 || !text[index - 1].is_ascii_alphanumeric()
+}
+
+fn digits(text: &[u8], start: usize, count: usize) -> bool {
+    text.len() >= start + count && text[start..start + count].iter().all(u8::is_ascii_digit)
+}
+
+/// `T1234`, `T1234.001`, `S0001` and `G0001`, as `(start, end)`.
+fn attack_id_spans(text: &[u8]) -> Vec<(usize, usize)> {
+    let mut spans = vec![];
+    for start in 0..text.len() {
+        if !matches!(text[start], b'T' | b'S' | b'G') || !is_word_start(text, start) {
+            continue;
+        }
+        if !digits(text, start + 1, 4) {
+            continue;
+        }
+        let mut end = start + 5;
+        if text[start] == b'T'
+            && text.get(end) == Some(&b'.')
+            && digits(text, end + 1, 3)
+            && is_boundary(text, end + 4)
+        {
+            end += 4;
+        }
+        if is_boundary(text, end) {
+            spans.push((start, end));
+        }
+    }
+    spans
+}
+
+/// `CVE-YYYY-NNNN…`, any case, as `(start, end)`.
+fn cve_spans(text: &[u8]) -> Vec<(usize, usize)> {
+    let mut spans = vec![];
+    for start in 0..text.len().saturating_sub(12) {
+        if !is_word_start(text, start)
+            || !text[start..start + 4].eq_ignore_ascii_case(b"cve-")
+            || !digits(text, start + 4, 4)
+            || text[start + 8] != b'-'
+        {
+            continue;
+        }
+        let mut end = start + 9;
+        while end < text.len() && text[end].is_ascii_digit() {
+            end += 1;
+        }
+        if end - start >= 13 && is_boundary(text, end) {
+            spans.push((start, end));
+        }
+    }
+    spans
+}
+
+/// Extracts the entity mentions of one document.
+///
+/// # Arguments
+///
+/// - `dictionary`: The names and IDs to look for.
+/// - `report_id`: Node ID for the report.
+/// - `document`: The report as read, markup included.
+/// - `format`: How to strip the markup.
+/// - `options`: Confidence threshold and handling of unknown IDs.
+///
+/// # Returns
+///
+/// - `Report`: The mentions in document order, with offsets into `document`.
+pub fn extract(
+    dictionary: &EntityDictionary,
+    report_id: &str,
+    document: &str,
+    format: ReportFormat,
+    options: &ExtractionOptions,
+) -> Report {
+    let text = visible_text(document, format);
+    let bytes = text.as_bytes();
+    let mut mentions = vec![];
+    let mention = |id: String, kind, start: usize, end: usize, confidence| Mention {
+        entity_id: id,
+        kind,
+        text: document[start..end].to_string(),
+        start,
+        end,
+        confidence,
+    };
+
+    for (start, end) in attack_id_spans(bytes) {
+        let id = &text[start..end];
+        match dictionary.attack_ids.get(id) {
+            Some(&kind) => mentions.push(mention(id.to_string(), kind, start, end, ID_CONFIDENCE)),
+            None if options.unknown_attack_ids => {
+                let kind = match id.as_bytes()[0] {
+                    b'S' => EntityKind::Software,
+                    b'G' => EntityKind::Group,
+                    _ => EntityKind::Technique,
+                };
+                mentions.push(mention(id.to_string(), kind, start, end, UNKNOWN_ID_CONFIDENCE));
+            }
+            None => {}
+        }
+    }
+
+    for (start, end) in cve_spans(bytes) {
+        let id = text[start..end].to_ascii_uppercase();
+        mentions.push(mention(id, EntityKind::Vulnerability, start, end, ID_CONFIDENCE));
+    }
+
+    // Full VERIS paths, longest first where one is a prefix of another.
+    let lower = text.to_ascii_lowercase();
+    let mut paths: Vec<(&String, &String)> = dictionary.veris_paths.iter().collect();
+    paths.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(b.0)));
+    let mut sections: Vec<&str> = paths
+        .iter()
+        .filter_map(|(path, _)| path.split_once('.').map(|(section, _)| section))
+        .collect();
+    sections.sort_unstable();
+    sections.dedup();
+    for section in sections {
+        let prefix = format!("{}.", section);
+        for (start, _) in lower.match_indices(&prefix) {
+            if !is_word_start(bytes, start) {
+                continue;
+            }
+            let found = paths.iter().find(|(path, _)| {
+                lower[start..].starts_with(path.as_str()) && is_boundary(bytes, start + path.len())
+            });
+            if let Some((path, original)) = found {
+                let end = start + path.len();
+                mentions.push(mention(
+                    original.to_string(),
+                    EntityKind::Veris,
+                    start,
+                    end,
+                    ID_CONFIDENCE,
+                ));
+            }
+        }
+    }
+
+    // Names, longest first at each word.
+    let words: Vec<(usize, usize)> = {
+        let mut words = vec![];
+        let mut start = None;
+        for (i, c) in text.char_indices() {
+            match (c.is_alphanumeric(), start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    words.push((s, i));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            words.push((s, text.len()));
+        }
+        words
+    };
+    let explicit: Vec<(usize, usize)> = mentions.iter().map(|m| (m.start, m.end)).collect();
+    let mut i = 0;
+    while i < words.len() {
+        let mut matched = 0;
+        for length in (1..=dictionary.max_phrase_words.min(words.len() - i)).rev() {
+            let span = &words[i..i + length];
+            let gap_too_wide = span.windows(2).any(|w| {
+                let gap = &text[w[0].1..w[1].0];
+                gap.chars().filter(|c| !c.is_whitespace()).count() > MAX_WORD_GAP
+            });
+            if gap_too_wide {
+                continue;
+            }
+            let (start, end) = (span[0].0, span[length - 1].1);
+            if explicit.iter().any(|&(s, e)| s < end && start < e) {
+                continue;
+            }
+            let key = span
+                .iter()
+                .map(|&(s, e)| text[s..e].to_lowercase())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let Some(entries) = dictionary.phrases.get(&key) else {
+                continue;
+            };
+            let candidates: Vec<&Entry> = entries
+                .iter()
+                .filter(|e| e.exact.as_deref().is_none_or(|exact| exact == &text[start..end]))
+                .collect();
+            if candidates.is_empty() {
+                continue;
+            }
+            // A name shared by several entities of one kind is split between them.
+            let mut by_entity: BTreeMap<(EntityKind, &str), f32> = BTreeMap::new();
+            for entry in candidates {
+                let best = by_entity.entry((entry.kind, entry.id.as_str())).or_insert(0.0);
+                *best = best.max(entry.confidence);
+            }
+            for (&(kind, id), &confidence) in &by_entity {
+                let shared = by_entity.keys().filter(|(k, _)| *k == kind).count();
+                let confidence = confidence / shared as f32;
+                mentions.push(mention(id.to_string(), kind, start, end, confidence));
+            }
+            matched = length;
+            break;
+        }
+        i += matched.max(1);
+    }
+
+    mentions.retain(|m| m.confidence >= options.min_confidence);
+    mentions.sort_by(|a, b| (a.start, a.end, &a.entity_id).cmp(&(b.start, b.end, &b.entity_id)));
+
+    Report {
+        id: report_id.to_string(),
+        title: find_title(document, format),
+        source: None,
+        content_hash: sha256_hex(document),
+        mentions,
+    }
+}
+
+/// Reads a report file and extracts its mentions.
+///
+/// The report ID is `report:` followed by the file stem, and the format is
+/// guessed from the extension.
+pub fn extract_file<P: AsRef<Path>>(
+    dictionary: &EntityDictionary,
+    path: P,
+    options: &ExtractionOptions,
+) -> Result<Report> {
+    let path = path.as_ref();
+    let document = fs::read_to_string(path)?;
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| format!("report path has no file name: {}", path.display()))?;
+    let mut report = extract(
+        dictionary,
+        &format!("report:{}", stem),
+        &document,
+        ReportFormat::from_path(path),
+        options,
+    );
+    report.source = Some(path.display().to_string());
+    Ok(report)
+}
+
+/// Adds a report node with a `mentions` edge to every entity it mentions.
+///
+/// Entities missing from the graph are added. A report that is already in
+/// the graph has its old `mentions` edges replaced.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `node_indices`: Node IDs to graph indices; new nodes are added to it.
+/// - `report`: The extracted report.
+///
+/// # Returns
+///
+/// - `NodeIndex`: The report node. Its metadata holds the title, source,
+///   content hash and the mentions as JSON, with offsets and confidence. Each
+///   edge's strength is the entity's highest mention confidence.
+pub fn link_report(
+    graph: &mut MappingGraph,
+    node_indices: &mut HashMap<String, NodeIndex>,
+    report: &Report,
+) -> NodeIndex {
+    let report_node = add_node_if_not_exists(graph, node_indices, &report.id, NodeType::Report);
+
+    let mut old_edges: Vec<_> = graph.edges(report_node).map(|e| e.id()).collect();
+    old_edges.sort_unstable_by(|a, b| b.cmp(a));
+    for edge in old_edges {
+        graph.remove_edge(edge);
+    }
+
+    let metadata = &mut graph[report_node].metadata;
+    metadata.clear();
+    if let Some(title) = &report.title {
+        metadata.insert("title".to_string(), title.clone());
+    }
+    if let Some(source) = &report.source {
+        metadata.insert("source".to_string(), source.clone());
+    }
+    metadata.insert("content_hash".to_string(), report.content_hash.clone());
+    metadata.insert("mention_count".to_string(), report.mentions.len().to_string());
+    let mentions: Vec<Value> = report
+        .mentions
+        .iter()
+        .map(|m| {
+            json!({
+                "entity_id": m.entity_id,
+                "start": m.start,
+                "end": m.end,
+                "confidence": m.confidence,
+            })
+        })
+        .collect();
+    metadata.insert("mentions".to_string(), Value::Array(mentions).to_string());
+
+    for (id, entity) in report.entities() {
+        let node = add_node_if_not_exists(graph, node_indices, id, entity.kind.node_type());
+        graph.add_edge(
+            report_node,
+            node,
+            EdgeData {
+                mapping_type: MENTIONS.into(),
+                strength: entity.max_confidence,
+            },
+        );
+    }
+
+    report_node
+}
+
+/// Summarizes extracted reports for `export_to_json`.
+pub fn perform_report_extraction(reports: &[Report]) -> Value {
+    let mut by_kind: BTreeMap<EntityKind, usize> = BTreeMap::new();
+    for report in reports {
+        for entity in report.entities().values() {
+            *by_kind.entry(entity.kind).or_insert(0) += 1;
+        }
+    }
+    json!({
+        "report_count": reports.len(),
+        "mention_count": reports.iter().map(|r| r.mentions.len()).sum::<usize>(),
+        "entities_by_kind": by_kind,
+        "reports": reports
+            .iter()
+            .map(|r| json!({
+                "id": r.id,
+                "title": r.title,
+                "source": r.source,
+                "content_hash": r.content_hash,
+                "entities": r.entities(),
+                "mentions": r.mentions,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
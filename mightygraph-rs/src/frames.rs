@@ -0,0 +1,361 @@
+//! Polars views of the mappings and the mapping graph.
+//!
+//! [`GraphFrames`] exposes the mappings, the node table and the edge table as
+//! `DataFrame`s so they can be joined with other datasets, e.g.
+//!
+//! ```no_run
+//! use mighty_graph_rs::frames::GraphFrames;
+//! use mighty_graph_rs::utils::{create_graph, load_csv_data};
+//! use polars::prelude::*;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mappings = load_csv_data("data/veris-1.3.7_attack-12.1-enterprise.csv")?;
+//! let (graph, _) = create_graph(&mappings)?;
+//! let incidents = df!("technique_id" => ["T1110"], "incidents" => [12])?;
+//!
+//! let frames = GraphFrames::new(&mappings, &graph)?;
+//! let enriched = frames
+//!     .nodes
+//!     .lazy()
+//!     .join(incidents.lazy(), [col("id")], [col("technique_id")], JoinArgs::new(JoinType::Left))
+//!     .collect()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The tabular analyses (`perform_mapping_type_analysis` and friends) are
+//! `LazyFrame` queries over those tables; the `*_to_json` helpers turn their
+//! results into the analysis `serde_json::Value`s.
+
+use polars::prelude::*;
+use serde_json::{json, Map};
+
+use petgraph::graph::EdgeIndex;
+
+use crate::csr::GraphView;
+use crate::export::days_since_epoch;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::temporal::TimeBucket;
+use crate::utils::{calculate_strength, parse_mapping_date};
+
+/// The mappings, node and edge tables of a loaded graph.
+pub struct GraphFrames {
+    pub mappings: DataFrame,
+    pub nodes: DataFrame,
+    pub edges: DataFrame,
+}
+
+impl GraphFrames {
+    /// Builds all three tables.
+    ///
+    /// # Arguments
+    ///
+    /// - `mappings`: The mappings the graph was built from.
+    /// - `graph`: The mapping graph.
+    ///
+    /// # Returns
+    ///
+    /// - `PolarsResult<GraphFrames>`: The tables, or a Polars error.
+    pub fn new(mappings: &[Mapping], graph: &MappingGraph) -> PolarsResult<GraphFrames> {
+        Ok(GraphFrames {
+            mappings: mappings_frame(mappings)?,
+            nodes: nodes_frame(graph)?,
+            edges: edges_frame(graph)?,
+        })
+    }
+}
+
+/// Builds a frame with one row per mapping and one column per `Mapping` field.
+///
+/// `creation_date` and `last_update` are typed `Date` columns (null when the
+/// value cannot be parsed) and a `strength` column is added.
+pub fn mappings_frame(mappings: &[Mapping]) -> PolarsResult<DataFrame> {
+    let text = |name: &str, f: fn(&Mapping) -> &str| {
+        Column::new(name.into(), mappings.iter().map(f).collect::<Vec<_>>())
+    };
+    let date = |name: &str, f: fn(&Mapping) -> &str| {
+        Column::new(
+            name.into(),
+            mappings
+                .iter()
+                .map(|m| parse_mapping_date(f(m)).map(days_since_epoch))
+                .collect::<Vec<_>>(),
+        )
+        .cast(&DataType::Date)
+    };
+
+    DataFrame::new(vec![
+        text("mapping_framework", |m| m.mapping_framework.as_str()),
+        text("mapping_framework_version", |m| {
+            m.mapping_framework_version.as_str()
+        }),
+        text("capability_group", |m| m.capability_group.as_str()),
+        text("capability_id", |m| m.capability_id.as_str()),
+        text("capability_description", |m| {
+            m.capability_description.as_str()
+        }),
+        text("mapping_type", |m| m.mapping_type.as_str()),
+        text("attack_object_id", |m| m.attack_object_id.as_str()),
+        text("attack_object_name", |m| m.attack_object_name.as_str()),
+        text("attack_version", |m| m.attack_version.as_str()),
+        text("technology_domain", |m| m.technology_domain.as_str()),
+        text("references", |m| m.references.as_str()),
+        text("comments", |m| m.comments.as_str()),
+        text("organization", |m| m.organization.as_str()),
+        date("creation_date", |m| m.creation_date.as_str())?,
+        date("last_update", |m| m.last_update.as_str())?,
+        Column::new(
+            "strength".into(),
+            mappings.iter().map(calculate_strength).collect::<Vec<_>>(),
+        ),
+    ])
+}
+
+/// Builds a frame with one row per node: `id`, `node_type` and `degree`.
+///
+/// Works on any [`GraphView`]; [`nodes_frame`] adds the metadata column for
+/// a petgraph graph.
+pub fn node_table<G: GraphView>(graph: &G) -> PolarsResult<DataFrame> {
+    let nodes: Vec<_> = (0..graph.node_count()).map(NodeIndex::new).collect();
+
+    DataFrame::new(vec![
+        Column::new(
+            "id".into(),
+            nodes.iter().map(|&n| graph.node_id(n)).collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "node_type".into(),
+            nodes
+                .iter()
+                .map(|&n| format!("{:?}", graph.node_type(n)))
+                .collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "degree".into(),
+            nodes
+                .iter()
+                .map(|&n| graph.degree(n) as u32)
+                .collect::<Vec<_>>(),
+        ),
+    ])
+}
+
+/// Builds a frame with one row per node: `id`, `node_type`, `degree` and the
+/// node metadata serialized as a JSON string.
+pub fn nodes_frame(graph: &MappingGraph) -> PolarsResult<DataFrame> {
+    let mut frame = node_table(graph)?;
+    frame.with_column(Column::new(
+        "metadata".into(),
+        graph
+            .node_weights()
+            .map(|node| serde_json::to_string(&node.metadata).unwrap_or_default())
+            .collect::<Vec<_>>(),
+    ))?;
+    Ok(frame)
+}
+
+/// Builds a frame with one row per edge: `source`, `target` (node IDs),
+/// `mapping_type` and `strength`.
+pub fn edges_frame<G: GraphView>(graph: &G) -> PolarsResult<DataFrame> {
+    let edges: Vec<_> = (0..graph.edge_count())
+        .map(EdgeIndex::new)
+        .map(|e| {
+            let (s, t) = graph.edge_endpoints(e);
+            (e, s, t)
+        })
+        .collect();
+
+    DataFrame::new(vec![
+        Column::new(
+            "source".into(),
+            edges
+                .iter()
+                .map(|&(_, s, _)| graph.node_id(s))
+                .collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "target".into(),
+            edges
+                .iter()
+                .map(|&(_, _, t)| graph.node_id(t))
+                .collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "mapping_type".into(),
+            edges
+                .iter()
+                .map(|&(e, _, _)| graph.mapping_type(e))
+                .collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "strength".into(),
+            edges
+                .iter()
+                .map(|&(e, _, _)| graph.strength(e))
+                .collect::<Vec<_>>(),
+        ),
+    ])
+}
+
+/// Lazy form of `perform_mapping_type_analysis`: edge count per `mapping_type`.
+pub fn mapping_type_analysis_lazy(edges: LazyFrame) -> LazyFrame {
+    count_by(edges, "mapping_type")
+}
+
+/// Lazy form of `perform_tech_domain_analysis`: mapping count per `technology_domain`.
+pub fn tech_domain_analysis_lazy(mappings: LazyFrame) -> LazyFrame {
+    count_by(mappings, "technology_domain")
+}
+
+/// Lazy form of `perform_node_type_distribution`: node count per `node_type`.
+pub fn node_type_distribution_lazy(nodes: LazyFrame) -> LazyFrame {
+    count_by(nodes, "node_type")
+}
+
+/// Lazy form of `perform_node_degree_analysis`: `id` and `degree`, highest
+/// degree first.
+pub fn node_degree_analysis_lazy(nodes: LazyFrame) -> LazyFrame {
+    nodes
+        .select([col("id"), col("degree")])
+        .sort(["degree"], descending())
+}
+
+/// Lazy form of `perform_edge_strength_analysis`: `mapping_type` and
+/// `strength` of every edge, strongest first; ties keep edge order.
+pub fn edge_strength_analysis_lazy(edges: LazyFrame) -> LazyFrame {
+    edges
+        .select([col("mapping_type"), col("strength")])
+        .sort(["strength"], descending())
+}
+
+/// Lazy form of the summary part of `perform_temporal_analysis`: earliest
+/// and latest `creation_date` and `last_update`, and how many of each could
+/// not be parsed.
+pub fn temporal_analysis_lazy(mappings: LazyFrame) -> LazyFrame {
+    mappings.select([
+        col("creation_date").min().alias("min_date"),
+        col("creation_date").max().alias("max_date"),
+        col("last_update").min().alias("min_last_update"),
+        col("last_update").max().alias("max_last_update"),
+        col("creation_date").null_count().alias("unparsed_creation_dates"),
+        col("last_update").null_count().alias("unparsed_last_updates"),
+    ])
+}
+
+/// Lazy form of `temporal::date_histogram`: count of the parsed values of the
+/// date column `column` per `year` and `period` (month or quarter number),
+/// in chronological order.
+pub fn date_histogram_lazy(mappings: LazyFrame, column: &str, bucket: TimeBucket) -> LazyFrame {
+    let period = match bucket {
+        TimeBucket::Month => col(column).dt().month(),
+        TimeBucket::Quarter => col(column).dt().quarter(),
+    };
+    mappings
+        .filter(col(column).is_not_null())
+        .group_by([
+            col(column).dt().year().alias("year"),
+            period.alias("period"),
+        ])
+        .agg([len().alias("count")])
+        .sort(["year", "period"], Default::default())
+}
+
+/// Runs the temporal queries over a [`mappings_frame`] and assembles the
+/// same object as `perform_temporal_analysis`.
+pub fn temporal_analysis(mappings: &DataFrame) -> PolarsResult<Value> {
+    let mut result = row_to_json(&temporal_analysis_lazy(mappings.clone().lazy()).collect()?)?;
+    let histograms = [
+        ("creation_by_month", "creation_date", TimeBucket::Month),
+        ("creation_by_quarter", "creation_date", TimeBucket::Quarter),
+        ("update_by_month", "last_update", TimeBucket::Month),
+        ("update_by_quarter", "last_update", TimeBucket::Quarter),
+    ];
+    for (name, column, bucket) in histograms {
+        let counts = date_histogram_lazy(mappings.clone().lazy(), column, bucket).collect()?;
+        result[name] = histogram_to_json(&counts, bucket)?;
+    }
+    Ok(result)
+}
+
+fn descending() -> SortMultipleOptions {
+    SortMultipleOptions::default()
+        .with_order_descending(true)
+        .with_maintain_order(true)
+}
+
+fn count_by(frame: LazyFrame, key: &str) -> LazyFrame {
+    frame
+        .group_by([col(key)])
+        .agg([len().alias("count")])
+        .sort(["count"], descending())
+}
+
+/// Turns a two-column `key`/`count` frame into a `{ key: count }` object.
+pub fn counts_to_json(df: &DataFrame, key: &str) -> PolarsResult<Value> {
+    object_to_json(df, key, "count")
+}
+
+/// Turns the `key` and integer `value` columns of a frame into a
+/// `{ key: value }` object.
+pub fn object_to_json(df: &DataFrame, key: &str, value: &str) -> PolarsResult<Value> {
+    let keys = df.column(key)?.cast(&DataType::String)?;
+    let values = df.column(value)?.cast(&DataType::UInt64)?;
+    let mut object = Map::new();
+    for (k, v) in keys.str()?.iter().zip(values.u64()?.iter()) {
+        object.insert(k.unwrap_or("null").to_string(), json!(v.unwrap_or(0)));
+    }
+    Ok(Value::Object(object))
+}
+
+/// Turns the rows of a two-column frame into `[first, second]` pairs, in row
+/// order.
+pub fn pairs_to_json(df: &DataFrame) -> PolarsResult<Value> {
+    let [first, second] = df.get_columns() else {
+        polars_bail!(ShapeMismatch: "expected two columns, got {}", df.width());
+    };
+    let rows = (0..df.height())
+        .map(|i| Ok(json!([any_to_json(first.get(i)?), any_to_json(second.get(i)?)])))
+        .collect::<PolarsResult<Vec<_>>>()?;
+    Ok(Value::Array(rows))
+}
+
+/// Turns a [`date_histogram_lazy`] result into a `{ bucket label: count }`
+/// object, with the labels of [`TimeBucket::label`].
+pub fn histogram_to_json(df: &DataFrame, bucket: TimeBucket) -> PolarsResult<Value> {
+    let years = df.column("year")?.cast(&DataType::Int32)?;
+    let periods = df.column("period")?.cast(&DataType::UInt32)?;
+    let counts = df.column("count")?.cast(&DataType::UInt64)?;
+    let mut object = Map::new();
+    let rows = years.i32()?.iter().zip(periods.u32()?.iter()).zip(counts.u64()?.iter());
+    for ((year, period), count) in rows {
+        let (Some(year), Some(period)) = (year, period) else {
+            continue;
+        };
+        let label = match bucket {
+            TimeBucket::Month => format!("{}-{:02}", year, period),
+            TimeBucket::Quarter => format!("{}-Q{}", year, period),
+        };
+        object.insert(label, json!(count.unwrap_or(0)));
+    }
+    Ok(Value::Object(object))
+}
+
+/// Turns a single-row frame into a `{ column: value }` object. Numbers stay
+/// numbers; other values use their display form (dates become `YYYY-MM-DD`).
+pub fn row_to_json(df: &DataFrame) -> PolarsResult<Value> {
+    let mut object = Map::new();
+    for series in df.get_columns() {
+        object.insert(series.name().to_string(), any_to_json(series.get(0)?));
+    }
+    Ok(Value::Object(object))
+}
+
+fn any_to_json(value: AnyValue) -> Value {
+    match value {
+        AnyValue::Null => Value::Null,
+        AnyValue::Float32(v) => json!(v),
+        AnyValue::Float64(v) => json!(v),
+        other if other.is_integer() => json!(other.extract::<i64>()),
+        other => json!(other.str_value()),
+    }
+}
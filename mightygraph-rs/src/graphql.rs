@@ -0,0 +1,279 @@
+//! GraphQL endpoint over the mapping graph (`graphql` feature).
+//!
+//! Served at `POST /graphql` (GraphiQL at `GET /graphql`) next to the REST
+//! routes. Nodes and edges are exposed with the same unified types whatever
+//! framework they come from, so traversals nest freely:
+//!
+//! ```graphql
+//! {
+//!   node(id: "T1003") {
+//!     id
+//!     neighbors(filter: { nodeType: VERIS }, edgeFilter: { minStrength: 0.5 }) {
+//!       id
+//!       neighbors { id nodeType }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Every request runs against one snapshot of the hot-reloaded state. Query
+//! depth and complexity are capped; list fields count as their `limit` times
+//! the cost of their children.
+
+use std::sync::Arc;
+
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object, Schema,
+    SimpleObject, ID,
+};
+use axum::extract::State;
+use axum::response::{Html, IntoResponse};
+use axum::{Extension, Json};
+
+use petgraph::graph::EdgeIndex;
+
+use crate::csr::GraphView;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::server::{AppState, GraphState};
+
+pub const MAX_QUERY_DEPTH: usize = 8;
+pub const MAX_QUERY_COMPLEXITY: usize = 10_000;
+pub const DEFAULT_LIST_LIMIT: usize = 50;
+pub const MAX_LIST_LIMIT: usize = 500;
+
+pub type MappingSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(remote = "NodeType")]
+pub enum GqlNodeType {
+    Veris,
+    Mitre,
+    Report,
+    Software,
+    Group,
+    Vulnerability,
+}
+
+/// Node filter; all set fields must match.
+#[derive(InputObject, Default)]
+pub struct NodeFilter {
+    pub node_type: Option<GqlNodeType>,
+    pub id_prefix: Option<String>,
+    /// Node has at least one mapping in this `technology_domain`.
+    pub technology_domain: Option<String>,
+}
+
+/// Edge filter; all set fields must match.
+#[derive(InputObject, Default)]
+pub struct EdgeFilter {
+    pub mapping_type: Option<String>,
+    pub min_strength: Option<f32>,
+    pub max_strength: Option<f32>,
+    pub technology_domain: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct MetadataEntry {
+    pub key: String,
+    pub value: String,
+}
+
+fn clamp_limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT)
+}
+
+fn state<'a>(ctx: &Context<'a>) -> &'a Arc<GraphState> {
+    ctx.data_unchecked::<Arc<GraphState>>()
+}
+
+fn edge_domains(state: &GraphState, edge: EdgeIndex) -> &[String] {
+    state.edge_domains.get(&edge).map(Vec::as_slice).unwrap_or(&[])
+}
+
+impl NodeFilter {
+    fn matches(&self, state: &GraphState, node: NodeIndex) -> bool {
+        let data = &state.graph[node];
+        self.node_type.is_none_or(|t| NodeType::from(t) == data.node_type)
+            && self.id_prefix.as_deref().is_none_or(|p| data.id.starts_with(p))
+            && self.technology_domain.as_ref().is_none_or(|domain| {
+                state.graph.incident_edges(node)
+                    .any(|(e, _)| edge_domains(state, e).contains(domain))
+            })
+    }
+}
+
+impl EdgeFilter {
+    fn matches(&self, state: &GraphState, edge: EdgeIndex) -> bool {
+        let data = &state.graph[edge];
+        self.mapping_type.as_ref().is_none_or(|t| *data.mapping_type == **t)
+            && self.min_strength.is_none_or(|s| data.strength >= s)
+            && self.max_strength.is_none_or(|s| data.strength <= s)
+            && self
+                .technology_domain
+                .as_ref()
+                .is_none_or(|domain| edge_domains(state, edge).contains(domain))
+    }
+}
+
+/// A node of any framework.
+pub struct Node(NodeIndex);
+
+#[Object]
+impl Node {
+    async fn id(&self, ctx: &Context<'_>) -> ID {
+        ID(state(ctx).graph[self.0].id.clone())
+    }
+
+    async fn node_type(&self, ctx: &Context<'_>) -> GqlNodeType {
+        state(ctx).graph[self.0].node_type.into()
+    }
+
+    async fn degree(&self, ctx: &Context<'_>) -> usize {
+        state(ctx).graph.neighbors_undirected(self.0).count()
+    }
+
+    async fn metadata(&self, ctx: &Context<'_>) -> Vec<MetadataEntry> {
+        let mut entries: Vec<_> = state(ctx).graph[self.0]
+            .metadata
+            .iter()
+            .map(|(key, value)| MetadataEntry {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        entries
+    }
+
+    /// Adjacent nodes, following edges in both directions.
+    #[graphql(complexity = "clamp_limit(limit) * child_complexity")]
+    async fn neighbors(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<NodeFilter>,
+        edge_filter: Option<EdgeFilter>,
+        limit: Option<usize>,
+    ) -> Vec<Node> {
+        let state = state(ctx);
+        let filter = filter.unwrap_or_default();
+        let edge_filter = edge_filter.unwrap_or_default();
+        state.graph.incident_edges(self.0)
+            .filter(|&(e, n)| edge_filter.matches(state, e) && filter.matches(state, n))
+            .map(|(_, n)| Node(n))
+            .take(clamp_limit(limit))
+            .collect()
+    }
+
+    /// Incident edges in both directions.
+    #[graphql(complexity = "clamp_limit(limit) * child_complexity")]
+    async fn edges(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<EdgeFilter>,
+        limit: Option<usize>,
+    ) -> Vec<Edge> {
+        let state = state(ctx);
+        let filter = filter.unwrap_or_default();
+        state.graph.incident_edges(self.0)
+            .filter(|&(e, _)| filter.matches(state, e))
+            .map(|(e, _)| Edge(e))
+            .take(clamp_limit(limit))
+            .collect()
+    }
+}
+
+/// A mapping between two nodes.
+pub struct Edge(EdgeIndex);
+
+#[Object]
+impl Edge {
+    async fn mapping_type(&self, ctx: &Context<'_>) -> String {
+        state(ctx).graph[self.0].mapping_type.to_string()
+    }
+
+    async fn strength(&self, ctx: &Context<'_>) -> f32 {
+        state(ctx).graph[self.0].strength
+    }
+
+    async fn technology_domains(&self, ctx: &Context<'_>) -> Vec<String> {
+        edge_domains(state(ctx), self.0).to_vec()
+    }
+
+    async fn source(&self, ctx: &Context<'_>) -> Node {
+        Node(state(ctx).graph.edge_endpoints(self.0).unwrap().0)
+    }
+
+    async fn target(&self, ctx: &Context<'_>) -> Node {
+        Node(state(ctx).graph.edge_endpoints(self.0).unwrap().1)
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn node(&self, ctx: &Context<'_>, id: ID) -> Option<Node> {
+        state(ctx).node_indices.get(id.as_str()).map(|&n| Node(n))
+    }
+
+    #[graphql(complexity = "clamp_limit(limit) * child_complexity")]
+    async fn nodes(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<NodeFilter>,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> Vec<Node> {
+        let state = state(ctx);
+        let filter = filter.unwrap_or_default();
+        state
+            .graph
+            .node_indices()
+            .filter(|&n| filter.matches(state, n))
+            .skip(offset.unwrap_or(0))
+            .take(clamp_limit(limit))
+            .map(Node)
+            .collect()
+    }
+
+    #[graphql(complexity = "clamp_limit(limit) * child_complexity")]
+    async fn edges(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<EdgeFilter>,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> Vec<Edge> {
+        let state = state(ctx);
+        let filter = filter.unwrap_or_default();
+        state
+            .graph
+            .edge_indices()
+            .filter(|&e| filter.matches(state, e))
+            .skip(offset.unwrap_or(0))
+            .take(clamp_limit(limit))
+            .map(Edge)
+            .collect()
+    }
+}
+
+/// Builds the schema with the depth and complexity limits applied.
+pub fn schema() -> MappingSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+}
+
+pub async fn graphql_handler(
+    State(app): State<AppState>,
+    Extension(schema): Extension<MappingSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request.data(app.snapshot())).await)
+}
+
+pub async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
@@ -0,0 +1,729 @@
+//! Incremental updates of the mapping graph.
+//!
+//! [`IncrementalGraph`] owns the graph, the mapping rows and the ID lookups,
+//! and changes them in place instead of rebuilding with
+//! [`create_graph`](crate::utils::create_graph). Nodes are keyed by their ID,
+//! mappings by [`mapping_key`] and other edges by a key of the caller's
+//! choosing (the STIX ID for feed relationships). petgraph fills the slot of
+//! a removed node or edge with the last one, so the lookups are fixed up on
+//! every removal and the keys stay valid.
+//!
+//! Every change is appended to a change log. Only the analyses a change
+//! affects are marked stale: the degree of each touched node, the connected
+//! components on any structural change, and coverage when a VERIS–ATT&CK
+//! edge comes or goes. [`IncrementalGraph::refresh`] recomputes just those.
+//! [`IncrementalGraph::apply_stix_bundle`] applies an OpenCTI or ATT&CK
+//! STIX 2.1 bundle as a delta.
+
+use chrono::{DateTime, Utc};
+use petgraph::graph::EdgeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::catalog::{relationship_strength, stix_node, AttackCatalog, VerisCatalog};
+use crate::coverage::{perform_coverage_analysis, CoverageOptions, CoverageReport};
+use crate::petgraph_full_0x0::prelude::*;
+use crate::utils::calculate_strength;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Prefix of the edge keys owned by mapping rows.
+const MAPPING_EDGE_PREFIX: &str = "mapping:";
+
+/// Stable key of a mapping row: its VERIS and ATT&CK IDs.
+pub fn mapping_key(mapping: &Mapping) -> String {
+    format!("{}|{}", mapping.capability_id, mapping.attack_object_id)
+}
+
+fn mapping_edge_key(key: &str) -> String {
+    format!("{}{}", MAPPING_EDGE_PREFIX, key)
+}
+
+/// One entry of the change log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    NodeAdded { id: String },
+    NodeUpdated { id: String },
+    NodeRemoved { id: String },
+    MappingAdded { key: String },
+    MappingUpdated { key: String },
+    MappingRemoved { key: String },
+    EdgeAdded { key: String },
+    EdgeUpdated { key: String },
+    EdgeRemoved { key: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeRecord {
+    /// Increases by one per change, starting at 1.
+    pub sequence: u64,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+/// What [`IncrementalGraph::refresh`] recomputed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Refresh {
+    /// Nodes whose degree was recomputed.
+    pub degrees: usize,
+    pub components: bool,
+    pub coverage: bool,
+}
+
+/// What [`IncrementalGraph::apply_stix_bundle`] did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StixDelta {
+    /// Objects added or updated as nodes.
+    pub nodes: usize,
+    /// Relationships added or updated as edges.
+    pub edges: usize,
+    /// Revoked objects and relationships removed.
+    pub removed: usize,
+    /// Objects not newer than what the graph already has.
+    pub unchanged: usize,
+    /// Objects of other types, without an ID, or with unknown endpoints.
+    pub skipped: usize,
+}
+
+#[derive(Debug, Default)]
+struct Stale {
+    degrees: HashSet<String>,
+    components: bool,
+    coverage: bool,
+}
+
+#[derive(Debug)]
+struct CoverageInputs {
+    attack: AttackCatalog,
+    veris: VerisCatalog,
+    options: CoverageOptions,
+    report: CoverageReport,
+}
+
+/// A mapping graph with keyed, in-place updates.
+#[derive(Debug, Default)]
+pub struct IncrementalGraph {
+    graph: MappingGraph,
+    node_indices: HashMap<String, NodeIndex>,
+    /// Nodes added with `upsert_node`; other nodes go with their last edge.
+    pinned: HashSet<String>,
+    mappings: Vec<Mapping>,
+    /// Key of `mappings[i]`.
+    mapping_keys: Vec<String>,
+    mapping_positions: HashMap<String, usize>,
+    /// Key of the edge with index `i`.
+    edge_keys: Vec<String>,
+    edge_indices: HashMap<String, EdgeIndex>,
+    /// STIX ID to node ID, for resolving relationships.
+    stix_ids: HashMap<String, String>,
+    log: Vec<ChangeRecord>,
+    stale: Stale,
+    degrees: HashMap<String, usize>,
+    components: usize,
+    coverage: Option<CoverageInputs>,
+}
+
+impl IncrementalGraph {
+    pub fn new() -> IncrementalGraph {
+        IncrementalGraph::default()
+    }
+
+    /// Builds the graph from mapping rows, like `create_graph`.
+    ///
+    /// Repeated rows for the same VERIS and ATT&CK pair are keyed
+    /// `{key}#2`, `{key}#3` and so on. The change log starts empty and the
+    /// analyses are computed.
+    ///
+    /// # Arguments
+    ///
+    /// - `mappings`: The mapping rows.
+    ///
+    /// # Returns
+    ///
+    /// - `IncrementalGraph`: The graph, with fresh analyses.
+    pub fn from_mappings(mappings: Vec<Mapping>) -> IncrementalGraph {
+        let mut graph = IncrementalGraph::new();
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for mapping in mappings {
+            let key = mapping_key(&mapping);
+            let count = seen.entry(key.clone()).or_default();
+            *count += 1;
+            let key = if *count == 1 { key } else { format!("{}#{}", key, count) };
+            graph.upsert_mapping(&key, mapping);
+        }
+        graph.log.clear();
+        graph.refresh();
+        graph
+    }
+
+    /// Keeps a coverage report up to date against the given catalogs.
+    pub fn with_coverage(
+        mut self,
+        attack: AttackCatalog,
+        veris: VerisCatalog,
+        options: CoverageOptions,
+    ) -> IncrementalGraph {
+        self.coverage = Some(CoverageInputs {
+            attack,
+            veris,
+            options,
+            report: CoverageReport::default(),
+        });
+        self.stale.coverage = true;
+        self.refresh();
+        self
+    }
+
+    pub fn graph(&self) -> &MappingGraph {
+        &self.graph
+    }
+
+    pub fn node_indices(&self) -> &HashMap<String, NodeIndex> {
+        &self.node_indices
+    }
+
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    pub fn mapping(&self, key: &str) -> Option<&Mapping> {
+        self.mapping_positions.get(key).map(|&i| &self.mappings[i])
+    }
+
+    /// Mapping rows with their keys, in storage order.
+    pub fn keyed_mappings(&self) -> impl Iterator<Item = (&str, &Mapping)> {
+        self.mapping_keys.iter().map(String::as_str).zip(&self.mappings)
+    }
+
+    /// The edge of a mapping row.
+    pub fn mapping_edge(&self, key: &str) -> Option<EdgeIndex> {
+        self.edge(&mapping_edge_key(key))
+    }
+
+    pub fn edge(&self, key: &str) -> Option<EdgeIndex> {
+        self.edge_indices.get(key).copied()
+    }
+
+    /// The whole change log, oldest first.
+    pub fn changes(&self) -> &[ChangeRecord] {
+        &self.log
+    }
+
+    /// Changes after `sequence`, for consumers that poll.
+    pub fn changes_since(&self, sequence: u64) -> &[ChangeRecord] {
+        let start = self.log.partition_point(|r| r.sequence <= sequence);
+        &self.log[start..]
+    }
+
+    /// Whether some analysis needs a [`refresh`](IncrementalGraph::refresh).
+    pub fn is_stale(&self) -> bool {
+        !self.stale.degrees.is_empty() || self.stale.components || self.stale.coverage
+    }
+
+    /// Undirected degree per node ID, as of the last refresh.
+    pub fn node_degrees(&self) -> &HashMap<String, usize> {
+        &self.degrees
+    }
+
+    /// Number of connected components, as of the last refresh.
+    pub fn components(&self) -> usize {
+        self.components
+    }
+
+    /// The coverage report, when built [`with_coverage`](IncrementalGraph::with_coverage).
+    pub fn coverage(&self) -> Option<&CoverageReport> {
+        self.coverage.as_ref().map(|c| &c.report)
+    }
+
+    /// Same shape as `perform_node_degree_analysis`.
+    pub fn node_degree_analysis(&self) -> Value {
+        json!(self.degrees)
+    }
+
+    /// Same shape as `perform_connected_components_analysis`.
+    pub fn connected_components_analysis(&self) -> Value {
+        json!({ "number_of_components": self.components })
+    }
+
+    /// Recomputes the stale analyses.
+    ///
+    /// # Returns
+    ///
+    /// - `Refresh`: Which analyses were recomputed.
+    pub fn refresh(&mut self) -> Refresh {
+        let stale = std::mem::take(&mut self.stale);
+        let mut refresh = Refresh {
+            degrees: stale.degrees.len(),
+            ..Refresh::default()
+        };
+
+        for id in stale.degrees {
+            match self.node_indices.get(&id) {
+                Some(&node) => {
+                    let degree = self.graph.neighbors_undirected(node).count();
+                    self.degrees.insert(id, degree);
+                }
+                None => {
+                    self.degrees.remove(&id);
+                }
+            }
+        }
+        if stale.components {
+            self.components = connected_components(&self.graph);
+            refresh.components = true;
+        }
+        if let Some(coverage) = self.coverage.as_mut().filter(|_| stale.coverage) {
+            coverage.report = perform_coverage_analysis(
+                &self.graph,
+                &self.mappings,
+                &coverage.attack,
+                &coverage.veris,
+                &coverage.options,
+            );
+            refresh.coverage = true;
+        }
+        refresh
+    }
+
+    /// Adds a node or updates its type and metadata.
+    ///
+    /// Nodes added this way stay in the graph without edges, until
+    /// [`remove_node`](IncrementalGraph::remove_node).
+    ///
+    /// # Arguments
+    ///
+    /// - `id`: The node ID.
+    /// - `node_type`: The node type.
+    /// - `metadata`: Replaces the node's metadata.
+    ///
+    /// # Returns
+    ///
+    /// - `NodeIndex`: The node's current index.
+    pub fn upsert_node(
+        &mut self,
+        id: &str,
+        node_type: NodeType,
+        metadata: HashMap<String, String>,
+    ) -> NodeIndex {
+        self.pinned.insert(id.to_string());
+        let Some(&node) = self.node_indices.get(id) else {
+            let node = self.add_node(id, node_type);
+            self.graph[node].metadata = metadata;
+            return node;
+        };
+
+        let data = &mut self.graph[node];
+        if data.node_type == node_type && data.metadata == metadata {
+            return node;
+        }
+        if data.node_type != node_type {
+            // VERIS–ATT&CK pairs are counted by node type.
+            self.stale.coverage = true;
+        }
+        data.node_type = node_type;
+        data.metadata = metadata;
+        self.record(Change::NodeUpdated { id: id.to_string() });
+        node
+    }
+
+    /// Removes a node with its mappings and edges.
+    ///
+    /// # Returns
+    ///
+    /// - `bool`: Whether the node existed.
+    pub fn remove_node(&mut self, id: &str) -> bool {
+        if !self.node_indices.contains_key(id) {
+            return false;
+        }
+        self.pinned.remove(id);
+
+        let mapping_keys: Vec<String> = self
+            .mapping_keys
+            .iter()
+            .zip(&self.mappings)
+            .filter(|(_, m)| m.capability_id == id || m.attack_object_id == id)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in mapping_keys {
+            self.remove_mapping(&key);
+        }
+
+        if let Some(&node) = self.node_indices.get(id) {
+            let edge_keys: Vec<String> = self
+                .graph
+                .edges_directed(node, Direction::Outgoing)
+                .chain(self.graph.edges_directed(node, Direction::Incoming))
+                .map(|e| self.edge_keys[e.id().index()].clone())
+                .collect();
+            for key in edge_keys {
+                self.remove_edge(&key);
+            }
+        }
+        // An unpinned node went with its last edge; a pinned one may be left.
+        if self.node_indices.contains_key(id) {
+            self.drop_isolated(id);
+        }
+        true
+    }
+
+    /// Adds a mapping row or replaces the row with the same key.
+    ///
+    /// Missing VERIS and ATT&CK nodes are created, and dropped again when
+    /// their last edge goes.
+    ///
+    /// # Arguments
+    ///
+    /// - `key`: Stable key of the row, usually [`mapping_key`].
+    /// - `mapping`: The row.
+    ///
+    /// # Returns
+    ///
+    /// - `bool`: Whether the row is new.
+    pub fn upsert_mapping(&mut self, key: &str, mapping: Mapping) -> bool {
+        let edge_key = mapping_edge_key(key);
+        let data = EdgeData {
+            mapping_type: mapping.mapping_type.as_str().into(),
+            strength: calculate_strength(&mapping),
+        };
+
+        let Some(&position) = self.mapping_positions.get(key) else {
+            self.attach_mapping(edge_key, &mapping, data);
+            self.mapping_positions.insert(key.to_string(), self.mappings.len());
+            self.mapping_keys.push(key.to_string());
+            self.mappings.push(mapping);
+            self.record(Change::MappingAdded { key: key.to_string() });
+            return true;
+        };
+
+        let old = &self.mappings[position];
+        let edge = self.edge_indices[&edge_key];
+        if old.capability_id == mapping.capability_id
+            && old.attack_object_id == mapping.attack_object_id
+        {
+            // Same endpoints: only the edge weight changes.
+            self.graph[edge] = data;
+            if old.technology_domain != mapping.technology_domain {
+                // Coverage is broken down by the rows' domains.
+                self.stale.coverage = true;
+            }
+        } else {
+            // Attach first, so that a shared endpoint is not pruned in between.
+            self.attach_mapping(edge_key, &mapping, data);
+            self.remove_edge_at(edge);
+        }
+        self.mappings[position] = mapping;
+        self.record(Change::MappingUpdated { key: key.to_string() });
+        false
+    }
+
+    /// Removes a mapping row and its edge.
+    ///
+    /// # Returns
+    ///
+    /// - `Option<Mapping>`: The removed row, if the key existed.
+    pub fn remove_mapping(&mut self, key: &str) -> Option<Mapping> {
+        let position = self.mapping_positions.remove(key)?;
+        self.mapping_keys.swap_remove(position);
+        let mapping = self.mappings.swap_remove(position);
+        if let Some(moved) = self.mapping_keys.get(position) {
+            self.mapping_positions.insert(moved.clone(), position);
+        }
+        self.record(Change::MappingRemoved { key: key.to_string() });
+        self.take_edge(&mapping_edge_key(key));
+        Some(mapping)
+    }
+
+    /// Adds an edge between existing nodes, or replaces the edge with the
+    /// same key.
+    ///
+    /// # Arguments
+    ///
+    /// - `key`: Stable key of the edge; keys of mapping edges are reserved.
+    /// - `source`, `target`: Node IDs.
+    /// - `data`: The edge weight.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<bool>`: Whether the edge is new, or an error for an unknown
+    ///   node or a reserved key.
+    pub fn upsert_edge(
+        &mut self,
+        key: &str,
+        source: &str,
+        target: &str,
+        data: EdgeData,
+    ) -> Result<bool> {
+        if key.starts_with(MAPPING_EDGE_PREFIX) {
+            return Err(format!("edge key {} is reserved for mappings", key).into());
+        }
+        let endpoint = |id: &str| {
+            self.node_indices
+                .get(id)
+                .copied()
+                .ok_or_else(|| format!("unknown node {}", id))
+        };
+        let (source, target) = (endpoint(source)?, endpoint(target)?);
+
+        let Some(&edge) = self.edge_indices.get(key) else {
+            self.add_edge(key.to_string(), source, target, data);
+            self.record(Change::EdgeAdded { key: key.to_string() });
+            return Ok(true);
+        };
+        if self.graph.edge_endpoints(edge) == Some((source, target)) {
+            let old = &self.graph[edge];
+            if old.mapping_type == data.mapping_type && old.strength == data.strength {
+                return Ok(false);
+            }
+            self.graph[edge] = data;
+        } else {
+            self.add_edge(key.to_string(), source, target, data);
+            self.remove_edge_at(edge);
+        }
+        self.record(Change::EdgeUpdated { key: key.to_string() });
+        Ok(false)
+    }
+
+    /// Removes an edge that is not owned by a mapping row.
+    ///
+    /// # Returns
+    ///
+    /// - `bool`: Whether the edge existed.
+    pub fn remove_edge(&mut self, key: &str) -> bool {
+        if key.starts_with(MAPPING_EDGE_PREFIX) || !self.edge_indices.contains_key(key) {
+            return false;
+        }
+        self.record(Change::EdgeRemoved { key: key.to_string() });
+        self.take_edge(key);
+        true
+    }
+
+    /// Applies a STIX 2.1 bundle, e.g. from an OpenCTI feed, as a delta.
+    ///
+    /// Attack patterns, malware and tools, intrusion sets and vulnerabilities
+    /// become ATT&CK, software, group and vulnerability nodes keyed by their
+    /// ATT&CK ID (the name for vulnerabilities); relationships between them
+    /// become edges keyed by their STIX ID. Objects whose `modified` is not
+    /// newer than the applied version are left alone. Revoked objects are
+    /// removed, except nodes still referenced by a mapping, which are marked
+    /// `revoked` instead.
+    ///
+    /// # Arguments
+    ///
+    /// - `bundle`: The parsed bundle.
+    ///
+    /// # Returns
+    ///
+    /// - `StixDelta`: Counts of what was applied.
+    pub fn apply_stix_bundle(&mut self, bundle: &Value) -> StixDelta {
+        let mut delta = StixDelta::default();
+        let objects = bundle["objects"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+        let (relationships, objects): (Vec<&Value>, Vec<&Value>) = objects
+            .iter()
+            .partition(|o| o["type"].as_str() == Some("relationship"));
+
+        // Objects first, so that relationships can resolve both ends.
+        for object in objects {
+            self.apply_stix_object(object, &mut delta);
+        }
+        for relationship in relationships {
+            self.apply_stix_relationship(relationship, &mut delta);
+        }
+        delta
+    }
+
+    fn apply_stix_object(&mut self, object: &Value, delta: &mut StixDelta) {
+        let (Some((id, node_type)), Some(stix_id)) = (stix_node(object), object["id"].as_str())
+        else {
+            delta.skipped += 1;
+            return;
+        };
+
+        let existing = self.node_indices.get(&id).map(|&n| &self.graph[n].metadata);
+        if !is_newer(object, existing) {
+            delta.unchanged += 1;
+            return;
+        }
+        let mut metadata = existing.cloned().unwrap_or_default();
+        metadata.insert("stix_id".to_string(), stix_id.to_string());
+        if let Some(name) = object["name"].as_str() {
+            metadata.insert("name".to_string(), name.to_string());
+        }
+        if let Some(modified) = object["modified"].as_str() {
+            metadata.insert("stix_modified".to_string(), modified.to_string());
+        }
+
+        if object["revoked"].as_bool().unwrap_or(false) {
+            if self.is_mapped(&id) {
+                metadata.insert("revoked".to_string(), "true".to_string());
+                self.upsert_node(&id, node_type, metadata);
+            } else {
+                self.stix_ids.remove(stix_id);
+                if self.remove_node(&id) {
+                    delta.removed += 1;
+                }
+            }
+            return;
+        }
+        self.upsert_node(&id, node_type, metadata);
+        self.stix_ids.insert(stix_id.to_string(), id);
+        delta.nodes += 1;
+    }
+
+    fn apply_stix_relationship(&mut self, relationship: &Value, delta: &mut StixDelta) {
+        let Some(key) = relationship["id"].as_str() else {
+            delta.skipped += 1;
+            return;
+        };
+        if relationship["revoked"].as_bool().unwrap_or(false) {
+            if self.remove_edge(key) {
+                delta.removed += 1;
+            }
+            return;
+        }
+        let endpoint = |field: &str| {
+            relationship[field]
+                .as_str()
+                .and_then(|stix_id| self.stix_ids.get(stix_id))
+                .cloned()
+        };
+        let (Some(source), Some(target)) = (endpoint("source_ref"), endpoint("target_ref")) else {
+            delta.skipped += 1;
+            return;
+        };
+        let data = EdgeData {
+            mapping_type: relationship["relationship_type"]
+                .as_str()
+                .unwrap_or("related-to")
+                .into(),
+            strength: relationship_strength(relationship),
+        };
+        match self.upsert_edge(key, &source, &target, data) {
+            Ok(_) => delta.edges += 1,
+            Err(_) => delta.skipped += 1,
+        }
+    }
+
+    fn is_mapped(&self, id: &str) -> bool {
+        self.mappings
+            .iter()
+            .any(|m| m.capability_id == id || m.attack_object_id == id)
+    }
+
+    fn record(&mut self, change: Change) {
+        let sequence = self.log.last().map_or(1, |r| r.sequence + 1);
+        self.log.push(ChangeRecord {
+            sequence,
+            at: Utc::now(),
+            change,
+        });
+    }
+
+    fn add_node(&mut self, id: &str, node_type: NodeType) -> NodeIndex {
+        let node = self.graph.add_node(NodeData {
+            id: id.to_string(),
+            node_type,
+            metadata: HashMap::new(),
+        });
+        self.node_indices.insert(id.to_string(), node);
+        self.stale.degrees.insert(id.to_string());
+        self.stale.components = true;
+        self.record(Change::NodeAdded { id: id.to_string() });
+        node
+    }
+
+    fn ensure_node(&mut self, id: &str, node_type: NodeType) -> NodeIndex {
+        match self.node_indices.get(id) {
+            Some(&node) => node,
+            None => self.add_node(id, node_type),
+        }
+    }
+
+    fn attach_mapping(&mut self, edge_key: String, mapping: &Mapping, data: EdgeData) {
+        let veris = self.ensure_node(&mapping.capability_id, NodeType::Veris);
+        let mitre = self.ensure_node(&mapping.attack_object_id, NodeType::Mitre);
+        self.add_edge(edge_key, veris, mitre, data);
+    }
+
+    fn add_edge(&mut self, key: String, source: NodeIndex, target: NodeIndex, data: EdgeData) {
+        let edge = self.graph.add_edge(source, target, data);
+        self.edge_keys.push(key.clone());
+        self.edge_indices.insert(key, edge);
+        self.touch(source, target);
+    }
+
+    fn take_edge(&mut self, key: &str) -> Option<EdgeData> {
+        let edge = self.edge_indices.remove(key)?;
+        self.remove_edge_at(edge)
+    }
+
+    /// Removes an edge and prunes endpoints left without edges.
+    ///
+    /// The key lookup of the removed edge is left alone, since a replacement
+    /// may already be registered under the same key.
+    fn remove_edge_at(&mut self, edge: EdgeIndex) -> Option<EdgeData> {
+        let (source, target) = self.graph.edge_endpoints(edge)?;
+        self.touch(source, target);
+        let ends = [self.graph[source].id.clone(), self.graph[target].id.clone()];
+
+        let data = self.graph.remove_edge(edge);
+        self.edge_keys.swap_remove(edge.index());
+        if let Some(moved) = self.edge_keys.get(edge.index()) {
+            self.edge_indices.insert(moved.clone(), edge);
+        }
+
+        for id in ends {
+            let orphan = !self.pinned.contains(&id)
+                && self.node_indices.get(&id).is_some_and(|&n| {
+                    self.graph.neighbors_undirected(n).next().is_none()
+                });
+            if orphan {
+                self.drop_isolated(&id);
+            }
+        }
+        data
+    }
+
+    /// Removes a node that has no edges left.
+    fn drop_isolated(&mut self, id: &str) {
+        let Some(node) = self.node_indices.remove(id) else {
+            return;
+        };
+        self.graph.remove_node(node);
+        if let Some(moved) = self.graph.node_weight(node) {
+            self.node_indices.insert(moved.id.clone(), node);
+        }
+        self.stix_ids.retain(|_, node_id| node_id != id);
+        self.stale.degrees.insert(id.to_string());
+        self.stale.components = true;
+        self.record(Change::NodeRemoved { id: id.to_string() });
+    }
+
+    /// Marks what an edge change between two nodes makes stale.
+    fn touch(&mut self, source: NodeIndex, target: NodeIndex) {
+        let (source, target) = (&self.graph[source], &self.graph[target]);
+        self.stale.degrees.insert(source.id.clone());
+        self.stale.degrees.insert(target.id.clone());
+        self.stale.components = true;
+        let types = [source.node_type, target.node_type];
+        if types.contains(&NodeType::Veris) && types.contains(&NodeType::Mitre) {
+            self.stale.coverage = true;
+        }
+    }
+}
+
+/// Whether a STIX object is newer than the version applied to a node.
+fn is_newer(object: &Value, applied: Option<&HashMap<String, String>>) -> bool {
+    let parse = |s: &str| DateTime::parse_from_rfc3339(s).ok();
+    let incoming = object["modified"].as_str().and_then(parse);
+    let current = applied
+        .and_then(|m| m.get("stix_modified"))
+        .and_then(|s| parse(s));
+    match (incoming, current) {
+        (Some(incoming), Some(current)) => incoming > current,
+        _ => true,
+    }
+}
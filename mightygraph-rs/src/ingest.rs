@@ -0,0 +1,561 @@
+//! Memory-efficient ingestion.
+//!
+//! [`load_csv_data`](crate::utils::load_csv_data) holds every row as a
+//! [`Mapping`] of fifteen owned `String`s, and `create_graph` copies the
+//! mapping type into every edge. The [`Ingestor`] instead streams its inputs
+//! straight into a [`CsrBuilder`]:
+//!
+//! - mapping CSVs are read one row at a time into a reused record buffer
+//!   ([`CsvMappingReader`]), and each row is kept, if at all, as a
+//!   [`MappingRecord`] of symbols;
+//! - STIX bundles are read one object at a time ([`Ingestor::read_stix`]);
+//!   relationships are resolved once every object has been seen.
+//!
+//! Every string (IDs, mapping types, domains, organizations, metadata) goes
+//! through the graph's [`Interner`], so repeated values are stored once.
+//! Install [`TrackingAllocator`] as the global allocator to get the peak heap
+//! use of the run in the [`RunReport`].
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::{DateTime, Utc};
+use csv::StringRecord;
+use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::catalog::{relationship_strength, stix_node};
+use crate::csr::{CsrBuilder, CsrGraph, GraphView};
+use crate::interner::{Interner, Symbol};
+use crate::petgraph_full_0x0::prelude::*;
+use crate::utils::mapping_type_strength;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting the bytes in use and their peak.
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+/// ```
+pub struct TrackingAllocator;
+
+fn grow(bytes: usize) {
+    let allocated = ALLOCATED.fetch_add(bytes, Ordering::Relaxed) + bytes;
+    PEAK.fetch_max(allocated, Ordering::Relaxed);
+}
+
+fn shrink(bytes: usize) {
+    ALLOCATED.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                grow(new_size - layout.size());
+            } else {
+                shrink(layout.size() - new_size);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// Heap bytes in use, as counted by [`TrackingAllocator`].
+pub fn allocated_bytes() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// Highest heap use since start-up or the last [`reset_peak`]; `None` when
+/// [`TrackingAllocator`] is not the global allocator.
+pub fn peak_allocated_bytes() -> Option<usize> {
+    Some(PEAK.load(Ordering::Relaxed)).filter(|&peak| peak > 0)
+}
+
+/// Starts a new peak measurement from the current heap use.
+pub fn reset_peak() {
+    PEAK.store(ALLOCATED.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// One CSV row, borrowed from the reader's record buffer. The columns are
+/// those of [`Mapping`].
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct MappingRow<'a> {
+    pub mapping_framework: &'a str,
+    pub mapping_framework_version: &'a str,
+    pub capability_group: &'a str,
+    pub capability_id: &'a str,
+    pub capability_description: &'a str,
+    pub mapping_type: &'a str,
+    pub attack_object_id: &'a str,
+    pub attack_object_name: &'a str,
+    pub attack_version: &'a str,
+    pub technology_domain: &'a str,
+    pub references: &'a str,
+    pub comments: &'a str,
+    pub organization: &'a str,
+    pub creation_date: &'a str,
+    pub last_update: &'a str,
+}
+
+impl<'a> From<&'a Mapping> for MappingRow<'a> {
+    fn from(m: &'a Mapping) -> MappingRow<'a> {
+        MappingRow {
+            mapping_framework: &m.mapping_framework,
+            mapping_framework_version: &m.mapping_framework_version,
+            capability_group: &m.capability_group,
+            capability_id: &m.capability_id,
+            capability_description: &m.capability_description,
+            mapping_type: &m.mapping_type,
+            attack_object_id: &m.attack_object_id,
+            attack_object_name: &m.attack_object_name,
+            attack_version: &m.attack_version,
+            technology_domain: &m.technology_domain,
+            references: &m.references,
+            comments: &m.comments,
+            organization: &m.organization,
+            creation_date: &m.creation_date,
+            last_update: &m.last_update,
+        }
+    }
+}
+
+/// A [`Mapping`] with every field interned: 60 bytes, whatever the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappingRecord {
+    pub mapping_framework: Symbol,
+    pub mapping_framework_version: Symbol,
+    pub capability_group: Symbol,
+    pub capability_id: Symbol,
+    pub capability_description: Symbol,
+    pub mapping_type: Symbol,
+    pub attack_object_id: Symbol,
+    pub attack_object_name: Symbol,
+    pub attack_version: Symbol,
+    pub technology_domain: Symbol,
+    pub references: Symbol,
+    pub comments: Symbol,
+    pub organization: Symbol,
+    pub creation_date: Symbol,
+    pub last_update: Symbol,
+}
+
+impl MappingRecord {
+    fn intern(row: &MappingRow, builder: &mut CsrBuilder) -> MappingRecord {
+        MappingRecord {
+            mapping_framework: builder.intern(row.mapping_framework),
+            mapping_framework_version: builder.intern(row.mapping_framework_version),
+            capability_group: builder.intern(row.capability_group),
+            capability_id: builder.intern(row.capability_id),
+            capability_description: builder.intern(row.capability_description),
+            mapping_type: builder.intern(row.mapping_type),
+            attack_object_id: builder.intern(row.attack_object_id),
+            attack_object_name: builder.intern(row.attack_object_name),
+            attack_version: builder.intern(row.attack_version),
+            technology_domain: builder.intern(row.technology_domain),
+            references: builder.intern(row.references),
+            comments: builder.intern(row.comments),
+            organization: builder.intern(row.organization),
+            creation_date: builder.intern(row.creation_date),
+            last_update: builder.intern(row.last_update),
+        }
+    }
+
+    /// The owned mapping, for code that needs a [`Mapping`].
+    ///
+    /// # Arguments
+    ///
+    /// - `strings`: The interner the record was built with, i.e.
+    ///   [`CsrGraph::strings`] of the ingested graph.
+    pub fn to_mapping(&self, strings: &Interner) -> Mapping {
+        let text = |symbol: Symbol| strings.resolve(symbol).to_string();
+        Mapping {
+            mapping_framework: text(self.mapping_framework),
+            mapping_framework_version: text(self.mapping_framework_version),
+            capability_group: text(self.capability_group),
+            capability_id: text(self.capability_id),
+            capability_description: text(self.capability_description),
+            mapping_type: text(self.mapping_type),
+            attack_object_id: text(self.attack_object_id),
+            attack_object_name: text(self.attack_object_name),
+            attack_version: text(self.attack_version),
+            technology_domain: text(self.technology_domain),
+            references: text(self.references),
+            comments: text(self.comments),
+            organization: text(self.organization),
+            creation_date: text(self.creation_date),
+            last_update: text(self.last_update),
+        }
+    }
+}
+
+/// Reads a mapping CSV one row at a time, reusing one record buffer.
+pub struct CsvMappingReader<R: Read> {
+    reader: csv::Reader<R>,
+    headers: StringRecord,
+    record: StringRecord,
+}
+
+impl CsvMappingReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        CsvMappingReader::from_reader(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CsvMappingReader<R> {
+    pub fn from_reader(reader: R) -> Result<Self> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let headers = reader.headers()?.clone();
+        Ok(CsvMappingReader {
+            reader,
+            headers,
+            record: StringRecord::new(),
+        })
+    }
+
+    /// The next row, borrowed until the next call.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<Option<MappingRow>>`: `None` at the end of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or a row does not
+    /// deserialize, like `load_csv_data`.
+    pub fn next_row(&mut self) -> Result<Option<MappingRow<'_>>> {
+        if !self.reader.read_record(&mut self.record)? {
+            return Ok(None);
+        }
+        Ok(Some(self.record.deserialize(Some(&self.headers))?))
+    }
+}
+
+/// Ingestion options.
+#[derive(Debug, Clone)]
+pub struct IngestOptions {
+    /// Keep every CSV row as a [`MappingRecord`]. Without them only the graph
+    /// is built.
+    pub keep_mappings: bool,
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        IngestOptions { keep_mappings: true }
+    }
+}
+
+/// What an ingestion read and built.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IngestReport {
+    /// CSV rows read.
+    pub mapping_rows: usize,
+    /// STIX objects read, relationships included.
+    pub stix_objects: usize,
+    /// STIX objects that are not graph nodes, and relationships with an end
+    /// that is not in the graph.
+    pub skipped: usize,
+    pub nodes: usize,
+    pub edges: usize,
+    /// Distinct strings in the symbol table.
+    pub strings: usize,
+    /// Heap bytes of the graph and the mapping records.
+    pub graph_bytes: usize,
+}
+
+/// A STIX relationship waiting for both of its ends.
+struct PendingEdge {
+    source: Symbol,
+    target: Symbol,
+    relationship_type: Symbol,
+    strength: f32,
+}
+
+/// Streams mapping CSVs and STIX bundles into one graph.
+pub struct Ingestor {
+    options: IngestOptions,
+    builder: CsrBuilder,
+    records: Vec<MappingRecord>,
+    /// Node of each STIX ID seen so far.
+    stix_nodes: HashMap<Symbol, NodeIndex>,
+    pending: Vec<PendingEdge>,
+    report: IngestReport,
+}
+
+/// The ingested graph, with the mapping records when they were kept.
+#[derive(Debug)]
+pub struct Ingested {
+    pub graph: CsrGraph,
+    pub records: Vec<MappingRecord>,
+    pub report: IngestReport,
+}
+
+impl Ingested {
+    /// The mappings, materialized one at a time.
+    pub fn mappings(&self) -> impl Iterator<Item = Mapping> + '_ {
+        self.records.iter().map(|r| r.to_mapping(self.graph.strings()))
+    }
+}
+
+impl Ingestor {
+    pub fn new(options: IngestOptions) -> Ingestor {
+        Ingestor {
+            options,
+            builder: CsrBuilder::new(),
+            records: Vec::new(),
+            stix_nodes: HashMap::new(),
+            pending: Vec::new(),
+            report: IngestReport::default(),
+        }
+    }
+
+    /// Adds the nodes and edge of one mapping row, as `create_graph` does.
+    pub fn add_mapping_row(&mut self, row: &MappingRow) {
+        let veris = self.builder.add_node(row.capability_id, NodeType::Veris);
+        let mitre = self.builder.add_node(row.attack_object_id, NodeType::Mitre);
+        let strength = mapping_type_strength(row.mapping_type);
+        self.builder.add_edge(veris, mitre, row.mapping_type, strength);
+        if self.options.keep_mappings {
+            self.records.push(MappingRecord::intern(row, &mut self.builder));
+        }
+        self.report.mapping_rows += 1;
+    }
+
+    /// Reads a mapping CSV row by row.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<usize>`: The number of rows read.
+    pub fn read_csv<R: Read>(&mut self, reader: R) -> Result<usize> {
+        let mut rows = CsvMappingReader::from_reader(reader)?;
+        let mut count = 0;
+        while let Some(row) = rows.next_row()? {
+            self.add_mapping_row(&row);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Reads a STIX 2.1 bundle object by object; only the object being read
+    /// is held as JSON. Attack patterns, software, groups and vulnerabilities
+    /// become nodes (merged with mapped nodes of the same ID), relationships
+    /// become edges once [`finish`](Ingestor::finish) has seen every object.
+    /// Revoked objects and relationships are skipped.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<usize>`: The number of objects read.
+    pub fn read_stix<R: Read>(&mut self, reader: R) -> Result<usize> {
+        let before = self.report.stix_objects;
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        deserializer.deserialize_map(BundleVisitor { ingestor: self })?;
+        deserializer.end()?;
+        Ok(self.report.stix_objects - before)
+    }
+
+    /// Reads a file by extension: `.json` as a STIX bundle, anything else as
+    /// a mapping CSV.
+    pub fn read_path<P: AsRef<Path>>(&mut self, path: P) -> Result<usize> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path)?);
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => self.read_stix(reader),
+            _ => self.read_csv(reader),
+        }
+    }
+
+    /// Adds one STIX object.
+    pub fn add_stix_object(&mut self, object: &Value) {
+        self.report.stix_objects += 1;
+        let revoked = object["revoked"].as_bool().unwrap_or(false);
+        let Some(stix_id) = object["id"].as_str().filter(|_| !revoked) else {
+            self.report.skipped += 1;
+            return;
+        };
+        let builder = &mut self.builder;
+
+        if object["type"].as_str() == Some("relationship") {
+            let (Some(source), Some(target)) =
+                (object["source_ref"].as_str(), object["target_ref"].as_str())
+            else {
+                self.report.skipped += 1;
+                return;
+            };
+            let relationship_type = object["relationship_type"].as_str().unwrap_or("related-to");
+            self.pending.push(PendingEdge {
+                source: builder.intern(source),
+                target: builder.intern(target),
+                relationship_type: builder.intern(relationship_type),
+                strength: relationship_strength(object),
+            });
+            return;
+        }
+
+        let Some((id, node_type)) = stix_node(object) else {
+            self.report.skipped += 1;
+            return;
+        };
+        let node = builder.add_node(&id, node_type);
+        builder.set_metadata(node, "stix_id", stix_id);
+        if let Some(name) = object["name"].as_str() {
+            builder.set_metadata(node, "name", name);
+        }
+        if let Some(modified) = object["modified"].as_str() {
+            builder.set_metadata(node, "stix_modified", modified);
+        }
+        let stix_id = builder.intern(stix_id);
+        self.stix_nodes.insert(stix_id, node);
+    }
+
+    /// Resolves the STIX relationships and lays out the graph.
+    pub fn finish(mut self) -> Ingested {
+        for edge in std::mem::take(&mut self.pending) {
+            let ends = (self.stix_nodes.get(&edge.source), self.stix_nodes.get(&edge.target));
+            let (Some(&source), Some(&target)) = ends else {
+                self.report.skipped += 1;
+                continue;
+            };
+            self.builder
+                .add_interned_edge(source, target, edge.relationship_type, edge.strength);
+        }
+
+        let graph = self.builder.build();
+        let mut records = self.records;
+        records.shrink_to_fit();
+        let report = IngestReport {
+            nodes: graph.node_count(),
+            edges: graph.edge_count(),
+            strings: graph.strings().len(),
+            graph_bytes: graph.heap_bytes()
+                + records.capacity() * std::mem::size_of::<MappingRecord>(),
+            ..self.report
+        };
+        Ingested {
+            graph,
+            records,
+            report,
+        }
+    }
+}
+
+/// Ingests files in order into one graph (see [`Ingestor::read_path`]).
+pub fn ingest_paths<P: AsRef<Path>>(paths: &[P], options: IngestOptions) -> Result<Ingested> {
+    let mut ingestor = Ingestor::new(options);
+    for path in paths {
+        ingestor.read_path(path)?;
+    }
+    Ok(ingestor.finish())
+}
+
+/// Visits the top-level bundle object, streaming its `objects`.
+struct BundleVisitor<'a> {
+    ingestor: &'a mut Ingestor,
+}
+
+impl<'de> Visitor<'de> for BundleVisitor<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a STIX bundle")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<(), A::Error> {
+        let ingestor = self.ingestor;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "objects" {
+                map.next_value_seed(ObjectsVisitor {
+                    ingestor: &mut *ingestor,
+                })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Visits the `objects` array, one object at a time.
+struct ObjectsVisitor<'a> {
+    ingestor: &'a mut Ingestor,
+}
+
+impl<'de> DeserializeSeed<'de> for ObjectsVisitor<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ObjectsVisitor<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of STIX objects")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        let ingestor = self.ingestor;
+        while let Some(object) = seq.next_element::<Value>()? {
+            ingestor.add_stix_object(&object);
+        }
+        Ok(())
+    }
+}
+
+/// Summary of an `ingest` run, written next to the analyses.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub inputs: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub ingest: IngestReport,
+    /// Highest heap use of the run; `None` without [`TrackingAllocator`].
+    pub peak_memory_bytes: Option<usize>,
+}
+
+impl RunReport {
+    /// A report finished now, with the peak heap use so far.
+    pub fn new(inputs: Vec<String>, started_at: DateTime<Utc>, ingest: IngestReport) -> RunReport {
+        RunReport {
+            inputs,
+            started_at,
+            finished_at: Utc::now(),
+            ingest,
+            peak_memory_bytes: peak_allocated_bytes(),
+        }
+    }
+}
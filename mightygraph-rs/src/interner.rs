@@ -0,0 +1,132 @@
+//! String interning.
+//!
+//! An [`Interner`] stores every distinct string once, back to back in one
+//! buffer, and hands out a 4-byte [`Symbol`] for it. Node IDs, mapping types
+//! and metadata values repeat across hundreds of thousands of nodes and
+//! edges, so storing symbols instead of `String`s saves most of the memory.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroU32;
+
+/// Handle of an interned string. `Option<Symbol>` is 4 bytes as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(NonZeroU32);
+
+impl Symbol {
+    /// Position of the string in the interner, starting at 0.
+    pub fn index(self) -> usize {
+        self.0.get() as usize - 1
+    }
+}
+
+/// A symbol table. Strings are never removed.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    buffer: String,
+    /// End offset of each string in `buffer`.
+    ends: Vec<u32>,
+    /// Symbol by string hash.
+    by_hash: HashMap<u64, Symbol>,
+    /// Strings whose hash is already taken by another string.
+    collisions: HashMap<Box<str>, Symbol>,
+}
+
+fn hash(text: &str) -> u64 {
+    // `DefaultHasher::new` uses fixed keys, so hashes are the same every run.
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Number of distinct strings.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The symbol of `text`, interning it on first sight.
+    ///
+    /// # Panics
+    ///
+    /// When the strings outgrow 4 GiB in total.
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        let hash = hash(text);
+        match self.by_hash.get(&hash) {
+            Some(&symbol) if self.resolve(symbol) == text => symbol,
+            Some(_) => match self.collisions.get(text) {
+                Some(&symbol) => symbol,
+                None => {
+                    let symbol = self.push(text);
+                    self.collisions.insert(text.into(), symbol);
+                    symbol
+                }
+            },
+            None => {
+                let symbol = self.push(text);
+                self.by_hash.insert(hash, symbol);
+                symbol
+            }
+        }
+    }
+
+    /// The symbol of `text`, if it was interned.
+    pub fn get(&self, text: &str) -> Option<Symbol> {
+        match self.by_hash.get(&hash(text)) {
+            Some(&symbol) if self.resolve(symbol) == text => Some(symbol),
+            Some(_) => self.collisions.get(text).copied(),
+            None => None,
+        }
+    }
+
+    /// The string of a symbol from this interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        let index = symbol.index();
+        let start = if index == 0 { 0 } else { self.ends[index - 1] as usize };
+        &self.buffer[start..self.ends[index] as usize]
+    }
+
+    /// Every symbol with its string, in interning order.
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> {
+        (0..self.len()).map(|i| {
+            let symbol = Symbol(NonZeroU32::new(i as u32 + 1).unwrap());
+            (symbol, self.resolve(symbol))
+        })
+    }
+
+    /// Approximate heap memory in use, in bytes.
+    pub fn heap_bytes(&self) -> usize {
+        self.buffer.capacity()
+            + self.ends.capacity() * std::mem::size_of::<u32>()
+            + self.by_hash.capacity() * std::mem::size_of::<(u64, Symbol)>()
+            + self
+                .collisions
+                .keys()
+                .map(|k| k.len() + std::mem::size_of::<(Box<str>, Symbol)>())
+                .sum::<usize>()
+    }
+
+    /// Releases spare capacity once interning is done.
+    pub fn shrink_to_fit(&mut self) {
+        self.buffer.shrink_to_fit();
+        self.ends.shrink_to_fit();
+        self.by_hash.shrink_to_fit();
+        self.collisions.shrink_to_fit();
+    }
+
+    fn push(&mut self, text: &str) -> Symbol {
+        self.buffer.push_str(text);
+        let end = u32::try_from(self.buffer.len()).expect("interned strings exceed 4 GiB");
+        self.ends.push(end);
+        Symbol(NonZeroU32::new(self.ends.len() as u32).unwrap())
+    }
+}
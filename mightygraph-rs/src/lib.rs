@@ -0,0 +1,39 @@
+//! Graph analyses over the VERIS to MITRE ATT&CK mappings.
+//!
+//! The `mighty_graph_rs` binary (`main.rs`) drives these modules from the
+//! command line; the integration tests and benchmarks use them directly.
+
+pub mod cache;
+pub mod catalog;
+pub mod centrality;
+pub mod coverage;
+pub mod csr;
+pub mod ecs;
+pub mod elastic;
+pub mod embeddings;
+pub mod export;
+pub mod extraction;
+pub mod frames;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod incremental;
+pub mod ingest;
+pub mod interner;
+pub mod link_prediction;
+pub mod metrics;
+pub mod navigator;
+pub mod petgraph_full_0x0;
+pub mod query;
+pub mod rag;
+pub mod search;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod similarity;
+#[cfg(feature = "postgres")]
+pub mod storage;
+pub mod subgraph_optimized;
+pub mod summarize;
+pub mod temporal;
+pub mod traversal;
+pub mod utils;
+pub mod validation;
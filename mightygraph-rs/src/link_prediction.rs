@@ -0,0 +1,302 @@
+//! Link prediction for missing VERIS ↔ ATT&CK mappings.
+//!
+//! The mapping graph is bipartite, so a VERIS node and a technique never
+//! share a neighbor directly. The neighborhood scores are therefore taken
+//! over paths of length three, `v - t' - v' - t`: the "common neighbors" of
+//! `(v, t)` are the techniques `t'` already mapped from `v` that share a
+//! VERIS node `v'` with `t`. Those `t'` and `v'` are returned with every
+//! candidate so analysts can see why it was proposed.
+//!
+//! Katz sums the walks of every length up to `katz_max_length`, each length
+//! weighted by `katz_beta` per hop, so it also ranks pairs that are further
+//! apart. Walks of even length end on a VERIS node, so only the odd lengths
+//! add to a technique's score.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::path::Path;
+
+use polars::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::petgraph_full_0x0::prelude::*;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Score used to rank candidates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkMetric {
+    CommonNeighbors,
+    Jaccard,
+    AdamicAdar,
+    ResourceAllocation,
+    Katz,
+}
+
+impl LinkMetric {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "common_neighbors" => Some(LinkMetric::CommonNeighbors),
+            "jaccard" => Some(LinkMetric::Jaccard),
+            "adamic_adar" => Some(LinkMetric::AdamicAdar),
+            "resource_allocation" => Some(LinkMetric::ResourceAllocation),
+            "katz" => Some(LinkMetric::Katz),
+            _ => None,
+        }
+    }
+
+    pub fn score(&self, candidate: &CandidateLink) -> f64 {
+        match self {
+            LinkMetric::CommonNeighbors => candidate.common_neighbors as f64,
+            LinkMetric::Jaccard => candidate.jaccard,
+            LinkMetric::AdamicAdar => candidate.adamic_adar,
+            LinkMetric::ResourceAllocation => candidate.resource_allocation,
+            LinkMetric::Katz => candidate.katz,
+        }
+    }
+}
+
+/// Options for [`predict_links`].
+#[derive(Debug, Clone)]
+pub struct LinkPredictionOptions {
+    pub metric: LinkMetric,
+    /// Keeps the best `top_k` candidates overall.
+    pub top_k: Option<usize>,
+    /// Keeps at most this many candidates per VERIS node.
+    pub per_veris_limit: Option<usize>,
+    /// Candidates need at least this many common neighbors; with 0 every
+    /// pair with a non-zero Katz score is a candidate.
+    pub min_common_neighbors: usize,
+    /// Katz attenuation factor per hop.
+    pub katz_beta: f64,
+    /// Longest walk counted by Katz.
+    pub katz_max_length: usize,
+}
+
+impl Default for LinkPredictionOptions {
+    fn default() -> Self {
+        LinkPredictionOptions {
+            metric: LinkMetric::AdamicAdar,
+            top_k: Some(100),
+            per_veris_limit: None,
+            min_common_neighbors: 1,
+            katz_beta: 0.05,
+            katz_max_length: 5,
+        }
+    }
+}
+
+/// A proposed VERIS → ATT&CK mapping.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CandidateLink {
+    pub veris_id: String,
+    pub mitre_id: String,
+    /// Value of the ranking metric.
+    pub score: f64,
+    pub common_neighbors: usize,
+    pub jaccard: f64,
+    pub adamic_adar: f64,
+    pub resource_allocation: f64,
+    pub katz: f64,
+    /// Techniques mapped from the VERIS node that share a VERIS node with
+    /// the candidate technique.
+    pub shared_neighbors: Vec<String>,
+    /// VERIS nodes mapped to both the candidate and a shared neighbor.
+    pub bridging_veris: Vec<String>,
+}
+
+/// Distinct neighbors of every node, ignoring edge direction.
+fn undirected_adjacency(graph: &MappingGraph) -> Vec<Vec<NodeIndex>> {
+    graph
+        .node_indices()
+        .map(|n| {
+            let neighbors: BTreeSet<NodeIndex> = graph.neighbors_undirected(n).collect();
+            neighbors.into_iter().collect()
+        })
+        .collect()
+}
+
+/// Sum over walk lengths `1..=max_length` of `beta^l` times the number of
+/// walks of length `l` from `source`, for every node.
+fn katz_scores(
+    adjacency: &[Vec<NodeIndex>],
+    source: NodeIndex,
+    beta: f64,
+    max_length: usize,
+) -> Vec<f64> {
+    let mut walks = vec![0.0; adjacency.len()];
+    walks[source.index()] = 1.0;
+    let mut scores = vec![0.0; adjacency.len()];
+    let mut weight = 1.0;
+
+    for _ in 0..max_length {
+        let mut next = vec![0.0; adjacency.len()];
+        for (node, &count) in walks.iter().enumerate() {
+            if count != 0.0 {
+                for neighbor in &adjacency[node] {
+                    next[neighbor.index()] += count;
+                }
+            }
+        }
+        weight *= beta;
+        for (score, &count) in scores.iter_mut().zip(&next) {
+            *score += weight * count;
+        }
+        walks = next;
+    }
+
+    scores
+}
+
+/// Scores unconnected VERIS/ATT&CK pairs and ranks them.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `options`: Ranking metric, limits and Katz parameters.
+///
+/// # Returns
+///
+/// - `Vec<CandidateLink>`: Candidates, best first; ties are ordered by id.
+pub fn predict_links(graph: &MappingGraph, options: &LinkPredictionOptions) -> Vec<CandidateLink> {
+    let adjacency = undirected_adjacency(graph);
+    let degree = |n: NodeIndex| adjacency[n.index()].len() as f64;
+    let mut two_hop_sizes: HashMap<NodeIndex, usize> = HashMap::new();
+    let mut candidates = vec![];
+
+    for v in graph.node_indices().filter(|&n| graph[n].node_type == NodeType::Veris) {
+        let mapped = &adjacency[v.index()];
+
+        // Length-3 paths v - t' - v' - t, grouped by t.
+        let mut paths: BTreeMap<NodeIndex, (BTreeSet<NodeIndex>, BTreeSet<NodeIndex>)> =
+            BTreeMap::new();
+        for &shared in mapped {
+            for &bridge in &adjacency[shared.index()] {
+                if bridge == v {
+                    continue;
+                }
+                for &t in &adjacency[bridge.index()] {
+                    if graph[t].node_type == NodeType::Mitre && mapped.binary_search(&t).is_err() {
+                        let entry = paths.entry(t).or_default();
+                        entry.0.insert(shared);
+                        entry.1.insert(bridge);
+                    }
+                }
+            }
+        }
+
+        let katz = katz_scores(&adjacency, v, options.katz_beta, options.katz_max_length);
+        if options.min_common_neighbors == 0 {
+            for t in graph.node_indices() {
+                if graph[t].node_type == NodeType::Mitre
+                    && katz[t.index()] > 0.0
+                    && mapped.binary_search(&t).is_err()
+                {
+                    paths.entry(t).or_default();
+                }
+            }
+        }
+
+        let mut per_veris = vec![];
+        for (t, (shared, bridges)) in paths {
+            if shared.len() < options.min_common_neighbors {
+                continue;
+            }
+            let two_hop = *two_hop_sizes.entry(t).or_insert_with(|| {
+                let reachable: BTreeSet<NodeIndex> = adjacency[t.index()]
+                    .iter()
+                    .flat_map(|b| adjacency[b.index()].iter().copied())
+                    .filter(|&n| n != t)
+                    .collect();
+                reachable.len()
+            });
+            let union = mapped.len() + two_hop - shared.len();
+
+            let mut candidate = CandidateLink {
+                veris_id: graph[v].id.clone(),
+                mitre_id: graph[t].id.clone(),
+                score: 0.0,
+                common_neighbors: shared.len(),
+                jaccard: if union == 0 {
+                    0.0
+                } else {
+                    shared.len() as f64 / union as f64
+                },
+                // Every shared technique touches v and a bridge, so its degree is at least 2.
+                adamic_adar: shared.iter().map(|&n| 1.0 / degree(n).ln()).sum(),
+                resource_allocation: shared.iter().map(|&n| 1.0 / degree(n)).sum(),
+                katz: katz[t.index()],
+                shared_neighbors: shared.iter().map(|&n| graph[n].id.clone()).collect(),
+                bridging_veris: bridges.iter().map(|&n| graph[n].id.clone()).collect(),
+            };
+            candidate.score = options.metric.score(&candidate);
+            per_veris.push(candidate);
+        }
+
+        sort_candidates(&mut per_veris);
+        if let Some(limit) = options.per_veris_limit {
+            per_veris.truncate(limit);
+        }
+        candidates.extend(per_veris);
+    }
+
+    sort_candidates(&mut candidates);
+    if let Some(top_k) = options.top_k {
+        candidates.truncate(top_k);
+    }
+    candidates
+}
+
+fn sort_candidates(candidates: &mut [CandidateLink]) {
+    candidates.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.veris_id.cmp(&b.veris_id))
+            .then_with(|| a.mitre_id.cmp(&b.mitre_id))
+    });
+}
+
+/// Runs [`predict_links`] and wraps the candidates for `export_to_json`.
+pub fn perform_link_prediction(graph: &MappingGraph, options: &LinkPredictionOptions) -> Value {
+    let candidates = predict_links(graph, options);
+    json!({
+        "metric": options.metric,
+        "candidate_count": candidates.len(),
+        "candidates": candidates,
+    })
+}
+
+/// Candidates as a table, one row per proposed mapping, with the
+/// explanations joined by `;`.
+pub fn candidates_frame(candidates: &[CandidateLink]) -> PolarsResult<DataFrame> {
+    let column = |f: fn(&CandidateLink) -> f64| candidates.iter().map(f).collect::<Vec<_>>();
+    let joined = |f: fn(&CandidateLink) -> &Vec<String>| {
+        candidates.iter().map(|c| f(c).join(";")).collect::<Vec<_>>()
+    };
+
+    DataFrame::new(vec![
+        Column::new("veris_id".into(), candidates.iter().map(|c| c.veris_id.as_str()).collect::<Vec<_>>()),
+        Column::new("mitre_id".into(), candidates.iter().map(|c| c.mitre_id.as_str()).collect::<Vec<_>>()),
+        Column::new("score".into(), column(|c| c.score)),
+        Column::new(
+            "common_neighbors".into(),
+            candidates.iter().map(|c| c.common_neighbors as u32).collect::<Vec<_>>(),
+        ),
+        Column::new("jaccard".into(), column(|c| c.jaccard)),
+        Column::new("adamic_adar".into(), column(|c| c.adamic_adar)),
+        Column::new("resource_allocation".into(), column(|c| c.resource_allocation)),
+        Column::new("katz".into(), column(|c| c.katz)),
+        Column::new("shared_neighbors".into(), joined(|c| &c.shared_neighbors)),
+        Column::new("bridging_veris".into(), joined(|c| &c.bridging_veris)),
+    ])
+}
+
+/// Writes the candidates to a CSV file for review.
+pub fn export_candidates<P: AsRef<Path>>(candidates: &[CandidateLink], path: P) -> Result<()> {
+    let mut df = candidates_frame(candidates)?;
+    CsvWriter::new(File::create(path)?).finish(&mut df)?;
+    Ok(())
+}
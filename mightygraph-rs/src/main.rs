@@ -30,7 +30,89 @@
 //! directory.
 //!
 //! ```bash
-//! $ cargo run --release
+//! $ cargo run --release -- path/to/mappings.csv
+//! ```
+//!
+//! Threat reports (text, Markdown or HTML) in a directory can be linked into the graph
+//! by the techniques, software, groups, CVEs and VERIS terms they mention:
+//!
+//! ```bash
+//! $ cargo run --release -- path/to/mappings.csv --reports path/to/reports
+//! ```
+//!
+//! With the `server` feature, the graph can also be served over HTTP (see the
+//! `server` module for the routes); the input files are hot-reloaded:
+//!
+//! ```bash
+//! $ cargo run --release --features server -- serve mappings.csv --attack enterprise-attack.json
+//! ```
+//!
+//! Node summaries can be generated with any OpenAI-compatible endpoint, such as a local
+//! llama.cpp or Ollama server (see the `summarize` module):
+//!
+//! ```bash
+//! $ LLM_ENDPOINT=http://localhost:11434/v1 LLM_MODEL=llama3 cargo run --release -- summarize mappings.csv
+//! ```
+//!
+//! Mapping files can be linted before they are analysed; the exit status is non-zero
+//! when a finding reaches the `--fail-on` severity:
+//!
+//! ```bash
+//! $ cargo run --release -- validate mappings.csv --attack enterprise-attack.json --format json
+//! ```
+//!
+//! `coverage` reports which ATT&CK techniques and VERIS enumerations have no mapping,
+//! per tactic, platform and ATT&CK domain, as CSV and JSON plus a Navigator layer
+//! (see the `coverage` and `navigator` modules):
+//!
+//! ```bash
+//! $ cargo run --release -- coverage mappings.csv --attack enterprise-attack.json --out analysed/coverage
+//! ```
+//!
+//! The ECS records (nodes, edges, mappings and analyses) can be pushed straight into
+//! Elasticsearch or OpenSearch; the index template is created first and re-runs
+//! overwrite the documents of the previous run (see the `elastic` module):
+//!
+//! ```bash
+//! $ ELASTIC_API_KEY=... cargo run --release -- index mappings.csv --url http://localhost:9200
+//! ```
+//!
+//! With the `postgres` feature, `--store` keeps the run (input hash, versions, graph,
+//! mappings and analysis results) in the `docker-compose.yml` database, or the one in
+//! `DATABASE_URL` (see the `storage` module):
+//!
+//! ```bash
+//! $ cargo run --release --features postgres -- path/to/mappings.csv --store
+//! ```
+//!
+//! The analyses run in parallel. `--threads <n>` (or `MIGHTYGRAPH_THREADS`), accepted by
+//! every subcommand, sets the number of worker threads; results do not depend on it:
+//!
+//! ```bash
+//! $ cargo run --release -- path/to/mappings.csv --threads 8
+//! ```
+//!
+//! Analysis results are cached by a hash of the inputs. With the `redis-cache` feature
+//! and `REDIS_URL` (or the `docker-compose.yml` Redis variables) set, the cache is
+//! shared between runs (see the `cache` module).
+//!
+//! `--metrics` writes Prometheus metrics (graph size, analysis durations, cache hits,
+//! peak heap use, the most novel techniques, and coverage when `--attack` gives the
+//! ATT&CK bundle)
+//! for a node exporter textfile collector; the server serves the same metrics on
+//! `/metrics`. `dashboard` writes a Grafana dashboard over them (see the `metrics` module):
+//!
+//! ```bash
+//! $ cargo run --release -- path/to/mappings.csv --metrics /var/lib/node_exporter/mightygraph.prom --attack enterprise-attack.json
+//! $ cargo run --release -- dashboard analysed/grafana/mightygraph.json
+//! ```
+//!
+//! Inputs too large to hold as `Mapping`s can be streamed into a compact graph instead;
+//! `ingest` takes mapping CSVs and STIX bundles, and writes the graph analyses and a run
+//! report with the peak heap use (see the `ingest` module):
+//!
+//! ```bash
+//! $ cargo run --release -- ingest mappings.csv enterprise-attack.json
 //! ```
 //!
 //! ## Example Workflow
@@ -45,22 +127,34 @@
 //! The output is saved in an `analysed/data/` directory, containing files such as:
 //! - `combined_analysis.csv`: Combined data exported in CSV format.
 //! - `combined_analysis.parquet`: Combined data exported in Parquet format.
+//! - `combined_analysis.arrow`: Combined data exported in Arrow IPC format.
 //! - Individual JSON files for each type of analysis.
+//! - `rag_chunks.jsonl`: Node and neighborhood text chunks for retrieval pipelines.
+//! - `ecs_bulk.ndjson`: Node, edge, mapping and analysis records in ECS, as `_bulk` NDJSON.
+//! - `report_mentions.json`: Entity mentions of the linked reports, with `--reports`.
+//! - `ingest_analyses.json`, `run_report.json`: Graph analyses and run report of `ingest`.
 //!
 //! ## Example Code
 //!
-//! ```rust
-//! fn main() -> Result<()> {
+//! The same steps through the library:
+//!
+//! ```no_run
+//! use mighty_graph_rs::export::{export_combined, node_metrics_from_analyses, ExportOptions};
+//! use mighty_graph_rs::petgraph_full_0x0::{export_to_json, perform_analyses};
+//! use mighty_graph_rs::utils::{create_graph, load_csv_data};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // Load CSV data and build graph
 //!     let mappings = load_csv_data("path/to/csv")?;
 //!     let (graph, node_indices) = create_graph(&mappings)?;
 //!
 //!     // Perform analyses
-//!     let analyses = perform_analyses(&graph, &mappings, &node_indices)?;
+//!     let analyses = perform_analyses(&graph, &mappings, &node_indices);
 //!
 //!     // Export the results
-//!     export_results(&analyses)?;
-//!     export_combined_data(&mappings, &analyses.node_degree_analysis)?;
+//!     export_to_json("basic_stats", &analyses.basic_stats)?;
+//!     let metrics = node_metrics_from_analyses(&analyses);
+//!     export_combined(&mappings, &metrics, &ExportOptions::default())?;
 //!
 //!     Ok(())
 //! }
@@ -69,29 +163,45 @@
 //! ## External Crates Used
 //!
 //! - `petgraph`: For graph data structures and algorithms.
-//! - `polars`: For exporting data to Parquet and CSV formats.
+//! - `polars`: For exporting data to Parquet, Arrow IPC and CSV formats.
 //! - `serde`, `serde_json`: For JSON serialization.
 //! - `csv`: For CSV data parsing and export.
 //!
-// Declare the external modules
-pub mod petgraph_full_0x0;
-pub mod utils;
+#[cfg(feature = "server")]
+use mighty_graph_rs::server;
+#[cfg(feature = "postgres")]
+use mighty_graph_rs::storage;
+use mighty_graph_rs::{
+    cache, catalog, coverage, ecs, elastic, export, extraction, ingest, metrics, petgraph_full_0x0,
+    rag, subgraph_optimized, summarize, validation,
+};
 
 use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
+use std::io::BufWriter;
 use std::path::Path;
-use petgraph::graph::{Graph, NodeIndex};
-use polars::prelude::ParquetWriter;
-use polars::series::Series;
-use petgraph_full_0x0::prelude::*;
-use csv;
-use utils::*;
+use petgraph::graph::NodeIndex;
+use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+use mighty_graph_rs::utils::*;
 
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-// Structures definitions remain the same
+/// Mappings file read when no path is given on the command line.
+const DEFAULT_MAPPINGS_CSV: &str = "data/veris-1.3.7_attack-12.1-enterprise.csv";
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+/// Where the `coverage` subcommand writes its report by default.
+const DEFAULT_COVERAGE_DIR: &str = "./analysed/coverage";
+
+/// Number of techniques whose novelty score goes into the `--metrics` file.
+const NOVELTY_TOP_N: usize = 10;
+
+/// Where the `dashboard` subcommand writes the Grafana dashboard by default.
+const DEFAULT_DASHBOARD_JSON: &str = "./analysed/grafana/mightygraph.json";
+
+/// Counts heap use, for the peak memory in the `ingest` run report and the
+/// batch run's metrics.
+#[global_allocator]
+static ALLOCATOR: ingest::TrackingAllocator = ingest::TrackingAllocator;
 
 
 /// Main function to load CSV data, create a graph, perform analyses, and export results to JSON, Parquet, and CSV.
@@ -100,55 +210,490 @@ type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 /// 
 /// - `Result<()>`: Indicates the success or failure of the main process.
 fn main() -> Result<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let threads = match args.iter().position(|a| a == "--threads") {
+        Some(i) => {
+            let count = args.get(i + 1).ok_or("missing value for --threads")?.parse()?;
+            args.drain(i..=i + 1);
+            Some(count)
+        }
+        None => None,
+    };
+    configure_threads(threads)?;
+
+    if args.first().map(String::as_str) == Some("serve") {
+        return run_server(&args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("summarize") {
+        return run_summarize(&args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("validate") {
+        return run_validate(&args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("index") {
+        return run_index(&args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("ingest") {
+        return run_ingest(&args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("coverage") {
+        return run_coverage(&args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("dashboard") {
+        let path = args.get(1).map(String::as_str).unwrap_or(DEFAULT_DASHBOARD_JSON);
+        metrics::export_dashboard(path)?;
+        println!("wrote Grafana dashboard to {}", path);
+        return Ok(());
+    }
+
     // 1. Load the CSV data
-    let csv_file = "/Users/nullzero/Documents/repos/opencti/veris_mitre_map/veris_mitre_map/data/veris-1.3.7_attack-12.1-enterprise.csv";
-    let file = File::open(csv_file)?;
-    let reader = BufReader::new(file);
-    let mut rdr = csv::Reader::from_reader(reader);
-    let mappings: Vec<Mapping> = rdr.deserialize().collect::<Option<Result<(), Err()>>;
+    let started_at = chrono::Utc::now();
+    let csv_file = args
+        .first()
+        .filter(|a| !a.starts_with("--"))
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_MAPPINGS_CSV);
+    let reports_dir = match args.iter().position(|a| a == "--reports") {
+        Some(i) => Some(args.get(i + 1).ok_or("missing value for --reports")?),
+        None => None,
+    };
+    let store = args.iter().any(|a| a == "--store");
+    let metrics_file = match args.iter().position(|a| a == "--metrics") {
+        Some(i) => Some(args.get(i + 1).ok_or("missing value for --metrics")?),
+        None => None,
+    };
+    let attack = match args.iter().position(|a| a == "--attack") {
+        Some(i) => {
+            let path = args.get(i + 1).ok_or("missing value for --attack")?;
+            Some(catalog::AttackCatalog::from_stix_bundle(path)?)
+        }
+        None => None,
+    };
+    let mappings = match load_csv_data(csv_file) {
+        Ok(mappings) => mappings,
+        Err(e) => {
+            // A failed load is still reported to the textfile collector, so it can alert.
+            if let Some(path) = metrics_file {
+                metrics::record_loader_error(metrics::global(), "csv");
+                metrics::global().write_textfile(path)?;
+            }
+            return Err(e);
+        }
+    };
 
-    // 2. Create the graph and add nodes/edges
-    let (graph, node_indices) = create_graph(&mappings)?;
+    // 2. Create the graph and add nodes/edges, plus any threat reports
+    let (mut graph, mut node_indices) = create_graph(&mappings)?;
+    if let Some(dir) = reports_dir {
+        link_reports(&mut graph, &mut node_indices, &mappings, Path::new(dir))?;
+    }
 
-    // 3. Perform the analyses
-    let analyses = perform_analyses(&graph, &mappings, &node_indices)?;
+    // 3. Perform the analyses, reusing results cached for the same inputs
+    let analysis_cache = cache::AnalysisCache::from_env();
+    let analyses = cache::cached_analyses(&analysis_cache, &graph, &mappings, &node_indices);
 
     // 4. Export results to JSON
     export_results(&analyses)?;
 
-    // 5. Combine all information and export to Parquet and CSV
-    export_combined_data(&mappings, &analyses.node_degree_analysis)?;
+    // 5. Combine all information and export to Parquet, Arrow IPC and CSV
+    export_combined_data(&mappings, &analyses)?;
+
+    // 6. Export retrieval chunks for RAG pipelines
+    export_rag_chunks(&graph, &mappings, &analyses)?;
+
+    // 7. Export ECS-validated records for Elasticsearch `_bulk`
+    export_ecs_records(&graph, &mappings, &analyses)?;
+
+    // 8. Keep the run in Postgres
+    if store {
+        store_run(csv_file, started_at, reports_dir.map(String::as_str), &graph, &mappings, &analyses)?;
+    }
+
+    // 9. Write the run's metrics for the Prometheus textfile collector
+    if let Some(path) = metrics_file {
+        let registry = metrics::global();
+        metrics::record_graph(registry, &graph, &mappings);
+        if let Some(attack) = &attack {
+            let veris = catalog::VerisCatalog::from_mappings(&mappings);
+            let options = coverage::CoverageOptions::default();
+            let report = coverage::perform_coverage_analysis(&graph, &mappings, attack, &veris, &options);
+            metrics::record_coverage(registry, &report);
+        }
+        record_novelty(registry, &analysis_cache, &graph, &mappings);
+        metrics::record_cache(registry, &analysis_cache.stats());
+        if let Some(bytes) = ingest::peak_allocated_bytes() {
+            metrics::record_peak_memory(registry, bytes);
+        }
+        registry.write_textfile(path)?;
+    }
 
     Ok(())
 }
 
+/// Records the highest technique novelty scores for the textfile collector.
+/// The scores are cached like the analyses, under the inputs' content hash.
+///
+/// # Arguments
+/// - `registry`: The metrics registry of the run.
+/// - `analysis_cache`: The results cache of the run.
+/// - `graph`: The mapping graph the scores are computed on.
+/// - `mappings`: Technique names, for nodes without a catalog `name`.
+fn record_novelty(
+    registry: &metrics::Metrics,
+    analysis_cache: &cache::AnalysisCache,
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+) {
+    let content_hash = cache::content_hash(graph, mappings);
+    let scores = analysis_cache.get_or_compute("novelty_scores", &content_hash, &serde_json::json!({}), || {
+        let names: HashMap<&str, &str> = mappings
+            .iter()
+            .map(|m| (m.attack_object_id.as_str(), m.attack_object_name.as_str()))
+            .collect();
+        let novelty_graph = subgraph_optimized::from_mapping_graph(graph);
+        let scores: Vec<(String, String, f64)> = subgraph_optimized::novelty_scores(&novelty_graph)
+            .into_iter()
+            .filter(|&(node, _)| graph[node].node_type == NodeType::Mitre)
+            .map(|(node, score)| {
+                let object = &novelty_graph[node];
+                let name = match object.name.as_str() {
+                    "" => names.get(object.id.as_str()).copied().unwrap_or_default(),
+                    name => name,
+                };
+                (object.id.clone(), name.to_string(), score)
+            })
+            .collect();
+        serde_json::json!(scores)
+    });
+    let scores: Vec<(String, String, f64)> = serde_json::from_value(scores).unwrap_or_default();
+    metrics::record_novelty(registry, &scores, NOVELTY_TOP_N);
+}
 
-/// Creates a graph based on the provided mappings.
-/// 
+/// Stores the run (metadata, graph, mappings and analyses) in the Postgres database
+/// given by `DATABASE_URL` or the `docker-compose.yml` variables.
+///
 /// # Arguments
-///  `mappings` - A slice of `Mapping` structs containing the data for creating the graph.
-/// 
-/// # Returns
-/// A tuple containing the created `MappingGraph` and a `HashMap` with node indices.
-/// 
-/// # Errors
-/// Returns an error if there are issues during the graph creation process.
-fn create_graph(mappings: &[Mapping]) -> Result<(MappingGraph, HashMap<String, NodeIndex>)> {
-    let mut graph = Graph::<NodeData, EdgeData>::new();
-    let mut node_indices = HashMap::new();
+/// - `csv_file`: The mappings file the run was computed from.
+/// - `started_at`: When the run started.
+/// - `reports_dir`: The `--reports` directory, recorded as a run parameter.
+/// - `graph`, `mappings`, `analyses`: What the run computed.
+#[cfg(feature = "postgres")]
+fn store_run(
+    csv_file: &str,
+    started_at: chrono::DateTime<chrono::Utc>,
+    reports_dir: Option<&str>,
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    analyses: &AnalysisResults,
+) -> Result<()> {
+    let url = storage::database_url_from_env().ok_or("--store needs DATABASE_URL or USER_PSQL")?;
+    let mut store = storage::PgStore::connect(&url)?;
+    let metadata = storage::RunMetadata {
+        started_at,
+        parameters: serde_json::json!({ "reports": reports_dir }),
+        ..storage::RunMetadata::new(csv_file, graph, mappings)?
+    };
+    let run_id = store.save_run(&metadata, graph, mappings, analyses)?;
+    println!("stored run {} (graph {})", run_id, metadata.graph_hash);
+    Ok(())
+}
 
-    for mapping in mappings {
-        let veris_index = add_node_if_not_exists(&mut graph, &mut node_indices, &mapping.capability_id, NodeType::Veris);
-        let mitre_index = add_node_if_not_exists(&mut graph, &mut node_indices, &mapping.attack_object_id, NodeType::Mitre);
+#[cfg(not(feature = "postgres"))]
+fn store_run(
+    _csv_file: &str,
+    _started_at: chrono::DateTime<chrono::Utc>,
+    _reports_dir: Option<&str>,
+    _graph: &MappingGraph,
+    _mappings: &[Mapping],
+    _analyses: &AnalysisResults,
+) -> Result<()> {
+    Err("--store requires building with `--features postgres`".into())
+}
 
-        let strength = calculate_strength(&mapping);
-        graph.add_edge(veris_index, mitre_index, EdgeData {
-            mapping_type: mapping.mapping_type.clone(),
-            strength,
-        });
+
+/// Runs the HTTP query API (`serve` subcommand).
+///
+/// # Arguments
+/// - `args`: `<mappings.csv> [--attack <bundle.json>] [--veris <verisc-enum.json>] [--addr <host:port>]`
+#[cfg(feature = "server")]
+fn run_server(args: &[String]) -> Result<()> {
+    let mappings = args.first().ok_or("usage: serve <mappings.csv> [--attack <bundle.json>] [--veris <enum.json>] [--addr <host:port>]")?;
+    let mut config = server::ServerConfig::new(mappings);
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--attack" => config.attack_path = Some(value.into()),
+            "--veris" => config.veris_path = Some(value.into()),
+            "--addr" => config.addr = value.parse()?,
+            other => return Err(format!("unknown option {}", other).into()),
+        }
     }
 
-    Ok((graph, node_indices))
+    let runtime = tokio::runtime::Runtime::new()?;
+    println!("serving mapping graph on http://{}", config.addr);
+    runtime.block_on(server::serve(config)).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(not(feature = "server"))]
+fn run_server(_args: &[String]) -> Result<()> {
+    Err("server mode requires building with `--features server`".into())
+}
+
+/// Lints a mappings CSV (`validate` subcommand).
+///
+/// Prints the findings as text or JSON and exits with status 1 when any finding reaches
+/// the `--fail-on` severity (default `error`), so it can gate CI.
+///
+/// # Arguments
+/// - `args`: `<mappings.csv> [--attack <bundle.json>] [--veris <verisc-enum.json>]
+///   [--format text|json] [--fail-on error|warning|info] [--disable <rule>]...`
+fn run_validate(args: &[String]) -> Result<()> {
+    let usage = "usage: validate <mappings.csv> [--attack <bundle.json>] [--veris <enum.json>] \
+                 [--format text|json] [--fail-on error|warning|info] [--disable <rule>]";
+    let csv_file = args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?;
+    let mut attack = None;
+    let mut veris = None;
+    let mut json_output = false;
+    let mut fail_on = validation::Severity::Error;
+    let mut validator = validation::Validator::default();
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--attack" => attack = Some(catalog::AttackCatalog::from_stix_bundle(value)?),
+            "--veris" => veris = Some(catalog::VerisCatalog::from_enum_file(value)?),
+            "--format" => json_output = value == "json",
+            "--fail-on" => fail_on = value.parse()?,
+            "--disable" => validator = validator.without_rule(value),
+            other => return Err(format!("unknown option {}\n{}", other, usage).into()),
+        }
+    }
+
+    let mappings = load_csv_data(csv_file)?;
+    let context = validation::ValidationContext {
+        mappings: &mappings,
+        attack: attack.as_ref(),
+        veris: veris.as_ref(),
+    };
+    let report = validator.validate(&context);
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&report.to_json())?);
+    } else {
+        print!("{}", report.to_text());
+    }
+    if report.fails(fail_on) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Indexes the ECS records into Elasticsearch or OpenSearch (`index` subcommand).
+///
+/// The index template is put first. `ELASTIC_API_KEY`, when set, is sent as an
+/// `ApiKey` authorization header.
+///
+/// # Arguments
+/// - `args`: `<mappings.csv> [--url <endpoint>] [--opensearch] [--prefix <index prefix>]
+///   [--batch-size <n>] [--concurrency <n>]`
+fn run_index(args: &[String]) -> Result<()> {
+    let usage = "usage: index <mappings.csv> [--url <endpoint>] [--opensearch] [--prefix <prefix>] \
+                 [--batch-size <n>] [--concurrency <n>]";
+    let csv_file = args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?;
+    let mut config = elastic::ElasticConfig {
+        authorization: std::env::var("ELASTIC_API_KEY").ok().map(|key| format!("ApiKey {}", key)),
+        ..elastic::ElasticConfig::default()
+    };
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        if flag == "--opensearch" {
+            config.flavor = elastic::ClusterFlavor::OpenSearch;
+            continue;
+        }
+        let value = rest.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--url" => config.endpoint = value.clone(),
+            "--prefix" => config.index_prefix = value.clone(),
+            "--batch-size" => config.batch_size = value.parse()?,
+            "--concurrency" => config.concurrency = value.parse()?,
+            other => return Err(format!("unknown option {}\n{}", other, usage).into()),
+        }
+    }
+
+    let mappings = load_csv_data(csv_file)?;
+    let (graph, node_indices) = create_graph(&mappings)?;
+    let analyses = perform_analyses(&graph, &mappings, &node_indices)?;
+    let options = ecs::EcsOptions {
+        index_prefix: config.index_prefix.clone(),
+        ..ecs::EcsOptions::default()
+    };
+    let records = ecs_records(&graph, &mappings, &analyses, &options);
+
+    let schema = ecs::EcsSchema::bundled();
+    schema.ensure_valid(&records)?;
+
+    let indexer = elastic::BulkIndexer::new(config);
+    indexer.put_template(&schema)?;
+    let report = indexer.index(&records)?;
+    println!(
+        "indexed {} of {} records in {} requests ({} retries), {} failed",
+        report.indexed,
+        records.len(),
+        report.requests,
+        report.retries,
+        report.failed.len()
+    );
+    for (id, reason) in &report.failed {
+        eprintln!("{}: {}", id, reason);
+    }
+    Ok(())
+}
+
+/// Writes the ATT&CK and VERIS coverage report and its Navigator layer
+/// (`coverage` subcommand).
+///
+/// Without `--veris`, the VERIS enumerations are the ones the mappings use.
+///
+/// # Arguments
+/// - `args`: `<mappings.csv> --attack <bundle.json> [--veris <verisc-enum.json>] [--out <dir>]`
+fn run_coverage(args: &[String]) -> Result<()> {
+    let usage = "usage: coverage <mappings.csv> --attack <bundle.json> [--veris <enum.json>] [--out <dir>]";
+    let csv_file = args.first().filter(|a| !a.starts_with("--")).ok_or(usage)?;
+    let mut attack = None;
+    let mut veris = None;
+    let mut output_dir = DEFAULT_COVERAGE_DIR.to_string();
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--attack" => attack = Some(catalog::AttackCatalog::from_stix_bundle(value)?),
+            "--veris" => veris = Some(catalog::VerisCatalog::from_enum_file(value)?),
+            "--out" => output_dir = value.clone(),
+            other => return Err(format!("unknown option {}\n{}", other, usage).into()),
+        }
+    }
+    let attack = attack.ok_or(usage)?;
+
+    let mappings = load_csv_data(csv_file)?;
+    let veris = veris.unwrap_or_else(|| catalog::VerisCatalog::from_mappings(&mappings));
+    let (graph, _) = create_graph(&mappings)?;
+    let report = coverage::perform_coverage_analysis(&graph, &mappings, &attack, &veris, &coverage::CoverageOptions::default());
+    coverage::export_coverage(&report, &attack, Path::new(&output_dir))?;
+    println!(
+        "{:.1}% of techniques and {:.1}% of VERIS enumerations mapped; wrote {}",
+        report.techniques.coverage_pct, report.veris.coverage_pct, output_dir
+    );
+    Ok(())
+}
+
+/// Streams mapping CSVs and STIX bundles into a CSR graph and runs the graph
+/// analyses on it (`ingest` subcommand).
+///
+/// Nothing holds all the mappings as `Mapping`s, so this is the way to analyse
+/// inputs too large for the default run. The analyses go to
+/// `./analysed/data/ingest_analyses.json`; the run report, with the input
+/// sizes and the peak heap use, to `./analysed/data/run_report.json`. Only the
+/// graph is kept; the analyses do not need the mapping rows.
+///
+/// # Arguments
+/// - `args`: `<mappings.csv | bundle.json>...`
+fn run_ingest(args: &[String]) -> Result<()> {
+    let usage = "usage: ingest <mappings.csv | bundle.json>...";
+    let started_at = chrono::Utc::now();
+    let mut inputs = Vec::new();
+    let options = ingest::IngestOptions { keep_mappings: false };
+    for arg in args {
+        match arg.as_str() {
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}\n{}", flag, usage).into()),
+            path => inputs.push(path.to_string()),
+        }
+    }
+    if inputs.is_empty() {
+        return Err(usage.into());
+    }
+
+    let ingested = ingest::ingest_paths(&inputs, options)?;
+    let graph = &ingested.graph;
+    let analyses = serde_json::json!({
+        "mapping_type_analysis": perform_mapping_type_analysis(graph),
+        "node_degree_analysis": perform_node_degree_analysis(graph),
+        "connected_components_analysis": perform_connected_components_analysis(graph),
+        "edge_strength_analysis": perform_edge_strength_analysis(graph),
+        "node_type_distribution": perform_node_type_distribution(graph),
+    });
+    export_to_json("ingest_analyses", &analyses)?;
+
+    let report = ingest::RunReport::new(inputs, started_at, ingested.report);
+    export_to_json("run_report", &report)?;
+    println!(
+        "ingested {} rows and {} STIX objects: {} nodes, {} edges, {} strings, peak heap {}",
+        report.ingest.mapping_rows,
+        report.ingest.stix_objects,
+        report.ingest.nodes,
+        report.ingest.edges,
+        report.ingest.strings,
+        report.peak_memory_bytes.map_or("unknown".to_string(), |b| format!("{} bytes", b))
+    );
+    Ok(())
+}
+
+/// Summarizes the graph nodes with an LLM (`summarize` subcommand).
+///
+/// The endpoint and model come from `LLM_ENDPOINT`, `LLM_MODEL` and `LLM_API_KEY`.
+/// Summaries are cached in `./analysed/data/summary_cache.json` and written, with
+/// the rest of each node's metadata, to `./analysed/data/node_summaries.json`.
+///
+/// # Arguments
+/// - `args`: `[<mappings.csv>] [--dry-run] [--limit <n>] [--rpm <requests per minute>]`
+fn run_summarize(args: &[String]) -> Result<()> {
+    let mut config = summarize::SummarizerConfig::from_env();
+    let mut csv_file = DEFAULT_MAPPINGS_CSV;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--dry-run" => config.dry_run = true,
+            "--limit" => config.limit = Some(rest.next().ok_or("missing value for --limit")?.parse()?),
+            "--rpm" => config.requests_per_minute = rest.next().ok_or("missing value for --rpm")?.parse()?,
+            path => csv_file = path,
+        }
+    }
+
+    let mappings = load_csv_data(csv_file)?;
+    let (mut graph, node_indices) = create_graph(&mappings)?;
+    let analyses = perform_analyses(&graph, &mappings, &node_indices)?;
+    let metrics = export::node_metrics_from_analyses(&analyses);
+
+    let dir = Path::new("./analysed/data");
+    let mut cache = summarize::SummaryCache::open(dir.join("summary_cache.json"))?;
+    let report = summarize::summarize_graph(&mut graph, &mappings, &metrics, &config, &mut cache)?;
+
+    if config.dry_run {
+        for (id, prompt) in &report.prompts {
+            println!("### {}\n{}\n", id, prompt);
+        }
+        return Ok(());
+    }
+
+    let summaries: HashMap<&str, &HashMap<String, String>> = graph
+        .node_weights()
+        .filter(|node| !node.metadata.is_empty())
+        .map(|node| (node.id.as_str(), &node.metadata))
+        .collect();
+    fs::create_dir_all(dir)?;
+    let writer = BufWriter::new(File::create(dir.join("node_summaries.json"))?);
+    serde_json::to_writer_pretty(writer, &summaries)?;
+
+    println!(
+        "summarized {} nodes, {} from cache, {} failed",
+        report.summarized,
+        report.cached,
+        report.failed.len()
+    );
+    for (id, error) in &report.failed {
+        eprintln!("{}: {}", id, error);
+    }
+    Ok(())
 }
 
 
@@ -164,26 +709,20 @@ fn create_graph(mappings: &[Mapping]) -> Result<(MappingGraph, HashMap<String, N
 /// 
 /// # Returns
 /// A Result containing the AnalysisResults struct with the results of the performed analyses.
+/// The analyses run in parallel on the pool sized by `--threads`.
 fn perform_analyses(graph: &MappingGraph, mappings: &[Mapping], node_indices: &HashMap<String, NodeIndex>) -> Result<AnalysisResults> {
-    Ok(AnalysisResults {
-        basic_stats: perform_basic_stats(graph, mappings),
-        mapping_type_analysis: perform_mapping_type_analysis(graph),
-        node_degree_analysis: perform_node_degree_analysis(graph),
-        connected_components_analysis: perform_connected_components_analysis(graph),
-        shortest_path_analysis: perform_shortest_path_analysis(graph, node_indices),
-        edge_strength_analysis: perform_edge_strength_analysis(graph),
-        node_type_distribution: perform_node_type_distribution(graph),
-        utils::calculate_strength: perform_node_type_distribution(graph),
-        temporal_analysis: perform_temporal_analysis(mappings),
-        tech_domain_analysis: perform_tech_domain_analysis(mappings),
-    })
+    Ok(petgraph_full_0x0::perform_analyses(graph, mappings, node_indices))
 }
 
 fn export_results(analyses: &AnalysisResults) -> Result<()> {
     let output_dir = Path::new("./analysed/data");
     fs::create_dir_all(output_dir)?;
 
-    for (name, data) in analyses.iter() {
+    // One file per analysis, named after its `AnalysisResults` field.
+    let Value::Object(analyses) = serde_json::to_value(analyses)? else {
+        return Err("analysis results did not serialize to an object".into());
+    };
+    for (name, data) in &analyses {
         let file = File::create(output_dir.join(format!("{}.json", name)))?;
         let writer = BufWriter::new(file);
         serde_json::to_writer_pretty(writer, data)?;
@@ -193,56 +732,144 @@ fn export_results(analyses: &AnalysisResults) -> Result<()> {
 }
 
 
-/// Export combined data to Parquet and CSV formats based on mappings and node degree analysis.
+/// Export combined data to Parquet, Arrow IPC and CSV formats based on mappings and the
+/// per-node analysis results.
 /// 
 /// # Arguments
 ///  `mappings` - A slice of Mapping structs containing the data to be exported.
-/// `node_degree_analysis` - A serde_json Value representing the node degree analysis data.
+/// `analyses` - The analysis results providing the node metrics.
 /// 
 /// # Returns
 /// A Result indicating success or an error if the export fails.
-fn export_combined_data(mappings: &[Mapping], node_degree_analysis: &serde_json::Value) -> Result<()> {
-    let combined_data = prepare_combined_data(mappings, node_degree_analysis);
-
-    // Pre-allocate vectors for DataFrame creation
-    let mut veris_ids = Vec::with_capacity(combined_data.len());
-    let mut mitre_ids = Vec::with_capacity(combined_data.len());
-    let mut mapping_types = Vec::with_capacity(combined_data.len());
-    let mut strengths = Vec::with_capacity(combined_data.len());
-    let mut frequencies = Vec::with_capacity(combined_data.len());
-    let mut impact_scores = Vec::with_capacity(combined_data.len());
-    let mut tech_domains = Vec::with_capacity(combined_data.len());
-    let mut creation_dates = Vec::with_capacity(combined_data.len());
-
-    for row in &combined_data {
-        veris_ids.push(row["veris_id"].as_str().unwrap());
-        mitre_ids.push(row["mitre_id"].as_str().unwrap());
-        mapping_types.push(row["mapping_type"].as_str().unwrap());
-        strengths.push(row["strength"].as_f64().unwrap());
-        frequencies.push(row["frequency"].as_u64().unwrap() as i32);
-        impact_scores.push(row["impact_score"].as_f64().unwrap());
-        tech_domains.push(row["technology_domain"].as_str().unwrap());
-        creation_dates.push(row["creation_date"].as_str().unwrap());
-    }
-
-    let df = DataFrame::new(vec![
-        Series::new("veris_id", veris_ids),
-        Series::new("mitre_id", mitre_ids),
-        Series::new("mapping_type", mapping_types),
-        Series::new("strength", strengths),
-        Series::new("frequency", frequencies),
-        Series::new("impact_score", impact_scores),
-        Series::new("technology_domain", tech_domains),
-        Series::new("creation_date", creation_dates),
-    ])?;
-
-    // Export to Parquet
-    let file = File::create("./analysed/data/combined_analysis.parquet")?;
-    ParquetWriter::new(file).finish(&df)?;
-
-    // Export to CSV
-    let file = File::create("./analysed/ds/combined_analysis.csv")?;
-    CsvWriter::new(file).finish(&mut df)?;
+fn export_combined_data(mappings: &[Mapping], analyses: &AnalysisResults) -> Result<()> {
+    let metrics = export::node_metrics_from_analyses(analyses);
+    let options = export::ExportOptions {
+        include_all_mapping_fields: true,
+        include_node_metrics: true,
+        ..Default::default()
+    };
+    export::export_combined(mappings, &metrics, &options)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Extracts entity mentions from every report in a directory and links the
+/// reports into the graph. The mentions are written to
+/// `./analysed/data/report_mentions.json`.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph; report nodes and `mentions` edges are added.
+/// - `node_indices`: Node IDs to graph indices.
+/// - `mappings`: The mappings, for technique names and VERIS terms.
+/// - `dir`: Directory of `.txt`, `.md` and `.html` reports.
+///
+/// # Returns
+///
+/// - `Result<()>`: Indicates success or failure of reading and exporting.
+fn link_reports(
+    graph: &mut MappingGraph,
+    node_indices: &mut HashMap<String, NodeIndex>,
+    mappings: &[Mapping],
+    dir: &Path,
+) -> Result<()> {
+    let dictionary = extraction::EntityDictionary::from_graph(graph).with_mappings(mappings);
+    let options = extraction::ExtractionOptions::default();
+
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    paths.retain(|p| p.is_file());
+    paths.sort();
+
+    let mut reports = vec![];
+    for path in paths {
+        let report = extraction::extract_file(&dictionary, &path, &options)?;
+        extraction::link_report(graph, node_indices, &report);
+        reports.push(report);
+    }
+
+    let output_dir = Path::new("./analysed/data");
+    fs::create_dir_all(output_dir)?;
+    let writer = BufWriter::new(File::create(output_dir.join("report_mentions.json"))?);
+    serde_json::to_writer_pretty(writer, &extraction::perform_report_extraction(&reports))?;
+
+    Ok(())
+}
+
+/// Writes node and neighborhood chunks to `./analysed/data/rag_chunks.jsonl`.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `mappings`: The mappings, for labels and technology domains.
+/// - `analyses`: Analysis results whose per-node metrics go into the chunks.
+///
+/// # Returns
+///
+/// - `Result<()>`: Indicates success or failure of the export.
+fn export_rag_chunks(
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    analyses: &AnalysisResults,
+) -> Result<()> {
+    let metrics = export::node_metrics_from_analyses(analyses);
+    let chunks = rag::build_chunks(graph, mappings, &metrics, &rag::RagOptions::default());
+    let dir = Path::new("./analysed/data");
+    fs::create_dir_all(dir)?;
+    rag::write_jsonl(&chunks, dir.join("rag_chunks.jsonl"))?;
+
+    Ok(())
+}
+
+/// Builds the node, edge, mapping and analysis records in ECS.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `mappings`: The mapping rows.
+/// - `analyses`: Analysis results; their per-node metrics also go into the node records.
+/// - `options`: Index prefix, timestamp and ECS version of the records.
+///
+/// # Returns
+///
+/// - `Vec<ecs::EcsRecord>`: The records, nodes first.
+fn ecs_records(
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    analyses: &AnalysisResults,
+    options: &ecs::EcsOptions,
+) -> Vec<ecs::EcsRecord> {
+    let metrics = export::node_metrics_from_analyses(analyses);
+    let mut records = ecs::node_records(graph, &metrics, options);
+    records.extend(ecs::edge_records(graph, options));
+    records.extend(ecs::mapping_records(mappings, options));
+    records.extend(ecs::analysis_records(analyses, options));
+    records
+}
+
+/// Writes node, edge, mapping and analysis records, validated against the bundled
+/// ECS schema, to `./analysed/data/ecs_bulk.ndjson`.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `mappings`: The mapping rows.
+/// - `analyses`: The analysis results.
+///
+/// # Returns
+///
+/// - `Result<()>`: An error if a record violates the schema or the file cannot be written.
+fn export_ecs_records(
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    analyses: &AnalysisResults,
+) -> Result<()> {
+    let records = ecs_records(graph, mappings, analyses, &ecs::EcsOptions::default());
+
+    let dir = Path::new("./analysed/data");
+    fs::create_dir_all(dir)?;
+    ecs::export_bulk(&ecs::EcsSchema::bundled(), &records, dir.join("ecs_bulk.ndjson"))?;
+
+    Ok(())
+}
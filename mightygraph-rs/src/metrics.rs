@@ -0,0 +1,441 @@
+//! Prometheus metrics and a Grafana dashboard for them.
+//!
+//! [`Metrics`] is a small registry of counters, gauges and summaries that
+//! renders the Prometheus text exposition format. The process-wide registry
+//! is [`global`]; analyses run through [`crate::cache::cached_analysis`]
+//! record their durations there.
+//!
+//! | Metric                                   | Type    | Labels              |
+//! |------------------------------------------|---------|---------------------|
+//! | `mightygraph_graph_nodes`                | gauge   | `node_type`         |
+//! | `mightygraph_graph_edges`                | gauge   |                     |
+//! | `mightygraph_mappings`                   | gauge   |                     |
+//! | `mightygraph_unparsed_dates`             | gauge   | `field`             |
+//! | `mightygraph_analysis_duration_seconds`  | summary | `analysis`          |
+//! | `mightygraph_loader_errors_total`        | counter | `source`            |
+//! | `mightygraph_reloads_total`              | counter | `outcome`           |
+//! | `mightygraph_coverage_percent`           | gauge   | `scope`             |
+//! | `mightygraph_tactic_coverage_percent`    | gauge   | `tactic`            |
+//! | `mightygraph_novelty_score`              | gauge   | `node`, `name`      |
+//! | `mightygraph_cache_requests_total`       | counter | `backend`, `result` |
+//! | `mightygraph_peak_memory_bytes`          | gauge   |                     |
+//!
+//! In batch mode the registry is written to a node exporter textfile
+//! collector directory with [`Metrics::write_textfile`]; in server mode it is
+//! served on `GET /metrics`. [`grafana_dashboard`] builds a dashboard over
+//! these metrics for import into Grafana.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use serde_json::json;
+
+use crate::cache::CacheStats;
+use crate::coverage::CoverageReport;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::temporal::{parsed_dates, DateField};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// `Content-Type` of the text exposition format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub const GRAPH_NODES: &str = "mightygraph_graph_nodes";
+pub const GRAPH_EDGES: &str = "mightygraph_graph_edges";
+pub const MAPPINGS: &str = "mightygraph_mappings";
+pub const UNPARSED_DATES: &str = "mightygraph_unparsed_dates";
+pub const ANALYSIS_DURATION: &str = "mightygraph_analysis_duration_seconds";
+pub const LOADER_ERRORS: &str = "mightygraph_loader_errors_total";
+pub const RELOADS: &str = "mightygraph_reloads_total";
+pub const COVERAGE: &str = "mightygraph_coverage_percent";
+pub const TACTIC_COVERAGE: &str = "mightygraph_tactic_coverage_percent";
+pub const NOVELTY_SCORE: &str = "mightygraph_novelty_score";
+pub const CACHE_REQUESTS: &str = "mightygraph_cache_requests_total";
+pub const PEAK_MEMORY: &str = "mightygraph_peak_memory_bytes";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    /// Sum and count of observations, without quantiles.
+    Summary,
+}
+
+impl MetricKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Summary => "summary",
+        }
+    }
+}
+
+/// Label pairs, sorted by name.
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Sample {
+    value: f64,
+    count: u64,
+}
+
+#[derive(Debug)]
+struct Family {
+    help: String,
+    kind: MetricKind,
+    samples: BTreeMap<Labels, Sample>,
+}
+
+/// A registry of metric families.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    families: Mutex<BTreeMap<String, Family>>,
+}
+
+/// The process-wide registry.
+pub fn global() -> &'static Metrics {
+    static GLOBAL: OnceLock<Metrics> = OnceLock::new();
+    GLOBAL.get_or_init(Metrics::new)
+}
+
+fn labels(pairs: &[(&str, &str)]) -> Labels {
+    let mut labels: Labels = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    labels.sort();
+    labels
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        let sign = if value > 0.0 { "+" } else { "-" };
+        format!("{}Inf", sign)
+    } else {
+        value.to_string()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    fn update(
+        &self,
+        name: &str,
+        help: &str,
+        kind: MetricKind,
+        pairs: &[(&str, &str)],
+        apply: impl FnOnce(&mut Sample),
+    ) {
+        let mut families = self.families.lock().unwrap();
+        let family = families.entry(name.to_string()).or_insert_with(|| Family {
+            help: help.to_string(),
+            kind,
+            samples: BTreeMap::new(),
+        });
+        apply(family.samples.entry(labels(pairs)).or_default());
+    }
+
+    pub fn set_gauge(&self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, help, MetricKind::Gauge, labels, |s| s.value = value);
+    }
+
+    pub fn inc_counter(&self, name: &str, help: &str, labels: &[(&str, &str)], by: f64) {
+        self.update(name, help, MetricKind::Counter, labels, |s| s.value += by);
+    }
+
+    /// Sets a counter whose total is kept elsewhere.
+    pub fn set_counter(&self, name: &str, help: &str, labels: &[(&str, &str)], total: f64) {
+        self.update(name, help, MetricKind::Counter, labels, |s| s.value = total);
+    }
+
+    /// Adds an observation to a summary.
+    pub fn observe(&self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, help, MetricKind::Summary, labels, |s| {
+            s.value += value;
+            s.count += 1;
+        });
+    }
+
+    /// Drops every sample of a family, e.g. before recording a new top-N.
+    pub fn reset(&self, name: &str) {
+        if let Some(family) = self.families.lock().unwrap().get_mut(name) {
+            family.samples.clear();
+        }
+    }
+
+    /// Runs an analysis and observes its duration in [`ANALYSIS_DURATION`].
+    pub fn time_analysis<T>(&self, analysis: &str, run: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = run();
+        self.observe(
+            ANALYSIS_DURATION,
+            "Time spent computing an analysis.",
+            &[("analysis", analysis)],
+            started.elapsed().as_secs_f64(),
+        );
+        result
+    }
+
+    /// The current value of a counter or gauge sample.
+    pub fn value(&self, name: &str, pairs: &[(&str, &str)]) -> Option<f64> {
+        let families = self.families.lock().unwrap();
+        families.get(name)?.samples.get(&labels(pairs)).map(|s| s.value)
+    }
+
+    /// Renders the text exposition format.
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap();
+        let mut text = String::new();
+        for (name, family) in families.iter().filter(|(_, f)| !f.samples.is_empty()) {
+            let help = family.help.replace('\\', "\\\\").replace('\n', "\\n");
+            let _ = writeln!(text, "# HELP {} {}", name, help);
+            let _ = writeln!(text, "# TYPE {} {}", name, family.kind.as_str());
+            for (labels, sample) in &family.samples {
+                let labels = if labels.is_empty() {
+                    String::new()
+                } else {
+                    let pairs: Vec<String> = labels
+                        .iter()
+                        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                        .collect();
+                    format!("{{{}}}", pairs.join(","))
+                };
+                if family.kind == MetricKind::Summary {
+                    let sum = format_value(sample.value);
+                    let _ = writeln!(text, "{}_sum{} {}", name, labels, sum);
+                    let _ = writeln!(text, "{}_count{} {}", name, labels, sample.count);
+                } else {
+                    let _ = writeln!(text, "{}{} {}", name, labels, format_value(sample.value));
+                }
+            }
+        }
+        text
+    }
+
+    /// Writes the metrics for the node exporter textfile collector.
+    ///
+    /// The file is written next to its final name and renamed into place, so
+    /// the collector never reads a partial file.
+    ///
+    /// # Arguments
+    ///
+    /// - `path`: The `.prom` file, inside the collector's directory.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<()>`: Indicates success or failure of the write.
+    pub fn write_textfile<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let temporary = path.with_extension("prom.tmp");
+        fs::write(&temporary, self.render())?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+/// Records graph size and the number of unparseable mapping dates.
+pub fn record_graph(metrics: &Metrics, graph: &MappingGraph, mappings: &[Mapping]) {
+    metrics.reset(GRAPH_NODES);
+    let mut by_type: BTreeMap<String, usize> = BTreeMap::new();
+    for node in graph.node_weights() {
+        *by_type.entry(format!("{:?}", node.node_type)).or_default() += 1;
+    }
+    for (node_type, count) in &by_type {
+        let help = "Nodes in the mapping graph.";
+        metrics.set_gauge(GRAPH_NODES, help, &[("node_type", node_type)], *count as f64);
+    }
+    let help = "Edges in the mapping graph.";
+    metrics.set_gauge(GRAPH_EDGES, help, &[], graph.edge_count() as f64);
+    metrics.set_gauge(MAPPINGS, "Mapping rows loaded.", &[], mappings.len() as f64);
+
+    for (field, date_field) in [
+        ("creation_date", DateField::CreationDate),
+        ("last_update", DateField::LastUpdate),
+    ] {
+        let unparsed = mappings.len() - parsed_dates(mappings, date_field).len();
+        let help = "Mapping rows whose date does not parse.";
+        metrics.set_gauge(UNPARSED_DATES, help, &[("field", field)], unparsed as f64);
+    }
+}
+
+/// Counts a failed load, e.g. a reload that kept the previous graph.
+pub fn record_loader_error(metrics: &Metrics, source: &str) {
+    let help = "Inputs that failed to load.";
+    metrics.inc_counter(LOADER_ERRORS, help, &[("source", source)], 1.0);
+}
+
+/// Counts a reload of the served graph after its input files changed.
+pub fn record_reload(metrics: &Metrics, succeeded: bool) {
+    let outcome = if succeeded { "ok" } else { "failed" };
+    let help = "Reloads of the served graph.";
+    metrics.inc_counter(RELOADS, help, &[("outcome", outcome)], 1.0);
+}
+
+/// Records overall and per-tactic coverage.
+pub fn record_coverage(metrics: &Metrics, report: &CoverageReport) {
+    let help = "Share of catalog entries with at least one mapping.";
+    metrics.set_gauge(COVERAGE, help, &[("scope", "techniques")], report.techniques.coverage_pct);
+    metrics.set_gauge(COVERAGE, help, &[("scope", "veris")], report.veris.coverage_pct);
+    metrics.reset(TACTIC_COVERAGE);
+    for (tactic, bucket) in &report.by_tactic {
+        let help = "Share of a tactic's techniques with at least one mapping.";
+        metrics.set_gauge(TACTIC_COVERAGE, help, &[("tactic", tactic)], bucket.coverage_pct);
+    }
+}
+
+/// Records the `top_n` highest novelty scores, replacing earlier ones.
+///
+/// # Arguments
+///
+/// - `metrics`: The registry.
+/// - `scores`: `(node ID, name, score)` of any number of nodes.
+/// - `top_n`: How many to keep.
+pub fn record_novelty(metrics: &Metrics, scores: &[(String, String, f64)], top_n: usize) {
+    let mut top: Vec<&(String, String, f64)> = scores.iter().collect();
+    top.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    metrics.reset(NOVELTY_SCORE);
+    for (node, name, score) in top.into_iter().take(top_n) {
+        let help = "Novelty score of the most novel techniques.";
+        metrics.set_gauge(NOVELTY_SCORE, help, &[("node", node), ("name", name)], *score);
+    }
+}
+
+/// Records the highest heap use of a run.
+pub fn record_peak_memory(metrics: &Metrics, bytes: usize) {
+    metrics.set_gauge(PEAK_MEMORY, "Highest heap use of the run.", &[], bytes as f64);
+}
+
+/// Records analysis cache hits and misses.
+pub fn record_cache(metrics: &Metrics, stats: &CacheStats) {
+    let help = "Analysis cache lookups.";
+    for (result, count) in [("hit", stats.hits), ("miss", stats.misses)] {
+        let labels = [("backend", stats.backend.as_str()), ("result", result)];
+        metrics.set_counter(CACHE_REQUESTS, help, &labels, count as f64);
+    }
+}
+
+fn panel(id: u32, title: &str, kind: &str, grid: [u32; 4], targets: Value) -> Value {
+    json!({
+        "id": id,
+        "title": title,
+        "type": kind,
+        "datasource": { "type": "prometheus", "uid": "${datasource}" },
+        "gridPos": { "x": grid[0], "y": grid[1], "w": grid[2], "h": grid[3] },
+        "targets": targets,
+    })
+}
+
+fn target(ref_id: &str, expr: &str, legend: &str) -> Value {
+    json!({
+        "refId": ref_id,
+        "datasource": { "type": "prometheus", "uid": "${datasource}" },
+        "expr": expr,
+        "legendFormat": legend,
+    })
+}
+
+/// A Grafana dashboard over the metrics of this module.
+///
+/// # Returns
+///
+/// - `Value`: Dashboard JSON with a `datasource` variable for the Prometheus
+///   data source, ready for import.
+pub fn grafana_dashboard() -> Value {
+    let mut coverage = panel(
+        5,
+        "Coverage by tactic",
+        "bargauge",
+        [0, 16, 12, 10],
+        json!([target("A", TACTIC_COVERAGE, "{{tactic}}")]),
+    );
+    coverage["fieldConfig"] = json!({ "defaults": { "unit": "percent", "min": 0, "max": 100 } });
+    coverage["options"] = json!({ "orientation": "horizontal", "displayMode": "gradient" });
+
+    let mut novelty = panel(
+        6,
+        "Top novelty scores",
+        "table",
+        [12, 16, 12, 10],
+        json!([{
+            "refId": "A",
+            "datasource": { "type": "prometheus", "uid": "${datasource}" },
+            "expr": format!("sort_desc({})", NOVELTY_SCORE),
+            "format": "table",
+            "instant": true,
+        }]),
+    );
+    novelty["transformations"] = json!([{
+        "id": "organize",
+        "options": {
+            "excludeByName": { "Time": true, "__name__": true, "instance": true, "job": true }
+        }
+    }]);
+
+    let mut durations = panel(
+        3,
+        "Analysis duration (mean)",
+        "timeseries",
+        [0, 8, 12, 8],
+        json!([target(
+            "A",
+            &format!("{0}_sum / {0}_count", ANALYSIS_DURATION),
+            "{{analysis}}"
+        )]),
+    );
+    durations["fieldConfig"] = json!({ "defaults": { "unit": "s" } });
+
+    json!({
+        "uid": "mightygraph",
+        "title": "MightyGraph",
+        "tags": ["mightygraph", "attack", "veris"],
+        "timezone": "browser",
+        "schemaVersion": 39,
+        "refresh": "1m",
+        "time": { "from": "now-7d", "to": "now" },
+        "templating": {
+            "list": [{
+                "name": "datasource",
+                "label": "Data source",
+                "type": "datasource",
+                "query": "prometheus",
+            }]
+        },
+        "panels": [
+            panel(1, "Nodes by type", "timeseries", [0, 0, 12, 8],
+                json!([target("A", GRAPH_NODES, "{{node_type}}")])),
+            panel(2, "Edges and mappings", "stat", [12, 0, 12, 8],
+                json!([target("A", GRAPH_EDGES, "edges"), target("B", MAPPINGS, "mappings")])),
+            durations,
+            panel(4, "Loader errors", "timeseries", [12, 8, 12, 8], json!([
+                target("A", &format!("increase({}[1h])", LOADER_ERRORS), "{{source}}"),
+                target("B", UNPARSED_DATES, "unparsed {{field}}"),
+            ])),
+            coverage,
+            novelty,
+        ],
+    })
+}
+
+/// Writes [`grafana_dashboard`] to a file.
+pub fn export_dashboard<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&grafana_dashboard())?)?;
+    Ok(())
+}
@@ -1,3 +0,0 @@
-pub mod main;
-pub mod petgraph_full_0x0;
-pub mod subgraph_optimized;
\ No newline at end of file
@@ -0,0 +1,313 @@
+//! ATT&CK Navigator layer files (layer format 4.5).
+//!
+//! [`layer_from_metric`] turns any per-technique metric into a layer; the
+//! `*_metric` helpers extract the metrics the crate computes.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::catalog::AttackCatalog;
+use crate::coverage::CoverageReport;
+use crate::export::node_metric_from_value;
+use crate::petgraph_full_0x0::perform_node_degree_analysis;
+use crate::petgraph_full_0x0::prelude::*;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+pub const LAYER_FORMAT_VERSION: &str = "4.5";
+pub const NAVIGATOR_VERSION: &str = "4.9.1";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerVersions {
+    pub attack: String,
+    pub navigator: String,
+    pub layer: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerGradient {
+    pub colors: Vec<String>,
+    #[serde(rename = "minValue")]
+    pub min_value: f64,
+    #[serde(rename = "maxValue")]
+    pub max_value: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerMetadata {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerLegendItem {
+    pub label: String,
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayerTechnique {
+    #[serde(rename = "techniqueID")]
+    pub technique_id: String,
+    /// Restricts the annotation to one tactic column; `None` applies it to all.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tactic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    pub color: String,
+    pub comment: String,
+    pub enabled: bool,
+    pub metadata: Vec<LayerMetadata>,
+    pub show_subtechniques: bool,
+}
+
+/// A Navigator layer, serialized with the field names Navigator expects.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavigatorLayer {
+    pub name: String,
+    pub versions: LayerVersions,
+    /// `enterprise-attack` unless set from the catalog (see [`layer_from_metric`]).
+    pub domain: String,
+    pub description: String,
+    pub sorting: u8,
+    pub hide_disabled: bool,
+    pub techniques: Vec<LayerTechnique>,
+    pub gradient: LayerGradient,
+    pub legend_items: Vec<LayerLegendItem>,
+    pub metadata: Vec<LayerMetadata>,
+    pub show_tactic_row_background: bool,
+    pub select_techniques_across_tactics: bool,
+    pub select_subtechniques_with_parent: bool,
+}
+
+impl NavigatorLayer {
+    /// An empty layer with Navigator's default red-yellow-green gradient.
+    pub fn new(name: &str, description: &str, attack_version: Option<&str>) -> NavigatorLayer {
+        NavigatorLayer {
+            name: name.to_string(),
+            versions: LayerVersions {
+                attack: attack_version.unwrap_or("14").to_string(),
+                navigator: NAVIGATOR_VERSION.to_string(),
+                layer: LAYER_FORMAT_VERSION.to_string(),
+            },
+            domain: "enterprise-attack".to_string(),
+            description: description.to_string(),
+            sorting: 0,
+            hide_disabled: false,
+            techniques: vec![],
+            gradient: LayerGradient {
+                colors: vec![
+                    "#ff6666ff".to_string(),
+                    "#ffe766ff".to_string(),
+                    "#8ec843ff".to_string(),
+                ],
+                min_value: 0.0,
+                max_value: 100.0,
+            },
+            legend_items: vec![],
+            metadata: vec![],
+            show_tactic_row_background: false,
+            select_techniques_across_tactics: true,
+            select_subtechniques_with_parent: false,
+        }
+    }
+
+    /// Writes the layer as pretty-printed JSON.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
+
+/// How a per-technique metric is rendered into a layer.
+#[derive(Debug, Clone)]
+pub struct LayerOptions {
+    pub name: String,
+    pub description: String,
+    /// `None` scales Navigator's default gradient to the metric's min/max.
+    pub gradient: Option<LayerGradient>,
+    /// Only annotates techniques in these tactics; empty means all tactics.
+    pub tactics: Vec<String>,
+    /// Emits one entry per (technique, tactic) instead of one per technique,
+    /// so the annotation only shows in the scoped tactic columns.
+    pub per_tactic: bool,
+    /// Lists the mapped VERIS IDs in each technique's comment.
+    pub comment_veris_ids: bool,
+    /// Adds catalog techniques without a score as disabled entries.
+    pub disable_unscored: bool,
+    pub legend_items: Vec<LayerLegendItem>,
+}
+
+impl Default for LayerOptions {
+    fn default() -> Self {
+        LayerOptions {
+            name: "mightygraph metric".to_string(),
+            description: String::new(),
+            gradient: None,
+            tactics: vec![],
+            per_tactic: false,
+            comment_veris_ids: true,
+            disable_unscored: false,
+            legend_items: vec![],
+        }
+    }
+}
+
+/// Builds a layer from any per-technique metric.
+///
+/// # Arguments
+///
+/// - `metric`: Score by ATT&CK ID; IDs missing from the catalog are skipped.
+/// - `attack`: The ATT&CK catalog, for tactics, the domain and the ATT&CK version.
+/// - `veris_by_technique`: Mapped VERIS IDs per technique, used for comments
+///   (see `coverage::veris_by_technique`).
+/// - `options`: Gradient, tactic scoping and comment options.
+///
+/// # Returns
+///
+/// - `NavigatorLayer`: The layer, with techniques sorted by ID.
+pub fn layer_from_metric(
+    metric: &HashMap<String, f64>,
+    attack: &AttackCatalog,
+    veris_by_technique: &BTreeMap<String, Vec<String>>,
+    options: &LayerOptions,
+) -> NavigatorLayer {
+    let mut layer = NavigatorLayer::new(
+        &options.name,
+        &options.description,
+        attack.version.as_deref(),
+    );
+    layer.legend_items = options.legend_items.clone();
+    if let Some(domain) = attack.domain() {
+        layer.domain = domain.to_string();
+    }
+
+    let in_scope = |tactic: &String| options.tactics.is_empty() || options.tactics.contains(tactic);
+    let mut min = f64::INFINITY;
+    let mut max = f64::NEG_INFINITY;
+
+    for technique in attack.active_techniques() {
+        let tactics: Vec<&String> = technique.tactics.iter().filter(|t| in_scope(t)).collect();
+        if tactics.is_empty() {
+            continue;
+        }
+        let score = metric.get(&technique.id).copied();
+        if score.is_none() && !options.disable_unscored {
+            continue;
+        }
+        if let Some(score) = score {
+            min = min.min(score);
+            max = max.max(score);
+        }
+
+        let comment = if options.comment_veris_ids {
+            veris_by_technique
+                .get(&technique.id)
+                .map(|ids| ids.join(", "))
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        let entry = |tactic: Option<String>| LayerTechnique {
+            technique_id: technique.id.clone(),
+            tactic,
+            score,
+            color: String::new(),
+            comment: comment.clone(),
+            enabled: score.is_some(),
+            metadata: vec![],
+            show_subtechniques: false,
+        };
+
+        if options.per_tactic || !options.tactics.is_empty() {
+            layer
+                .techniques
+                .extend(tactics.into_iter().map(|t| entry(Some(t.clone()))));
+        } else {
+            layer.techniques.push(entry(None));
+        }
+    }
+
+    layer.gradient = options.gradient.clone().unwrap_or_else(|| {
+        let mut gradient = layer.gradient.clone();
+        if min.is_finite() {
+            gradient.min_value = min;
+            gradient.max_value = if max > min { max } else { min + 1.0 };
+        }
+        gradient
+    });
+
+    layer
+}
+
+/// Technique degree from `perform_node_degree_analysis`.
+pub fn degree_metric(graph: &MappingGraph) -> HashMap<String, f64> {
+    node_metric_from_value(&perform_node_degree_analysis(graph))
+}
+
+/// Highest `impact_score` per technique from `prepare_combined_data` rows.
+pub fn impact_score_metric(combined_data: &[Value]) -> HashMap<String, f64> {
+    let mut metric = HashMap::new();
+    for row in combined_data {
+        if let (Some(id), Some(score)) = (row["mitre_id"].as_str(), row["impact_score"].as_f64()) {
+            let entry = metric.entry(id.to_string()).or_insert(score);
+            *entry = f64::max(*entry, score);
+        }
+    }
+    metric
+}
+
+/// Number of mapped VERIS IDs per covered technique.
+pub fn coverage_metric(report: &CoverageReport) -> HashMap<String, f64> {
+    report
+        .veris_by_technique
+        .iter()
+        .map(|(id, ids)| (id.clone(), ids.len() as f64))
+        .collect()
+}
+
+/// Any `(technique ID, score)` pairs, e.g. novelty scores or incident frequencies.
+pub fn metric_from_pairs<I, S>(pairs: I) -> HashMap<String, f64>
+where
+    I: IntoIterator<Item = (S, f64)>,
+    S: Into<String>,
+{
+    pairs
+        .into_iter()
+        .map(|(id, score)| (id.into(), score))
+        .collect()
+}
+
+/// Builds a layer scoring every active technique 1 if it has a VERIS
+/// mapping and 0 otherwise, with the mapped VERIS IDs as comment.
+pub fn coverage_layer(report: &CoverageReport, attack: &AttackCatalog) -> NavigatorLayer {
+    let metric = attack
+        .active_techniques()
+        .map(|t| {
+            let mapped = report.veris_by_technique.contains_key(&t.id);
+            (t.id.clone(), if mapped { 1.0 } else { 0.0 })
+        })
+        .collect();
+    let options = LayerOptions {
+        name: "VERIS coverage".to_string(),
+        description: format!(
+            "ATT&CK techniques with at least one VERIS mapping ({:.1}% covered)",
+            report.techniques.coverage_pct
+        ),
+        gradient: Some(LayerGradient {
+            colors: vec!["#ff6666ff".to_string(), "#8ec843ff".to_string()],
+            min_value: 0.0,
+            max_value: 1.0,
+        }),
+        ..Default::default()
+    };
+
+    layer_from_metric(&metric, attack, &report.veris_by_technique, &options)
+}
@@ -1,147 +1,23 @@
-
-
-use crate::add_node_if_not_exists;
 pub mod prelude;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::BufReader;
 use std::path::Path;
-use chrono::NaiveDate;
-use petgraph::graph::{Graph, NodeIndex};
-use petgraph::algo::{connected_components, dijkstra};
+use crate::centrality::{perform_closeness_analysis, perform_path_length_analysis};
+use crate::csr::{connected_component_count, GraphView};
+use crate::frames::{
+    counts_to_json, edge_strength_analysis_lazy, edges_frame, mapping_type_analysis_lazy,
+    mappings_frame, node_degree_analysis_lazy, node_table, node_type_distribution_lazy,
+    object_to_json, pairs_to_json, tech_domain_analysis_lazy, temporal_analysis,
+};
+use crate::link_prediction::{perform_link_prediction, LinkPredictionOptions};
+use petgraph::graph::NodeIndex;
+use petgraph::algo::dijkstra;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use polars::prelude::*;
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Mapping {
-    mapping_framework: String,
-    mapping_framework_version: String,
-    capability_group: String,
-    capability_id: String,
-    capability_description: String,
-    mapping_type: String,
-    attack_object_id: String,
-    attack_object_name: String,
-    attack_version: String,
-    technology_domain: String,
-    references: String,
-    comments: String,
-    organization: String,
-    creation_date: String,
-    last_update: String,
-}
-
-#[derive(Debug, Serialize)]
-pub struct NodeData {
-    id: String,
-    node_type: NodeType,
-    metadata: HashMap<String, String>,
-}
-
-#[derive(Debug, PartialEq, Serialize)]
-enum NodeType {
-    Veris,
-    Mitre,
-}
-
-#[derive(Debug, Serialize)]
-pub struct EdgeData {
-    mapping_type: String,
-    strength: f32,
-}
-
-type MappingGraph = Graph<NodeData, EdgeData>;
-
-pub fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Load the CSV data
-    let file = File::open("veris_mitre_mapping.csv")?;
-    let reader = BufReader::new(file);
-    let mut rdr = csv::Reader::from_reader(reader);
-    let mappings: Vec<Mapping> = rdr.deserialize().collect::<Result<_, _>>()?;
-
-    // Create the graph
-    let mut graph = Graph::<NodeData, EdgeData>::new();
-    let mut node_indices = HashMap::new();
-
-    for mapping in &mappings {
-        let veris_index = add_node_if_not_exists(&mut graph, &mut node_indices, &mapping.capability_id, NodeType::Veris);
-        let mitre_index = add_node_if_not_exists(&mut graph, &mut node_indices, &mapping.attack_object_id, NodeType::Mitre);
-
-        let strength = calculate_strength(&mapping);
-        graph.add_edge(veris_index, mitre_index, EdgeData {
-            mapping_type: mapping.mapping_type.clone(),
-            strength,
-        });
-    }
-
-    // Perform analyses
-    let basic_stats = perform_basic_stats(&graph, &mappings);
-    let mapping_type_analysis = perform_mapping_type_analysis(&graph);
-    let node_degree_analysis = perform_node_degree_analysis(&graph);
-    let connected_components_analysis = perform_connected_components_analysis(&graph);
-    let shortest_path_analysis = perform_shortest_path_analysis(&graph, &node_indices);
-    let edge_strength_analysis = perform_edge_strength_analysis(&graph);
-    let node_type_distribution = perform_node_type_distribution(&graph);
-    let temporal_analysis = perform_temporal_analysis(&mappings);
-    let tech_domain_analysis = perform_tech_domain_analysis(&mappings);
-
-    // 1. Export each artifact to a JSON file
-    export_to_json("basic_stats", &basic_stats)?;
-    export_to_json("mapping_type_analysis", &mapping_type_analysis)?;
-    export_to_json("node_degree_analysis", &node_degree_analysis)?;
-    export_to_json("connected_components_analysis", &connected_components_analysis)?;
-    export_to_json("shortest_path_analysis", &shortest_path_analysis)?;
-    export_to_json("edge_strength_analysis", &edge_strength_analysis)?;
-    export_to_json("node_type_distribution", &node_type_distribution)?;
-    export_to_json("temporal_analysis", &temporal_analysis)?;
-    export_to_json("tech_domain_analysis", &tech_domain_analysis)?;
-
-    // 2 & 3. Combine all information into Parquet and CSV files
-    let mut combined_data = vec![];
-    for mapping in &mappings {
-        let frequency = node_degree_analysis.get(&mapping.attack_object_id).unwrap_or(&0);
-        let strength = calculate_strength(&mapping);
-        let impact_score = (*frequency * strength) / 10.0; // Normalize to 0-10 scale
-
-        combined_data.push(json!({
-            "veris_id": mapping.capability_id,
-            "mitre_id": mapping.attack_object_id,
-            "mapping_type": mapping.mapping_type,
-            "strength": strength,
-            "frequency": frequency,
-            "impact_score": impact_score,
-            "technology_domain": mapping.technology_domain,
-            "creation_date": mapping.creation_date,
-        }));
-    }
-
-    // Sort by impact score (highest to lowest)
-    combined_data.sort_by(|a, b| b["impact_score"].as_f64().partial_cmp(&a["impact_score"].as_f64()).unwrap());
-
-    // Create DataFrame
-    let df = DataFrame::new(vec![
-        Series::new("veris_id", combined_data.iter().map(|row| row["veris_id"].as_str().unwrap()).collect::<Vec<_>>()),
-        Series::new("mitre_id", combined_data.iter().map(|row| row["mitre_id"].as_str().unwrap()).collect::<Vec<_>>()),
-        Series::new("mapping_type", combined_data.iter().map(|row| row["mapping_type"].as_str().unwrap()).collect::<Vec<_>>()),
-        Series::new("strength", combined_data.iter().map(|row| row["strength"].as_f64().unwrap()).collect::<Vec<_>>()),
-        Series::new("frequency", combined_data.iter().map(|row| row["frequency"].as_u64().unwrap() as i32).collect::<Vec<_>>()),
-        Series::new("impact_score", combined_data.iter().map(|row| row["impact_score"].as_f64().unwrap()).collect::<Vec<_>>()),
-        Series::new("technology_domain", combined_data.iter().map(|row| row["technology_domain"].as_str().unwrap()).collect::<Vec<_>>()),
-        Series::new("creation_date", combined_data.iter().map(|row| row["creation_date"].as_str().unwrap()).collect::<Vec<_>>()),
-    ])?;
-
-    // Export to Parquet
-    let mut file = File::create("./analysed/data/combined_analysis.parquet")?;
-    polars::prelude::ParquetWriter::new(&mut file).finish(&df)?;
-
-    // Export to CSV
-    let mut file = File::create("./analysed/ds/combined_analysis.csv")?;
-    CsvWriter::new(&mut file).finish(&mut df)?;
-
-    Ok(())
-}
+use rayon::prelude::*;
+use self::prelude::{AnalysisResults, Mapping, MappingGraph};
 
 pub fn export_to_json<T: Serialize>(name: &str, data: &T) -> Result<(), Box<dyn std::error::Error>> {
     let dir = Path::new("./analysed/data");
@@ -152,9 +28,7 @@ pub fn export_to_json<T: Serialize>(name: &str, data: &T) -> Result<(), Box<dyn
     Ok(())
 }
 
-
-
-pub fn perform_basic_stats(graph: &MappingGraph, mappings: &[Mapping]) -> serde_json::Value {
+pub fn perform_basic_stats<G: GraphView>(graph: &G, mappings: &[Mapping]) -> serde_json::Value {
     json!({
         "total_mappings": mappings.len(),
         "total_nodes": graph.node_count(),
@@ -162,32 +36,29 @@ pub fn perform_basic_stats(graph: &MappingGraph, mappings: &[Mapping]) -> serde_
     })
 }
 
-pub fn perform_mapping_type_analysis(graph: &MappingGraph) -> serde_json::Value {
-    let mapping_type_counts = graph.edge_indices()
-        .map(|e| &graph[e].mapping_type)
-        .fold(HashMap::new(), |mut acc, mt| {
-            *acc.entry(mt).or_insert(0) += 1;
-            acc
-        });
-    json!(mapping_type_counts)
+pub fn perform_mapping_type_analysis<G: GraphView>(graph: &G) -> serde_json::Value {
+    frame_analysis(|| {
+        let counts = mapping_type_analysis_lazy(edges_frame(graph)?.lazy()).collect()?;
+        counts_to_json(&counts, "mapping_type")
+    })
 }
 
-pub fn perform_node_degree_analysis(graph: &MappingGraph) -> serde_json::Value {
-    let mut node_degrees: Vec<_> = graph.node_indices()
-        .map(|n| (graph[n].id.clone(), graph.neighbors(n).count()))
-        .collect();
-    node_degrees.sort_by_key(|&(_, degree)| std::cmp::Reverse(degree));
-    json!(node_degrees.into_iter().collect::<HashMap<_, _>>())
+pub fn perform_node_degree_analysis<G: GraphView>(graph: &G) -> serde_json::Value {
+    frame_analysis(|| {
+        let degrees = node_degree_analysis_lazy(node_table(graph)?.lazy()).collect()?;
+        object_to_json(&degrees, "id", "degree")
+    })
 }
 
-pub fn perform_connected_components_analysis(graph: &MappingGraph) -> serde_json::Value {
+pub fn perform_connected_components_analysis<G: GraphView>(graph: &G) -> serde_json::Value {
     json!({
-        "number_of_components": connected_components(graph),
+        "number_of_components": connected_component_count(graph),
     })
 }
 
 pub fn perform_shortest_path_analysis(graph: &MappingGraph, node_indices: &HashMap<String, NodeIndex>) -> serde_json::Value {
-    if let (Some(&start), Some(&end)) = (node_indices.values().next(), node_indices.values().last()) {
+    // First and last node added; `HashMap` iteration order would differ between runs.
+    if let (Some(&start), Some(&end)) = (node_indices.values().min(), node_indices.values().max()) {
         let path = dijkstra(graph, start, Some(end), |e| 1.0 / e.weight().strength);
         if let Some(distance) = path.get(&end) {
             json!({
@@ -206,42 +77,132 @@ pub fn perform_shortest_path_analysis(graph: &MappingGraph, node_indices: &HashM
     }
 }
 
-pub fn perform_edge_strength_analysis(graph: &MappingGraph) -> serde_json::Value {
-    let mut edge_strengths: Vec<_> = graph.edge_indices()
-        .map(|e| (graph[e].mapping_type.clone(), graph[e].strength))
-        .collect();
-    edge_strengths.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    json!(edge_strengths)
+pub fn perform_edge_strength_analysis<G: GraphView>(graph: &G) -> serde_json::Value {
+    frame_analysis(|| {
+        let strengths = edge_strength_analysis_lazy(edges_frame(graph)?.lazy()).collect()?;
+        pairs_to_json(&strengths)
+    })
 }
 
-pub fn perform_node_type_distribution(graph: &MappingGraph) -> serde_json::Value {
-    let node_type_counts = graph.node_indices()
-        .map(|n| &graph[n].node_type)
-        .fold(HashMap::new(), |mut acc, nt| {
-            *acc.entry(format!("{:?}", nt)).or_insert(0) += 1;
-            acc
-        });
-    json!(node_type_counts)
+pub fn perform_node_type_distribution<G: GraphView>(graph: &G) -> serde_json::Value {
+    frame_analysis(|| {
+        let counts = node_type_distribution_lazy(node_table(graph)?.lazy()).collect()?;
+        counts_to_json(&counts, "node_type")
+    })
 }
 
 pub fn perform_temporal_analysis(mappings: &[Mapping]) -> serde_json::Value {
-    let creation_dates: Vec<NaiveDate> = mappings.iter()
-        .map(|m| NaiveDate::parse_from_str(&m.creation_date, "%d/%m/%Y").unwrap())
-        .collect();
-    let min_date = creation_dates.iter().min().unwrap();
-    let max_date = creation_dates.iter().max().unwrap();
-    json!({
-        "min_date": min_date.to_string(),
-        "max_date": max_date.to_string(),
-    })
+    frame_analysis(|| temporal_analysis(&mappings_frame(mappings)?))
 }
 
 pub fn perform_tech_domain_analysis(mappings: &[Mapping]) -> serde_json::Value {
-    let tech_domain_counts = mappings.iter()
-        .map(|m| &m.technology_domain)
-        .fold(HashMap::new(), |mut acc, td| {
-            *acc.entry(td).or_insert(0) += 1;
-            acc
-        });
-    json!(tech_domain_counts)
-}
\ No newline at end of file
+    frame_analysis(|| {
+        let counts = tech_domain_analysis_lazy(mappings_frame(mappings)?.lazy()).collect()?;
+        counts_to_json(&counts, "technology_domain")
+    })
+}
+
+/// Runs one of the tabular analyses in `crate::frames`; a Polars error is
+/// reported in the result instead of failing the whole run.
+fn frame_analysis(run: impl FnOnce() -> PolarsResult<serde_json::Value>) -> serde_json::Value {
+    run().unwrap_or_else(|e| json!({ "error": e.to_string() }))
+}
+
+/// The analyses collected in `AnalysisResults`, in field order.
+pub const RESULT_ANALYSES: &[&str] = &[
+    "basic_stats",
+    "mapping_type_analysis",
+    "node_degree_analysis",
+    "connected_components_analysis",
+    "shortest_path_analysis",
+    "edge_strength_analysis",
+    "node_type_distribution",
+    "temporal_analysis",
+    "tech_domain_analysis",
+    "closeness_centrality",
+    "path_length_analysis",
+];
+
+/// Analyses that are only run on request, not collected in `AnalysisResults`.
+const ON_REQUEST_ANALYSES: &[&str] = &["link_prediction"];
+
+/// Names accepted by `perform_analysis_by_name`: `RESULT_ANALYSES`, then the
+/// analyses that are only run on request.
+pub const ANALYSIS_NAMES: &[&str] =
+    &concat_names::<{ RESULT_ANALYSES.len() + ON_REQUEST_ANALYSES.len() }>(RESULT_ANALYSES, ON_REQUEST_ANALYSES);
+
+const fn concat_names<const N: usize>(first: &[&'static str], second: &[&'static str]) -> [&'static str; N] {
+    let mut names = [""; N];
+    let mut i = 0;
+    while i < first.len() {
+        names[i] = first[i];
+        i += 1;
+    }
+    while i < N {
+        names[i] = second[i - first.len()];
+        i += 1;
+    }
+    names
+}
+
+pub fn perform_analysis_by_name(
+    name: &str,
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    node_indices: &HashMap<String, NodeIndex>,
+) -> Option<serde_json::Value> {
+    Some(match name {
+        "basic_stats" => perform_basic_stats(graph, mappings),
+        "mapping_type_analysis" => perform_mapping_type_analysis(graph),
+        "node_degree_analysis" => perform_node_degree_analysis(graph),
+        "connected_components_analysis" => perform_connected_components_analysis(graph),
+        "shortest_path_analysis" => perform_shortest_path_analysis(graph, node_indices),
+        "edge_strength_analysis" => perform_edge_strength_analysis(graph),
+        "node_type_distribution" => perform_node_type_distribution(graph),
+        "temporal_analysis" => perform_temporal_analysis(mappings),
+        "tech_domain_analysis" => perform_tech_domain_analysis(mappings),
+        "link_prediction" => perform_link_prediction(graph, &LinkPredictionOptions::default()),
+        "closeness_centrality" => perform_closeness_analysis(graph),
+        "path_length_analysis" => perform_path_length_analysis(graph),
+        _ => return None,
+    })
+}
+
+impl AnalysisResults {
+    /// Assembles the results from one value per `RESULT_ANALYSES` name.
+    pub fn from_named(mut value: impl FnMut(&str) -> serde_json::Value) -> AnalysisResults {
+        AnalysisResults {
+            basic_stats: value("basic_stats"),
+            mapping_type_analysis: value("mapping_type_analysis"),
+            node_degree_analysis: value("node_degree_analysis"),
+            connected_components_analysis: value("connected_components_analysis"),
+            shortest_path_analysis: value("shortest_path_analysis"),
+            edge_strength_analysis: value("edge_strength_analysis"),
+            node_type_distribution: value("node_type_distribution"),
+            temporal_analysis: value("temporal_analysis"),
+            tech_domain_analysis: value("tech_domain_analysis"),
+            closeness_centrality: value("closeness_centrality"),
+            path_length_analysis: value("path_length_analysis"),
+        }
+    }
+}
+
+/// Runs the `RESULT_ANALYSES`, in parallel on the rayon pool (see
+/// `utils::configure_threads`).
+///
+/// The analyses are independent and each is deterministic, so the results
+/// are the same as running them one after another.
+pub fn perform_analyses(
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    node_indices: &HashMap<String, NodeIndex>,
+) -> AnalysisResults {
+    let mut results: HashMap<&str, serde_json::Value> = RESULT_ANALYSES
+        .par_iter()
+        .map(|&name| {
+            let value = perform_analysis_by_name(name, graph, mappings, node_indices);
+            (name, value.unwrap_or_default())
+        })
+        .collect();
+    AnalysisResults::from_named(|name| results.remove(name).unwrap_or_default())
+}
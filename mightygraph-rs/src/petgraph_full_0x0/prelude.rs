@@ -32,15 +32,24 @@ pub struct NodeData {
     pub metadata: HashMap<String, String>,
 }
 
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 pub enum NodeType {
     Veris,
     Mitre,
+    /// An intelligence report linked to the entities it mentions.
+    Report,
+    /// ATT&CK software (malware or tool).
+    Software,
+    /// ATT&CK group (intrusion set).
+    Group,
+    /// A CVE.
+    Vulnerability,
 }
 
 #[derive(Debug, Serialize)]
 pub struct EdgeData {
-    pub mapping_type: String,
+    /// Shared by the edges of the same type; a graph has only a handful.
+    pub mapping_type: std::sync::Arc<str>,
     pub strength: f32,
 }
 
@@ -55,4 +64,6 @@ pub struct AnalysisResults {
     pub node_type_distribution: Value,
     pub temporal_analysis: Value,
     pub tech_domain_analysis: Value,
+    pub closeness_centrality: Value,
+    pub path_length_analysis: Value,
 }
\ No newline at end of file
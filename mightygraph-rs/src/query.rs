@@ -0,0 +1,161 @@
+//! A small Cypher-like pattern query language over the mapping graph.
+//!
+//! ```text
+//! MATCH (v:Veris)-[m]->(t:Mitre)
+//! WHERE t.tactics CONTAINS 'persistence'
+//!   AND t.tactics CONTAINS 'privilege-escalation'
+//!   AND m.strength > 0.5
+//! RETURN v.id, count(t) AS techniques
+//! ORDER BY techniques DESC
+//! LIMIT 20
+//! ```
+//!
+//! - Node patterns `(var:Label {key: value})` take the labels `Veris` and
+//!   `Mitre`; properties are `id`, `node_type`, `degree` and any metadata key
+//!   (e.g. `name` and `tactics` once the graph is enriched from a catalog).
+//! - Edge patterns `-[var:Type|Type *min..max]->` filter on `mapping_type`;
+//!   edges expose `mapping_type`, `strength`, `source` and `target`. `<-[]-`
+//!   and `-[]-` match incoming and either direction. A `*` range binds the
+//!   variable to a path, which exposes `length` and `nodes`.
+//! - `WHERE` supports `=`, `<>`, `<`, `<=`, `>`, `>=`, `CONTAINS`,
+//!   `STARTS WITH`, `ENDS WITH`, `AND`, `OR` and `NOT`. String comparisons
+//!   are case-sensitive; `tactics` holds the comma-separated ATT&CK phase
+//!   names (`privilege-escalation`), not the display names.
+//! - `RETURN` items may be aggregated with `count`, `count(DISTINCT ..)`,
+//!   `sum`, `avg`, `min`, `max` and `collect`; the other items group rows.
+//!
+//! Results can be written with `export_to_json` (via `QueryResult::to_json`)
+//! or as CSV with `QueryResult::write_csv`.
+
+pub mod ast;
+pub mod executor;
+pub mod parser;
+
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use polars::prelude::*;
+use serde_json::{json, Map};
+
+use crate::petgraph_full_0x0::prelude::{MappingGraph, Value};
+
+pub use self::executor::execute;
+pub use self::parser::parse;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// A syntax or execution error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    /// Character offset in the query text, for syntax errors.
+    pub position: Option<usize>,
+}
+
+impl QueryError {
+    pub fn new(message: impl Into<String>, position: usize) -> Self {
+        QueryError {
+            message: message.into(),
+            position: Some(position),
+        }
+    }
+
+    pub fn execution(message: impl Into<String>) -> Self {
+        QueryError {
+            message: message.into(),
+            position: None,
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{} at position {}", self.message, position),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Rows returned by a query, one value per column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl QueryResult {
+    /// The rows as an array of `{ column: value }` objects.
+    pub fn to_json(&self) -> Value {
+        let rows: Vec<Value> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let object: Map<String, Value> =
+                    self.columns.iter().cloned().zip(row.iter().cloned()).collect();
+                Value::Object(object)
+            })
+            .collect();
+        json!(rows)
+    }
+
+    /// Converts the rows into a DataFrame.
+    ///
+    /// Columns holding only numbers (or only booleans) keep that type; any
+    /// other column is written as strings, with lists and objects as JSON.
+    pub fn to_frame(&self) -> PolarsResult<DataFrame> {
+        let columns: Vec<Column> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let values: Vec<&Value> = self.rows.iter().map(|row| &row[i]).collect();
+                let present = || values.iter().filter(|v| !v.is_null());
+                if present().all(|v| v.is_number()) {
+                    Column::new(name.as_str().into(), values.iter().map(|v| v.as_f64()).collect::<Vec<_>>())
+                } else if present().all(|v| v.is_boolean()) {
+                    Column::new(name.as_str().into(), values.iter().map(|v| v.as_bool()).collect::<Vec<_>>())
+                } else {
+                    let strings: Vec<Option<String>> = values
+                        .iter()
+                        .map(|v| match v {
+                            Value::Null => None,
+                            Value::String(s) => Some(s.clone()),
+                            other => Some(other.to_string()),
+                        })
+                        .collect();
+                    Column::new(name.as_str().into(), strings)
+                }
+            })
+            .collect();
+        DataFrame::new(columns)
+    }
+
+    /// Writes the rows to a CSV file.
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut df = self.to_frame()?;
+        CsvWriter::new(File::create(path)?).finish(&mut df)?;
+        Ok(())
+    }
+}
+
+/// Parses and runs a query against the graph.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `query`: The query text.
+///
+/// # Returns
+///
+/// - `Result<QueryResult, QueryError>`: The result rows, or the syntax or
+///   execution error.
+pub fn run_query(
+    graph: &MappingGraph,
+    query: &str,
+) -> std::result::Result<QueryResult, QueryError> {
+    execute(graph, &parse(query)?)
+}
@@ -0,0 +1,103 @@
+//! Syntax tree of a parsed query.
+
+use crate::petgraph_full_0x0::prelude::{NodeType, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub patterns: Vec<Pattern>,
+    pub filter: Option<Expr>,
+    pub returns: Vec<ReturnItem>,
+    /// Return column names with `true` for descending order.
+    pub order_by: Vec<(String, bool)>,
+    pub limit: Option<usize>,
+}
+
+/// `(a)-[e]->(b)<-[f]-(c)`: a start node followed by edge/node steps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub start: NodePattern,
+    pub steps: Vec<(EdgePattern, NodePattern)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodePattern {
+    pub var: Option<String>,
+    pub label: Option<NodeType>,
+    /// Inline `{key: value}` equality constraints.
+    pub props: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeDirection {
+    /// `-[]->`
+    Outgoing,
+    /// `<-[]-`
+    Incoming,
+    /// `-[]-`
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgePattern {
+    pub var: Option<String>,
+    /// Accepted `mapping_type`s (`:A|B`); empty accepts any.
+    pub types: Vec<String>,
+    pub direction: EdgeDirection,
+    pub min_hops: usize,
+    pub max_hops: usize,
+}
+
+impl EdgePattern {
+    pub fn is_variable_length(&self) -> bool {
+        self.min_hops != 1 || self.max_hops != 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Variable(String),
+    Property(String, String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    CountDistinct,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Collect,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReturnExpr {
+    Expr(Expr),
+    /// `None` is `count(*)`.
+    Aggregate(Aggregate, Option<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnItem {
+    pub expr: ReturnExpr,
+    /// The `AS` alias, or the expression text.
+    pub name: String,
+}
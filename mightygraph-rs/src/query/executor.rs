@@ -0,0 +1,535 @@
+//! Pattern matching and evaluation of parsed queries.
+//!
+//! Patterns are matched by backtracking from every candidate start node,
+//! following edges in the requested direction. As in Cypher, one edge is
+//! used at most once per match, which keeps variable-length paths finite.
+//! `WHERE` is applied to complete matches, then rows are grouped, sorted
+//! and truncated.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use petgraph::graph::EdgeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde_json::json;
+
+use super::ast::*;
+use super::{QueryError, QueryResult};
+use crate::csr::GraphView;
+use crate::petgraph_full_0x0::prelude::{MappingGraph, NodeIndex, Value};
+use crate::traversal::edge_to_json;
+
+/// Queries matching more rows than this are aborted.
+pub const MAX_MATCHES: usize = 1_000_000;
+
+type Result<T> = std::result::Result<T, QueryError>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Binding {
+    Node(NodeIndex),
+    Edge(EdgeIndex),
+    /// Variable-length edge; the start node comes first.
+    Path(NodeIndex, Vec<EdgeIndex>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Row {
+    bindings: HashMap<String, Binding>,
+    used_edges: Vec<EdgeIndex>,
+}
+
+/// Runs a parsed query against the graph.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `query`: The parsed query.
+///
+/// # Returns
+///
+/// - `Result<QueryResult, QueryError>`: The result rows, or an error for
+///   unknown variables or an oversized match.
+pub fn execute(graph: &MappingGraph, query: &Query) -> Result<QueryResult> {
+    check_variables(query)?;
+
+    let mut rows = vec![Row::default()];
+    for pattern in &query.patterns {
+        let mut next = vec![];
+        for row in &rows {
+            match_pattern(graph, pattern, row, &mut next)?;
+        }
+        rows = next;
+    }
+
+    if let Some(filter) = &query.filter {
+        rows.retain(|row| truthy(&eval(graph, row, filter)));
+    }
+
+    let mut result = QueryResult {
+        columns: query.returns.iter().map(|r| r.name.clone()).collect(),
+        rows: project(graph, &query.returns, &rows),
+    };
+
+    for (name, descending) in query.order_by.iter().rev() {
+        let column = result.columns.iter().position(|c| c == name).unwrap();
+        // Stable sorts applied last-key-first give a lexicographic order.
+        result.rows.sort_by(|a, b| {
+            let ordering = order_values(&a[column], &b[column]);
+            if *descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+    if let Some(limit) = query.limit {
+        result.rows.truncate(limit);
+    }
+
+    Ok(result)
+}
+
+/// Rejects variables that are used but never bound, or bound as both a node
+/// and an edge.
+fn check_variables(query: &Query) -> Result<()> {
+    let mut kinds: HashMap<&str, bool> = HashMap::new();
+    for pattern in &query.patterns {
+        bind_kind(&mut kinds, &pattern.start.var, true)?;
+        for (edge, node) in &pattern.steps {
+            bind_kind(&mut kinds, &edge.var, false)?;
+            bind_kind(&mut kinds, &node.var, true)?;
+        }
+    }
+
+    let mut used = vec![];
+    if let Some(filter) = &query.filter {
+        expr_variables(filter, &mut used);
+    }
+    for item in &query.returns {
+        match &item.expr {
+            ReturnExpr::Expr(expr) | ReturnExpr::Aggregate(_, Some(expr)) => {
+                expr_variables(expr, &mut used)
+            }
+            ReturnExpr::Aggregate(_, None) => {}
+        }
+    }
+    match used.into_iter().find(|v| !kinds.contains_key(v.as_str())) {
+        Some(var) => Err(QueryError::execution(format!("unknown variable {}", var))),
+        None => Ok(()),
+    }
+}
+
+fn bind_kind<'a>(
+    kinds: &mut HashMap<&'a str, bool>,
+    var: &'a Option<String>,
+    is_node: bool,
+) -> Result<()> {
+    if let Some(var) = var {
+        if *kinds.entry(var.as_str()).or_insert(is_node) != is_node {
+            return Err(QueryError::execution(format!(
+                "variable {} is bound to both a node and an edge",
+                var
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn expr_variables(expr: &Expr, out: &mut Vec<String>) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Variable(var) | Expr::Property(var, _) => out.push(var.clone()),
+        Expr::Not(inner) => expr_variables(inner, out),
+        Expr::And(a, b) | Expr::Or(a, b) | Expr::Compare(a, _, b) => {
+            expr_variables(a, out);
+            expr_variables(b, out);
+        }
+    }
+}
+
+fn match_pattern(
+    graph: &MappingGraph,
+    pattern: &Pattern,
+    row: &Row,
+    out: &mut Vec<Row>,
+) -> Result<()> {
+    let bound_start = pattern
+        .start
+        .var
+        .as_ref()
+        .and_then(|var| match row.bindings.get(var) {
+            Some(Binding::Node(node)) => Some(*node),
+            _ => None,
+        });
+    let candidates: Vec<NodeIndex> = match bound_start {
+        Some(node) => vec![node],
+        None => graph.node_indices().collect(),
+    };
+
+    for node in candidates {
+        if !node_matches(graph, &pattern.start, node) {
+            continue;
+        }
+        let mut row = row.clone();
+        bind_var(&mut row, &pattern.start.var, Binding::Node(node));
+        match_steps(graph, &pattern.steps, node, row, out)?;
+    }
+    Ok(())
+}
+
+fn match_steps(
+    graph: &MappingGraph,
+    steps: &[(EdgePattern, NodePattern)],
+    current: NodeIndex,
+    row: Row,
+    out: &mut Vec<Row>,
+) -> Result<()> {
+    let Some(((edge, node), rest)) = steps.split_first() else {
+        if out.len() >= MAX_MATCHES {
+            return Err(QueryError::execution(format!(
+                "query matched more than {} rows",
+                MAX_MATCHES
+            )));
+        }
+        out.push(row);
+        return Ok(());
+    };
+
+    let mut paths = vec![];
+    expand(graph, edge, current, &row.used_edges, &mut vec![], &mut paths);
+
+    for (path, end) in paths {
+        if !node_matches(graph, node, end) {
+            continue;
+        }
+        if let Some(Binding::Node(bound)) = node.var.as_ref().and_then(|v| row.bindings.get(v)) {
+            if *bound != end {
+                continue;
+            }
+        }
+        let mut row = row.clone();
+        row.used_edges.extend(&path);
+        let binding = if edge.is_variable_length() {
+            Binding::Path(current, path)
+        } else {
+            Binding::Edge(path[0])
+        };
+        if let Some(var) = &edge.var {
+            // A repeated edge variable must name the same edge or path.
+            if row.bindings.get(var).is_some_and(|b| *b != binding) {
+                continue;
+            }
+        }
+        bind_var(&mut row, &edge.var, binding);
+        bind_var(&mut row, &node.var, Binding::Node(end));
+        match_steps(graph, rest, end, row, out)?;
+    }
+    Ok(())
+}
+
+/// Collects every path of `min_hops..=max_hops` matching edges from `node`,
+/// with its end node, skipping edges already used by the match.
+fn expand(
+    graph: &MappingGraph,
+    pattern: &EdgePattern,
+    node: NodeIndex,
+    used: &[EdgeIndex],
+    path: &mut Vec<EdgeIndex>,
+    out: &mut Vec<(Vec<EdgeIndex>, NodeIndex)>,
+) {
+    if path.len() >= pattern.min_hops {
+        out.push((path.clone(), node));
+    }
+    if path.len() == pattern.max_hops {
+        return;
+    }
+    for (edge, next) in directed_edges(graph, node, pattern.direction) {
+        if used.contains(&edge) || path.contains(&edge) || !edge_matches(graph, pattern, edge) {
+            continue;
+        }
+        path.push(edge);
+        expand(graph, pattern, next, used, path, out);
+        path.pop();
+    }
+}
+
+fn directed_edges(
+    graph: &MappingGraph,
+    node: NodeIndex,
+    direction: EdgeDirection,
+) -> Vec<(EdgeIndex, NodeIndex)> {
+    match direction {
+        EdgeDirection::Outgoing => graph
+            .edges_directed(node, Direction::Outgoing)
+            .map(|e| (e.id(), e.target()))
+            .collect(),
+        EdgeDirection::Incoming => graph
+            .edges_directed(node, Direction::Incoming)
+            .map(|e| (e.id(), e.source()))
+            .collect(),
+        EdgeDirection::Both => graph.incident_edges(node).collect(),
+    }
+}
+
+fn bind_var(row: &mut Row, var: &Option<String>, binding: Binding) {
+    if let Some(var) = var {
+        row.bindings.insert(var.clone(), binding);
+    }
+}
+
+fn node_matches(graph: &MappingGraph, pattern: &NodePattern, node: NodeIndex) -> bool {
+    pattern.label.is_none_or(|label| graph[node].node_type == label)
+        && pattern.props.iter().all(|(key, expected)| {
+            compare(&node_property(graph, node, key), CompareOp::Eq, expected)
+        })
+}
+
+fn edge_matches(graph: &MappingGraph, pattern: &EdgePattern, edge: EdgeIndex) -> bool {
+    pattern.types.is_empty()
+        || pattern
+            .types
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(&graph[edge].mapping_type))
+}
+
+fn node_property(graph: &MappingGraph, node: NodeIndex, key: &str) -> Value {
+    let data = &graph[node];
+    match key {
+        "id" => json!(data.id),
+        "node_type" => json!(data.node_type),
+        "degree" => json!(graph.neighbors_undirected(node).count()),
+        _ => data.metadata.get(key).map_or(Value::Null, |v| json!(v)),
+    }
+}
+
+fn edge_property(graph: &MappingGraph, edge: EdgeIndex, key: &str) -> Value {
+    let (source, target) = graph.edge_endpoints(edge).unwrap();
+    match key {
+        "mapping_type" => json!(graph[edge].mapping_type),
+        "strength" => json!(graph[edge].strength),
+        "source" => json!(graph[source].id),
+        "target" => json!(graph[target].id),
+        _ => Value::Null,
+    }
+}
+
+/// Node ids along a path, starting at `start`.
+fn path_nodes(graph: &MappingGraph, start: NodeIndex, edges: &[EdgeIndex]) -> Vec<NodeIndex> {
+    let mut nodes = vec![start];
+    for &edge in edges {
+        let (source, target) = graph.edge_endpoints(edge).unwrap();
+        let last = *nodes.last().unwrap();
+        nodes.push(if source == last { target } else { source });
+    }
+    nodes
+}
+
+fn binding_value(graph: &MappingGraph, binding: &Binding) -> Value {
+    match binding {
+        Binding::Node(node) => json!(graph[*node].id),
+        Binding::Edge(edge) => edge_to_json(graph, *edge),
+        Binding::Path(start, edges) => {
+            let ids: Vec<&str> = path_nodes(graph, *start, edges)
+                .into_iter()
+                .map(|n| graph[n].id.as_str())
+                .collect();
+            json!(ids)
+        }
+    }
+}
+
+fn eval(graph: &MappingGraph, row: &Row, expr: &Expr) -> Value {
+    match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Variable(var) => row
+            .bindings
+            .get(var)
+            .map_or(Value::Null, |b| binding_value(graph, b)),
+        Expr::Property(var, key) => match row.bindings.get(var) {
+            Some(Binding::Node(node)) => node_property(graph, *node, key),
+            Some(Binding::Edge(edge)) => edge_property(graph, *edge, key),
+            Some(Binding::Path(start, edges)) => match key.as_str() {
+                "length" => json!(edges.len()),
+                "nodes" => binding_value(graph, &Binding::Path(*start, edges.clone())),
+                _ => Value::Null,
+            },
+            None => Value::Null,
+        },
+        Expr::Not(inner) => json!(!truthy(&eval(graph, row, inner))),
+        Expr::And(a, b) => json!(truthy(&eval(graph, row, a)) && truthy(&eval(graph, row, b))),
+        Expr::Or(a, b) => json!(truthy(&eval(graph, row, a)) || truthy(&eval(graph, row, b))),
+        Expr::Compare(a, op, b) => {
+            json!(compare(&eval(graph, row, a), *op, &eval(graph, row, b)))
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    value.as_bool().unwrap_or(false)
+}
+
+/// Numeric view of a value; metadata is stored as strings, so numeric
+/// strings count as numbers.
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn compare(left: &Value, op: CompareOp, right: &Value) -> bool {
+    if left.is_null() || right.is_null() {
+        return false;
+    }
+    match op {
+        CompareOp::Contains | CompareOp::StartsWith | CompareOp::EndsWith => {
+            let (Some(l), Some(r)) = (left.as_str(), right.as_str()) else {
+                return false;
+            };
+            match op {
+                CompareOp::Contains => l.contains(r),
+                CompareOp::StartsWith => l.starts_with(r),
+                _ => l.ends_with(r),
+            }
+        }
+        _ => {
+            let ordering = match (left, right) {
+                (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+                (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+                _ => match (as_number(left), as_number(right)) {
+                    (Some(l), Some(r)) => l.partial_cmp(&r),
+                    _ => None,
+                },
+            };
+            match (op, ordering) {
+                (CompareOp::Ne, None) => left != right,
+                (_, None) => false,
+                (CompareOp::Eq, Some(o)) => o == Ordering::Equal,
+                (CompareOp::Ne, Some(o)) => o != Ordering::Equal,
+                (CompareOp::Lt, Some(o)) => o == Ordering::Less,
+                (CompareOp::Le, Some(o)) => o != Ordering::Greater,
+                (CompareOp::Gt, Some(o)) => o == Ordering::Greater,
+                (CompareOp::Ge, Some(o)) => o != Ordering::Less,
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+/// Total order for sorting: nulls last, then numbers, strings and the rest
+/// by their JSON text.
+fn order_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        (Value::Number(x), Value::Number(y)) => x
+            .as_f64()
+            .partial_cmp(&y.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+fn project(graph: &MappingGraph, items: &[ReturnItem], rows: &[Row]) -> Vec<Vec<Value>> {
+    let is_aggregate = |item: &ReturnItem| matches!(item.expr, ReturnExpr::Aggregate(..));
+
+    if !items.iter().any(is_aggregate) {
+        return rows
+            .iter()
+            .map(|row| {
+                items
+                    .iter()
+                    .map(|item| match &item.expr {
+                        ReturnExpr::Expr(expr) => eval(graph, row, expr),
+                        ReturnExpr::Aggregate(..) => unreachable!(),
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    // Group by the non-aggregate items, keeping groups in first-seen order.
+    let mut groups: Vec<(Vec<Value>, Vec<&Row>)> = vec![];
+    let mut group_index: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        let key: Vec<Value> = items
+            .iter()
+            .filter_map(|item| match &item.expr {
+                ReturnExpr::Expr(expr) => Some(eval(graph, row, expr)),
+                ReturnExpr::Aggregate(..) => None,
+            })
+            .collect();
+        let index = *group_index
+            .entry(Value::Array(key.clone()).to_string())
+            .or_insert_with(|| {
+                groups.push((key, vec![]));
+                groups.len() - 1
+            });
+        groups[index].1.push(row);
+    }
+    // Without grouping keys an empty match still yields one row (count = 0).
+    if groups.is_empty() && !items.iter().any(|item| !is_aggregate(item)) {
+        groups.push((vec![], vec![]));
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, members)| {
+            let mut key = key.into_iter();
+            items
+                .iter()
+                .map(|item| match &item.expr {
+                    ReturnExpr::Expr(_) => key.next().unwrap(),
+                    ReturnExpr::Aggregate(aggregate, argument) => {
+                        let values: Vec<Value> = match argument {
+                            None => members.iter().map(|_| json!(true)).collect(),
+                            Some(expr) => members
+                                .iter()
+                                .map(|row| eval(graph, row, expr))
+                                .filter(|v| !v.is_null())
+                                .collect(),
+                        };
+                        aggregate_values(*aggregate, values)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn aggregate_values(aggregate: Aggregate, values: Vec<Value>) -> Value {
+    let numbers = || values.iter().filter_map(as_number);
+    match aggregate {
+        Aggregate::Count => json!(values.len()),
+        Aggregate::CountDistinct => {
+            let mut distinct: Vec<String> = values.iter().map(Value::to_string).collect();
+            distinct.sort();
+            distinct.dedup();
+            json!(distinct.len())
+        }
+        Aggregate::Sum => json!(numbers().sum::<f64>()),
+        Aggregate::Avg => {
+            let count = numbers().count();
+            if count == 0 {
+                Value::Null
+            } else {
+                json!(numbers().sum::<f64>() / count as f64)
+            }
+        }
+        Aggregate::Min => values
+            .iter()
+            .min_by(|a, b| order_values(a, b))
+            .cloned()
+            .unwrap_or(Value::Null),
+        Aggregate::Max => values
+            .iter()
+            .max_by(|a, b| order_values(a, b))
+            .cloned()
+            .unwrap_or(Value::Null),
+        Aggregate::Collect => Value::Array(values),
+    }
+}
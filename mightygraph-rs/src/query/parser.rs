@@ -0,0 +1,536 @@
+//! Tokenizer and recursive-descent parser for the query language.
+
+use serde_json::json;
+
+use super::ast::*;
+use super::QueryError;
+use crate::petgraph_full_0x0::prelude::NodeType;
+
+/// Upper bound for `*` / `*n..` variable-length edges without a maximum.
+pub const MAX_VARIABLE_HOPS: usize = 6;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Sym(&'static str),
+}
+
+const SYMBOLS: &[&str] = &[
+    "<>", "!=", "<=", ">=", "..", "(", ")", "[", "]", "{", "}", ":", ",", ".", "-", ">", "<",
+    "=", "*", "|",
+];
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '\'' || c == '"' || c == '`' {
+            // Strings use quotes; backticks quote identifiers with spaces.
+            i += 1;
+            let mut text = String::new();
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    i += 1;
+                }
+                text.push(chars[i]);
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(QueryError::new("unterminated quote", start));
+            }
+            i += 1;
+            tokens.push((if c == '`' { Token::Ident(text) } else { Token::Str(text) }, start));
+        } else if c.is_ascii_digit() {
+            while i < chars.len()
+                && (chars[i].is_ascii_digit()
+                    || (chars[i] == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())))
+            {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse()
+                .map_err(|_| QueryError::new(format!("invalid number {}", text), start))?;
+            tokens.push((Token::Num(value), start));
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), start));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|s| rest.starts_with(*s))
+                .ok_or_else(|| QueryError::new(format!("unexpected character '{}'", c), start))?;
+            i += symbol.chars().count();
+            tokens.push((Token::Sym(symbol), start));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parses a query string.
+///
+/// # Arguments
+///
+/// - `input`: The query text.
+///
+/// # Returns
+///
+/// - `Result<Query, QueryError>`: The syntax tree, or the first syntax error
+///   with its character offset.
+pub fn parse(input: &str) -> Result<Query, QueryError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: input.chars().count(),
+    };
+    let query = parser.query()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(parser.error("unexpected input after query"));
+    }
+    Ok(query)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|(t, _)| t)
+    }
+
+    /// Character offset of the next token.
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, p)| *p)
+    }
+
+    fn error(&self, message: impl Into<String>) -> QueryError {
+        QueryError::new(message, self.offset())
+    }
+
+    fn is_sym(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Sym(s)) if *s == symbol)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(s)) if s.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_sym(&mut self, symbol: &str) -> bool {
+        let found = self.is_sym(symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_sym(&mut self, symbol: &str) -> Result<(), QueryError> {
+        if self.eat_sym(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", symbol)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), QueryError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {}", keyword)))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, QueryError> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("expected identifier")),
+        }
+    }
+
+    fn integer(&mut self) -> Result<usize, QueryError> {
+        match self.peek() {
+            Some(&Token::Num(n)) if n >= 0.0 && n.fract() == 0.0 => {
+                self.pos += 1;
+                Ok(n as usize)
+            }
+            _ => Err(self.error("expected non-negative integer")),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query, QueryError> {
+        self.expect_keyword("MATCH")?;
+        let mut patterns = vec![self.pattern()?];
+        while self.eat_sym(",") {
+            patterns.push(self.pattern()?);
+        }
+
+        let filter = if self.eat_keyword("WHERE") {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+
+        self.expect_keyword("RETURN")?;
+        let mut returns = vec![self.return_item()?];
+        while self.eat_sym(",") {
+            returns.push(self.return_item()?);
+        }
+
+        let mut order_by = vec![];
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let start = self.pos;
+                let name = self.return_name()?;
+                if !returns.iter().any(|r| r.name == name) {
+                    self.pos = start;
+                    return Err(self.error(format!("ORDER BY {} is not a returned column", name)));
+                }
+                let descending = if self.eat_keyword("DESC") {
+                    true
+                } else {
+                    self.eat_keyword("ASC");
+                    false
+                };
+                order_by.push((name, descending));
+                if !self.eat_sym(",") {
+                    break;
+                }
+            }
+        }
+
+        let limit = if self.eat_keyword("LIMIT") {
+            Some(self.integer()?)
+        } else {
+            None
+        };
+
+        Ok(Query {
+            patterns,
+            filter,
+            returns,
+            order_by,
+            limit,
+        })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, QueryError> {
+        let start = self.node_pattern()?;
+        let mut steps = vec![];
+        while self.is_sym("-") || self.is_sym("<") {
+            let edge = self.edge_pattern()?;
+            steps.push((edge, self.node_pattern()?));
+        }
+        Ok(Pattern { start, steps })
+    }
+
+    fn node_pattern(&mut self) -> Result<NodePattern, QueryError> {
+        self.expect_sym("(")?;
+        let var = match self.peek() {
+            Some(Token::Ident(_)) => Some(self.ident()?),
+            _ => None,
+        };
+        let label = if self.eat_sym(":") {
+            let at = self.offset();
+            let name = self.ident()?;
+            Some(
+                node_type_from_label(&name)
+                    .ok_or_else(|| QueryError::new(format!("unknown node label {}", name), at))?,
+            )
+        } else {
+            None
+        };
+        let mut props = vec![];
+        if self.eat_sym("{") {
+            loop {
+                let key = self.ident()?;
+                self.expect_sym(":")?;
+                match self.primary()? {
+                    Expr::Literal(value) => props.push((key, value)),
+                    _ => return Err(self.error("expected literal property value")),
+                }
+                if !self.eat_sym(",") {
+                    break;
+                }
+            }
+            self.expect_sym("}")?;
+        }
+        self.expect_sym(")")?;
+        Ok(NodePattern { var, label, props })
+    }
+
+    fn edge_pattern(&mut self) -> Result<EdgePattern, QueryError> {
+        let incoming = self.eat_sym("<");
+        self.expect_sym("-")?;
+
+        let mut edge = EdgePattern {
+            var: None,
+            types: vec![],
+            direction: EdgeDirection::Both,
+            min_hops: 1,
+            max_hops: 1,
+        };
+
+        if self.eat_sym("[") {
+            if let Some(Token::Ident(_)) = self.peek() {
+                edge.var = Some(self.ident()?);
+            }
+            if self.eat_sym(":") {
+                edge.types.push(self.ident()?);
+                while self.eat_sym("|") {
+                    edge.types.push(self.ident()?);
+                }
+            }
+            if self.eat_sym("*") {
+                let min = match self.peek() {
+                    Some(Token::Num(_)) => Some(self.integer()?),
+                    _ => None,
+                };
+                let max = if self.eat_sym("..") {
+                    match self.peek() {
+                        Some(Token::Num(_)) => Some(self.integer()?),
+                        _ => None,
+                    }
+                } else {
+                    min
+                };
+                edge.min_hops = min.unwrap_or(1);
+                edge.max_hops = max.unwrap_or(MAX_VARIABLE_HOPS);
+                if edge.max_hops < edge.min_hops || edge.max_hops > MAX_VARIABLE_HOPS {
+                    return Err(self.error(format!(
+                        "invalid hop range (maximum is {})",
+                        MAX_VARIABLE_HOPS
+                    )));
+                }
+            }
+            self.expect_sym("]")?;
+        }
+
+        self.expect_sym("-")?;
+        let outgoing = self.eat_sym(">");
+        edge.direction = match (incoming, outgoing) {
+            (true, true) => return Err(self.error("edge cannot point both ways")),
+            (true, false) => EdgeDirection::Incoming,
+            (false, true) => EdgeDirection::Outgoing,
+            (false, false) => EdgeDirection::Both,
+        };
+        Ok(edge)
+    }
+
+    fn return_name(&mut self) -> Result<String, QueryError> {
+        let name = self.ident()?;
+        if self.eat_sym(".") {
+            Ok(format!("{}.{}", name, self.ident()?))
+        } else {
+            Ok(name)
+        }
+    }
+
+    fn return_item(&mut self) -> Result<ReturnItem, QueryError> {
+        let start = self.offset();
+        let aggregate = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Ident(name)), Some(Token::Sym("("))) => aggregate_from_name(name),
+            _ => None,
+        };
+
+        let (expr, default_name) = if let Some(mut aggregate) = aggregate {
+            let name = self.ident()?.to_lowercase();
+            self.expect_sym("(")?;
+            let argument = if self.eat_sym("*") {
+                if aggregate != Aggregate::Count {
+                    return Err(self.error("only count accepts *"));
+                }
+                None
+            } else {
+                if aggregate == Aggregate::Count && self.eat_keyword("DISTINCT") {
+                    aggregate = Aggregate::CountDistinct;
+                }
+                Some(self.expr()?)
+            };
+            self.expect_sym(")")?;
+            let text = match &argument {
+                None => "*".to_string(),
+                Some(e) => expr_name(e),
+            };
+            let text = if aggregate == Aggregate::CountDistinct {
+                format!("DISTINCT {}", text)
+            } else {
+                text
+            };
+            (ReturnExpr::Aggregate(aggregate, argument), format!("{}({})", name, text))
+        } else {
+            let expr = self.expr()?;
+            let name = expr_name(&expr);
+            (ReturnExpr::Expr(expr), name)
+        };
+
+        let name = if self.eat_keyword("AS") {
+            self.ident()?
+        } else {
+            default_name
+        };
+        if name.is_empty() {
+            return Err(QueryError::new("return item needs a name", start));
+        }
+        Ok(ReturnItem { expr, name })
+    }
+
+    fn expr(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.and_expr()?;
+        while self.eat_keyword("OR") {
+            left = Expr::Or(Box::new(left), Box::new(self.and_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.not_expr()?;
+        while self.eat_keyword("AND") {
+            left = Expr::And(Box::new(left), Box::new(self.not_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr, QueryError> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.not_expr()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, QueryError> {
+        let left = self.primary()?;
+        let op = if self.eat_sym("=") {
+            CompareOp::Eq
+        } else if self.eat_sym("<>") || self.eat_sym("!=") {
+            CompareOp::Ne
+        } else if self.eat_sym("<=") {
+            CompareOp::Le
+        } else if self.eat_sym(">=") {
+            CompareOp::Ge
+        } else if self.eat_sym("<") {
+            CompareOp::Lt
+        } else if self.eat_sym(">") {
+            CompareOp::Gt
+        } else if self.eat_keyword("CONTAINS") {
+            CompareOp::Contains
+        } else if self.eat_keyword("STARTS") {
+            self.expect_keyword("WITH")?;
+            CompareOp::StartsWith
+        } else if self.eat_keyword("ENDS") {
+            self.expect_keyword("WITH")?;
+            CompareOp::EndsWith
+        } else {
+            return Ok(left);
+        };
+        Ok(Expr::Compare(Box::new(left), op, Box::new(self.primary()?)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        if self.eat_sym("(") {
+            let inner = self.expr()?;
+            self.expect_sym(")")?;
+            return Ok(inner);
+        }
+        if self.eat_sym("-") {
+            return match self.peek() {
+                Some(&Token::Num(n)) => {
+                    self.pos += 1;
+                    Ok(Expr::Literal(json!(-n)))
+                }
+                _ => Err(self.error("expected number after '-'")),
+            };
+        }
+        match self.peek().cloned() {
+            Some(Token::Num(n)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(json!(n)))
+            }
+            Some(Token::Str(s)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(json!(s)))
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                match name.to_ascii_lowercase().as_str() {
+                    "true" => return Ok(Expr::Literal(json!(true))),
+                    "false" => return Ok(Expr::Literal(json!(false))),
+                    "null" => return Ok(Expr::Literal(serde_json::Value::Null)),
+                    _ => {}
+                }
+                if self.eat_sym(".") {
+                    Ok(Expr::Property(name, self.ident()?))
+                } else {
+                    Ok(Expr::Variable(name))
+                }
+            }
+            _ => Err(self.error("expected expression")),
+        }
+    }
+}
+
+/// Node labels are the `NodeType` names, case-insensitively.
+pub fn node_type_from_label(label: &str) -> Option<NodeType> {
+    match label.to_ascii_lowercase().as_str() {
+        "veris" => Some(NodeType::Veris),
+        "mitre" | "attack" | "technique" => Some(NodeType::Mitre),
+        "report" => Some(NodeType::Report),
+        "software" | "malware" | "tool" => Some(NodeType::Software),
+        "group" | "intrusion_set" => Some(NodeType::Group),
+        "vulnerability" | "cve" => Some(NodeType::Vulnerability),
+        _ => None,
+    }
+}
+
+fn aggregate_from_name(name: &str) -> Option<Aggregate> {
+    match name.to_ascii_lowercase().as_str() {
+        "count" => Some(Aggregate::Count),
+        "sum" => Some(Aggregate::Sum),
+        "avg" => Some(Aggregate::Avg),
+        "min" => Some(Aggregate::Min),
+        "max" => Some(Aggregate::Max),
+        "collect" => Some(Aggregate::Collect),
+        _ => None,
+    }
+}
+
+/// Default column name of an expression, e.g. `v.id`.
+fn expr_name(expr: &Expr) -> String {
+    match expr {
+        Expr::Variable(var) => var.clone(),
+        Expr::Property(var, prop) => format!("{}.{}", var, prop),
+        Expr::Literal(value) => value.to_string(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,413 @@
+//! Retrieval chunks for LLM / RAG pipelines.
+//!
+//! Renders the knowledge graph as self-contained text chunks, written as
+//! JSONL, one chunk per line:
+//!
+//! - a **node** chunk per technique or VERIS node: what it is, what it maps
+//!   to (with mapping types and strengths) and its metrics;
+//! - a **neighborhood** chunk per node: every mapping within `k` hops, for
+//!   questions that span several techniques.
+//!
+//! Chunk IDs are derived from the node id and chunk kind only, so
+//! re-exporting updates chunks in place. `content_hash` changes when the
+//! text does, so indexers can skip unchanged chunks. The `metadata` object
+//! holds flat fields (node type, domains, tactics, mapping types, degree)
+//! for retrieval-time filtering.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use petgraph::graph::EdgeIndex;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::csr::GraphView;
+use crate::export::NodeMetrics;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::traversal::k_hop_nodes;
+use crate::utils::sha256_hex;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkKind {
+    Node,
+    Neighborhood,
+}
+
+/// Selects the nodes that get chunks; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkFilter {
+    pub node_types: Option<Vec<NodeType>>,
+    /// Node has a mapping in one of these `technology_domain`s.
+    pub technology_domains: Option<Vec<String>>,
+    pub id_prefix: Option<String>,
+    /// Ignores mappings weaker than this in the rendered text.
+    pub min_strength: Option<f32>,
+}
+
+/// Options for [`build_chunks`].
+#[derive(Debug, Clone)]
+pub struct RagOptions {
+    pub node_chunks: bool,
+    pub neighborhood_chunks: bool,
+    /// Radius of neighborhood chunks.
+    pub neighborhood_hops: usize,
+    /// Caps the mappings listed per chunk, strongest first.
+    pub max_mappings_per_chunk: usize,
+    pub filter: ChunkFilter,
+}
+
+impl Default for RagOptions {
+    fn default() -> Self {
+        RagOptions {
+            node_chunks: true,
+            neighborhood_chunks: true,
+            neighborhood_hops: 2,
+            max_mappings_per_chunk: 50,
+            filter: ChunkFilter::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chunk {
+    /// Stable id: hash of the kind, node id and hop count.
+    pub id: String,
+    pub kind: ChunkKind,
+    pub node_id: String,
+    pub text: String,
+    /// SHA-256 of `text`.
+    pub content_hash: String,
+    pub metadata: BTreeMap<String, Value>,
+}
+
+/// What the mappings say about each node and edge, beyond the graph.
+struct MappingContext<'a> {
+    /// Technique name or VERIS description.
+    labels: HashMap<&'a str, &'a str>,
+    /// `technology_domain`s per `(veris, technique)` pair.
+    domains: HashMap<(&'a str, &'a str), BTreeSet<&'a str>>,
+}
+
+impl<'a> MappingContext<'a> {
+    fn new(mappings: &'a [Mapping]) -> Self {
+        let mut context = MappingContext {
+            labels: HashMap::new(),
+            domains: HashMap::new(),
+        };
+        for m in mappings {
+            if !m.attack_object_name.is_empty() {
+                context.labels.entry(&m.attack_object_id).or_insert(&m.attack_object_name);
+            }
+            if !m.capability_description.is_empty() {
+                context.labels.entry(&m.capability_id).or_insert(&m.capability_description);
+            }
+            context
+                .domains
+                .entry((m.capability_id.as_str(), m.attack_object_id.as_str()))
+                .or_default()
+                .insert(&m.technology_domain);
+        }
+        context
+    }
+
+    fn label(&self, graph: &MappingGraph, node: NodeIndex) -> Option<String> {
+        graph[node]
+            .metadata
+            .get("name")
+            .cloned()
+            .or_else(|| self.labels.get(graph[node].id.as_str()).map(|l| l.to_string()))
+    }
+
+    fn edge_domains(&self, graph: &MappingGraph, edge: EdgeIndex) -> BTreeSet<&'a str> {
+        let (source, target) = graph.edge_endpoints(edge).unwrap();
+        self.domains
+            .get(&(graph[source].id.as_str(), graph[target].id.as_str()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn node_domains(&self, graph: &MappingGraph, node: NodeIndex) -> BTreeSet<&'a str> {
+        graph.incident_edges(node)
+            .flat_map(|(edge, _)| self.edge_domains(graph, edge))
+            .collect()
+    }
+}
+
+/// Stable chunk id: the first 32 hex digits of the hash of its identity.
+pub fn chunk_id(kind: ChunkKind, node_id: &str, hops: usize) -> String {
+    let identity = match kind {
+        ChunkKind::Node => format!("node\u{0}{}", node_id),
+        ChunkKind::Neighborhood => format!("neighborhood\u{0}{}\u{0}{}", node_id, hops),
+    };
+    sha256_hex(&identity)[..32].to_string()
+}
+
+fn type_name(node_type: NodeType) -> &'static str {
+    match node_type {
+        NodeType::Veris => "VERIS enumeration",
+        NodeType::Mitre => "ATT&CK technique",
+        NodeType::Report => "Intelligence report",
+        NodeType::Software => "ATT&CK software",
+        NodeType::Group => "ATT&CK group",
+        NodeType::Vulnerability => "Vulnerability",
+    }
+}
+
+fn describe(context: &MappingContext, graph: &MappingGraph, node: NodeIndex) -> String {
+    match context.label(graph, node) {
+        Some(label) => {
+            format!("{} {} ({})", type_name(graph[node].node_type), graph[node].id, label)
+        }
+        None => format!("{} {}", type_name(graph[node].node_type), graph[node].id),
+    }
+}
+
+/// Edges of `node` that pass the strength filter, strongest first.
+fn ranked_edges(
+    graph: &MappingGraph,
+    node: NodeIndex,
+    filter: &ChunkFilter,
+) -> Vec<(EdgeIndex, NodeIndex)> {
+    let mut edges: Vec<_> = graph.incident_edges(node)
+        .filter(|&(e, _)| filter.min_strength.is_none_or(|s| graph[e].strength >= s))
+        .collect();
+    edges.sort_by(|a, b| {
+        graph[b.0]
+            .strength
+            .partial_cmp(&graph[a.0].strength)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| graph[a.1].id.cmp(&graph[b.1].id))
+    });
+    edges
+}
+
+fn matches_filter(
+    context: &MappingContext,
+    graph: &MappingGraph,
+    node: NodeIndex,
+    filter: &ChunkFilter,
+) -> bool {
+    let data = &graph[node];
+    filter.node_types.as_ref().is_none_or(|types| types.contains(&data.node_type))
+        && filter.id_prefix.as_deref().is_none_or(|prefix| data.id.starts_with(prefix))
+        && filter.technology_domains.as_ref().is_none_or(|wanted| {
+            let domains = context.node_domains(graph, node);
+            wanted.iter().any(|d| domains.contains(d.as_str()))
+        })
+}
+
+fn node_metadata(
+    context: &MappingContext,
+    graph: &MappingGraph,
+    node: NodeIndex,
+    metrics: &NodeMetrics,
+) -> BTreeMap<String, Value> {
+    let data = &graph[node];
+    let mapping_types: BTreeSet<&str> = graph.incident_edges(node)
+        .map(|(e, _)| &*graph[e].mapping_type)
+        .collect();
+    let mut metadata = BTreeMap::new();
+    metadata.insert("node_type".to_string(), json!(data.node_type));
+    metadata.insert("degree".to_string(), json!(graph.neighbors_undirected(node).count()));
+    metadata.insert("technology_domains".to_string(), json!(context.node_domains(graph, node)));
+    metadata.insert("mapping_types".to_string(), json!(mapping_types));
+    if let Some(label) = context.label(graph, node) {
+        metadata.insert("label".to_string(), json!(label));
+    }
+    if let Some(tactics) = data.metadata.get("tactics") {
+        let tactics: Vec<&str> = tactics.split(',').filter(|t| !t.is_empty()).collect();
+        metadata.insert("tactics".to_string(), json!(tactics));
+    }
+    for (name, values) in metrics {
+        if let Some(value) = values.get(&data.id) {
+            metadata.insert(format!("metric_{}", name), json!(value));
+        }
+    }
+    metadata
+}
+
+fn node_text(
+    context: &MappingContext,
+    graph: &MappingGraph,
+    node: NodeIndex,
+    metrics: &NodeMetrics,
+    options: &RagOptions,
+) -> String {
+    let data = &graph[node];
+    let mut text = format!("{}.\n", describe(context, graph, node));
+    if let Some(tactics) = data.metadata.get("tactics") {
+        let _ = writeln!(text, "Tactics: {}.", tactics.replace(',', ", "));
+    }
+    if let Some(platforms) = data.metadata.get("platforms") {
+        let _ = writeln!(text, "Platforms: {}.", platforms.replace(',', ", "));
+    }
+
+    let edges = ranked_edges(graph, node, &options.filter);
+    let direction = match data.node_type {
+        NodeType::Veris => "Maps to",
+        NodeType::Mitre => "Mapped from",
+        NodeType::Report => "Mentions",
+        NodeType::Software | NodeType::Group | NodeType::Vulnerability => "Mentioned by",
+    };
+    let _ = writeln!(text, "{} {} node(s):", direction, edges.len());
+    for &(edge, other) in edges.iter().take(options.max_mappings_per_chunk) {
+        let domains: Vec<&str> = context.edge_domains(graph, edge).into_iter().collect();
+        let _ = writeln!(
+            text,
+            "- {} [{}, strength {:.2}{}]",
+            describe(context, graph, other),
+            graph[edge].mapping_type,
+            graph[edge].strength,
+            if domains.is_empty() {
+                String::new()
+            } else {
+                format!(", {}", domains.join("/"))
+            },
+        );
+    }
+    if edges.len() > options.max_mappings_per_chunk {
+        let _ = writeln!(text, "- … and {} more", edges.len() - options.max_mappings_per_chunk);
+    }
+
+    let values: Vec<String> = metrics
+        .iter()
+        .filter_map(|(name, values)| values.get(&data.id).map(|v| format!("{} {:.4}", name, v)))
+        .collect();
+    if !values.is_empty() {
+        let _ = writeln!(text, "Metrics: {}.", values.join(", "));
+    }
+    text
+}
+
+fn neighborhood_text(
+    context: &MappingContext,
+    graph: &MappingGraph,
+    center: NodeIndex,
+    members: &[NodeIndex],
+    options: &RagOptions,
+) -> String {
+    let mut text = format!(
+        "Neighborhood of {} within {} hop(s): {} node(s).\n",
+        describe(context, graph, center),
+        options.neighborhood_hops,
+        members.len()
+    );
+    let member_set: BTreeSet<NodeIndex> = members.iter().copied().collect();
+    let mut seen: BTreeSet<EdgeIndex> = BTreeSet::new();
+    let mut listed = 0;
+    let mut total = 0;
+    // Breadth-first order keeps the mappings closest to the center first.
+    for &node in members {
+        for (edge, other) in ranked_edges(graph, node, &options.filter) {
+            if !member_set.contains(&other) || !seen.insert(edge) {
+                continue;
+            }
+            total += 1;
+            if listed < options.max_mappings_per_chunk {
+                listed += 1;
+                let (source, target) = graph.edge_endpoints(edge).unwrap();
+                let _ = writeln!(
+                    text,
+                    "- {} -[{}, {:.2}]-> {}",
+                    describe(context, graph, source),
+                    graph[edge].mapping_type,
+                    graph[edge].strength,
+                    describe(context, graph, target),
+                );
+            }
+        }
+    }
+    if total > listed {
+        let _ = writeln!(text, "- … and {} more mapping(s)", total - listed);
+    }
+    text
+}
+
+fn chunk(
+    kind: ChunkKind,
+    node_id: &str,
+    hops: usize,
+    text: String,
+    metadata: BTreeMap<String, Value>,
+) -> Chunk {
+    Chunk {
+        id: chunk_id(kind, node_id, hops),
+        kind,
+        node_id: node_id.to_string(),
+        content_hash: sha256_hex(&text),
+        text,
+        metadata,
+    }
+}
+
+/// Renders node and neighborhood chunks.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph, optionally enriched from a catalog.
+/// - `mappings`: The mappings the graph was built from, for labels and
+///   technology domains.
+/// - `metrics`: Per-node metrics to include, e.g. from
+///   `node_metrics_from_analyses`.
+/// - `options`: Chunk kinds, neighborhood radius and filters.
+///
+/// # Returns
+///
+/// - `Vec<Chunk>`: Chunks in node index order, node chunk first.
+pub fn build_chunks(
+    graph: &MappingGraph,
+    mappings: &[Mapping],
+    metrics: &NodeMetrics,
+    options: &RagOptions,
+) -> Vec<Chunk> {
+    let context = MappingContext::new(mappings);
+    let mut chunks = vec![];
+
+    for node in graph.node_indices() {
+        if !matches_filter(&context, graph, node, &options.filter) {
+            continue;
+        }
+        let id = &graph[node].id;
+        let metadata = node_metadata(&context, graph, node, metrics);
+
+        if options.node_chunks {
+            let text = node_text(&context, graph, node, metrics, options);
+            chunks.push(chunk(ChunkKind::Node, id, 0, text, metadata.clone()));
+        }
+        if options.neighborhood_chunks {
+            let members = k_hop_nodes(graph, node, options.neighborhood_hops);
+            let text = neighborhood_text(&context, graph, node, &members, options);
+            let mut metadata = metadata;
+            metadata.insert("hops".to_string(), json!(options.neighborhood_hops));
+            let neighbor_ids: Vec<&str> =
+                members[1..].iter().map(|&n| graph[n].id.as_str()).collect();
+            metadata.insert("neighbor_ids".to_string(), json!(neighbor_ids));
+            chunks.push(chunk(
+                ChunkKind::Neighborhood,
+                id,
+                options.neighborhood_hops,
+                text,
+                metadata,
+            ));
+        }
+    }
+
+    chunks
+}
+
+/// Writes chunks as JSON Lines.
+pub fn write_jsonl<P: AsRef<Path>>(chunks: &[Chunk], path: P) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for chunk in chunks {
+        serde_json::to_writer(&mut writer, chunk)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
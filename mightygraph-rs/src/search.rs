@@ -0,0 +1,342 @@
+//! Full-text search over the mapping descriptions and ATT&CK techniques.
+//!
+//! Every mapping row is one document, built from its
+//! `capability_description`, `attack_object_name`, `comments` and
+//! `references`. With a catalog, every technique is another document built
+//! from its name and description. Documents are ranked with BM25. Terms are
+//! weighted per field, so a match in a technique name counts more than one
+//! in a reference URL.
+//!
+//! Query terms missing from the index are matched fuzzily, to vocabulary
+//! terms within a small Levenshtein distance, so "credental dumpng" still
+//! finds "credential dumping".
+//!
+//! Hits are resolved to graph nodes: a mapping document scores both its
+//! VERIS node and its technique. Each hit lists the node's mappings from
+//! the graph.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::catalog::AttackCatalog;
+use crate::csr::GraphView;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::utils::tokenize;
+
+/// BM25 term-frequency saturation.
+pub const BM25_K1: f32 = 1.2;
+/// BM25 document-length normalization.
+pub const BM25_B: f32 = 0.75;
+
+/// Field weights applied to term counts.
+pub const TECHNIQUE_NAME_BOOST: f32 = 3.0;
+pub const DESCRIPTION_BOOST: f32 = 1.0;
+pub const COMMENTS_BOOST: f32 = 0.5;
+pub const REFERENCES_BOOST: f32 = 0.25;
+
+/// Options for [`SearchIndex::search`].
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub limit: usize,
+    /// Only returns nodes of this type.
+    pub node_type: Option<NodeType>,
+    /// Expands unknown query terms to close vocabulary terms.
+    pub fuzzy: bool,
+    /// Largest edit distance for fuzzy terms; by default 1 for terms of 4 to
+    /// 7 characters, 2 for longer ones and none for shorter ones.
+    pub max_edits: Option<usize>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            limit: 10,
+            node_type: None,
+            fuzzy: true,
+            max_edits: None,
+        }
+    }
+}
+
+/// A mapping of a hit node, as stored in the graph.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HitMapping {
+    pub veris_id: String,
+    pub mitre_id: String,
+    pub mapping_type: String,
+    pub strength: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub node_type: NodeType,
+    /// Technique name or VERIS description, when known.
+    pub label: Option<String>,
+    /// Best BM25 score among the node's documents.
+    pub score: f32,
+    /// Index terms that matched, including fuzzy expansions.
+    pub matched_terms: Vec<String>,
+    pub mappings: Vec<HitMapping>,
+}
+
+#[derive(Debug, Clone)]
+struct Document {
+    /// Nodes the document resolves to.
+    nodes: Vec<(String, NodeType)>,
+    length: f32,
+}
+
+/// Inverted index with BM25 scoring.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    documents: Vec<Document>,
+    vocabulary: HashMap<String, usize>,
+    terms: Vec<String>,
+    /// Per term: `(document, weighted term frequency)`.
+    postings: Vec<Vec<(usize, f32)>>,
+    average_length: f32,
+    labels: HashMap<String, String>,
+}
+
+impl SearchIndex {
+    /// Indexes the mappings and, optionally, the ATT&CK techniques.
+    ///
+    /// # Arguments
+    ///
+    /// - `mappings`: The mapping rows.
+    /// - `attack`: ATT&CK catalog whose technique names and descriptions are
+    ///   indexed too.
+    ///
+    /// # Returns
+    ///
+    /// - `SearchIndex`: The index, ready for [`SearchIndex::search`].
+    pub fn build(mappings: &[Mapping], attack: Option<&AttackCatalog>) -> SearchIndex {
+        let mut index = SearchIndex::default();
+
+        for mapping in mappings {
+            index.add_document(
+                vec![
+                    (mapping.capability_id.clone(), NodeType::Veris),
+                    (mapping.attack_object_id.clone(), NodeType::Mitre),
+                ],
+                &[
+                    (&mapping.attack_object_name, TECHNIQUE_NAME_BOOST),
+                    (&mapping.capability_description, DESCRIPTION_BOOST),
+                    (&mapping.comments, COMMENTS_BOOST),
+                    (&mapping.references, REFERENCES_BOOST),
+                ],
+            );
+            for (id, label) in [
+                (&mapping.attack_object_id, &mapping.attack_object_name),
+                (&mapping.capability_id, &mapping.capability_description),
+            ] {
+                if !label.trim().is_empty() {
+                    index.labels.entry(id.clone()).or_insert_with(|| label.clone());
+                }
+            }
+        }
+
+        if let Some(catalog) = attack {
+            for technique in catalog.techniques.values() {
+                index.add_document(
+                    vec![(technique.id.clone(), NodeType::Mitre)],
+                    &[
+                        (&technique.name, TECHNIQUE_NAME_BOOST),
+                        (&technique.description, DESCRIPTION_BOOST),
+                    ],
+                );
+                index.labels.insert(technique.id.clone(), technique.name.clone());
+            }
+        }
+
+        let total: f32 = index.documents.iter().map(|d| d.length).sum();
+        index.average_length = total / index.documents.len().max(1) as f32;
+        index
+    }
+
+    fn add_document(&mut self, nodes: Vec<(String, NodeType)>, fields: &[(&String, f32)]) {
+        let document = self.documents.len();
+        let mut frequencies: HashMap<usize, f32> = HashMap::new();
+        let mut length = 0.0f32;
+        for &(text, boost) in fields {
+            for token in tokenize(text) {
+                let next = self.terms.len();
+                let term = *self.vocabulary.entry(token.clone()).or_insert(next);
+                if term == next {
+                    self.terms.push(token);
+                    self.postings.push(vec![]);
+                }
+                *frequencies.entry(term).or_insert(0.0) += boost;
+                length += boost;
+            }
+        }
+        for (term, frequency) in frequencies {
+            self.postings[term].push((document, frequency));
+        }
+        self.documents.push(Document { nodes, length });
+    }
+
+    pub fn document_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Index terms matching a query term, with a weight: 1 for an exact
+    /// match, `1 / (1 + distance)` for fuzzy ones.
+    fn expand(&self, token: &str, options: &SearchOptions) -> Vec<(usize, f32)> {
+        if let Some(&term) = self.vocabulary.get(token) {
+            return vec![(term, 1.0)];
+        }
+        if !options.fuzzy {
+            return vec![];
+        }
+        let length = token.chars().count();
+        let max_edits = options.max_edits.unwrap_or(match length {
+            0..=3 => 0,
+            4..=7 => 1,
+            _ => 2,
+        });
+        if max_edits == 0 {
+            return vec![];
+        }
+        self.terms
+            .iter()
+            .enumerate()
+            .filter(|(_, term)| term.chars().count().abs_diff(length) <= max_edits)
+            .filter_map(|(i, term)| {
+                let distance = levenshtein(token, term);
+                (distance <= max_edits).then_some((i, 1.0 / (1.0 + distance as f32)))
+            })
+            .collect()
+    }
+
+    /// Searches the index and resolves the hits to graph nodes.
+    ///
+    /// # Arguments
+    ///
+    /// - `graph`: The mapping graph, used to list each hit's mappings.
+    /// - `node_indices`: Node ids to graph indices.
+    /// - `query`: Free-text query, e.g. `credential dumping lsass`.
+    /// - `options`: Result limit, node type filter and fuzzy matching.
+    ///
+    /// # Returns
+    ///
+    /// - `Vec<SearchHit>`: Nodes by descending score; ties are ordered by id.
+    pub fn search(
+        &self,
+        graph: &MappingGraph,
+        node_indices: &HashMap<String, NodeIndex>,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Vec<SearchHit> {
+        let documents = self.documents.len() as f32;
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        let mut matched: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+
+        let query_terms: BTreeSet<String> = tokenize(query).into_iter().collect();
+        for token in &query_terms {
+            for (term, weight) in self.expand(token, options) {
+                let postings = &self.postings[term];
+                let df = postings.len() as f32;
+                let idf = ((documents - df + 0.5) / (df + 0.5) + 1.0).ln();
+                for &(document, tf) in postings {
+                    let length = self.documents[document].length / self.average_length;
+                    let saturation =
+                        tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * (1.0 - BM25_B + BM25_B * length));
+                    *scores.entry(document).or_insert(0.0) += weight * idf * saturation;
+                    matched.entry(document).or_default().insert(term);
+                }
+            }
+        }
+
+        // A node scores as its best document; matched terms are pooled.
+        let mut nodes: HashMap<&(String, NodeType), (f32, BTreeSet<usize>)> = HashMap::new();
+        for (document, score) in scores {
+            for node in &self.documents[document].nodes {
+                if options.node_type.is_some_and(|t| t != node.1) {
+                    continue;
+                }
+                let entry = nodes.entry(node).or_insert((0.0, BTreeSet::new()));
+                entry.0 = entry.0.max(score);
+                entry.1.extend(&matched[&document]);
+            }
+        }
+
+        let mut ranked: Vec<_> = nodes.into_iter().collect();
+        ranked.sort_by(|a, b| {
+            b.1 .0
+                .partial_cmp(&a.1 .0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0 .0.cmp(&b.0 .0))
+        });
+        ranked.truncate(options.limit);
+
+        ranked
+            .into_iter()
+            .map(|((id, node_type), (score, terms))| SearchHit {
+                id: id.clone(),
+                node_type: *node_type,
+                label: self.labels.get(id).cloned(),
+                score,
+                matched_terms: terms.into_iter().map(|t| self.terms[t].clone()).collect(),
+                mappings: node_indices
+                    .get(id)
+                    .map(|&node| hit_mappings(graph, node))
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+fn hit_mappings(graph: &MappingGraph, node: NodeIndex) -> Vec<HitMapping> {
+    let mut mappings: Vec<HitMapping> = graph.incident_edges(node)
+        .map(|(edge, _)| {
+            let (source, target) = graph.edge_endpoints(edge).unwrap();
+            HitMapping {
+                veris_id: graph[source].id.clone(),
+                mitre_id: graph[target].id.clone(),
+                mapping_type: graph[edge].mapping_type.to_string(),
+                strength: graph[edge].strength,
+            }
+        })
+        .collect();
+    mappings.sort_by(|a, b| (&a.veris_id, &a.mitre_id).cmp(&(&b.veris_id, &b.mitre_id)));
+    mappings
+}
+
+/// Edit distance between two strings, counted in characters.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Runs a search and wraps the hits for `export_to_json`.
+pub fn perform_search(
+    index: &SearchIndex,
+    graph: &MappingGraph,
+    node_indices: &HashMap<String, NodeIndex>,
+    query: &str,
+    options: &SearchOptions,
+) -> Value {
+    let hits = index.search(graph, node_indices, query, options);
+    json!({
+        "query": query,
+        "terms": tokenize(query),
+        "hit_count": hits.len(),
+        "hits": hits,
+    })
+}
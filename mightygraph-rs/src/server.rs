@@ -0,0 +1,571 @@
+//! Embedded HTTP query API over the mapping graph (`server` feature).
+//!
+//! Loads the mappings once into a [`GraphState`] and serves JSON:
+//!
+//! | Route                                | Description                              |
+//! |--------------------------------------|------------------------------------------|
+//! | `GET /health`                        | Load time and graph size                 |
+//! | `GET /nodes?node_type=&offset=&limit=` | Paged node list                        |
+//! | `GET /nodes/:id`                     | One node                                 |
+//! | `GET /nodes/:id/neighbors`           | Paged neighbors with the connecting edge |
+//! | `GET /paths/shortest?from=&to=`      | Weighted shortest path                   |
+//! | `GET /subgraph/:id?k=`               | k-hop subgraph around a node             |
+//! | `GET /coverage`                      | Coverage report (needs an ATT&CK bundle) |
+//! | `GET /search?q=&node_type=&limit=&fuzzy=` | Full-text search over descriptions  |
+//! | `GET /analyses`                      | Available analyses                       |
+//! | `GET /analyses/:name?offset=&limit=` | Runs one `perform_*` analysis, cached    |
+//! | `GET /metrics`                       | Prometheus metrics (see [`crate::metrics`]) |
+//! | `POST /graphql`                      | GraphQL endpoint (`graphql` feature)     |
+//!
+//! The mapping and catalog files are watched; when one changes the state is
+//! rebuilt in the background and swapped in atomically, so requests never see
+//! a half-loaded graph. Analysis results are cached (see [`crate::cache`]) by
+//! the content hash of the loaded state, so a reload invalidates them.
+
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use notify::{RecursiveMode, Watcher};
+use petgraph::graph::EdgeIndex;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::cache::{cached_analysis, content_hash, AnalysisCache};
+use crate::catalog::{AttackCatalog, VerisCatalog};
+use crate::coverage::{perform_coverage_analysis, CoverageOptions, CoverageReport};
+use crate::csr::GraphView;
+use crate::metrics::{self, PROMETHEUS_CONTENT_TYPE};
+use crate::petgraph_full_0x0::prelude::*;
+use crate::petgraph_full_0x0::ANALYSIS_NAMES;
+use crate::query::parser::node_type_from_label;
+use crate::search::{SearchIndex, SearchOptions};
+use crate::traversal::{k_hop_nodes, node_to_json, shortest_path, subgraph_to_json};
+use crate::utils::{create_graph, load_csv_data};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+pub const MAX_PAGE_SIZE: usize = 1000;
+pub const MAX_SUBGRAPH_HOPS: usize = 4;
+
+/// Where the server reads its inputs and listens.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub mappings_path: PathBuf,
+    /// ATT&CK STIX bundle; enables `/coverage` and node enrichment.
+    pub attack_path: Option<PathBuf>,
+    /// `verisc-enum.json`; defaults to the enumerations found in the mappings.
+    pub veris_path: Option<PathBuf>,
+    pub addr: SocketAddr,
+    /// Quiet period after a file change before reloading.
+    pub reload_debounce: Duration,
+}
+
+impl ServerConfig {
+    pub fn new(mappings_path: impl Into<PathBuf>) -> ServerConfig {
+        ServerConfig {
+            mappings_path: mappings_path.into(),
+            attack_path: None,
+            veris_path: None,
+            addr: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            reload_debounce: Duration::from_millis(500),
+        }
+    }
+
+    fn watched_paths(&self) -> Vec<PathBuf> {
+        std::iter::once(self.mappings_path.clone())
+            .chain(self.attack_path.clone())
+            .chain(self.veris_path.clone())
+            .collect()
+    }
+
+    /// Directories holding the input files, and the canonical paths of the
+    /// input files.
+    ///
+    /// The directories are watched rather than the files: editors that save
+    /// by writing a new file and renaming it over the old one replace the
+    /// inode, and a watch on the file itself would stop firing.
+    fn watched_dirs(&self) -> (HashSet<PathBuf>, HashSet<PathBuf>) {
+        let paths = self.watched_paths();
+        let dirs = paths
+            .iter()
+            .map(|path| match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+                _ => PathBuf::from("."),
+            })
+            .collect();
+        let files = paths.iter().filter_map(|path| canonical_path(path)).collect();
+        (dirs, files)
+    }
+}
+
+/// `path` with symlinks and relative components resolved. A file that was
+/// just removed or renamed away resolves through its directory.
+fn canonical_path(path: &std::path::Path) -> Option<PathBuf> {
+    path.canonicalize().ok().or_else(|| {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => std::path::Path::new("."),
+        };
+        Some(dir.canonicalize().ok()?.join(path.file_name()?))
+    })
+}
+
+/// Everything loaded from the input files.
+pub struct GraphState {
+    pub mappings: Vec<Mapping>,
+    pub graph: MappingGraph,
+    pub node_indices: HashMap<String, NodeIndex>,
+    pub attack: Option<AttackCatalog>,
+    pub veris: VerisCatalog,
+    pub coverage: Option<CoverageReport>,
+    pub search: SearchIndex,
+    /// `technology_domain` values of the mappings behind each edge.
+    pub edge_domains: HashMap<EdgeIndex, Vec<String>>,
+    /// [`content_hash`] of the graph and mappings; the analysis cache key.
+    pub content_hash: String,
+    pub loaded_at: SystemTime,
+}
+
+impl GraphState {
+    /// Loads the mappings and catalogs and builds the graph.
+    pub fn load(
+        config: &ServerConfig,
+    ) -> std::result::Result<GraphState, Box<dyn std::error::Error>> {
+        let mappings = load_csv_data(&config.mappings_path)?;
+        let (mut graph, node_indices) = create_graph(&mappings)?;
+        let attack = config
+            .attack_path
+            .as_ref()
+            .map(AttackCatalog::from_stix_bundle)
+            .transpose()?;
+        let veris = match &config.veris_path {
+            Some(path) => VerisCatalog::from_enum_file(path)?,
+            None => VerisCatalog::from_mappings(&mappings),
+        };
+
+        if let Some(attack) = &attack {
+            crate::catalog::enrich_graph(&mut graph, attack);
+        }
+        let coverage = attack
+            .as_ref()
+            .map(|a| perform_coverage_analysis(&graph, &mappings, a, &veris, &CoverageOptions::default()));
+
+        let search = SearchIndex::build(&mappings, attack.as_ref());
+        let edge_domains = edge_domains(&graph, &mappings);
+        let content_hash = content_hash(&graph, &mappings);
+
+        metrics::record_graph(metrics::global(), &graph, &mappings);
+        if let Some(coverage) = &coverage {
+            metrics::record_coverage(metrics::global(), coverage);
+        }
+
+        Ok(GraphState {
+            mappings,
+            graph,
+            node_indices,
+            attack,
+            veris,
+            coverage,
+            search,
+            edge_domains,
+            content_hash,
+            loaded_at: SystemTime::now(),
+        })
+    }
+}
+
+fn edge_domains(graph: &MappingGraph, mappings: &[Mapping]) -> HashMap<EdgeIndex, Vec<String>> {
+    let mut by_pair: HashMap<(&str, &str), Vec<String>> = HashMap::new();
+    for m in mappings {
+        let domains = by_pair.entry((&m.capability_id, &m.attack_object_id)).or_default();
+        if !domains.contains(&m.technology_domain) {
+            domains.push(m.technology_domain.clone());
+        }
+    }
+    graph
+        .edge_indices()
+        .filter_map(|e| {
+            let (s, t) = graph.edge_endpoints(e)?;
+            let domains = by_pair.get(&(graph[s].id.as_str(), graph[t].id.as_str()))?;
+            Some((e, domains.clone()))
+        })
+        .collect()
+}
+
+/// Shared handle to the current state; swapped wholesale on reload.
+#[derive(Clone)]
+pub struct AppState {
+    current: Arc<RwLock<Arc<GraphState>>>,
+    cache: Arc<AnalysisCache>,
+}
+
+impl AppState {
+    pub fn new(state: GraphState) -> AppState {
+        AppState::with_cache(state, AnalysisCache::from_env())
+    }
+
+    pub fn with_cache(state: GraphState, cache: AnalysisCache) -> AppState {
+        AppState {
+            current: Arc::new(RwLock::new(Arc::new(state))),
+            cache: Arc::new(cache),
+        }
+    }
+
+    pub fn snapshot(&self) -> Arc<GraphState> {
+        self.current.read().unwrap().clone()
+    }
+
+    fn replace(&self, state: GraphState) {
+        *self.current.write().unwrap() = Arc::new(state);
+    }
+}
+
+/// An error response: `{ "error": "..." }` with a status code.
+pub struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn not_found(what: &str, id: &str) -> ApiError {
+        ApiError(StatusCode::NOT_FOUND, format!("{} not found: {}", what, id))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult = std::result::Result<Json<Value>, ApiError>;
+
+#[derive(Debug, Deserialize)]
+pub struct PageParams {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+impl PageParams {
+    /// Wraps one page of `items` as `{ total, offset, limit, items }`.
+    fn apply(&self, items: Vec<Value>) -> Value {
+        let offset = self.offset.unwrap_or(0);
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        json!({
+            "total": items.len(),
+            "offset": offset,
+            "limit": limit,
+            "items": items.into_iter().skip(offset).take(limit).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NodeListParams {
+    pub node_type: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    pub q: String,
+    pub node_type: Option<String>,
+    pub limit: Option<usize>,
+    pub fuzzy: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PathParams {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubgraphParams {
+    pub k: Option<usize>,
+}
+
+fn lookup(state: &GraphState, id: &str) -> std::result::Result<NodeIndex, ApiError> {
+    state
+        .node_indices
+        .get(id)
+        .copied()
+        .ok_or_else(|| ApiError::not_found("node", id))
+}
+
+/// Parses a `node_type` query parameter; unknown types are a 400.
+fn parse_node_type(label: Option<&str>) -> std::result::Result<Option<NodeType>, ApiError> {
+    match label {
+        None => Ok(None),
+        Some(t) => node_type_from_label(t).map(Some).ok_or_else(|| {
+            ApiError(StatusCode::BAD_REQUEST, format!("unknown node_type: {}", t))
+        }),
+    }
+}
+
+async fn health(State(app): State<AppState>) -> ApiResult {
+    let state = app.snapshot();
+    let loaded_at = state
+        .loaded_at
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(Json(json!({
+        "status": "ok",
+        "loaded_at": loaded_at,
+        "total_nodes": state.graph.node_count(),
+        "total_edges": state.graph.edge_count(),
+        "content_hash": state.content_hash,
+        "cache": app.cache.stats(),
+    })))
+}
+
+async fn list_nodes(
+    State(app): State<AppState>,
+    Query(params): Query<NodeListParams>,
+) -> ApiResult {
+    let state = app.snapshot();
+    let graph = &state.graph;
+    let node_type = parse_node_type(params.node_type.as_deref())?;
+    let items = graph
+        .node_indices()
+        .filter(|&n| node_type.is_none_or(|t| graph[n].node_type == t))
+        .map(|n| node_to_json(graph, n))
+        .collect();
+    let page = PageParams {
+        offset: params.offset,
+        limit: params.limit,
+    };
+    Ok(Json(page.apply(items)))
+}
+
+async fn get_node(State(app): State<AppState>, Path(id): Path<String>) -> ApiResult {
+    let state = app.snapshot();
+    let node = lookup(&state, &id)?;
+    Ok(Json(node_to_json(&state.graph, node)))
+}
+
+async fn get_neighbors(
+    State(app): State<AppState>,
+    Path(id): Path<String>,
+    Query(page): Query<PageParams>,
+) -> ApiResult {
+    let state = app.snapshot();
+    let graph = &state.graph;
+    let node = lookup(&state, &id)?;
+    let items = graph.incident_edges(node)
+        .map(|(edge, neighbor)| {
+            json!({
+                "node": node_to_json(graph, neighbor),
+                "mapping_type": graph[edge].mapping_type,
+                "strength": graph[edge].strength,
+            })
+        })
+        .collect();
+    Ok(Json(page.apply(items)))
+}
+
+async fn get_shortest_path(
+    State(app): State<AppState>,
+    Query(params): Query<PathParams>,
+) -> ApiResult {
+    let state = app.snapshot();
+    let from = lookup(&state, &params.from)?;
+    let to = lookup(&state, &params.to)?;
+    match shortest_path(&state.graph, from, to) {
+        Some((cost, path)) => Ok(Json(json!({
+            "cost": cost,
+            "hops": path.len() - 1,
+            "path": path.iter().map(|&n| &state.graph[n].id).collect::<Vec<_>>(),
+        }))),
+        None => Err(ApiError(
+            StatusCode::NOT_FOUND,
+            format!("no path between {} and {}", params.from, params.to),
+        )),
+    }
+}
+
+async fn get_subgraph(
+    State(app): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<SubgraphParams>,
+) -> ApiResult {
+    let state = app.snapshot();
+    let node = lookup(&state, &id)?;
+    let k = params.k.unwrap_or(1).min(MAX_SUBGRAPH_HOPS);
+    let nodes = k_hop_nodes(&state.graph, node, k);
+    Ok(Json(subgraph_to_json(&state.graph, &nodes)))
+}
+
+async fn get_coverage(State(app): State<AppState>) -> ApiResult {
+    let state = app.snapshot();
+    state
+        .coverage
+        .as_ref()
+        .map(|report| Json(report.to_json()))
+        .ok_or_else(|| {
+            ApiError(
+                StatusCode::NOT_FOUND,
+                "no ATT&CK bundle configured".to_string(),
+            )
+        })
+}
+
+async fn run_search(
+    State(app): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult {
+    let state = app.snapshot();
+    let node_type = parse_node_type(params.node_type.as_deref())?;
+    let options = SearchOptions {
+        limit: params.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE),
+        node_type,
+        fuzzy: params.fuzzy.unwrap_or(true),
+        ..SearchOptions::default()
+    };
+    let hits = state.search.search(&state.graph, &state.node_indices, &params.q, &options);
+    Ok(Json(json!({ "query": params.q, "hits": hits })))
+}
+
+async fn list_analyses() -> ApiResult {
+    Ok(Json(json!(ANALYSIS_NAMES)))
+}
+
+async fn run_analysis(
+    State(app): State<AppState>,
+    Path(name): Path<String>,
+    Query(page): Query<PageParams>,
+) -> ApiResult {
+    let state = app.snapshot();
+    let cache = app.cache.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let hash = &state.content_hash;
+        cached_analysis(&cache, hash, &name, &state.graph, &state.mappings, &state.node_indices)
+            .ok_or_else(|| ApiError::not_found("analysis", &name))
+    })
+    .await
+    .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))??;
+
+    // Large results (arrays, per-node objects) are paged; scalars pass through.
+    Ok(Json(match result {
+        Value::Array(items) if page.offset.is_some() || page.limit.is_some() => page.apply(items),
+        Value::Object(object) if page.offset.is_some() || page.limit.is_some() => {
+            let mut entries: Vec<_> = object.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            page.apply(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| json!({ "key": k, "value": v }))
+                    .collect(),
+            )
+        }
+        other => other,
+    }))
+}
+
+/// Renders the process-wide metrics in the Prometheus text format, with the
+/// cache counters refreshed first.
+async fn get_metrics(State(app): State<AppState>) -> Response {
+    let registry = metrics::global();
+    metrics::record_cache(registry, &app.cache.stats());
+    let content_type = [(axum::http::header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)];
+    (content_type, registry.render()).into_response()
+}
+
+/// Builds the router; exposed separately so tests can drive it in-process.
+pub fn router(app: AppState) -> Router {
+    let router = Router::new()
+        .route("/health", get(health))
+        .route("/nodes", get(list_nodes))
+        .route("/nodes/:id", get(get_node))
+        .route("/nodes/:id/neighbors", get(get_neighbors))
+        .route("/paths/shortest", get(get_shortest_path))
+        .route("/subgraph/:id", get(get_subgraph))
+        .route("/coverage", get(get_coverage))
+        .route("/search", get(run_search))
+        .route("/analyses", get(list_analyses))
+        .route("/analyses/:name", get(run_analysis))
+        .route("/metrics", get(get_metrics));
+
+    #[cfg(feature = "graphql")]
+    let router = router
+        .route(
+            "/graphql",
+            get(crate::graphql::graphiql).post(crate::graphql::graphql_handler),
+        )
+        .layer(axum::Extension(crate::graphql::schema()));
+
+    router.with_state(app)
+}
+
+/// Reloads the state whenever a watched input file changes.
+///
+/// Watches the directories of the input files and keeps the events that
+/// touch one of the input files, so saves that replace the file are seen
+/// too. Events are debounced so an editor's save-rename-write sequence
+/// triggers a single reload. A failed reload keeps serving the previous graph.
+/// Reloads are counted in the metrics registry (see
+/// [`metrics::record_reload`]).
+///
+/// Must be called inside a Tokio runtime. Reloading stops when the returned
+/// watcher is dropped.
+pub fn spawn_reloader(config: ServerConfig, app: AppState) -> Result<notify::RecommendedWatcher> {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let (dirs, files) = config.watched_dirs();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else { return };
+        let touches_input = event
+            .paths
+            .iter()
+            .any(|path| canonical_path(path).is_some_and(|path| files.contains(&path)));
+        if touches_input && (event.kind.is_modify() || event.kind.is_create()) {
+            let _ = tx.blocking_send(());
+        }
+    })?;
+    for dir in &dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    tokio::spawn(async move {
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(config.reload_debounce).await;
+            while rx.try_recv().is_ok() {}
+
+            let reload_config = config.clone();
+            match tokio::task::spawn_blocking(move || {
+                GraphState::load(&reload_config).map_err(|e| e.to_string())
+            })
+            .await
+            {
+                Ok(Ok(state)) => {
+                    metrics::record_reload(metrics::global(), true);
+                    app.replace(state);
+                }
+                Ok(Err(_)) | Err(_) => {
+                    metrics::record_reload(metrics::global(), false);
+                    metrics::record_loader_error(metrics::global(), "reload");
+                }
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+/// Loads the graph and serves the API until the process is stopped.
+pub async fn serve(config: ServerConfig) -> Result<()> {
+    let state = GraphState::load(&config).map_err(|e| e.to_string())?;
+    let app = AppState::new(state);
+    let _watcher = spawn_reloader(config.clone(), app.clone())?;
+
+    let listener = tokio::net::TcpListener::bind(config.addr).await?;
+    axum::serve(listener, router(app)).await?;
+    Ok(())
+}
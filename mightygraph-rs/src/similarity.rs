@@ -0,0 +1,460 @@
+//! Nearest-neighbor search over techniques and VERIS nodes.
+//!
+//! Four notions of similarity are supported:
+//!
+//! - **Jaccard**: overlap of the mapped neighbors.
+//! - **SimRank**: "similar nodes map to similar nodes", iterated over the
+//!   bipartite graph.
+//! - **Embedding**: cosine between node vectors from the `embeddings` module.
+//! - **Text**: TF-IDF cosine over the mapping descriptions, technique names
+//!   and, with a catalog, technique descriptions.
+//!
+//! The index is built once. Jaccard and text lookups only score nodes
+//! reachable through shared neighbors or shared terms. Embedding lookups
+//! compare against every node. SimRank scores are precomputed, keeping only
+//! the best `top_k` per node so the index grows linearly with the graph.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::catalog::AttackCatalog;
+use crate::embeddings::Embeddings;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::utils::tokenize;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityMetric {
+    Jaccard,
+    #[serde(rename = "simrank")]
+    SimRank,
+    Embedding,
+    Text,
+}
+
+impl SimilarityMetric {
+    pub const ALL: [SimilarityMetric; 4] = [
+        SimilarityMetric::Jaccard,
+        SimilarityMetric::SimRank,
+        SimilarityMetric::Embedding,
+        SimilarityMetric::Text,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "jaccard" => Some(SimilarityMetric::Jaccard),
+            "simrank" => Some(SimilarityMetric::SimRank),
+            "embedding" => Some(SimilarityMetric::Embedding),
+            "text" => Some(SimilarityMetric::Text),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimilarNode {
+    pub id: String,
+    pub node_type: NodeType,
+    pub score: f32,
+}
+
+/// TF-IDF vectors and their inverted index.
+#[derive(Debug, Clone, Default)]
+struct TextIndex {
+    vocabulary: HashMap<String, usize>,
+    idf: Vec<f32>,
+    /// Per node: `(term, weight)` sorted by term, L2-normalized.
+    vectors: Vec<Vec<(usize, f32)>>,
+    /// Per term: `(node, weight)`.
+    postings: Vec<Vec<(usize, f32)>>,
+}
+
+/// Similarity index over the nodes of a mapping graph.
+#[derive(Debug, Clone)]
+pub struct SimilarityIndex {
+    ids: Vec<String>,
+    node_types: Vec<NodeType>,
+    lookup: HashMap<String, usize>,
+    /// Distinct undirected neighbors, sorted.
+    neighbors: Vec<Vec<usize>>,
+    /// Per node: its best SimRank scores, `(node, score)` sorted by node.
+    simrank: Option<Vec<Vec<(usize, f32)>>>,
+    /// L2-normalized rows, or `None` when the node has no embedding.
+    embeddings: Option<Vec<Option<Vec<f32>>>>,
+    text: Option<TextIndex>,
+    /// Restricts results to the node type of the query node.
+    pub same_type_only: bool,
+}
+
+impl SimilarityIndex {
+    /// Indexes the graph structure, enabling Jaccard lookups.
+    pub fn new(graph: &MappingGraph) -> Self {
+        let ids: Vec<String> = graph.node_indices().map(|n| graph[n].id.clone()).collect();
+        let neighbors = graph
+            .node_indices()
+            .map(|n| {
+                let mut list: Vec<usize> =
+                    graph.neighbors_undirected(n).map(|m| m.index()).collect();
+                list.sort_unstable();
+                list.dedup();
+                list
+            })
+            .collect();
+
+        SimilarityIndex {
+            lookup: ids.iter().enumerate().map(|(i, id)| (id.clone(), i)).collect(),
+            node_types: graph.node_indices().map(|n| graph[n].node_type).collect(),
+            ids,
+            neighbors,
+            simrank: None,
+            embeddings: None,
+            text: None,
+            same_type_only: true,
+        }
+    }
+
+    /// Precomputes SimRank scores, enabling SimRank lookups.
+    ///
+    /// Each iteration keeps the `top_k` best scores per node and treats the
+    /// pruned pairs as 0, so scores of weakly similar nodes are slightly
+    /// underestimated.
+    ///
+    /// # Arguments
+    ///
+    /// - `decay`: The SimRank constant `C`, usually 0.8.
+    /// - `iterations`: Number of iterations; 5 is close to convergence.
+    /// - `top_k`: Scores kept per node; at least the `k` of later lookups.
+    pub fn with_simrank(mut self, decay: f32, iterations: usize, top_k: usize) -> Self {
+        let mut scores: Vec<Vec<(usize, f32)>> = vec![vec![]; self.ids.len()];
+        for _ in 0..iterations {
+            scores = (0..self.ids.len())
+                .map(|a| self.simrank_row(a, &scores, decay, top_k))
+                .collect();
+        }
+
+        self.simrank = Some(scores);
+        self
+    }
+
+    /// One SimRank iteration for node `a`: `C / (|N(a)| |N(b)|)` times the
+    /// sum of `S(i, j)` over the neighbors `i` of `a` and `j` of `b`.
+    fn simrank_row(
+        &self,
+        a: usize,
+        scores: &[Vec<(usize, f32)>],
+        decay: f32,
+        top_k: usize,
+    ) -> Vec<(usize, f32)> {
+        // partial[j] = sum of S(i, j) over the neighbors i of a; S(i, i) = 1.
+        let mut partial: HashMap<usize, f32> = HashMap::new();
+        for &i in &self.neighbors[a] {
+            *partial.entry(i).or_insert(0.0) += 1.0;
+            for &(j, score) in &scores[i] {
+                *partial.entry(j).or_insert(0.0) += score;
+            }
+        }
+
+        // Only nodes b next to some j with partial[j] > 0 get a score.
+        let mut sums: HashMap<usize, f32> = HashMap::new();
+        for (&j, &p) in &partial {
+            for &b in &self.neighbors[j] {
+                if b != a && self.node_types[b] == self.node_types[a] {
+                    *sums.entry(b).or_insert(0.0) += p;
+                }
+            }
+        }
+
+        let degree = self.neighbors[a].len() as f32;
+        let mut row: Vec<(usize, f32)> = sums
+            .into_iter()
+            .map(|(b, sum)| (b, decay * sum / (degree * self.neighbors[b].len() as f32)))
+            .collect();
+        if row.len() > top_k {
+            let by_score = |x: &(usize, f32), y: &(usize, f32)| {
+                y.1.partial_cmp(&x.1).unwrap_or(std::cmp::Ordering::Equal).then(x.0.cmp(&y.0))
+            };
+            row.select_nth_unstable_by(top_k, by_score);
+            row.truncate(top_k);
+        }
+        row.sort_unstable_by_key(|&(b, _)| b);
+        row
+    }
+
+    /// Adds node vectors, enabling embedding lookups. Nodes missing from
+    /// `embeddings` are skipped.
+    pub fn with_embeddings(mut self, embeddings: &Embeddings) -> Self {
+        let rows = embeddings.index();
+        let vectors: Vec<Option<Vec<f32>>> = self
+            .ids
+            .iter()
+            .map(|id| {
+                rows.get(id.as_str()).map(|&row| {
+                    let vector = embeddings.vector(row);
+                    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt().max(f32::EPSILON);
+                    vector.iter().map(|x| x / norm).collect()
+                })
+            })
+            .collect();
+        self.embeddings = Some(vectors);
+        self
+    }
+
+    /// Adds TF-IDF vectors of the node texts, enabling text lookups.
+    ///
+    /// # Arguments
+    ///
+    /// - `texts`: Text per node id, e.g. from [`node_texts`].
+    pub fn with_text(mut self, texts: &HashMap<String, String>) -> Self {
+        let mut index = TextIndex::default();
+        let counts: Vec<BTreeMap<usize, usize>> = self
+            .ids
+            .iter()
+            .map(|id| {
+                let mut counts = BTreeMap::new();
+                for token in tokenize(texts.get(id).map_or("", String::as_str)) {
+                    let next = index.vocabulary.len();
+                    let term = *index.vocabulary.entry(token).or_insert(next);
+                    *counts.entry(term).or_insert(0) += 1;
+                }
+                counts
+            })
+            .collect();
+
+        let mut document_frequency = vec![0usize; index.vocabulary.len()];
+        for term in counts.iter().flat_map(|c| c.keys()) {
+            document_frequency[*term] += 1;
+        }
+        let documents = self.ids.len() as f32;
+        index.idf = document_frequency
+            .iter()
+            .map(|&df| ((documents + 1.0) / (df as f32 + 1.0)).ln() + 1.0)
+            .collect();
+
+        index.postings = vec![vec![]; index.vocabulary.len()];
+        for (node, terms) in counts.iter().enumerate() {
+            let vector = index.weigh(terms.iter().map(|(&term, &count)| (term, count)));
+            for &(term, weight) in &vector {
+                index.postings[term].push((node, weight));
+            }
+            index.vectors.push(vector);
+        }
+
+        self.text = Some(index);
+        self
+    }
+
+    fn position(&self, id: &str) -> Result<usize> {
+        self.lookup
+            .get(id)
+            .copied()
+            .ok_or_else(|| format!("unknown node {}", id).into())
+    }
+
+    fn not_built(metric: SimilarityMetric) -> Box<dyn std::error::Error> {
+        format!("{:?} similarity is not enabled on this index", metric).into()
+    }
+
+    /// Whether lookups with `metric` are available.
+    pub fn supports(&self, metric: SimilarityMetric) -> bool {
+        match metric {
+            SimilarityMetric::Jaccard => true,
+            SimilarityMetric::SimRank => self.simrank.is_some(),
+            SimilarityMetric::Embedding => self.embeddings.is_some(),
+            SimilarityMetric::Text => self.text.is_some(),
+        }
+    }
+
+    /// Finds the `k` nodes most similar to `id`.
+    ///
+    /// # Arguments
+    ///
+    /// - `id`: Node id of the technique or VERIS node.
+    /// - `metric`: Similarity to rank by; it must be enabled on the index.
+    /// - `k`: Number of results.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<Vec<SimilarNode>>`: Best first, excluding the node itself and
+    ///   nodes scoring 0.
+    pub fn most_similar(
+        &self,
+        id: &str,
+        metric: SimilarityMetric,
+        k: usize,
+    ) -> Result<Vec<SimilarNode>> {
+        let node = self.position(id)?;
+        let scores: Vec<(usize, f32)> = match metric {
+            SimilarityMetric::Jaccard => self.jaccard_scores(node),
+            SimilarityMetric::SimRank => {
+                let rows = self.simrank.as_ref().ok_or_else(|| Self::not_built(metric))?;
+                rows[node].clone()
+            }
+            SimilarityMetric::Embedding => {
+                let vectors = self.embeddings.as_ref().ok_or_else(|| Self::not_built(metric))?;
+                match &vectors[node] {
+                    None => vec![],
+                    Some(query) => vectors
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, v)| v.as_ref().map(|v| (i, dot(query, v))))
+                        .collect(),
+                }
+            }
+            SimilarityMetric::Text => {
+                let text = self.text.as_ref().ok_or_else(|| Self::not_built(metric))?;
+                text.scores(&text.vectors[node])
+            }
+        };
+
+        let node_type = self.same_type_only.then_some(self.node_types[node]);
+        Ok(self.top_k(scores, Some(node), node_type, k))
+    }
+
+    /// Finds the `k` nodes whose text best matches free text, e.g. the
+    /// summary of a new incident.
+    pub fn most_similar_to_text(
+        &self,
+        query: &str,
+        node_type: Option<NodeType>,
+        k: usize,
+    ) -> Result<Vec<SimilarNode>> {
+        let text = self
+            .text
+            .as_ref()
+            .ok_or_else(|| Self::not_built(SimilarityMetric::Text))?;
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        for token in tokenize(query) {
+            if let Some(&term) = text.vocabulary.get(&token) {
+                *counts.entry(term).or_insert(0) += 1;
+            }
+        }
+        let vector = text.weigh(counts.into_iter());
+        Ok(self.top_k(text.scores(&vector), None, node_type, k))
+    }
+
+    fn jaccard_scores(&self, node: usize) -> Vec<(usize, f32)> {
+        let own = &self.neighbors[node];
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for &middle in own {
+            for &other in &self.neighbors[middle] {
+                *shared.entry(other).or_insert(0) += 1;
+            }
+        }
+        shared
+            .into_iter()
+            .map(|(other, common)| {
+                let union = own.len() + self.neighbors[other].len() - common;
+                (other, common as f32 / union as f32)
+            })
+            .collect()
+    }
+
+    fn top_k(
+        &self,
+        scores: Vec<(usize, f32)>,
+        exclude: Option<usize>,
+        node_type: Option<NodeType>,
+        k: usize,
+    ) -> Vec<SimilarNode> {
+        let mut scores: Vec<(usize, f32)> = scores
+            .into_iter()
+            .filter(|&(i, score)| {
+                score > 0.0
+                    && Some(i) != exclude
+                    && node_type.is_none_or(|t| self.node_types[i] == t)
+            })
+            .collect();
+        let by_score = |a: &(usize, f32), b: &(usize, f32)| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| self.ids[a.0].cmp(&self.ids[b.0]))
+        };
+        if scores.len() > k {
+            scores.select_nth_unstable_by(k, by_score);
+            scores.truncate(k);
+        }
+        scores.sort_by(by_score);
+
+        scores
+            .into_iter()
+            .map(|(i, score)| SimilarNode {
+                id: self.ids[i].clone(),
+                node_type: self.node_types[i],
+                score,
+            })
+            .collect()
+    }
+}
+
+impl TextIndex {
+    /// TF-IDF weights (`1 + ln(tf)` times idf), L2-normalized.
+    fn weigh(&self, counts: impl Iterator<Item = (usize, usize)>) -> Vec<(usize, f32)> {
+        let mut vector: Vec<(usize, f32)> = counts
+            .map(|(term, count)| (term, (1.0 + (count as f32).ln()) * self.idf[term]))
+            .collect();
+        let norm = vector.iter().map(|(_, w)| w * w).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|(_, w)| *w /= norm);
+        }
+        vector
+    }
+
+    /// Cosine of `vector` with every node sharing a term with it.
+    fn scores(&self, vector: &[(usize, f32)]) -> Vec<(usize, f32)> {
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        for &(term, weight) in vector {
+            for &(node, node_weight) in &self.postings[term] {
+                *scores.entry(node).or_insert(0.0) += weight * node_weight;
+            }
+        }
+        scores.into_iter().collect()
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Text describing each node: the `capability_description`s of a VERIS
+/// node, and the `attack_object_name` of a technique followed by its catalog
+/// name and description when a catalog is given.
+pub fn node_texts(mappings: &[Mapping], attack: Option<&AttackCatalog>) -> HashMap<String, String> {
+    let mut parts: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add = |id: &str, text: &str| {
+        let entry = parts.entry(id.to_string()).or_default();
+        if !text.trim().is_empty() && !entry.iter().any(|t| t == text) {
+            entry.push(text.to_string());
+        }
+    };
+    for mapping in mappings {
+        add(&mapping.capability_id, &mapping.capability_description);
+        add(&mapping.attack_object_id, &mapping.attack_object_name);
+    }
+    if let Some(catalog) = attack {
+        for technique in catalog.techniques.values() {
+            add(&technique.id, &technique.name);
+            add(&technique.id, &technique.description);
+        }
+    }
+    parts.into_iter().map(|(id, texts)| (id, texts.join("\n"))).collect()
+}
+
+/// Runs every metric enabled on the index for one node.
+///
+/// # Returns
+///
+/// - `Result<Value>`: `{ id, k, results: { metric: [SimilarNode] } }`.
+pub fn perform_similarity_search(index: &SimilarityIndex, id: &str, k: usize) -> Result<Value> {
+    let mut results = serde_json::Map::new();
+    for metric in SimilarityMetric::ALL {
+        if index.supports(metric) {
+            let key = serde_json::to_value(metric)?.as_str().unwrap_or_default().to_string();
+            results.insert(key, json!(index.most_similar(id, metric, k)?));
+        }
+    }
+    Ok(json!({ "id": id, "k": k, "results": results }))
+}
@@ -0,0 +1,474 @@
+//! PostgreSQL storage of graphs and analysis runs.
+//!
+//! Every stored run gets a row in `runs` with its metadata: input file path
+//! and SHA-256, graph content hash, tool version, ATT&CK and framework
+//! versions of the mappings, counts, parameters and timestamps. The run's
+//! nodes, node metadata, edges, mapping rows and analysis results go into
+//! normalized tables keyed by the run ID, so earlier runs stay queryable
+//! (see [`PgStore::analysis_history`]) and any run's graph can be loaded back
+//! with [`PgStore::load_graph`].
+//!
+//! The schema is created by the SQL migrations in `src/storage/migrations`,
+//! applied in order by [`PgStore::migrate`] and recorded in
+//! `schema_migrations`. [`PgStore::connect`] migrates on connect. The
+//! `docker-compose.yml` database is reached with [`database_url_from_env`].
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use chrono::{DateTime, Utc};
+use postgres::{Client, NoTls, Row};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::petgraph_full_0x0::prelude::*;
+use crate::query::parser::node_type_from_label;
+use crate::utils::{add_node_if_not_exists, graph_hash, sha256_hex};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// One schema change, applied once in a transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// The migrations, in the order they are applied.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    name: "create_tables",
+    sql: include_str!("storage/migrations/0001_create_tables.sql"),
+}];
+
+/// Database of the `docker-compose.yml` setup.
+pub const DEFAULT_DATABASE: &str = "etl_pipeline_001";
+
+/// The connection URL: `DATABASE_URL`, or one built from the
+/// `docker-compose.yml` variables `USER_PSQL` and `PW_PSQL_PW` with
+/// `PGHOST` (default `localhost`) and `PGPORT` (default `5432`).
+pub fn database_url_from_env() -> Option<String> {
+    if let Ok(url) = std::env::var("DATABASE_URL") {
+        return Some(url);
+    }
+    let user = std::env::var("USER_PSQL").ok()?;
+    let password = std::env::var("PW_PSQL_PW").unwrap_or_default();
+    let host = std::env::var("PGHOST").unwrap_or_else(|_| "localhost".to_string());
+    let port = std::env::var("PGPORT").unwrap_or_else(|_| "5432".to_string());
+    Some(format!(
+        "host={} port={} user={} password={} dbname={}",
+        host, port, user, password, DEFAULT_DATABASE
+    ))
+}
+
+/// What a run was computed from, and when.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunMetadata {
+    pub started_at: DateTime<Utc>,
+    /// Set to the save time when the run is stored without one.
+    pub finished_at: Option<DateTime<Utc>>,
+    pub input_path: String,
+    pub input_sha256: String,
+    /// [`graph_hash`] of the stored graph.
+    pub graph_hash: String,
+    /// Version of this crate.
+    pub tool_version: String,
+    /// Distinct `attack_version` values of the mappings.
+    pub attack_versions: Vec<String>,
+    /// Distinct `mapping_framework_version` values of the mappings.
+    pub framework_versions: Vec<String>,
+    pub node_count: usize,
+    pub edge_count: usize,
+    /// Free-form run options, e.g. the reports directory.
+    pub parameters: Value,
+}
+
+impl RunMetadata {
+    /// Metadata of a run starting now.
+    ///
+    /// # Arguments
+    ///
+    /// - `input_path`: The mappings file; it is read to hash it.
+    /// - `graph`: The graph built from it.
+    /// - `mappings`: The mapping rows, for the versions.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<RunMetadata>`: The metadata, or an error if the file cannot be read.
+    pub fn new(
+        input_path: &str,
+        graph: &MappingGraph,
+        mappings: &[Mapping],
+    ) -> Result<RunMetadata> {
+        let distinct = |values: Vec<&String>| -> Vec<String> {
+            let mut values: Vec<String> = values.into_iter().cloned().collect();
+            values.sort();
+            values.dedup();
+            values
+        };
+        Ok(RunMetadata {
+            started_at: Utc::now(),
+            finished_at: None,
+            input_path: input_path.to_string(),
+            input_sha256: sha256_hex(&fs::read(input_path)?),
+            graph_hash: graph_hash(graph),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            attack_versions: distinct(mappings.iter().map(|m| &m.attack_version).collect()),
+            framework_versions: distinct(
+                mappings.iter().map(|m| &m.mapping_framework_version).collect(),
+            ),
+            node_count: graph.node_count(),
+            edge_count: graph.edge_count(),
+            parameters: json!({}),
+        })
+    }
+}
+
+/// A run as stored.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredRun {
+    pub id: i64,
+    pub metadata: RunMetadata,
+}
+
+const RUN_COLUMNS: &str = "id, started_at, finished_at, input_path, input_sha256, graph_hash, \
+     tool_version, attack_versions, framework_versions, node_count, edge_count, parameters";
+
+fn stored_run(row: &Row) -> StoredRun {
+    StoredRun {
+        id: row.get("id"),
+        metadata: RunMetadata {
+            started_at: row.get("started_at"),
+            finished_at: row.get("finished_at"),
+            input_path: row.get("input_path"),
+            input_sha256: row.get("input_sha256"),
+            graph_hash: row.get("graph_hash"),
+            tool_version: row.get("tool_version"),
+            attack_versions: row.get("attack_versions"),
+            framework_versions: row.get("framework_versions"),
+            node_count: row.get::<_, i32>("node_count") as usize,
+            edge_count: row.get::<_, i32>("edge_count") as usize,
+            parameters: row.get("parameters"),
+        },
+    }
+}
+
+/// A connection to the run database.
+pub struct PgStore {
+    client: Client,
+}
+
+impl PgStore {
+    /// Connects and applies pending migrations.
+    ///
+    /// # Arguments
+    ///
+    /// - `url`: A `postgresql://` URL or `key=value` connection string.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<PgStore>`: The store, with an up-to-date schema.
+    pub fn connect(url: &str) -> Result<PgStore> {
+        let mut store = PgStore { client: Client::connect(url, NoTls)? };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// Applies the [`MIGRATIONS`] that are not recorded in `schema_migrations`.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<Vec<i32>>`: The versions applied by this call.
+    pub fn migrate(&mut self) -> Result<Vec<i32>> {
+        self.client.batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+        )?;
+        let mut applied = vec![];
+        for migration in MIGRATIONS {
+            let mut transaction = self.client.transaction()?;
+            // Another process migrating at the same time waits here.
+            transaction.batch_execute("LOCK TABLE schema_migrations IN EXCLUSIVE MODE")?;
+            let done = transaction
+                .query_opt(
+                    "SELECT 1 FROM schema_migrations WHERE version = $1",
+                    &[&migration.version],
+                )?
+                .is_some();
+            if !done {
+                transaction.batch_execute(migration.sql)?;
+                transaction.execute(
+                    "INSERT INTO schema_migrations (version, name) VALUES ($1, $2)",
+                    &[&migration.version, &migration.name],
+                )?;
+                applied.push(migration.version);
+            }
+            transaction.commit()?;
+        }
+        Ok(applied)
+    }
+
+    /// Stores a run in one transaction.
+    ///
+    /// # Arguments
+    ///
+    /// - `metadata`: The run metadata.
+    /// - `graph`: The graph; node positions are kept so a loaded graph has
+    ///   the same node and edge indices.
+    /// - `mappings`: The mapping rows.
+    /// - `analyses`: The analysis results, one row per analysis.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<i64>`: The ID of the new run.
+    pub fn save_run(
+        &mut self,
+        metadata: &RunMetadata,
+        graph: &MappingGraph,
+        mappings: &[Mapping],
+        analyses: &AnalysisResults,
+    ) -> Result<i64> {
+        let mut transaction = self.client.transaction()?;
+        let run_id: i64 = transaction
+            .query_one(
+                "INSERT INTO runs (started_at, finished_at, input_path, input_sha256, graph_hash,
+                     tool_version, attack_versions, framework_versions, node_count, edge_count,
+                     parameters)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                 RETURNING id",
+                &[
+                    &metadata.started_at,
+                    &metadata.finished_at.unwrap_or_else(Utc::now),
+                    &metadata.input_path,
+                    &metadata.input_sha256,
+                    &metadata.graph_hash,
+                    &metadata.tool_version,
+                    &metadata.attack_versions,
+                    &metadata.framework_versions,
+                    &i32::try_from(metadata.node_count)?,
+                    &i32::try_from(metadata.edge_count)?,
+                    &metadata.parameters,
+                ],
+            )?
+            .get(0);
+
+        let insert_node = transaction.prepare(
+            "INSERT INTO nodes (run_id, node_id, position, node_type) VALUES ($1, $2, $3, $4)",
+        )?;
+        let insert_metadata = transaction.prepare(
+            "INSERT INTO node_metadata (run_id, node_id, key, value) VALUES ($1, $2, $3, $4)",
+        )?;
+        for index in graph.node_indices() {
+            let node = &graph[index];
+            let position = i32::try_from(index.index())?;
+            let node_type = format!("{:?}", node.node_type);
+            transaction.execute(&insert_node, &[&run_id, &node.id, &position, &node_type])?;
+            for (key, value) in &node.metadata {
+                transaction.execute(&insert_metadata, &[&run_id, &node.id, key, value])?;
+            }
+        }
+
+        let insert_edge = transaction.prepare(
+            "INSERT INTO edges (run_id, position, source_id, target_id, mapping_type, strength)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )?;
+        for (position, edge) in graph.raw_edges().iter().enumerate() {
+            transaction.execute(
+                &insert_edge,
+                &[
+                    &run_id,
+                    &i32::try_from(position)?,
+                    &graph[edge.source()].id,
+                    &graph[edge.target()].id,
+                    &&*edge.weight.mapping_type,
+                    &edge.weight.strength,
+                ],
+            )?;
+        }
+
+        let insert_mapping = transaction.prepare(
+            "INSERT INTO mappings (run_id, row_number, mapping_framework, mapping_framework_version,
+                 capability_group, capability_id, capability_description, mapping_type,
+                 attack_object_id, attack_object_name, attack_version, technology_domain,
+                 \"references\", comments, organization, creation_date, last_update)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)",
+        )?;
+        for (row_number, m) in mappings.iter().enumerate() {
+            transaction.execute(
+                &insert_mapping,
+                &[
+                    &run_id,
+                    &i32::try_from(row_number)?,
+                    &m.mapping_framework,
+                    &m.mapping_framework_version,
+                    &m.capability_group,
+                    &m.capability_id,
+                    &m.capability_description,
+                    &m.mapping_type,
+                    &m.attack_object_id,
+                    &m.attack_object_name,
+                    &m.attack_version,
+                    &m.technology_domain,
+                    &m.references,
+                    &m.comments,
+                    &m.organization,
+                    &m.creation_date,
+                    &m.last_update,
+                ],
+            )?;
+        }
+
+        let insert_analysis = transaction
+            .prepare("INSERT INTO analyses (run_id, name, result) VALUES ($1, $2, $3)")?;
+        if let Value::Object(results) = json!(analyses) {
+            for (name, result) in &results {
+                transaction.execute(&insert_analysis, &[&run_id, name, result])?;
+            }
+        }
+
+        transaction.commit()?;
+        Ok(run_id)
+    }
+
+    /// The stored runs, newest first.
+    pub fn runs(&mut self) -> Result<Vec<StoredRun>> {
+        let sql = format!("SELECT {} FROM runs ORDER BY id DESC", RUN_COLUMNS);
+        Ok(self.client.query(&sql, &[])?.iter().map(stored_run).collect())
+    }
+
+    pub fn run(&mut self, run_id: i64) -> Result<Option<StoredRun>> {
+        let sql = format!("SELECT {} FROM runs WHERE id = $1", RUN_COLUMNS);
+        Ok(self.client.query_opt(&sql, &[&run_id])?.as_ref().map(stored_run))
+    }
+
+    /// The newest run built from an input file with this SHA-256.
+    pub fn latest_run_for_input(&mut self, input_sha256: &str) -> Result<Option<StoredRun>> {
+        let sql = format!(
+            "SELECT {} FROM runs WHERE input_sha256 = $1 ORDER BY id DESC LIMIT 1",
+            RUN_COLUMNS
+        );
+        Ok(self.client.query_opt(&sql, &[&input_sha256])?.as_ref().map(stored_run))
+    }
+
+    /// Loads the graph of a run.
+    ///
+    /// # Arguments
+    ///
+    /// - `run_id`: The run.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<(MappingGraph, HashMap<String, NodeIndex>)>`: The graph, with
+    ///   the node and edge order it was stored with, and its node index map.
+    pub fn load_graph(
+        &mut self,
+        run_id: i64,
+    ) -> Result<(MappingGraph, HashMap<String, NodeIndex>)> {
+        let mut graph = MappingGraph::new();
+        let mut node_indices = HashMap::new();
+        let nodes = self.client.query(
+            "SELECT node_id, node_type FROM nodes WHERE run_id = $1 ORDER BY position",
+            &[&run_id],
+        )?;
+        for row in &nodes {
+            let id: &str = row.get("node_id");
+            let label: &str = row.get("node_type");
+            let node_type =
+                node_type_from_label(label).ok_or_else(|| format!("unknown node type {}", label))?;
+            add_node_if_not_exists(&mut graph, &mut node_indices, id, node_type);
+        }
+
+        let metadata = self.client.query(
+            "SELECT node_id, key, value FROM node_metadata WHERE run_id = $1",
+            &[&run_id],
+        )?;
+        for row in &metadata {
+            let index = node_indices[row.get::<_, &str>("node_id")];
+            graph[index].metadata.insert(row.get("key"), row.get("value"));
+        }
+
+        let edges = self.client.query(
+            "SELECT source_id, target_id, mapping_type, strength FROM edges
+             WHERE run_id = $1 ORDER BY position",
+            &[&run_id],
+        )?;
+        for row in &edges {
+            let source = node_indices[row.get::<_, &str>("source_id")];
+            let target = node_indices[row.get::<_, &str>("target_id")];
+            let edge = EdgeData {
+                mapping_type: row.get::<_, &str>("mapping_type").into(),
+                strength: row.get("strength"),
+            };
+            graph.add_edge(source, target, edge);
+        }
+        Ok((graph, node_indices))
+    }
+
+    /// Loads the mapping rows of a run, in their original order.
+    pub fn load_mappings(&mut self, run_id: i64) -> Result<Vec<Mapping>> {
+        let rows = self.client.query(
+            "SELECT * FROM mappings WHERE run_id = $1 ORDER BY row_number",
+            &[&run_id],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| Mapping {
+                mapping_framework: row.get("mapping_framework"),
+                mapping_framework_version: row.get("mapping_framework_version"),
+                capability_group: row.get("capability_group"),
+                capability_id: row.get("capability_id"),
+                capability_description: row.get("capability_description"),
+                mapping_type: row.get("mapping_type"),
+                attack_object_id: row.get("attack_object_id"),
+                attack_object_name: row.get("attack_object_name"),
+                attack_version: row.get("attack_version"),
+                technology_domain: row.get("technology_domain"),
+                references: row.get("references"),
+                comments: row.get("comments"),
+                organization: row.get("organization"),
+                creation_date: row.get("creation_date"),
+                last_update: row.get("last_update"),
+            })
+            .collect())
+    }
+
+    /// Loads the analysis results of a run, by analysis name.
+    pub fn load_analyses(&mut self, run_id: i64) -> Result<BTreeMap<String, Value>> {
+        let rows = self
+            .client
+            .query("SELECT name, result FROM analyses WHERE run_id = $1", &[&run_id])?;
+        Ok(rows.iter().map(|row| (row.get("name"), row.get("result"))).collect())
+    }
+
+    /// One analysis across all runs.
+    ///
+    /// # Arguments
+    ///
+    /// - `name`: The analysis, e.g. `basic_stats`.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<Vec<(i64, DateTime<Utc>, Value)>>`: Run ID, run start and
+    ///   result, oldest run first.
+    pub fn analysis_history(&mut self, name: &str) -> Result<Vec<(i64, DateTime<Utc>, Value)>> {
+        let rows = self.client.query(
+            "SELECT r.id, r.started_at, a.result FROM analyses a JOIN runs r ON r.id = a.run_id
+             WHERE a.name = $1 ORDER BY r.id",
+            &[&name],
+        )?;
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1), row.get(2))).collect())
+    }
+
+    /// Deletes a run and everything stored with it.
+    ///
+    /// # Returns
+    ///
+    /// - `Result<bool>`: Whether the run existed.
+    pub fn delete_run(&mut self, run_id: i64) -> Result<bool> {
+        Ok(self.client.execute("DELETE FROM runs WHERE id = $1", &[&run_id])? > 0)
+    }
+}
@@ -0,0 +1,79 @@
+-- One row per stored analysis run.
+CREATE TABLE runs (
+    id BIGSERIAL PRIMARY KEY,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ,
+    input_path TEXT NOT NULL,
+    input_sha256 TEXT NOT NULL,
+    graph_hash TEXT NOT NULL,
+    tool_version TEXT NOT NULL,
+    attack_versions TEXT[] NOT NULL DEFAULT '{}',
+    framework_versions TEXT[] NOT NULL DEFAULT '{}',
+    node_count INTEGER NOT NULL,
+    edge_count INTEGER NOT NULL,
+    parameters JSONB NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX runs_input_sha256_idx ON runs (input_sha256);
+CREATE INDEX runs_graph_hash_idx ON runs (graph_hash);
+
+CREATE TABLE nodes (
+    run_id BIGINT NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    node_id TEXT NOT NULL,
+    -- Position in the graph, so a loaded graph keeps the node indices.
+    position INTEGER NOT NULL,
+    node_type TEXT NOT NULL,
+    PRIMARY KEY (run_id, node_id),
+    UNIQUE (run_id, position)
+);
+
+CREATE TABLE node_metadata (
+    run_id BIGINT NOT NULL,
+    node_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (run_id, node_id, key),
+    FOREIGN KEY (run_id, node_id) REFERENCES nodes (run_id, node_id) ON DELETE CASCADE
+);
+
+CREATE TABLE edges (
+    run_id BIGINT NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    source_id TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    mapping_type TEXT NOT NULL,
+    strength REAL NOT NULL,
+    PRIMARY KEY (run_id, position),
+    FOREIGN KEY (run_id, source_id) REFERENCES nodes (run_id, node_id) ON DELETE CASCADE,
+    FOREIGN KEY (run_id, target_id) REFERENCES nodes (run_id, node_id) ON DELETE CASCADE
+);
+
+CREATE INDEX edges_target_idx ON edges (run_id, target_id);
+
+CREATE TABLE mappings (
+    run_id BIGINT NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    row_number INTEGER NOT NULL,
+    mapping_framework TEXT NOT NULL,
+    mapping_framework_version TEXT NOT NULL,
+    capability_group TEXT NOT NULL,
+    capability_id TEXT NOT NULL,
+    capability_description TEXT NOT NULL,
+    mapping_type TEXT NOT NULL,
+    attack_object_id TEXT NOT NULL,
+    attack_object_name TEXT NOT NULL,
+    attack_version TEXT NOT NULL,
+    technology_domain TEXT NOT NULL,
+    "references" TEXT NOT NULL,
+    comments TEXT NOT NULL,
+    organization TEXT NOT NULL,
+    creation_date TEXT NOT NULL,
+    last_update TEXT NOT NULL,
+    PRIMARY KEY (run_id, row_number)
+);
+
+CREATE TABLE analyses (
+    run_id BIGINT NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    result JSONB NOT NULL,
+    PRIMARY KEY (run_id, name)
+);
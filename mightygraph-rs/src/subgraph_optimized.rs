@@ -1,39 +1,72 @@
+//! Novelty scores and subgraphs of unusually connected techniques.
+//!
+//! A node's novelty averages three factors: the share of distinct
+//! relationship types among its outgoing edges, how small its strongly
+//! connected component is, and how close the nodes it reaches are. Scores
+//! are computed in parallel and ranked with ties in node order, so the
+//! ranking does not depend on the thread count.
+//!
+//! The graph is either loaded from an object/relationship export with
+//! [`load_mitre_data`], or derived from the mapping graph with
+//! [`from_mapping_graph`].
+
 use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::algo::{kosaraju_scc, dijkstra};
 use petgraph::visit::EdgeRef;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::BufReader;
+use std::path::Path;
+
+use crate::petgraph_full_0x0::prelude::{MappingGraph, NodeType};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct MitreObject {
-    id: String,
-    name: String,
-    object_type: String,
+pub struct MitreObject {
+    pub id: String,
+    pub name: String,
+    pub object_type: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct Relationship {
-    source_ref: String,
-    target_ref: String,
-    relationship_type: String,
+pub struct Relationship {
+    pub source_ref: String,
+    pub target_ref: String,
+    pub relationship_type: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct MitreData {
-    objects: Vec<MitreObject>,
-    relationships: Vec<Relationship>,
+pub struct MitreData {
+    pub objects: Vec<MitreObject>,
+    pub relationships: Vec<Relationship>,
 }
 
-fn load_mitre_data(file_path: &str) -> MitreData {
-    let file = File::open(file_path).expect("Unable to open file");
-    let reader = BufReader::new(file);
-    serde_json::from_reader(reader).expect("Unable to parse JSON")
+/// Objects as nodes, relationship types as edges.
+pub type NoveltyGraph = DiGraph<MitreObject, String>;
+
+/// Loads objects and relationships from a JSON file.
+///
+/// # Arguments
+///
+/// - `file_path`: JSON with `objects` (`id`, `name`, `object_type`) and
+///   `relationships` (`source_ref`, `target_ref`, `relationship_type`).
+///
+/// # Returns
+///
+/// - `Result<MitreData>`: An error if the file cannot be opened or parsed.
+pub fn load_mitre_data<P: AsRef<Path>>(file_path: P) -> Result<MitreData> {
+    let reader = BufReader::new(File::open(file_path)?);
+    Ok(serde_json::from_reader(reader)?)
 }
 
-fn build_graph(data: &MitreData) -> (DiGraph<MitreObject, String>, HashMap<String, NodeIndex>) {
+/// Builds the graph, dropping relationships to unknown objects.
+pub fn build_graph(data: &MitreData) -> (NoveltyGraph, HashMap<String, NodeIndex>) {
     let mut graph = DiGraph::new();
     let mut node_map = HashMap::new();
 
@@ -51,17 +84,51 @@ fn build_graph(data: &MitreData) -> (DiGraph<MitreObject, String>, HashMap<Strin
     (graph, node_map)
 }
 
-fn calculate_novelty_score(graph: &DiGraph<MitreObject, String>, node: NodeIndex) -> f64 {
+/// Novelty graph of the mapping graph, in the same node order. Nodes are
+/// named after their catalog `name` when the graph has been enriched. Every
+/// mapping is added in both directions, since novelty follows outgoing edges.
+pub fn from_mapping_graph(graph: &MappingGraph) -> NoveltyGraph {
+    let mut novelty = DiGraph::with_capacity(graph.node_count(), 2 * graph.edge_count());
+    for node in graph.node_weights() {
+        novelty.add_node(MitreObject {
+            id: node.id.clone(),
+            name: node.metadata.get("name").cloned().unwrap_or_default(),
+            object_type: match node.node_type {
+                NodeType::Mitre => "attack-pattern".to_string(),
+                other => format!("{:?}", other).to_lowercase(),
+            },
+        });
+    }
+    for edge in graph.raw_edges() {
+        let mapping_type = edge.weight.mapping_type.to_string();
+        novelty.add_edge(edge.source(), edge.target(), mapping_type.clone());
+        novelty.add_edge(edge.target(), edge.source(), mapping_type.clone());
+    }
+    novelty
+}
+
+// Size of the strongly connected component of each node, by node index
+fn component_sizes(graph: &NoveltyGraph) -> Vec<usize> {
+    let mut sizes = vec![0; graph.node_count()];
+    for component in kosaraju_scc(graph) {
+        for node in &component {
+            sizes[node.index()] = component.len();
+        }
+    }
+    sizes
+}
+
+fn calculate_novelty_score(graph: &NoveltyGraph, node: NodeIndex, component_size: usize) -> f64 {
     let mut score = 0.0;
 
     // Factor 1: Uniqueness of connections
     let edges = graph.edges(node).collect::<Vec<_>>();
     let unique_connections = edges.iter().map(|e| e.weight()).collect::<HashSet<_>>().len();
-    score += (unique_connections as f64) / (edges.len() as f64);
+    if !edges.is_empty() {
+        score += (unique_connections as f64) / (edges.len() as f64);
+    }
 
     // Factor 2: Betweenness centrality approximation
-    let scc = kosaraju_scc(&graph);
-    let component_size = scc.iter().find(|comp| comp.contains(&node)).map_or(0, |comp| comp.len());
     score += 1.0 - (component_size as f64) / (graph.node_count() as f64);
 
     // Factor 3: Path diversity
@@ -72,27 +139,43 @@ fn calculate_novelty_score(graph: &DiGraph<MitreObject, String>, node: NodeIndex
     score / 3.0  // Normalize the score
 }
 
-fn subsample_novel_techniques(graph: &DiGraph<MitreObject, String>, threshold: f64, sample_size: usize) -> Vec<NodeIndex> {
-    let mut novelty_scores: Vec<(NodeIndex, f64)> = graph
-        .node_indices()
-        .map(|node| (node, calculate_novelty_score(graph, node)))
+/// Scores every node in parallel; the components are computed once up front.
+///
+/// # Returns
+///
+/// - `Vec<(NodeIndex, f64)>`: Highest score first, ties in node order.
+pub fn novelty_scores(graph: &NoveltyGraph) -> Vec<(NodeIndex, f64)> {
+    let component_sizes = component_sizes(graph);
+    let mut novelty_scores: Vec<(NodeIndex, f64)> = (0..graph.node_count())
+        .into_par_iter()
+        .map(NodeIndex::new)
+        .map(|node| (node, calculate_novelty_score(graph, node, component_sizes[node.index()])))
         .collect();
 
-    novelty_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    // Ties in node order, so the ranking is the same on every run
+    novelty_scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    novelty_scores
+}
 
-    let potential_novel_techniques: Vec<NodeIndex> = novelty_scores
+/// Samples up to `sample_size` nodes scoring above `threshold`. The sample is
+/// drawn with a seeded RNG, so a seed always gives the same techniques.
+pub fn subsample_novel_techniques(graph: &NoveltyGraph, threshold: f64, sample_size: usize, seed: u64) -> Vec<NodeIndex> {
+    let potential_novel_techniques: Vec<NodeIndex> = novelty_scores(graph)
         .into_iter()
         .filter(|(_, score)| *score > threshold)
         .map(|(node, _)| node)
         .collect();
 
     potential_novel_techniques
-        .choose_multiple(&mut rand::thread_rng(), sample_size)
+        .choose_multiple(&mut StdRng::seed_from_u64(seed), sample_size)
         .cloned()
         .collect()
 }
 
-fn extract_subgraph(graph: &DiGraph<MitreObject, String>, nodes: &[NodeIndex], depth: usize) -> DiGraph<MitreObject, String> {
+/// The nodes within `depth` hops of `nodes`, following edge direction, with
+/// all their outgoing edges; the far ends of edges leaving the last hop are
+/// included too.
+pub fn extract_subgraph(graph: &NoveltyGraph, nodes: &[NodeIndex], depth: usize) -> NoveltyGraph {
     let mut subgraph = DiGraph::new();
     let mut node_map = HashMap::new();
 
@@ -127,24 +210,3 @@ fn extract_subgraph(graph: &DiGraph<MitreObject, String>, nodes: &[NodeIndex], d
 
     subgraph
 }
-
-fn main() {
-    let data = load_mitre_data("enterprise-attack.json");
-    let (graph, _) = build_graph(&data);
-
-    let novelty_threshold = 0.7;
-    let sample_size = 5;
-    let subgraph_depth = 2;
-
-    let novel_techniques = subsample_novel_techniques(&graph, novelty_threshold, sample_size);
-    let subgraph = extract_subgraph(&graph, &novel_techniques, subgraph_depth);
-
-    println!("Potential novel techniques:");
-    for node in subgraph.node_indices() {
-        println!("- {}: {}", subgraph[node].name, subgraph[node].id);
-    }
-
-    println!("\nSubgraph statistics:");
-    println!("Nodes: {}", subgraph.node_count());
-    println!("Edges: {}", subgraph.edge_count());
-}
\ No newline at end of file
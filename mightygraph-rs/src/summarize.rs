@@ -0,0 +1,368 @@
+//! LLM summaries of graph nodes through an OpenAI-compatible endpoint.
+//!
+//! Each node's neighborhood is rendered as text (the same rendering as the
+//! RAG node chunks) and sent to `POST {endpoint}/chat/completions`. That
+//! works with the OpenAI API, a local llama.cpp server, Ollama (`/v1`) or a
+//! mock. The model is asked for `{"summary": ..., "tags": [...]}`. The
+//! result goes into the node metadata as `summary`, `tags` (comma-joined)
+//! and `summary_model`.
+//!
+//! Responses are cached by a hash of the model, prompt and node text, so a
+//! rerun only calls the model for nodes whose neighborhood changed. Requests
+//! are spaced to respect `requests_per_minute`. In dry-run mode nothing is
+//! sent; the prompts that would be sent are returned instead.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::export::NodeMetrics;
+use crate::petgraph_full_0x0::prelude::*;
+use crate::rag::{build_chunks, RagOptions};
+use crate::utils::sha256_hex;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You are a cyber threat intelligence analyst. \
+Summarize the node described by the user in two or three sentences, for an analyst who \
+knows VERIS and MITRE ATT&CK. Then give up to five short lowercase tags. Answer with JSON \
+only: {\"summary\": \"...\", \"tags\": [\"...\"]}";
+
+/// Endpoint, model and pacing of the summarization stage.
+#[derive(Debug, Clone)]
+pub struct SummarizerConfig {
+    /// Base URL of the OpenAI-compatible API, e.g. `http://localhost:11434/v1`.
+    pub endpoint: String,
+    pub model: String,
+    /// Sent as a bearer token when set.
+    pub api_key: Option<String>,
+    pub system_prompt: String,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    /// Spaces requests so no more than this many are sent per minute.
+    pub requests_per_minute: u32,
+    /// Attempts per node on 429, 5xx and transport errors.
+    pub max_retries: u32,
+    pub timeout: Duration,
+    /// Only builds the prompts; nothing is sent and the graph is untouched.
+    pub dry_run: bool,
+    /// Only summarizes nodes of these types.
+    pub node_types: Option<Vec<NodeType>>,
+    /// Stops after this many nodes.
+    pub limit: Option<usize>,
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        SummarizerConfig {
+            endpoint: "http://localhost:8080/v1".to_string(),
+            model: "local-model".to_string(),
+            api_key: None,
+            system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+            temperature: 0.2,
+            max_tokens: 300,
+            requests_per_minute: 60,
+            max_retries: 3,
+            timeout: Duration::from_secs(120),
+            dry_run: false,
+            node_types: None,
+            limit: None,
+        }
+    }
+}
+
+impl SummarizerConfig {
+    /// Defaults overridden by `LLM_ENDPOINT`, `LLM_MODEL` and `LLM_API_KEY`.
+    pub fn from_env() -> Self {
+        let mut config = SummarizerConfig::default();
+        if let Ok(endpoint) = std::env::var("LLM_ENDPOINT") {
+            config.endpoint = endpoint;
+        }
+        if let Ok(model) = std::env::var("LLM_MODEL") {
+            config.model = model;
+        }
+        config.api_key = std::env::var("LLM_API_KEY").ok().filter(|k| !k.is_empty());
+        config
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeSummary {
+    pub summary: String,
+    pub tags: Vec<String>,
+    pub model: String,
+}
+
+/// Summaries keyed by content hash, optionally persisted as one JSON file.
+#[derive(Debug, Default)]
+pub struct SummaryCache {
+    path: Option<PathBuf>,
+    entries: HashMap<String, NodeSummary>,
+}
+
+impl SummaryCache {
+    pub fn in_memory() -> Self {
+        SummaryCache::default()
+    }
+
+    /// Opens the cache file, starting empty if it does not exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = if path.exists() {
+            serde_json::from_reader(File::open(&path)?)?
+        } else {
+            HashMap::new()
+        };
+        Ok(SummaryCache {
+            path: Some(path),
+            entries,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&NodeSummary> {
+        self.entries.get(key)
+    }
+
+    pub fn insert(&mut self, key: String, summary: NodeSummary) {
+        self.entries.insert(key, summary);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Writes the cache file, if the cache has one.
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            serde_json::to_writer_pretty(File::create(path)?, &self.entries)?;
+        }
+        Ok(())
+    }
+}
+
+/// What a summarization run did.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SummaryReport {
+    pub summarized: usize,
+    pub cached: usize,
+    pub failed: Vec<(String, String)>,
+    /// Dry run only: `(node id, user prompt)` that would have been sent.
+    pub prompts: Vec<(String, String)>,
+}
+
+/// Spaces calls at least `interval` apart.
+struct RateLimiter {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_minute: u32) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs(60) / requests_per_minute.max(1),
+            last: None,
+        }
+    }
+
+    fn wait(&mut self) {
+        if let Some(last) = self.last {
+            let elapsed = last.elapsed();
+            if elapsed < self.interval {
+                thread::sleep(self.interval - elapsed);
+            }
+        }
+        self.last = Some(Instant::now());
+    }
+}
+
+/// Cache key for one node prompt.
+pub fn cache_key(config: &SummarizerConfig, text: &str) -> String {
+    sha256_hex(&format!("{}\u{0}{}\u{0}{}", config.model, config.system_prompt, text))
+}
+
+/// Reads `{"summary", "tags"}` from the model reply. Replies that are not
+/// JSON (or wrap it in prose or code fences) fall back to the raw text.
+pub fn parse_reply(content: &str, model: &str) -> NodeSummary {
+    let json_part = match (content.find('{'), content.rfind('}')) {
+        (Some(start), Some(end)) if start < end => Some(&content[start..=end]),
+        _ => None,
+    };
+    let parsed: Option<Value> = json_part.and_then(|part| serde_json::from_str(part).ok());
+
+    match parsed {
+        Some(value) if value["summary"].is_string() => NodeSummary {
+            summary: value["summary"].as_str().unwrap().trim().to_string(),
+            tags: value["tags"]
+                .as_array()
+                .map(|tags| {
+                    tags.iter()
+                        .filter_map(Value::as_str)
+                        .map(|t| t.trim().to_lowercase())
+                        .filter(|t| !t.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            model: model.to_string(),
+        },
+        _ => NodeSummary {
+            summary: content.trim().to_string(),
+            tags: vec![],
+            model: model.to_string(),
+        },
+    }
+}
+
+/// Sends one chat completion request and returns the reply text.
+fn complete(agent: &ureq::Agent, config: &SummarizerConfig, user_prompt: &str) -> Result<String> {
+    let url = format!("{}/chat/completions", config.endpoint.trim_end_matches('/'));
+    let body = json!({
+        "model": config.model,
+        "temperature": config.temperature,
+        "max_tokens": config.max_tokens,
+        "messages": [
+            { "role": "system", "content": config.system_prompt },
+            { "role": "user", "content": user_prompt },
+        ],
+    });
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let mut request = agent.post(&url);
+        if let Some(key) = &config.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", key));
+        }
+        let retry_after = match request.send_json(body.clone()) {
+            Ok(response) => {
+                let reply: Value = response.into_json()?;
+                return reply["choices"][0]["message"]["content"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| "response has no choices[0].message.content".into());
+            }
+            Err(ureq::Error::Status(code, response)) if code == 429 || code >= 500 => {
+                let retry_after = response
+                    .header("Retry-After")
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                if attempt > config.max_retries {
+                    return Err(format!("endpoint returned {}", code).into());
+                }
+                retry_after
+            }
+            Err(ureq::Error::Status(code, response)) => {
+                let text = response.into_string().unwrap_or_default();
+                return Err(format!("endpoint returned {}: {}", code, text).into());
+            }
+            Err(error @ ureq::Error::Transport(_)) => {
+                if attempt > config.max_retries {
+                    return Err(error.into());
+                }
+                None
+            }
+        };
+        thread::sleep(retry_after.unwrap_or(Duration::from_millis(500 << attempt.min(6))));
+    }
+}
+
+/// Summarizes nodes and stores the results in their metadata.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph; `summary`, `tags` and `summary_model` are
+///   set on each summarized node.
+/// - `mappings`: The mappings the graph was built from, for the node text.
+/// - `metrics`: Per-node metrics to mention in the prompt.
+/// - `config`: Endpoint, model, pacing, filters and dry-run switch.
+/// - `cache`: Summaries from earlier runs; new ones are added to it.
+///
+/// # Returns
+///
+/// - `Result<SummaryReport>`: Counts of new and cached summaries, per-node
+///   failures, and in dry-run mode the prompts. A failing node does not stop
+///   the run.
+pub fn summarize_graph(
+    graph: &mut MappingGraph,
+    mappings: &[Mapping],
+    metrics: &NodeMetrics,
+    config: &SummarizerConfig,
+    cache: &mut SummaryCache,
+) -> Result<SummaryReport> {
+    let options = RagOptions {
+        neighborhood_chunks: false,
+        ..RagOptions::default()
+    };
+    let texts: HashMap<String, String> = build_chunks(graph, mappings, metrics, &options)
+        .into_iter()
+        .map(|chunk| (chunk.node_id, chunk.text))
+        .collect();
+
+    let agent = ureq::AgentBuilder::new().timeout(config.timeout).build();
+    let mut limiter = RateLimiter::new(config.requests_per_minute);
+    let mut report = SummaryReport::default();
+
+    let nodes: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|&n| {
+            config
+                .node_types
+                .as_ref()
+                .is_none_or(|types| types.contains(&graph[n].node_type))
+        })
+        .take(config.limit.unwrap_or(usize::MAX))
+        .collect();
+
+    for node in nodes {
+        let id = graph[node].id.clone();
+        let Some(text) = texts.get(&id) else {
+            continue;
+        };
+        let key = cache_key(config, text);
+
+        let summary = if let Some(summary) = cache.get(&key) {
+            report.cached += 1;
+            summary.clone()
+        } else if config.dry_run {
+            report.prompts.push((id, text.clone()));
+            continue;
+        } else {
+            limiter.wait();
+            match complete(&agent, config, text) {
+                Ok(content) => {
+                    let summary = parse_reply(&content, &config.model);
+                    cache.insert(key, summary.clone());
+                    report.summarized += 1;
+                    summary
+                }
+                Err(error) => {
+                    report.failed.push((id, error.to_string()));
+                    continue;
+                }
+            }
+        };
+
+        if !config.dry_run {
+            let metadata = &mut graph[node].metadata;
+            metadata.insert("summary".to_string(), summary.summary);
+            metadata.insert("tags".to_string(), summary.tags.join(","));
+            metadata.insert("summary_model".to_string(), summary.model);
+        }
+    }
+
+    if !config.dry_run {
+        cache.save()?;
+    }
+    Ok(report)
+}
@@ -0,0 +1,190 @@
+//! Time-aware views of the mappings.
+//!
+//! Provides per-month/per-quarter histograms of `creation_date` and
+//! `last_update`, and rebuilds the mapping graph as it stood at chosen dates
+//! so coverage and centrality can be compared across snapshots.
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+use petgraph::algo::connected_components;
+use serde_json::json;
+
+use crate::petgraph_full_0x0::prelude::*;
+use crate::utils::{create_graph, parse_mapping_date};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Which date of a mapping to look at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    CreationDate,
+    LastUpdate,
+}
+
+impl DateField {
+    fn get(self, mapping: &Mapping) -> &str {
+        match self {
+            DateField::CreationDate => &mapping.creation_date,
+            DateField::LastUpdate => &mapping.last_update,
+        }
+    }
+}
+
+/// Histogram bucket width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeBucket {
+    Month,
+    Quarter,
+}
+
+impl TimeBucket {
+    /// Label of the bucket containing `date`, e.g. `2023-02` or `2023-Q1`.
+    pub fn label(self, date: NaiveDate) -> String {
+        match self {
+            TimeBucket::Month => format!("{}-{:02}", date.year(), date.month()),
+            TimeBucket::Quarter => format!("{}-Q{}", date.year(), (date.month() - 1) / 3 + 1),
+        }
+    }
+
+    /// Last day of the bucket containing `date`.
+    pub fn end_of(self, date: NaiveDate) -> NaiveDate {
+        let last_month = match self {
+            TimeBucket::Month => date.month(),
+            TimeBucket::Quarter => ((date.month() - 1) / 3 + 1) * 3,
+        };
+        let (year, month) = if last_month == 12 {
+            (date.year() + 1, 1)
+        } else {
+            (date.year(), last_month + 1)
+        };
+        NaiveDate::from_ymd_opt(year, month, 1)
+            .unwrap()
+            .pred_opt()
+            .unwrap()
+    }
+}
+
+/// Parses one date field of every mapping, skipping values that do not parse.
+pub fn parsed_dates(mappings: &[Mapping], field: DateField) -> Vec<NaiveDate> {
+    mappings
+        .iter()
+        .filter_map(|m| parse_mapping_date(field.get(m)))
+        .collect()
+}
+
+/// Counts dates per bucket. Buckets are sorted chronologically.
+///
+/// # Arguments
+///
+/// - `dates`: The dates to count.
+/// - `bucket`: The bucket width.
+///
+/// # Returns
+///
+/// - `BTreeMap<String, usize>`: Count per bucket label; empty buckets are omitted.
+pub fn date_histogram(dates: &[NaiveDate], bucket: TimeBucket) -> BTreeMap<String, usize> {
+    dates.iter().fold(BTreeMap::new(), |mut acc, &date| {
+        *acc.entry(bucket.label(date)).or_insert(0) += 1;
+        acc
+    })
+}
+
+/// Returns the end date of every bucket between the earliest and latest
+/// creation date, suitable as snapshot dates.
+pub fn bucket_end_dates(mappings: &[Mapping], bucket: TimeBucket) -> Vec<NaiveDate> {
+    let dates = parsed_dates(mappings, DateField::CreationDate);
+    let (Some(&first), Some(&last)) = (dates.iter().min(), dates.iter().max()) else {
+        return vec![];
+    };
+
+    let mut ends = vec![];
+    let mut current = bucket.end_of(first);
+    while current < bucket.end_of(last) {
+        ends.push(current);
+        current = bucket.end_of(current.succ_opt().unwrap());
+    }
+    ends.push(current);
+    ends
+}
+
+/// Rebuilds the graph from the mappings created on or before `date`.
+///
+/// Mappings whose `creation_date` cannot be parsed are left out.
+///
+/// # Arguments
+///
+/// - `mappings`: All loaded mappings.
+/// - `date`: The snapshot date (inclusive).
+///
+/// # Returns
+///
+/// - `Result<(MappingGraph, HashMap<String, NodeIndex>)>`: The graph as of `date`.
+pub fn graph_snapshot_at(
+    mappings: &[Mapping],
+    date: NaiveDate,
+) -> Result<(MappingGraph, HashMap<String, NodeIndex>)> {
+    create_graph(
+        mappings
+            .iter()
+            .filter(|m| parse_mapping_date(&m.creation_date).is_some_and(|d| d <= date)),
+    )
+}
+
+/// Builds a snapshot per date and reports how the graph evolved.
+///
+/// For every date: graph size, how many ATT&CK and VERIS nodes are mapped,
+/// the number of connected components and the `top_n` nodes by degree
+/// centrality (degree divided by `n - 1`).
+///
+/// # Arguments
+///
+/// - `mappings`: All loaded mappings.
+/// - `dates`: The snapshot dates, e.g. from [`bucket_end_dates`].
+/// - `top_n`: How many central nodes to list per snapshot.
+///
+/// # Returns
+///
+/// - `Result<serde_json::Value>`: An array with one object per snapshot date.
+pub fn perform_temporal_snapshots(
+    mappings: &[Mapping],
+    dates: &[NaiveDate],
+    top_n: usize,
+) -> Result<serde_json::Value> {
+    let mut snapshots = vec![];
+
+    for &date in dates {
+        let (graph, _) = graph_snapshot_at(mappings, date)?;
+        let count_type = |node_type: NodeType| {
+            graph
+                .node_indices()
+                .filter(|&n| graph[n].node_type == node_type)
+                .count()
+        };
+
+        let denominator = graph.node_count().saturating_sub(1).max(1) as f64;
+        let mut centrality: Vec<(&str, f64)> = graph
+            .node_indices()
+            .map(|n| {
+                (
+                    graph[n].id.as_str(),
+                    graph.neighbors_undirected(n).count() as f64 / denominator,
+                )
+            })
+            .collect();
+        centrality.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then_with(|| a.0.cmp(b.0)));
+        centrality.truncate(top_n);
+
+        snapshots.push(json!({
+            "date": date.to_string(),
+            "total_edges": graph.edge_count(),
+            "total_nodes": graph.node_count(),
+            "mapped_mitre_nodes": count_type(NodeType::Mitre),
+            "mapped_veris_nodes": count_type(NodeType::Veris),
+            "number_of_components": connected_components(&graph),
+            "top_degree_centrality": centrality,
+        }));
+    }
+
+    Ok(json!(snapshots))
+}
@@ -0,0 +1,196 @@
+//! Traversals over the mapping graph.
+//!
+//! Mapping edges point from VERIS to ATT&CK, but analysts think of a mapping
+//! as a link both ways, so every traversal here ignores edge direction.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+
+use petgraph::graph::EdgeIndex;
+use serde_json::json;
+
+use crate::csr::GraphView;
+use crate::petgraph_full_0x0::prelude::*;
+
+#[derive(Copy, Clone, PartialEq)]
+struct State {
+    cost: f32,
+    node: NodeIndex,
+}
+
+impl Eq for State {}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Weighted shortest path, with the same `1 / strength` edge cost as
+/// `perform_shortest_path_analysis`.
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `from`: Start node.
+/// - `to`: End node.
+///
+/// # Returns
+///
+/// - `Option<(f32, Vec<NodeIndex>)>`: Total cost and the nodes on the path
+///   (both ends included), or `None` if `to` is unreachable.
+pub fn shortest_path(
+    graph: &MappingGraph,
+    from: NodeIndex,
+    to: NodeIndex,
+) -> Option<(f32, Vec<NodeIndex>)> {
+    let mut distances = vec![f32::INFINITY; graph.node_count()];
+    let mut previous: Vec<Option<NodeIndex>> = vec![None; graph.node_count()];
+    let mut heap = BinaryHeap::new();
+
+    distances[from.index()] = 0.0;
+    heap.push(State {
+        cost: 0.0,
+        node: from,
+    });
+
+    while let Some(State { cost, node }) = heap.pop() {
+        if node == to {
+            let mut path = vec![to];
+            let mut current = to;
+            while let Some(prev) = previous[current.index()] {
+                path.push(prev);
+                current = prev;
+            }
+            path.reverse();
+            return Some((cost, path));
+        }
+        if cost > distances[node.index()] {
+            continue;
+        }
+        for (edge, next) in graph.incident_edges(node) {
+            let next_cost = cost + 1.0 / graph.strength(edge).max(f32::EPSILON);
+            if next_cost < distances[next.index()] {
+                distances[next.index()] = next_cost;
+                previous[next.index()] = Some(node);
+                heap.push(State {
+                    cost: next_cost,
+                    node: next,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Weighted distances from one node to every node, with the same edge cost
+/// as [`shortest_path`].
+///
+/// # Arguments
+///
+/// - `graph`: The mapping graph.
+/// - `from`: Start node.
+///
+/// # Returns
+///
+/// - `Vec<f32>`: Distance per node index; `f32::INFINITY` where unreachable.
+pub fn shortest_distances<G: GraphView>(graph: &G, from: NodeIndex) -> Vec<f32> {
+    let mut distances = vec![f32::INFINITY; graph.node_count()];
+    let mut heap = BinaryHeap::new();
+
+    distances[from.index()] = 0.0;
+    heap.push(State {
+        cost: 0.0,
+        node: from,
+    });
+
+    while let Some(State { cost, node }) = heap.pop() {
+        if cost > distances[node.index()] {
+            continue;
+        }
+        for (edge, next) in graph.incident_edges(node) {
+            let next_cost = cost + 1.0 / graph.strength(edge).max(f32::EPSILON);
+            if next_cost < distances[next.index()] {
+                distances[next.index()] = next_cost;
+                heap.push(State {
+                    cost: next_cost,
+                    node: next,
+                });
+            }
+        }
+    }
+
+    distances
+}
+
+/// Nodes within `k` hops of `start`, in breadth-first order.
+pub fn k_hop_nodes(graph: &MappingGraph, start: NodeIndex, k: usize) -> Vec<NodeIndex> {
+    let mut visited = HashSet::from([start]);
+    let mut order = vec![start];
+    let mut queue = VecDeque::from([(start, 0)]);
+
+    while let Some((node, depth)) = queue.pop_front() {
+        if depth == k {
+            continue;
+        }
+        for (_, next) in graph.incident_edges(node) {
+            if visited.insert(next) {
+                order.push(next);
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+
+    order
+}
+
+/// Serializes a node as `{ id, node_type, degree, metadata }`.
+pub fn node_to_json(graph: &MappingGraph, node: NodeIndex) -> Value {
+    json!({
+        "id": graph[node].id,
+        "node_type": graph[node].node_type,
+        "degree": graph.neighbors_undirected(node).count(),
+        "metadata": graph[node].metadata,
+    })
+}
+
+/// Serializes an edge as `{ source, target, mapping_type, strength }`.
+pub fn edge_to_json(graph: &MappingGraph, edge: EdgeIndex) -> Value {
+    let (source, target) = graph.edge_endpoints(edge).unwrap();
+    json!({
+        "source": graph[source].id,
+        "target": graph[target].id,
+        "mapping_type": graph[edge].mapping_type,
+        "strength": graph[edge].strength,
+    })
+}
+
+/// The subgraph induced by `nodes`, as `{ nodes: [...], edges: [...] }`.
+pub fn subgraph_to_json(graph: &MappingGraph, nodes: &[NodeIndex]) -> Value {
+    let members: HashSet<NodeIndex> = nodes.iter().copied().collect();
+    let edges: Vec<Value> = graph
+        .edge_indices()
+        .filter(|&e| {
+            graph
+                .edge_endpoints(e)
+                .is_some_and(|(s, t)| members.contains(&s) && members.contains(&t))
+        })
+        .map(|e| edge_to_json(graph, e))
+        .collect();
+
+    json!({
+        "nodes": nodes.iter().map(|&n| node_to_json(graph, n)).collect::<Vec<_>>(),
+        "edges": edges,
+    })
+}
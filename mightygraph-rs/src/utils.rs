@@ -1,3 +1,62 @@
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use sha2::{Digest, Sha256};
+use crate::petgraph_full_0x0::prelude::*;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Loads the VERIS-MITRE mappings from a CSV file.
+/// 
+/// # Arguments
+///  `path` - Path to the mapping CSV, with one column per `Mapping` field.
+/// 
+/// # Returns
+/// The mappings, in file order.
+/// 
+/// # Errors
+/// Returns an error if the file cannot be opened or a row does not deserialize.
+pub fn load_csv_data<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<Mapping>> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut rdr = csv::Reader::from_reader(reader);
+    let mappings = rdr.deserialize().collect::<std::result::Result<Vec<Mapping>, _>>()?;
+    Ok(mappings)
+}
+
+/// Creates a graph based on the provided mappings.
+/// Edges of the same mapping type share one `mapping_type` string.
+/// 
+/// # Arguments
+///  `mappings` - The `Mapping` structs containing the data for creating the graph.
+/// 
+/// # Returns
+/// A tuple containing the created `MappingGraph` and a `HashMap` with node indices.
+/// 
+/// # Errors
+/// Returns an error if there are issues during the graph creation process.
+pub fn create_graph<'a>(
+    mappings: impl IntoIterator<Item = &'a Mapping>
+) -> Result<(MappingGraph, HashMap<String, NodeIndex>)> {
+    let mut graph = Graph::<NodeData, EdgeData>::new();
+    let mut node_indices = HashMap::new();
+    let mut mapping_types: HashMap<&str, Arc<str>> = HashMap::new();
+
+    for mapping in mappings {
+        let veris_index = add_node_if_not_exists(&mut graph, &mut node_indices, &mapping.capability_id, NodeType::Veris);
+        let mitre_index = add_node_if_not_exists(&mut graph, &mut node_indices, &mapping.attack_object_id, NodeType::Mitre);
+
+        let strength = calculate_strength(mapping);
+        let mapping_type = mapping_types
+            .entry(&mapping.mapping_type)
+            .or_insert_with(|| Arc::from(mapping.mapping_type.as_str()))
+            .clone();
+        graph.add_edge(veris_index, mitre_index, EdgeData { mapping_type, strength });
+    }
+
+    Ok((graph, node_indices))
+}
+
 pub fn add_node_if_not_exists(
     graph: &mut MappingGraph,
     node_indices: &mut HashMap<String, NodeIndex>,
@@ -29,8 +88,14 @@ pub fn add_node_if_not_exists(
 /// 
 /// - `f32`: The strength of the mapping.
 pub fn calculate_strength(mapping: &Mapping) -> f32 {
+    mapping_type_strength(&mapping.mapping_type)
+}
+
+/// The strength [`calculate_strength`] gives a mapping of this type, for
+/// callers that only have the mapping type at hand.
+pub fn mapping_type_strength(mapping_type: &str) -> f32 {
     // Example calculation based on arbitrary logic; adapt as needed
-    match mapping.mapping_type.as_str() {
+    match mapping_type {
         "Strong" => 1.0,
         "Moderate" => 0.7,
         "Weak" => 0.4,
@@ -55,11 +120,11 @@ pub fn prepare_combined_data(
     let mut combined_data = vec![];
 
     for mapping in mappings {
-        let frequency = node_degree_analysis.get(&mapping.attack_object_id).unwrap_or(&Value).as_i64().unwrap_or(0);
+        let frequency = node_degree_analysis.get(&mapping.attack_object_id).and_then(Value::as_i64).unwrap_or(0);
         let strength = calculate_strength(mapping);
         let impact_score = (frequency as f32 * strength) / 10.0; // Normalize to 0-10 scale
 
-        combined_data.push(serde_json::json({
+        combined_data.push(serde_json::json!({
             "veris_id": mapping.capability_id,
             "mitre_id": mapping.attack_object_id,
             "mapping_type": mapping.mapping_type,
@@ -75,4 +140,168 @@ pub fn prepare_combined_data(
     combined_data.sort_by(|a, b| b["impact_score"].as_f64().partial_cmp(&a["impact_score"].as_f64()).unwrap());
     
     combined_data
-}
\ No newline at end of file
+}
+
+/// Date formats seen in the mapping CSVs, tried in order. Day-first formats come
+/// before month-first ones, matching the CTID mapping files.
+pub const MAPPING_DATE_FORMATS: &[&str] = &[
+    "%d/%m/%Y",
+    "%Y-%m-%d",
+    "%d-%m-%Y",
+    "%Y/%m/%d",
+    "%d.%m.%Y",
+    "%m/%d/%Y",
+    "%Y%m%d",
+    "%d %B %Y",
+    "%d %b %Y",
+    "%B %d, %Y",
+    "%b %d, %Y",
+];
+
+/// Date-time formats accepted when the value carries a time component.
+pub const MAPPING_DATETIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S%.f"];
+
+/// Parses a mapping `creation_date`/`last_update` value.
+///
+/// Tries [`MAPPING_DATE_FORMATS`], then RFC 3339 timestamps, then
+/// [`MAPPING_DATETIME_FORMATS`]; never panics.
+///
+/// # Arguments
+///
+/// - `value`: The raw date string from the mapping file.
+///
+/// # Returns
+///
+/// - `Option<NaiveDate>`: The parsed date, or `None` if no known format matches.
+pub fn parse_mapping_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    MAPPING_DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+        .or_else(|| DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.date_naive()))
+        .or_else(|| {
+            MAPPING_DATETIME_FORMATS
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+                .map(|dt| dt.date())
+        })
+}
+
+/// English words too common to help match descriptions.
+pub const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "have", "in", "into",
+    "is", "it", "its", "of", "on", "or", "that", "the", "their", "this", "to", "was", "were",
+    "which", "with",
+];
+
+/// Splits free text into lowercase search terms.
+///
+/// Splits on anything that is not a letter or digit and drops one-character
+/// tokens and [`STOP_WORDS`], so "OS Credential Dumping: LSASS Memory"
+/// becomes `["os", "credential", "dumping", "lsass", "memory"]`.
+///
+/// # Arguments
+///
+/// - `text`: The text to split.
+///
+/// # Returns
+///
+/// - `Vec<String>`: The terms, in text order, with repeats kept.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.chars().count() > 1)
+        .map(str::to_lowercase)
+        .filter(|token| !STOP_WORDS.contains(&token.as_str()))
+        .collect()
+}
+
+/// Lowercase hex SHA-256 of `data`, text or raw bytes.
+///
+/// Used for content hashes and stable ids across the crate.
+pub fn sha256_hex<T: AsRef<[u8]> + ?Sized>(data: &T) -> String {
+    Sha256::digest(data.as_ref())
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+/// Content hash of a graph.
+///
+/// Hashes every node (ID, type and metadata) and edge (endpoint IDs, mapping
+/// type and strength) in a canonical order, so the hash does not depend on
+/// insertion order or node indices.
+///
+/// # Arguments
+///
+/// - `graph`: The graph to hash.
+///
+/// # Returns
+///
+/// - `String`: The SHA-256 of the canonical form, as lowercase hex.
+pub fn graph_hash(graph: &MappingGraph) -> String {
+    let mut lines: Vec<String> = graph
+        .node_weights()
+        .map(|node| {
+            let mut metadata: Vec<_> = node.metadata.iter().collect();
+            metadata.sort();
+            format!("node\t{}\t{:?}\t{:?}", node.id, node.node_type, metadata)
+        })
+        .collect();
+    lines.extend(graph.raw_edges().iter().map(|edge| {
+        format!(
+            "edge\t{}\t{}\t{}\t{}",
+            graph[edge.source()].id,
+            graph[edge.target()].id,
+            edge.weight.mapping_type,
+            edge.weight.strength
+        )
+    }));
+    lines.sort();
+    sha256_hex(&lines.join("\n"))
+}
+
+/// Environment variable read by [`configure_threads`] when no count is given.
+pub const THREADS_ENV: &str = "MIGHTYGRAPH_THREADS";
+
+/// Sizes the global rayon pool used by the parallel analyses.
+///
+/// Must run before the first parallel analysis; the pool cannot be resized
+/// afterwards.
+///
+/// # Arguments
+///
+/// - `threads`: Worker count; `None` reads [`THREADS_ENV`], and without it
+///   rayon uses one thread per CPU.
+///
+/// # Returns
+///
+/// - `Result<usize>`: The number of worker threads, or an error for an
+///   invalid count or an already initialized pool.
+pub fn configure_threads(threads: Option<usize>) -> Result<usize> {
+    let threads = match threads {
+        Some(threads) => Some(threads),
+        None => match std::env::var(THREADS_ENV) {
+            Ok(value) => {
+                let threads = value.parse().map_err(|_| format!("invalid {}: {}", THREADS_ENV, value))?;
+                Some(threads)
+            }
+            Err(_) => None,
+        },
+    };
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new().num_threads(threads).build_global()?;
+    }
+    Ok(rayon::current_num_threads())
+}
+
+/// Runs `run` on a dedicated pool of `threads` workers, e.g. to compare
+/// results across thread counts without touching the global pool.
+pub fn with_threads<T: Send>(threads: usize, run: impl FnOnce() -> T + Send) -> Result<T> {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build()?;
+    Ok(pool.install(run))
+}
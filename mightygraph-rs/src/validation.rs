@@ -0,0 +1,709 @@
+//! Validation and linting of mapping rows.
+//!
+//! Mapping CSVs come from several organizations and are checked row by row
+//! with a set of [`Rule`]s before they are analysed. The built-in rules are:
+//!
+//! | Rule                | Default severity | Finds                                            |
+//! |---------------------|------------------|--------------------------------------------------|
+//! | `attack-id-format`  | error            | malformed `attack_object_id`, with a suggestion  |
+//! | `veris-id-format`   | error            | malformed `capability_id`                        |
+//! | `attack-catalog`    | error / warning  | unknown or revoked / deprecated techniques       |
+//! | `veris-catalog`     | error            | enumerations missing from the VERIS schema       |
+//! | `duplicate-mapping` | error / warning  | repeated rows, conflicting mapping types         |
+//! | `self-loop`         | error            | rows mapping an ID to itself                     |
+//! | `mapping-type`      | warning          | empty or inconsistently spelled `mapping_type`   |
+//! | `attack-version`    | warning          | `attack_version` differing from catalog or file  |
+//! | `empty-references`  | info             | rows without `references`                        |
+//!
+//! The catalog rules are skipped when no catalog is loaded. Other rules can
+//! be added by implementing [`Rule`], and every rule's severity can be
+//! overridden. A [`ValidationReport`] renders as text or JSON and tells
+//! whether a severity threshold was reached, for CI gating.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::catalog::{AttackCatalog, VerisCatalog};
+use crate::petgraph_full_0x0::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" | "warn" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            other => Err(format!("unknown severity: {}", other)),
+        }
+    }
+}
+
+/// One problem found by a rule.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub rule: String,
+    pub severity: Severity,
+    /// Index into the mappings; the CSV line is `row + 2`.
+    pub row: Option<usize>,
+    pub organization: Option<String>,
+    /// The `Mapping` field at fault.
+    pub field: Option<&'static str>,
+    pub value: Option<String>,
+    pub message: String,
+    /// Likely intended value, when the rule can tell.
+    pub suggestion: Option<String>,
+}
+
+impl Finding {
+    /// A finding about one field of one row.
+    pub fn row(
+        rule: &str,
+        severity: Severity,
+        row: usize,
+        mapping: &Mapping,
+        field: &'static str,
+        value: &str,
+        message: String,
+    ) -> Finding {
+        Finding {
+            rule: rule.to_string(),
+            severity,
+            row: Some(row),
+            organization: Some(mapping.organization.clone()).filter(|o| !o.is_empty()),
+            field: Some(field),
+            value: Some(value.to_string()),
+            message,
+            suggestion: None,
+        }
+    }
+
+    pub fn with_suggestion(mut self, suggestion: String) -> Finding {
+        self.suggestion = Some(suggestion);
+        self
+    }
+}
+
+/// What the rules check: the mappings and whichever catalogs are loaded.
+pub struct ValidationContext<'a> {
+    pub mappings: &'a [Mapping],
+    pub attack: Option<&'a AttackCatalog>,
+    pub veris: Option<&'a VerisCatalog>,
+}
+
+/// A validation rule.
+pub trait Rule {
+    /// Kebab-case name, used in findings and to configure the rule.
+    fn name(&self) -> &'static str;
+
+    /// One line on what the rule checks.
+    fn description(&self) -> &'static str;
+
+    /// Appends the rule's findings for the mappings.
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>);
+}
+
+/// `T1234` or `T1234.001`.
+pub fn is_attack_id(id: &str) -> bool {
+    let bytes = id.as_bytes();
+    let digits = |range: std::ops::Range<usize>| bytes[range].iter().all(u8::is_ascii_digit);
+    match bytes.len() {
+        5 => bytes[0] == b'T' && digits(1..5),
+        9 => bytes[0] == b'T' && digits(1..5) && bytes[5] == b'.' && digits(6..9),
+        _ => false,
+    }
+}
+
+/// Repairs common typos in an ATT&CK ID: case, whitespace, missing padding
+/// of the sub-technique, and `,`, `_` or `-` instead of `.`.
+pub fn suggest_attack_id(id: &str) -> Option<String> {
+    let cleaned: String = id
+        .trim()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            ',' | '_' | '-' | '/' => '.',
+            c => c.to_ascii_uppercase(),
+        })
+        .collect();
+    let (technique, sub) = match cleaned.split_once('.') {
+        Some((technique, sub)) => (technique, Some(sub)),
+        None => (cleaned.as_str(), None),
+    };
+    let number = technique.strip_prefix('T')?;
+    if number.len() != 4 || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let suggestion = match sub {
+        None => format!("T{}", number),
+        Some(sub) if (1..=3).contains(&sub.len()) && sub.bytes().all(|b| b.is_ascii_digit()) => {
+            format!("T{}.{:0>3}", number, sub)
+        }
+        Some(_) => return None,
+    };
+    (suggestion != id).then_some(suggestion)
+}
+
+pub struct AttackIdFormat;
+
+impl Rule for AttackIdFormat {
+    fn name(&self) -> &'static str {
+        "attack-id-format"
+    }
+
+    fn description(&self) -> &'static str {
+        "attack_object_id is a technique ID like T1234 or T1234.001"
+    }
+
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>) {
+        for (row, mapping) in context.mappings.iter().enumerate() {
+            let id = &mapping.attack_object_id;
+            if is_attack_id(id) {
+                continue;
+            }
+            let finding = Finding::row(
+                self.name(),
+                Severity::Error,
+                row,
+                mapping,
+                "attack_object_id",
+                id,
+                format!("malformed ATT&CK ID {:?}", id),
+            );
+            findings.push(match suggest_attack_id(id) {
+                Some(suggestion) => finding.with_suggestion(suggestion),
+                None => finding,
+            });
+        }
+    }
+}
+
+pub struct VerisIdFormat;
+
+impl Rule for VerisIdFormat {
+    fn name(&self) -> &'static str {
+        "veris-id-format"
+    }
+
+    fn description(&self) -> &'static str {
+        "capability_id is a dotted VERIS path within its capability_group"
+    }
+
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>) {
+        for (row, mapping) in context.mappings.iter().enumerate() {
+            let id = &mapping.capability_id;
+            let problem = if id.trim() != id {
+                Some("leading or trailing whitespace".to_string())
+            } else if id.split('.').count() < 2 || id.split('.').any(|s| s.trim().is_empty()) {
+                Some("not a dotted path".to_string())
+            } else if !mapping.capability_group.is_empty()
+                && !id.starts_with(&format!("{}.", mapping.capability_group))
+            {
+                Some(format!("not under capability_group {}", mapping.capability_group))
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                findings.push(Finding::row(
+                    self.name(),
+                    Severity::Error,
+                    row,
+                    mapping,
+                    "capability_id",
+                    id,
+                    format!("malformed VERIS ID {:?}: {}", id, problem),
+                ));
+            }
+        }
+    }
+}
+
+pub struct AttackCatalogRule;
+
+impl Rule for AttackCatalogRule {
+    fn name(&self) -> &'static str {
+        "attack-catalog"
+    }
+
+    fn description(&self) -> &'static str {
+        "attack_object_id exists in the ATT&CK catalog and is not revoked or deprecated"
+    }
+
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>) {
+        let Some(catalog) = context.attack else {
+            return;
+        };
+        for (row, mapping) in context.mappings.iter().enumerate() {
+            let id = &mapping.attack_object_id;
+            let (severity, message) = match catalog.get(id) {
+                None => (Severity::Error, format!("{} is not in the ATT&CK catalog", id)),
+                Some(t) if t.revoked => (Severity::Error, format!("{} is revoked", id)),
+                Some(t) if t.deprecated => (Severity::Warning, format!("{} is deprecated", id)),
+                Some(_) => continue,
+            };
+            let finding =
+                Finding::row(self.name(), severity, row, mapping, "attack_object_id", id, message);
+            let suggestion = suggest_attack_id(id).filter(|s| catalog.get(s).is_some());
+            findings.push(match suggestion {
+                Some(suggestion) => finding.with_suggestion(suggestion),
+                None => finding,
+            });
+        }
+    }
+}
+
+pub struct VerisCatalogRule;
+
+impl Rule for VerisCatalogRule {
+    fn name(&self) -> &'static str {
+        "veris-catalog"
+    }
+
+    fn description(&self) -> &'static str {
+        "capability_id exists in the VERIS enumerations"
+    }
+
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>) {
+        let Some(catalog) = context.veris else {
+            return;
+        };
+        for (row, mapping) in context.mappings.iter().enumerate() {
+            let id = &mapping.capability_id;
+            if catalog.contains(id) {
+                continue;
+            }
+            let finding = Finding::row(
+                self.name(),
+                Severity::Error,
+                row,
+                mapping,
+                "capability_id",
+                id,
+                format!("{} is not a VERIS enumeration", id),
+            );
+            let suggestion = catalog
+                .enumerations
+                .iter()
+                .find(|e| e.eq_ignore_ascii_case(id.trim()))
+                .cloned();
+            findings.push(match suggestion {
+                Some(suggestion) => finding.with_suggestion(suggestion),
+                None => finding,
+            });
+        }
+    }
+}
+
+pub struct DuplicateMapping;
+
+impl Rule for DuplicateMapping {
+    fn name(&self) -> &'static str {
+        "duplicate-mapping"
+    }
+
+    fn description(&self) -> &'static str {
+        "each VERIS-technique pair is mapped once, with one mapping_type"
+    }
+
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>) {
+        let mut first_row: HashMap<(&str, &str), usize> = HashMap::new();
+        for (row, mapping) in context.mappings.iter().enumerate() {
+            let pair = (mapping.capability_id.as_str(), mapping.attack_object_id.as_str());
+            let Some(first) = first_row.get(&pair).copied() else {
+                first_row.insert(pair, row);
+                continue;
+            };
+            let original = &context.mappings[first];
+            let (severity, message) = if original.mapping_type == mapping.mapping_type {
+                (Severity::Error, format!("duplicate of line {}", first + 2))
+            } else {
+                (
+                    Severity::Warning,
+                    format!(
+                        "maps the same pair as line {} with mapping_type {:?} instead of {:?}",
+                        first + 2,
+                        mapping.mapping_type,
+                        original.mapping_type
+                    ),
+                )
+            };
+            let value = format!("{} -> {}", pair.0, pair.1);
+            findings.push(Finding::row(
+                self.name(),
+                severity,
+                row,
+                mapping,
+                "attack_object_id",
+                &value,
+                message,
+            ));
+        }
+    }
+}
+
+pub struct SelfLoop;
+
+impl Rule for SelfLoop {
+    fn name(&self) -> &'static str {
+        "self-loop"
+    }
+
+    fn description(&self) -> &'static str {
+        "capability_id and attack_object_id differ"
+    }
+
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>) {
+        for (row, mapping) in context.mappings.iter().enumerate() {
+            if mapping.capability_id.trim() == mapping.attack_object_id.trim() {
+                findings.push(Finding::row(
+                    self.name(),
+                    Severity::Error,
+                    row,
+                    mapping,
+                    "attack_object_id",
+                    &mapping.attack_object_id,
+                    "maps an ID to itself".to_string(),
+                ));
+            }
+        }
+    }
+}
+
+/// Flags empty mapping types, and spellings that differ from the most common
+/// spelling of the same type (`Related-To`, `related_to`, `related-to `). With
+/// `allowed` set, anything else is flagged too.
+#[derive(Default)]
+pub struct MappingTypeRule {
+    pub allowed: Option<Vec<String>>,
+}
+
+fn normalize_mapping_type(mapping_type: &str) -> String {
+    mapping_type
+        .trim()
+        .to_ascii_lowercase()
+        .replace(['_', ' '], "-")
+}
+
+impl Rule for MappingTypeRule {
+    fn name(&self) -> &'static str {
+        "mapping-type"
+    }
+
+    fn description(&self) -> &'static str {
+        "mapping_type is set and spelled consistently"
+    }
+
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>) {
+        let mut spellings: HashMap<String, BTreeMap<&str, usize>> = HashMap::new();
+        for mapping in context.mappings {
+            *spellings
+                .entry(normalize_mapping_type(&mapping.mapping_type))
+                .or_default()
+                .entry(mapping.mapping_type.as_str())
+                .or_insert(0) += 1;
+        }
+        // Most common spelling, ties broken alphabetically.
+        let canonical: HashMap<&String, &str> = spellings
+            .iter()
+            .map(|(normalized, counts)| {
+                let best = counts
+                    .iter()
+                    .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                    .map(|(spelling, _)| *spelling)
+                    .unwrap_or_default();
+                (normalized, best)
+            })
+            .collect();
+
+        for (row, mapping) in context.mappings.iter().enumerate() {
+            let value = &mapping.mapping_type;
+            let normalized = normalize_mapping_type(value);
+            let canonical = canonical[&normalized];
+            let finding = |message: String| {
+                Finding::row(
+                    self.name(),
+                    Severity::Warning,
+                    row,
+                    mapping,
+                    "mapping_type",
+                    value,
+                    message,
+                )
+            };
+            if normalized.is_empty() {
+                findings.push(finding("empty mapping_type".to_string()));
+            } else if let Some(allowed) = &self.allowed {
+                if !allowed.contains(value) {
+                    let suggestion = allowed
+                        .iter()
+                        .find(|a| normalize_mapping_type(a) == normalized)
+                        .cloned();
+                    let finding = finding(format!("mapping_type {:?} is not allowed", value));
+                    findings.push(match suggestion {
+                        Some(suggestion) => finding.with_suggestion(suggestion),
+                        None => finding,
+                    });
+                }
+            } else if value != canonical {
+                findings.push(
+                    finding(format!("inconsistent spelling of mapping_type {:?}", canonical))
+                        .with_suggestion(canonical.to_string()),
+                );
+            }
+        }
+    }
+}
+
+pub struct AttackVersion;
+
+impl Rule for AttackVersion {
+    fn name(&self) -> &'static str {
+        "attack-version"
+    }
+
+    fn description(&self) -> &'static str {
+        "attack_version matches the loaded catalog, or else the rest of the file"
+    }
+
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>) {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for mapping in context.mappings {
+            *counts.entry(mapping.attack_version.trim()).or_insert(0) += 1;
+        }
+        let catalog_version = context.attack.and_then(|c| c.version.as_deref());
+        let expected = match catalog_version {
+            Some(version) => version,
+            None => match counts.iter().max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0))) {
+                Some((version, _)) => *version,
+                None => return,
+            },
+        };
+
+        for (row, mapping) in context.mappings.iter().enumerate() {
+            let version = mapping.attack_version.trim();
+            if version == expected {
+                continue;
+            }
+            let message = if catalog_version.is_some() {
+                format!("ATT&CK version {:?} but the catalog is {}", version, expected)
+            } else {
+                format!("ATT&CK version {:?} but most rows use {}", version, expected)
+            };
+            findings.push(Finding::row(
+                self.name(),
+                Severity::Warning,
+                row,
+                mapping,
+                "attack_version",
+                &mapping.attack_version,
+                message,
+            ));
+        }
+    }
+}
+
+pub struct EmptyReferences;
+
+impl Rule for EmptyReferences {
+    fn name(&self) -> &'static str {
+        "empty-references"
+    }
+
+    fn description(&self) -> &'static str {
+        "references is not empty"
+    }
+
+    fn check(&self, context: &ValidationContext, findings: &mut Vec<Finding>) {
+        for (row, mapping) in context.mappings.iter().enumerate() {
+            if mapping.references.trim().is_empty() {
+                findings.push(Finding::row(
+                    self.name(),
+                    Severity::Info,
+                    row,
+                    mapping,
+                    "references",
+                    &mapping.references,
+                    "no references".to_string(),
+                ));
+            }
+        }
+    }
+}
+
+/// Runs a set of rules, with optional severity overrides.
+pub struct Validator {
+    rules: Vec<Box<dyn Rule>>,
+    severities: HashMap<String, Severity>,
+}
+
+impl Default for Validator {
+    /// All built-in rules at their default severities.
+    fn default() -> Self {
+        Validator::empty()
+            .with_rule(Box::new(AttackIdFormat))
+            .with_rule(Box::new(VerisIdFormat))
+            .with_rule(Box::new(AttackCatalogRule))
+            .with_rule(Box::new(VerisCatalogRule))
+            .with_rule(Box::new(DuplicateMapping))
+            .with_rule(Box::new(SelfLoop))
+            .with_rule(Box::new(MappingTypeRule::default()))
+            .with_rule(Box::new(AttackVersion))
+            .with_rule(Box::new(EmptyReferences))
+    }
+}
+
+impl Validator {
+    /// A validator without rules.
+    pub fn empty() -> Self {
+        Validator {
+            rules: vec![],
+            severities: HashMap::new(),
+        }
+    }
+
+    /// Adds a rule, replacing any rule of the same name.
+    pub fn with_rule(mut self, rule: Box<dyn Rule>) -> Self {
+        self.rules.retain(|r| r.name() != rule.name());
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn without_rule(mut self, name: &str) -> Self {
+        self.rules.retain(|r| r.name() != name);
+        self
+    }
+
+    /// Reports every finding of the rule at `severity`.
+    pub fn with_severity(mut self, name: &str, severity: Severity) -> Self {
+        self.severities.insert(name.to_string(), severity);
+        self
+    }
+
+    /// `(name, description)` of each rule, in run order.
+    pub fn rules(&self) -> Vec<(&'static str, &'static str)> {
+        self.rules.iter().map(|r| (r.name(), r.description())).collect()
+    }
+
+    /// Runs every rule.
+    ///
+    /// # Arguments
+    ///
+    /// - `context`: The mappings and the loaded catalogs.
+    ///
+    /// # Returns
+    ///
+    /// - `ValidationReport`: The findings by row, then rule.
+    pub fn validate(&self, context: &ValidationContext) -> ValidationReport {
+        let mut findings = vec![];
+        for rule in &self.rules {
+            let start = findings.len();
+            rule.check(context, &mut findings);
+            if let Some(&severity) = self.severities.get(rule.name()) {
+                for finding in &mut findings[start..] {
+                    finding.severity = severity;
+                }
+            }
+        }
+        findings.sort_by(|a, b| (a.row, &a.rule).cmp(&(b.row, &b.rule)));
+
+        ValidationReport {
+            rows_checked: context.mappings.len(),
+            rules: self.rules.iter().map(|r| r.name().to_string()).collect(),
+            findings,
+        }
+    }
+}
+
+/// The result of [`Validator::validate`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub rows_checked: usize,
+    pub rules: Vec<String>,
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings.iter().filter(|f| f.severity == severity).count()
+    }
+
+    /// Whether any finding is at least as severe as `threshold`.
+    pub fn fails(&self, threshold: Severity) -> bool {
+        self.findings.iter().any(|f| f.severity >= threshold)
+    }
+
+    /// Findings and counts by severity, rule and organization.
+    pub fn to_json(&self) -> Value {
+        let mut by_rule: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut by_organization: BTreeMap<&str, usize> = BTreeMap::new();
+        for finding in &self.findings {
+            *by_rule.entry(finding.rule.as_str()).or_insert(0) += 1;
+            let organization = finding.organization.as_deref().unwrap_or("");
+            *by_organization.entry(organization).or_insert(0) += 1;
+        }
+        json!({
+            "rows_checked": self.rows_checked,
+            "rules": self.rules,
+            "counts": {
+                "error": self.count(Severity::Error),
+                "warning": self.count(Severity::Warning),
+                "info": self.count(Severity::Info),
+            },
+            "by_rule": by_rule,
+            "by_organization": by_organization,
+            "findings": self.findings,
+        })
+    }
+
+    /// One line per finding, then a summary line.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for finding in &self.findings {
+            let location = match finding.row {
+                Some(row) => format!("line {}", row + 2),
+                None => "file".to_string(),
+            };
+            text.push_str(&format!(
+                "{}[{}] {}: {}",
+                finding.severity, finding.rule, location, finding.message
+            ));
+            if let Some(organization) = &finding.organization {
+                text.push_str(&format!(" ({})", organization));
+            }
+            if let Some(suggestion) = &finding.suggestion {
+                text.push_str(&format!("; did you mean {:?}?", suggestion));
+            }
+            text.push('\n');
+        }
+        text.push_str(&format!(
+            "{} rows checked: {} errors, {} warnings, {} infos\n",
+            self.rows_checked,
+            self.count(Severity::Error),
+            self.count(Severity::Warning),
+            self.count(Severity::Info),
+        ));
+        text
+    }
+}
@@ -0,0 +1,89 @@
+//! Fixtures shared by the integration tests.
+//!
+//! Each test crate uses a different part of this module.
+#![allow(dead_code)]
+
+use mighty_graph_rs::petgraph_full_0x0::prelude::Mapping;
+
+/// A `related-to` enterprise mapping from `veris` to `mitre`, created
+/// 01/02/2023 and last updated 2023-03-04. Tests change the fields they care
+/// about with the [`MappingFixture`] setters.
+pub fn mapping(veris: &str, mitre: &str) -> Mapping {
+    Mapping {
+        mapping_framework: "veris".to_string(),
+        mapping_framework_version: "1.3.7".to_string(),
+        capability_group: "action.hacking".to_string(),
+        capability_id: veris.to_string(),
+        capability_description: "".to_string(),
+        mapping_type: "related-to".to_string(),
+        attack_object_id: mitre.to_string(),
+        attack_object_name: "".to_string(),
+        attack_version: "12.1".to_string(),
+        technology_domain: "enterprise".to_string(),
+        references: "".to_string(),
+        comments: "".to_string(),
+        organization: "Acme".to_string(),
+        creation_date: "01/02/2023".to_string(),
+        last_update: "2023-03-04".to_string(),
+    }
+}
+
+/// Setters for the [`mapping`] fixture, e.g.
+/// `mapping("V1", "T1003").with_type("Strong").with_domain("mobile")`.
+pub trait MappingFixture {
+    fn with_type(self, mapping_type: &str) -> Self;
+
+    fn with_domain(self, technology_domain: &str) -> Self;
+
+    /// Sets `capability_description`.
+    fn with_description(self, description: &str) -> Self;
+
+    /// Sets `attack_object_name`.
+    fn with_name(self, name: &str) -> Self;
+
+    fn with_references(self, references: &str) -> Self;
+
+    fn with_comments(self, comments: &str) -> Self;
+
+    fn with_created(self, creation_date: &str) -> Self;
+
+    fn with_dates(self, creation_date: &str, last_update: &str) -> Self;
+}
+
+impl MappingFixture for Mapping {
+    fn with_type(self, mapping_type: &str) -> Self {
+        Mapping { mapping_type: mapping_type.to_string(), ..self }
+    }
+
+    fn with_domain(self, technology_domain: &str) -> Self {
+        Mapping { technology_domain: technology_domain.to_string(), ..self }
+    }
+
+    fn with_description(self, description: &str) -> Self {
+        Mapping { capability_description: description.to_string(), ..self }
+    }
+
+    fn with_name(self, name: &str) -> Self {
+        Mapping { attack_object_name: name.to_string(), ..self }
+    }
+
+    fn with_references(self, references: &str) -> Self {
+        Mapping { references: references.to_string(), ..self }
+    }
+
+    fn with_comments(self, comments: &str) -> Self {
+        Mapping { comments: comments.to_string(), ..self }
+    }
+
+    fn with_created(self, creation_date: &str) -> Self {
+        Mapping { creation_date: creation_date.to_string(), ..self }
+    }
+
+    fn with_dates(self, creation_date: &str, last_update: &str) -> Self {
+        Mapping {
+            creation_date: creation_date.to_string(),
+            last_update: last_update.to_string(),
+            ..self
+        }
+    }
+}
@@ -0,0 +1,100 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use mighty_graph_rs::cache::*;
+    use mighty_graph_rs::utils::create_graph;
+    use serde_json::json;
+
+    use crate::common::mapping;
+
+    /// A backend whose server has gone away.
+    struct Unreachable;
+
+    impl CacheBackend for Unreachable {
+        fn name(&self) -> &'static str {
+            "unreachable"
+        }
+
+        fn get(&self, _key: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+            Err("connection refused".into())
+        }
+
+        fn set(&self, _key: &str, _value: &str, _ttl: Option<Duration>) -> Result<(), Box<dyn std::error::Error>> {
+            Err("connection refused".into())
+        }
+    }
+
+    #[test]
+    fn test_results_are_reused_until_inputs_change() {
+        let mut mappings = vec![mapping("action.hacking.variety.Brute force", "T1110")];
+        let (graph, node_indices) = create_graph(&mappings).unwrap();
+        let cache = AnalysisCache::default();
+
+        let first = cached_analyses(&cache, &graph, &mappings, &node_indices);
+        let second = cached_analyses(&cache, &graph, &mappings, &node_indices);
+        assert_eq!(first.basic_stats, second.basic_stats);
+        let stats = cache.stats();
+        assert_eq!((stats.backend.as_str(), stats.hits, stats.misses), ("memory", 11, 11));
+
+        // A changed mapping row is a new key, even though the graph is the same.
+        mappings[0].technology_domain = "ics".to_string();
+        let third = cached_analyses(&cache, &graph, &mappings, &node_indices);
+        assert_eq!(third.tech_domain_analysis, json!({ "ics": 1 }));
+        assert_eq!(cache.stats().misses, 22);
+
+        let hash = content_hash(&graph, &mappings);
+        assert_eq!(cached_analysis(&cache, &hash, "no_such_analysis", &graph, &mappings, &node_indices), None);
+    }
+
+    #[test]
+    fn test_keys_depend_on_parameters() {
+        let cache = AnalysisCache::default();
+        let key = cache.key("closeness", "abc", &json!({ "normalized": true, "k": 3 }));
+        assert_eq!(key, cache.key("closeness", "abc", &json!({ "k": 3, "normalized": true })));
+        assert_ne!(key, cache.key("closeness", "abc", &json!({ "normalized": false, "k": 3 })));
+        assert!(key.starts_with(&format!("mightygraph:{}:closeness:abc:", env!("CARGO_PKG_VERSION"))));
+
+        let mut calls = 0;
+        for _ in 0..2 {
+            cache.get_or_compute("closeness", "abc", &json!({ "k": 3 }), || {
+                calls += 1;
+                json!([1, 2])
+            });
+        }
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_missing_results_are_not_cached() {
+        let cache = AnalysisCache::default();
+        let mut calls = 0;
+        for _ in 0..2 {
+            let value = cache.try_get_or_compute("closeness", "abc", &json!({}), || {
+                calls += 1;
+                None
+            });
+            assert_eq!(value, None);
+        }
+        assert_eq!(calls, 2);
+        assert_eq!(cache.stats().hits, 0);
+    }
+
+    #[test]
+    fn test_failing_backend_falls_back_to_memory() {
+        let cache = AnalysisCache::new(Box::new(Unreachable));
+        let mut calls = 0;
+        for _ in 0..3 {
+            let value = cache.get_or_compute("novelty", "abc", &json!({}), || {
+                calls += 1;
+                json!({ "T1110": 0.5 })
+            });
+            assert_eq!(value, json!({ "T1110": 0.5 }));
+        }
+        assert_eq!(calls, 1);
+        let stats = cache.stats();
+        assert_eq!((stats.backend.as_str(), stats.hits, stats.errors), ("memory", 2, 1));
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::centrality::*;
+    use mighty_graph_rs::export::node_metrics_from_analyses;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::petgraph_full_0x0::{perform_analyses, perform_analysis_by_name};
+    use mighty_graph_rs::utils::{create_graph, with_threads};
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn mappings() -> Vec<Mapping> {
+        vec![
+            mapping("action.hacking.variety.Brute force", "T1110").with_type("Strong"),
+            mapping("action.hacking.variety.Use of stolen creds", "T1110").with_type("Strong"),
+            mapping("action.malware.variety.Ransomware", "T1486").with_type("Strong"),
+        ]
+    }
+
+    #[test]
+    fn test_closeness_and_path_lengths() {
+        let (graph, _) = create_graph(&mappings()).unwrap();
+
+        let closeness = perform_closeness_analysis(&graph);
+        let score = |id: &str| closeness[id].as_f64().unwrap();
+        assert!((score("action.hacking.variety.Brute force") - 1.0 / 3.0).abs() < 1e-9);
+        assert!((score("T1110") - 0.5).abs() < 1e-9);
+        // Only a quarter of the graph is reachable from the ransomware pair.
+        assert!((score("T1486") - 0.25).abs() < 1e-9);
+
+        let paths = perform_path_length_analysis(&graph);
+        assert_eq!(paths["reachable_pairs"], 8);
+        assert_eq!(paths["average_path_length"], 1.25);
+        assert_eq!((paths["diameter"].as_f64(), paths["radius"].as_f64()), (Some(2.0), Some(1.0)));
+        assert_eq!(paths["eccentricity"]["T1110"], 1.0);
+        assert_eq!(perform_analysis_by_name("path_length_analysis", &graph, &mappings(), &HashMap::new()), Some(paths));
+    }
+
+    #[test]
+    fn test_results_do_not_depend_on_thread_count() {
+        let mappings = mappings();
+        let (graph, node_indices) = create_graph(&mappings).unwrap();
+        let run = || {
+            let analyses = perform_analyses(&graph, &mappings, &node_indices);
+            (serde_json::to_value(&analyses).unwrap(), source_paths(&graph))
+        };
+        let single = with_threads(1, run).unwrap();
+        let parallel = with_threads(4, run).unwrap();
+        assert_eq!(single, parallel);
+        assert_eq!(single.0["basic_stats"]["total_edges"], 3);
+        assert_eq!(single.1.iter().map(|p| p.id.as_str()).collect::<Vec<_>>()[0], "action.hacking.variety.Brute force");
+    }
+
+    #[test]
+    fn test_node_metrics_include_closeness_and_eccentricity() {
+        let mappings = mappings();
+        let (graph, node_indices) = create_graph(&mappings).unwrap();
+
+        let metrics = node_metrics_from_analyses(&perform_analyses(&graph, &mappings, &node_indices));
+
+        assert_eq!(metrics["degree"]["T1110"], 2.0);
+        assert_eq!(metrics["closeness"]["T1110"], 0.5);
+        assert_eq!(metrics["eccentricity"]["T1110"], 1.0);
+    }
+}
@@ -0,0 +1,150 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::catalog::*;
+    use mighty_graph_rs::coverage::*;
+    use mighty_graph_rs::navigator::*;
+    use mighty_graph_rs::petgraph_full_0x0::perform_node_degree_analysis;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::utils::add_node_if_not_exists;
+    use serde_json::json;
+
+    use crate::common::mapping;
+
+    fn attack_pattern(id: &str, tactics: &[&str], platforms: &[&str], revoked: bool) -> Value {
+        json!({
+            "type": "attack-pattern",
+            "id": format!("attack-pattern--{}", id),
+            "name": format!("Technique {}", id),
+            "external_references": [{"source_name": "mitre-attack", "external_id": id}],
+            "kill_chain_phases": tactics
+                .iter()
+                .map(|t| json!({"kill_chain_name": "mitre-attack", "phase_name": t}))
+                .collect::<Vec<_>>(),
+            "x_mitre_platforms": platforms,
+            "x_mitre_domains": ["enterprise-attack"],
+            "x_mitre_is_subtechnique": id.contains('.'),
+            "revoked": revoked,
+        })
+    }
+
+    fn catalogs() -> (AttackCatalog, VerisCatalog) {
+        let attack = AttackCatalog::from_stix_value(&json!({
+            "type": "bundle",
+            "objects": [
+                attack_pattern("T1003", &["credential-access"], &["Windows"], false),
+                attack_pattern("T1003.001", &["credential-access"], &["Windows"], false),
+                attack_pattern("T1053", &["persistence", "privilege-escalation"], &["Linux"], false),
+                attack_pattern("T1000", &["persistence"], &["Linux"], true),
+                {"type": "x-mitre-collection", "x_mitre_version": "12.1"},
+            ],
+        }));
+        let veris = VerisCatalog::from_enum_value(&json!({
+            "action": {"hacking": {"variety": ["Brute force", "Use of stolen creds"]}}
+        }));
+        (attack, veris)
+    }
+
+    fn graph() -> MappingGraph {
+        let mut graph = MappingGraph::new();
+        let mut indices = HashMap::new();
+        let v = add_node_if_not_exists(&mut graph, &mut indices, "action.hacking.variety.Brute force", NodeType::Veris);
+        let t = add_node_if_not_exists(&mut graph, &mut indices, "T1003", NodeType::Mitre);
+        graph.add_edge(v, t, EdgeData { mapping_type: "related-to".into(), strength: 1.0 });
+        graph
+    }
+
+    fn mappings() -> Vec<Mapping> {
+        vec![mapping("action.hacking.variety.Brute force", "T1003")]
+    }
+
+    #[test]
+    fn test_coverage_gaps_and_percentages() {
+        let (attack, veris) = catalogs();
+
+        let report = perform_coverage_analysis(&graph(), &mappings(), &attack, &veris, &CoverageOptions::default());
+
+        assert_eq!(report.techniques.total, 3);
+        assert_eq!(report.techniques.mapped, 1);
+        assert_eq!(report.by_tactic["credential-access"].mapped, 1);
+        assert_eq!(report.by_tactic["persistence"].total, 1);
+        assert_eq!(report.by_platform["Linux"].coverage_pct, 0.0);
+        assert_eq!(report.by_technology_domain["enterprise"].total, 3);
+        assert_eq!(report.by_technology_domain["enterprise"].mapped, 1);
+        assert_eq!(report.by_attack_domain["enterprise-attack"].total, 3);
+        assert_eq!(report.unmapped_techniques, vec!["T1053"]);
+        assert_eq!(report.unmapped_subtechniques, vec!["T1003.001"]);
+        assert_eq!(report.unmapped_veris, vec!["action.hacking.variety.Use of stolen creds"]);
+    }
+
+    #[test]
+    fn test_coverage_layer_comments_list_veris_ids() {
+        let (attack, veris) = catalogs();
+        let report = perform_coverage_analysis(&graph(), &mappings(), &attack, &veris, &CoverageOptions::default());
+
+        let layer = serde_json::to_value(coverage_layer(&report, &attack)).unwrap();
+
+        assert_eq!(layer["versions"]["attack"], "12.1");
+        assert_eq!(layer["domain"], "enterprise-attack");
+        let t1003 = layer["techniques"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|t| t["techniqueID"] == "T1003")
+            .unwrap();
+        assert_eq!(t1003["score"], 1.0);
+        assert_eq!(t1003["comment"], "action.hacking.variety.Brute force");
+    }
+
+    #[test]
+    fn test_node_degree_counts_both_directions() {
+        let mut graph = MappingGraph::new();
+        let mut indices = HashMap::new();
+        let edges = [("V1", "T1003"), ("V2", "T1003"), ("V2", "T1110")];
+        for (veris, mitre) in edges {
+            let v = add_node_if_not_exists(&mut graph, &mut indices, veris, NodeType::Veris);
+            let t = add_node_if_not_exists(&mut graph, &mut indices, mitre, NodeType::Mitre);
+            graph.add_edge(v, t, EdgeData { mapping_type: "related-to".into(), strength: 1.0 });
+        }
+
+        // Edges point VERIS -> ATT&CK, so counting outgoing edges only would
+        // give every technique degree 0.
+        assert_eq!(
+            perform_node_degree_analysis(&graph),
+            json!({ "V1": 1, "V2": 2, "T1003": 2, "T1110": 1 })
+        );
+        assert_eq!(degree_metric(&graph)["T1003"], 2.0);
+    }
+
+    #[test]
+    fn test_metric_layer_scopes_tactics_and_scales_gradient() {
+        let (attack, _) = catalogs();
+        let graph = graph();
+        let metric = metric_from_pairs([("T1003", 2.0), ("T1053", 5.0)]);
+        let options = LayerOptions {
+            tactics: vec!["persistence".to_string()],
+            ..Default::default()
+        };
+
+        let layer = layer_from_metric(&metric, &attack, &veris_by_technique(&graph), &options);
+
+        assert_eq!(layer.techniques.len(), 1);
+        assert_eq!(layer.techniques[0].technique_id, "T1053");
+        assert_eq!(layer.techniques[0].tactic.as_deref(), Some("persistence"));
+        assert_eq!(layer.gradient.min_value, 5.0);
+        assert_eq!(degree_metric(&graph)["T1003"], 1.0);
+    }
+
+    #[test]
+    fn test_layer_domain_comes_from_catalog() {
+        let mut technique = attack_pattern("T1411", &["credential-access"], &["Android"], false);
+        technique["x_mitre_domains"] = json!(["mobile-attack"]);
+        let attack = AttackCatalog::from_stix_value(&json!({ "type": "bundle", "objects": [technique] }));
+
+        let layer = layer_from_metric(&metric_from_pairs([("T1411", 1.0)]), &attack, &Default::default(), &LayerOptions::default());
+
+        assert_eq!(attack.domain(), Some("mobile-attack"));
+        assert_eq!(layer.domain, "mobile-attack");
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::centrality::{perform_closeness_analysis, perform_path_length_analysis};
+    use mighty_graph_rs::csr::*;
+    use mighty_graph_rs::interner::Interner;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::petgraph_full_0x0::{
+        perform_basic_stats, perform_connected_components_analysis, perform_edge_strength_analysis,
+        perform_mapping_type_analysis, perform_node_degree_analysis, perform_node_type_distribution,
+    };
+    use mighty_graph_rs::utils::create_graph;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn mappings() -> Vec<Mapping> {
+        vec![
+            mapping("action.hacking.variety.Brute force", "T1110").with_type("Strong"),
+            mapping("action.hacking.variety.Brute force", "T1110").with_type("Partial"),
+            mapping("action.malware.variety.Ransomware", "T1486").with_type("Strong"),
+            mapping("action.hacking.variety.Use of stolen creds", "T1078"),
+            mapping("action.hacking.variety.Use of stolen creds", "T1110").with_type("Strong"),
+        ]
+    }
+
+    #[test]
+    fn test_interner_deduplicates() {
+        let mut strings = Interner::new();
+        let a = strings.intern("T1110");
+        let b = strings.intern("Strong");
+        assert_eq!(strings.intern("T1110"), a);
+        assert_ne!(a, b);
+        assert_eq!(strings.len(), 2);
+        assert_eq!(strings.resolve(b), "Strong");
+        assert_eq!(strings.get("Strong"), Some(b));
+        assert_eq!(strings.get("Partial"), None);
+        assert_eq!(strings.iter().map(|(_, s)| s).collect::<Vec<_>>(), vec!["T1110", "Strong"]);
+        assert_eq!(std::mem::size_of::<Option<mighty_graph_rs::interner::Symbol>>(), 4);
+    }
+
+    #[test]
+    fn test_analyses_match_petgraph() {
+        let (graph, node_indices) = create_graph(&mappings()).unwrap();
+        let csr = CsrGraph::from_mappings(&mappings());
+
+        assert_eq!(GraphView::node_count(&csr), graph.node_count());
+        assert_eq!(GraphView::edge_count(&csr), graph.edge_count());
+        for (id, &node) in &node_indices {
+            assert_eq!(csr.node_index(id), Some(node));
+            assert_eq!(csr.node_type(node), graph[node].node_type);
+            assert_eq!(csr.degree(node), graph.neighbors_undirected(node).count());
+        }
+        assert_eq!(csr.node_index("Strong"), None);
+        assert_eq!(csr.node_index("T9999"), None);
+
+        assert_eq!(perform_basic_stats(&csr, &mappings()), perform_basic_stats(&graph, &mappings()));
+        assert_eq!(perform_node_degree_analysis(&csr), perform_node_degree_analysis(&graph));
+        assert_eq!(perform_mapping_type_analysis(&csr), perform_mapping_type_analysis(&graph));
+        assert_eq!(perform_edge_strength_analysis(&csr), perform_edge_strength_analysis(&graph));
+        assert_eq!(perform_node_type_distribution(&csr), perform_node_type_distribution(&graph));
+        assert_eq!(perform_connected_components_analysis(&csr)["number_of_components"], 2);
+        assert_eq!(perform_connected_components_analysis(&graph)["number_of_components"], 2);
+        assert_eq!(perform_closeness_analysis(&csr), perform_closeness_analysis(&graph));
+        assert_eq!(perform_path_length_analysis(&csr), perform_path_length_analysis(&graph));
+    }
+
+    #[test]
+    fn test_from_graph_keeps_indices_and_metadata() {
+        let (mut graph, node_indices) = create_graph(&mappings()).unwrap();
+        let t1110 = node_indices["T1110"];
+        graph[t1110].metadata.insert("name".to_string(), "Brute Force".to_string());
+
+        let csr = CsrGraph::from_graph(&graph);
+        assert_eq!(csr.node_index("T1110"), Some(t1110));
+        assert_eq!(csr.metadata(t1110, "name"), Some("Brute Force"));
+        assert_eq!(csr.metadata(node_indices["T1486"], "name"), None);
+        assert_eq!(csr.metadata(t1110, "tactic"), None);
+        assert_eq!(csr.metadata_keys().collect::<Vec<_>>(), vec!["name"]);
+        for edge in graph.edge_indices() {
+            assert_eq!(GraphView::edge_endpoints(&csr, edge), graph.edge_endpoints(edge).unwrap());
+            assert_eq!(csr.mapping_type(edge), &*graph[edge].mapping_type);
+        }
+
+        // "Strong" and "T1110" are stored once however often they occur.
+        assert_eq!(csr.strings().len(), 6 + 3 + 2);
+        assert!(csr.heap_bytes() > 0);
+    }
+}
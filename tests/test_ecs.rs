@@ -0,0 +1,125 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use chrono::{TimeZone, Utc};
+    use mighty_graph_rs::ecs::*;
+    use mighty_graph_rs::export::NodeMetrics;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::utils::create_graph;
+    use serde_json::json;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn options() -> EcsOptions {
+        EcsOptions { timestamp: Utc.with_ymd_and_hms(2024, 5, 6, 7, 8, 9).unwrap(), ..EcsOptions::default() }
+    }
+
+    fn records() -> Vec<EcsRecord> {
+        let mappings = vec![mapping("action.hacking.variety.Brute force", "T1003.001").with_name("OS Credential Dumping: LSASS Memory").with_description("Brute force attack").with_references("https://a.example https://b.example")];
+        let (graph, _) = create_graph(&mappings).unwrap();
+        let mut metrics = NodeMetrics::new();
+        metrics.insert("pagerank".to_string(), [("T1003.001".to_string(), 0.5)].into_iter().collect());
+
+        let mut records = node_records(&graph, &metrics, &options());
+        records.extend(edge_records(&graph, &options()));
+        records.extend(mapping_records(&mappings, &options()));
+        records
+    }
+
+    #[test]
+    fn test_records_conform_to_bundled_schema() {
+        let records = records();
+        assert_eq!(records.len(), 4);
+        assert!(EcsSchema::bundled().validate_all(&records).is_empty());
+
+        let technique = records[1].document();
+        assert_eq!(technique["@timestamp"], "2024-05-06T07:08:09.000Z");
+        assert_eq!(technique["threat"]["technique"]["id"], json!(["T1003"]));
+        assert_eq!(technique["threat"]["technique"]["subtechnique"]["id"], json!(["T1003.001"]));
+        assert_eq!(technique["mightygraph"]["metrics"]["pagerank"], 0.5);
+
+        let row = records[3].document();
+        assert_eq!(row["threat"]["technique"]["name"], json!(["OS Credential Dumping"]));
+        assert_eq!(row["threat"]["technique"]["subtechnique"]["name"], json!(["LSASS Memory"]));
+        assert_eq!(row["mightygraph"]["mapping"]["references"], json!(["https://a.example", "https://b.example"]));
+        assert_eq!(row["event"]["created"], "2023-02-01");
+
+        // IDs depend on content, not on the export time.
+        assert_eq!(records[0].id, document_id("node", "action.hacking.variety.Brute force"));
+    }
+
+    #[test]
+    fn test_schema_violations() {
+        let schema = EcsSchema::bundled();
+        let mut record = records().remove(0);
+        record.set("threat.technique.idd", "T1110");
+        record.set("mightygraph.node.degree", "three");
+        record.set("event.kind", "incident");
+        record.fields.remove("ecs.version");
+
+        let mut problems: Vec<String> = schema.validate(&record).into_iter().map(|v| v.field).collect();
+        problems.sort();
+        assert_eq!(problems, vec!["ecs.version", "event.kind", "mightygraph.node.degree", "threat.technique.idd"]);
+    }
+
+    #[test]
+    fn test_bulk_ndjson_is_accepted_by_mock_endpoint() {
+        let records = records();
+        let mut body = vec![];
+        write_bulk(&records, &mut body).unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&body).unwrap().lines().collect();
+        assert_eq!(lines.len(), 2 * records.len());
+        let action: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(action["index"]["_index"], "mightygraph-nodes");
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let items: Vec<Value> = String::from_utf8(body)
+                .unwrap()
+                .lines()
+                .step_by(2)
+                .enumerate()
+                .map(|(i, line)| {
+                    let id = serde_json::from_str::<Value>(line).unwrap()["index"]["_id"].clone();
+                    match i {
+                        0 => json!({ "index": { "_id": id, "status": 400, "error": { "reason": "mapper_parsing_exception" } } }),
+                        _ => json!({ "index": { "_id": id, "status": 201 } }),
+                    }
+                })
+                .collect();
+            let response = json!({ "took": 3, "errors": true, "items": items }).to_string();
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", response.len(), response).unwrap();
+            request_line
+        });
+
+        let response = post_bulk(&format!("http://{}", address), &records).unwrap();
+        assert!(server.join().unwrap().starts_with("POST /_bulk "));
+        assert_eq!(response.took, 3);
+        assert_eq!(response.indexed, records.len() - 1);
+        assert_eq!(response.failed, vec![(records[0].id.clone(), "mapper_parsing_exception".to_string())]);
+    }
+}
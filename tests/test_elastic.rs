@@ -0,0 +1,195 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use mighty_graph_rs::ecs::*;
+    use mighty_graph_rs::elastic::*;
+    use mighty_graph_rs::export::NodeMetrics;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::utils::create_graph;
+    use serde_json::json;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn analyses() -> AnalysisResults {
+        AnalysisResults {
+            basic_stats: json!({ "node_count": 2, "edge_count": 1 }),
+            mapping_type_analysis: json!({ "related-to": 1 }),
+            node_degree_analysis: json!([]),
+            connected_components_analysis: json!({ "count": 1 }),
+            shortest_path_analysis: json!({}),
+            edge_strength_analysis: json!({}),
+            node_type_distribution: json!({}),
+            temporal_analysis: json!({}),
+            tech_domain_analysis: json!("enterprise"),
+            closeness_centrality: json!({}),
+            path_length_analysis: json!({}),
+        }
+    }
+
+    fn records() -> Vec<EcsRecord> {
+        let mappings = vec![mapping("action.hacking.variety.Brute force", "T1110").with_name("Brute Force")];
+        let (graph, _) = create_graph(&mappings).unwrap();
+        let options = EcsOptions::default();
+        let mut records = node_records(&graph, &NodeMetrics::new(), &options);
+        records.extend(edge_records(&graph, &options));
+        records.extend(mapping_records(&mappings, &options));
+        records.extend(analysis_records(&analyses(), &options));
+        records
+    }
+
+    type Reply = Box<dyn Fn(&str) -> String + Send>;
+
+    fn reply(f: impl Fn(&str) -> String + Send + 'static) -> Reply {
+        Box::new(f)
+    }
+
+    /// Answers one request per connection with the next canned reply and returns the
+    /// request lines and bodies it received.
+    fn serve(replies: Vec<(u16, Reply)>) -> (String, thread::JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut requests = vec![];
+            for (status, reply) in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let body = String::from_utf8(body).unwrap();
+
+                let response = reply(&body);
+                write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nRetry-After: 0\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response
+                )
+                .unwrap();
+                requests.push((request_line.trim().to_string(), body));
+            }
+            requests
+        });
+        (address, server)
+    }
+
+    fn bulk_ids(body: &str) -> Vec<String> {
+        body.lines()
+            .step_by(2)
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["index"]["_id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_index_template_follows_the_schema() {
+        let schema = EcsSchema::bundled();
+        let template = index_template(&schema, &ElasticConfig::default());
+        assert_eq!(template["index_patterns"], json!(["mightygraph-*"]));
+        let properties = &template["template"]["mappings"]["properties"];
+        assert_eq!(properties["threat"]["properties"]["technique"]["properties"]["id"]["type"], "keyword");
+        assert_eq!(properties["mightygraph"]["properties"]["analysis"]["properties"]["result"]["type"], "flattened");
+        assert_eq!(
+            template["template"]["mappings"]["dynamic_templates"],
+            json!([{ "mightygraph_metrics": { "path_match": "mightygraph.metrics.*", "mapping": { "type": "float" } } }])
+        );
+
+        let config = ElasticConfig { flavor: ClusterFlavor::OpenSearch, index_prefix: "cti".to_string(), ..ElasticConfig::default() };
+        let template = index_template(&schema, &config);
+        assert_eq!(template["index_patterns"], json!(["cti-*"]));
+        let properties = &template["template"]["mappings"]["properties"];
+        assert_eq!(properties["mightygraph"]["properties"]["analysis"]["properties"]["result"]["type"], "flat_object");
+    }
+
+    #[test]
+    fn test_bulk_indexer_retries_throttled_requests_and_documents() {
+        let records = records();
+        assert!(EcsSchema::bundled().validate_all(&records).is_empty());
+        assert!(records.iter().any(|r| r.index == "mightygraph-analyses"));
+
+        let (endpoint, server) = serve(vec![
+            (200, reply(|_| json!({ "acknowledged": true }).to_string())),
+            (429, reply(|_| json!({ "error": "es_rejected_execution_exception" }).to_string())),
+            (
+                200,
+                reply(|body| {
+                    let items: Vec<Value> = bulk_ids(body)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, id)| match i {
+                            1 => json!({ "index": { "_id": id, "status": 429, "error": { "reason": "queue full" } } }),
+                            _ => json!({ "index": { "_id": id, "status": 201 } }),
+                        })
+                        .collect();
+                    json!({ "took": 2, "errors": true, "items": items }).to_string()
+                }),
+            ),
+            (
+                200,
+                reply(|body| {
+                    let items: Vec<Value> = bulk_ids(body).into_iter().map(|id| json!({ "index": { "_id": id, "status": 200 } })).collect();
+                    json!({ "took": 1, "errors": false, "items": items }).to_string()
+                }),
+            ),
+        ]);
+
+        let config = ElasticConfig {
+            endpoint,
+            concurrency: 1,
+            initial_backoff: Duration::from_millis(1),
+            ..ElasticConfig::default()
+        };
+        let indexer = BulkIndexer::new(config);
+        indexer.put_template(&EcsSchema::bundled()).unwrap();
+        let report = indexer.index(&records).unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.starts_with("PUT /_index_template/mightygraph "));
+        assert!(requests[1..].iter().all(|(line, _)| line.starts_with("POST /_bulk ")));
+        assert_eq!(bulk_ids(&requests[2].1), records.iter().map(|r| r.id.clone()).collect::<Vec<_>>());
+        // Only the throttled document is sent again.
+        assert_eq!(bulk_ids(&requests[3].1), vec![records[1].id.clone()]);
+
+        assert_eq!(report.requests, 3);
+        assert_eq!(report.retries, 2);
+        assert_eq!(report.indexed, records.len());
+        assert!(report.failed.is_empty());
+    }
+
+    #[test]
+    fn test_batches_respect_size_limits_and_rejections_are_reported() {
+        let records = records();
+        let (endpoint, server) = serve(
+            (0..records.len())
+                .map(|_| (400, reply(|_| json!({ "error": "illegal_argument_exception" }).to_string())))
+                .collect(),
+        );
+
+        let config = ElasticConfig { endpoint, batch_size: 1, concurrency: 2, ..ElasticConfig::default() };
+        let report = BulkIndexer::new(config).index(&records).unwrap();
+
+        assert_eq!(server.join().unwrap().len(), records.len());
+        assert_eq!(report.requests, records.len());
+        assert_eq!(report.retries, 0);
+        assert_eq!(report.failed.len(), records.len());
+        assert!(report.failed[0].1.starts_with("cluster returned 400"));
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::embeddings::*;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::utils::add_node_if_not_exists;
+
+    fn graph() -> MappingGraph {
+        let mut graph = MappingGraph::new();
+        let mut indices = HashMap::new();
+        for (veris, mitre) in [("V1", "T1"), ("V1", "T2"), ("V2", "T1"), ("V3", "T3")] {
+            let v = add_node_if_not_exists(&mut graph, &mut indices, veris, NodeType::Veris);
+            let t = add_node_if_not_exists(&mut graph, &mut indices, mitre, NodeType::Mitre);
+            graph.add_edge(v, t, EdgeData { mapping_type: "related-to".into(), strength: 1.0 });
+        }
+        graph
+    }
+
+    #[test]
+    fn test_walks_follow_edges() {
+        let graph = graph();
+        let walks = generate_walks(&graph, &WalkOptions { walk_length: 5, ..WalkOptions::node2vec(0.5, 2.0) });
+
+        assert_eq!(walks.len(), graph.node_count() * 10);
+        for walk in &walks {
+            assert_eq!(walk.len(), 5);
+            for pair in walk.windows(2) {
+                let (a, b) = (NodeIndex::new(pair[0]), NodeIndex::new(pair[1]));
+                assert!(graph.find_edge_undirected(a, b).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_training_is_seeded_and_npy_is_readable() {
+        let graph = graph();
+        let options = SkipGramOptions { dimensions: 8, epochs: 2, ..SkipGramOptions::default() };
+
+        let first = train_embeddings(&graph, &WalkOptions::deepwalk(), &options);
+        let second = train_embeddings(&graph, &WalkOptions::deepwalk(), &options);
+        assert_eq!(first, second);
+        assert_eq!(first.get("T3").unwrap().len(), 8);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("embeddings.npy");
+        first.write_npy(&path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.contains("'shape': (6, 8)"));
+        assert_eq!(bytes.len(), 10 + header_len + 6 * 8 * 4);
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::export::*;
+    use polars::prelude::*;
+    use tempfile::tempdir;
+
+    use crate::common::{mapping, MappingFixture};
+
+    #[test]
+    fn test_combined_frame_matches_schema() {
+        let mappings = vec![
+            mapping("V1", "T1003").with_type("Strong"),
+            mapping("V2", "T1059").with_type("Weak"),
+        ];
+        let mut metrics = NodeMetrics::new();
+        metrics.insert("degree".to_string(), [("T1003".to_string(), 3.0)].into_iter().collect());
+        let options = ExportOptions {
+            include_all_mapping_fields: true,
+            include_node_metrics: true,
+            ..Default::default()
+        };
+
+        let df = build_combined_frame(&mappings, &metrics, &options).unwrap();
+
+        assert_eq!(**df.schema(), combined_schema(&options, &metrics));
+        assert_eq!(df.column("creation_date").unwrap().dtype(), &DataType::Date);
+        assert_eq!(df.column("last_update").unwrap().null_count(), 0);
+        assert_eq!(df.column("veris_id").unwrap().get(0).unwrap(), AnyValue::String("V1"));
+    }
+
+    #[test]
+    fn test_export_creates_output_dir_and_partitions() {
+        let dir = tempdir().unwrap();
+        let mappings = vec![
+            mapping("V1", "T1003").with_type("Strong"),
+            mapping("V2", "T1059").with_type("Weak").with_domain("mobile"),
+        ];
+        let options = ExportOptions {
+            output_dir: dir.path().join("nested/out"),
+            partition_by: vec![PartitionKey::TechnologyDomain, PartitionKey::AttackVersion],
+            ..Default::default()
+        };
+
+        let written = export_combined(&mappings, &NodeMetrics::new(), &options).unwrap();
+
+        assert!(written.iter().all(|p| p.exists()));
+        assert!(options.output_dir.join("combined_analysis.csv").exists());
+        assert!(options.output_dir.join("combined_analysis.arrow").exists());
+        assert!(options
+            .output_dir
+            .join("combined_analysis/technology_domain=mobile/attack_version=12.1")
+            .is_dir());
+    }
+}
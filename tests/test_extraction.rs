@@ -0,0 +1,106 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::catalog::AttackCatalog;
+    use mighty_graph_rs::extraction::*;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::utils::create_graph;
+    use serde_json::json;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn mappings() -> Vec<Mapping> {
+        vec![
+            mapping("action.hacking.variety.Brute force", "T1110").with_name("Brute Force"),
+            mapping("action.malware.variety.Capture stored data", "T1003.001").with_name("OS Credential Dumping: LSASS Memory"),
+        ]
+    }
+
+    fn catalog() -> AttackCatalog {
+        AttackCatalog::from_stix_value(&json!({
+            "objects": [
+                {
+                    "type": "tool", "id": "tool--1", "name": "Mimikatz", "x_mitre_aliases": ["Mimikatz"],
+                    "external_references": [{ "source_name": "mitre-attack", "external_id": "S0002" }]
+                },
+                {
+                    "type": "intrusion-set", "id": "intrusion-set--1", "name": "APT29", "aliases": ["APT29", "Cozy Bear"],
+                    "external_references": [{ "source_name": "mitre-attack", "external_id": "G0016" }]
+                }
+            ]
+        }))
+    }
+
+    #[test]
+    fn test_extracts_ids_names_and_cves_with_offsets() {
+        let mappings = mappings();
+        let (graph, _) = create_graph(&mappings).unwrap();
+        let dictionary = EntityDictionary::from_graph(&graph).with_mappings(&mappings).with_catalog(&catalog());
+
+        let text = "Cozy Bear ran mimikatz and Mimikatz (S0002) for LSASS memory dumps, then T1059.001 and CVE-2021-44228.";
+        let report = extract(&dictionary, "report:r1", text, ReportFormat::Text, &ExtractionOptions::default());
+        let found: Vec<(&str, &str, f32)> = report.mentions.iter().map(|m| (m.entity_id.as_str(), m.text.as_str(), m.confidence)).collect();
+
+        assert_eq!(
+            found,
+            vec![
+                ("G0016", "Cozy Bear", NAME_CONFIDENCE),
+                ("S0002", "Mimikatz", SHORT_NAME_CONFIDENCE),
+                ("S0002", "S0002", ID_CONFIDENCE),
+                ("T1003.001", "LSASS memory", NAME_CONFIDENCE),
+                ("T1059.001", "T1059.001", UNKNOWN_ID_CONFIDENCE),
+                ("CVE-2021-44228", "CVE-2021-44228", ID_CONFIDENCE),
+            ]
+        );
+        for mention in &report.mentions {
+            assert_eq!(&text[mention.start..mention.end], mention.text);
+        }
+        assert_eq!(report.title.as_deref(), Some(text));
+    }
+
+    #[test]
+    fn test_html_offsets_point_into_the_original_document() {
+        let mappings = mappings();
+        let (graph, _) = create_graph(&mappings).unwrap();
+        let dictionary = EntityDictionary::from_graph(&graph).with_mappings(&mappings);
+
+        let html = "<html><head><title>Weekly &amp; brief</title><script>var T1110 = 1;</script></head>\
+                    <body><p>Seen: <b>action.hacking.variety.Brute force</b> and brute&nbsp;force.</p></body></html>";
+        let report = extract(&dictionary, "report:r2", html, ReportFormat::Html, &ExtractionOptions::default());
+
+        assert_eq!(report.title.as_deref(), Some("Weekly & brief"));
+        let found: Vec<(&str, &str)> = report.mentions.iter().map(|m| (m.entity_id.as_str(), m.text.as_str())).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("action.hacking.variety.Brute force", "action.hacking.variety.Brute force"),
+                ("T1110", "brute&nbsp;force"),
+                ("action.hacking.variety.Brute force", "brute&nbsp;force"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_report_adds_report_and_mentioned_nodes() {
+        let mappings = mappings();
+        let (mut graph, mut node_indices) = create_graph(&mappings).unwrap();
+        let dictionary = EntityDictionary::from_graph(&graph).with_mappings(&mappings);
+        let options = ExtractionOptions::default();
+
+        let report = extract(&dictionary, "report:r3", "# Intrusion\nT1110 via CVE-2023-1234.", ReportFormat::Markdown, &options);
+        let node = link_report(&mut graph, &mut node_indices, &report);
+
+        assert_eq!(graph[node].node_type, NodeType::Report);
+        assert_eq!(graph[node].metadata["title"], "Intrusion");
+        assert_eq!(graph[node_indices["CVE-2023-1234"]].node_type, NodeType::Vulnerability);
+        let mut linked: Vec<&str> = graph.neighbors(node).map(|n| graph[n].id.as_str()).collect();
+        linked.sort();
+        assert_eq!(linked, vec!["CVE-2023-1234", "T1110"]);
+
+        // Re-linking replaces the old edges.
+        let report = extract(&dictionary, "report:r3", "Only T1110 now.", ReportFormat::Text, &options);
+        link_report(&mut graph, &mut node_indices, &report);
+        assert_eq!(graph.neighbors(node).count(), 1);
+    }
+}
@@ -0,0 +1,107 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::frames::*;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::petgraph_full_0x0::{
+        perform_edge_strength_analysis, perform_mapping_type_analysis, perform_node_degree_analysis,
+        perform_node_type_distribution, perform_tech_domain_analysis, perform_temporal_analysis,
+    };
+    use mighty_graph_rs::temporal::TimeBucket;
+    use mighty_graph_rs::utils::create_graph;
+    use polars::prelude::*;
+    use serde_json::json;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn mappings() -> Vec<Mapping> {
+        vec![
+            mapping("V1", "T1003").with_type("Weak").with_dates("15/01/2023", "2023-05-01"),
+            mapping("V1", "T1059").with_type("Strong").with_dates("20/02/2023", "2023-05-01"),
+            mapping("V2", "T1059").with_type("Strong").with_domain("mobile").with_dates("2023-07-04", "2023-05-01"),
+            mapping("V3", "T1486").with_type("Moderate").with_dates("not a date", "2023-05-01"),
+        ]
+    }
+
+    #[test]
+    fn test_graph_analyses_run_as_lazy_queries() {
+        let (graph, _) = create_graph(&mappings()).unwrap();
+
+        assert_eq!(perform_mapping_type_analysis(&graph), json!({ "Strong": 2, "Weak": 1, "Moderate": 1 }));
+        assert_eq!(
+            perform_node_degree_analysis(&graph),
+            json!({ "V1": 2, "V2": 1, "V3": 1, "T1003": 1, "T1059": 2, "T1486": 1 })
+        );
+        assert_eq!(perform_node_type_distribution(&graph), json!({ "Veris": 3, "Mitre": 3 }));
+        assert_eq!(perform_tech_domain_analysis(&mappings()), json!({ "enterprise": 3, "mobile": 1 }));
+
+        // Strongest first; equal strengths keep edge order.
+        let strengths = perform_edge_strength_analysis(&graph);
+        let types: Vec<_> = strengths.as_array().unwrap().iter().map(|pair| pair[0].clone()).collect();
+        assert_eq!(types, vec![json!("Strong"), json!("Strong"), json!("Moderate"), json!("Weak")]);
+        assert_eq!(strengths[0][1], json!(1.0));
+    }
+
+    #[test]
+    fn test_count_queries_sort_by_count() {
+        let (graph, _) = create_graph(&mappings()).unwrap();
+        let counts = mapping_type_analysis_lazy(edges_frame(&graph).unwrap().lazy()).collect().unwrap();
+
+        assert_eq!(counts.get_column_names(), vec!["mapping_type", "count"]);
+        assert_eq!(counts.column("mapping_type").unwrap().get(0).unwrap(), AnyValue::String("Strong"));
+        assert_eq!(counts.column("count").unwrap().get(0).unwrap().extract::<u32>(), Some(2));
+    }
+
+    #[test]
+    fn test_temporal_analysis_includes_counts_and_histograms() {
+        let result = perform_temporal_analysis(&mappings());
+
+        assert_eq!(
+            result,
+            json!({
+                "min_date": "2023-01-15",
+                "max_date": "2023-07-04",
+                "min_last_update": "2023-05-01",
+                "max_last_update": "2023-05-01",
+                "unparsed_creation_dates": 1,
+                "unparsed_last_updates": 0,
+                "creation_by_month": { "2023-01": 1, "2023-02": 1, "2023-07": 1 },
+                "creation_by_quarter": { "2023-Q1": 2, "2023-Q3": 1 },
+                "update_by_month": { "2023-05": 4 },
+                "update_by_quarter": { "2023-Q2": 4 },
+            })
+        );
+    }
+
+    #[test]
+    fn test_temporal_analysis_without_dates() {
+        let result = perform_temporal_analysis(&[mapping("V1", "T1003").with_type("Weak").with_dates("", "2023-05-01")]);
+
+        assert_eq!(result["min_date"], json!(null));
+        assert_eq!(result["unparsed_creation_dates"], 1);
+        assert_eq!(result["creation_by_month"], json!({}));
+    }
+
+    #[test]
+    fn test_date_histogram_is_chronological() {
+        let frame = mappings_frame(&mappings()).unwrap();
+        let histogram = date_histogram_lazy(frame.lazy(), "creation_date", TimeBucket::Month)
+            .collect()
+            .unwrap();
+
+        let periods: Vec<_> = histogram.column("period").unwrap().cast(&DataType::UInt32).unwrap()
+            .u32().unwrap().iter().flatten().collect();
+        assert_eq!(periods, vec![1, 2, 7]);
+    }
+
+    #[test]
+    fn test_graph_frames_nodes_have_metadata() {
+        let (graph, _) = create_graph(&mappings()).unwrap();
+        let frames = GraphFrames::new(&mappings(), &graph).unwrap();
+
+        assert_eq!(frames.nodes.get_column_names(), vec!["id", "node_type", "degree", "metadata"]);
+        assert_eq!(frames.edges.height(), 4);
+        assert_eq!(frames.mappings.height(), 4);
+    }
+}
@@ -0,0 +1,157 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use mighty_graph_rs::cache::{AnalysisCache, MemoryCache};
+    use mighty_graph_rs::graphql::*;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::server::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn state(dir: &Path) -> Arc<GraphState> {
+        let path = dir.join("mappings.csv");
+        let mut writer = csv::Writer::from_path(&path).unwrap();
+        for m in [
+            mapping("V1", "T1110").with_type("Strong"),
+            mapping("V2", "T1110").with_type("Weak").with_domain("mobile"),
+            mapping("V2", "T1486").with_type("Moderate"),
+        ] {
+            writer.serialize(m).unwrap();
+        }
+        writer.flush().unwrap();
+        Arc::new(GraphState::load(&ServerConfig::new(path)).unwrap())
+    }
+
+    /// Runs `query` and returns `data`, or the error messages.
+    async fn execute(state: &Arc<GraphState>, query: &str) -> Result<Value, Vec<String>> {
+        let response = schema().execute(async_graphql::Request::new(query).data(state.clone())).await;
+        if response.errors.is_empty() {
+            Ok(response.data.into_json().unwrap())
+        } else {
+            Err(response.errors.into_iter().map(|e| e.message).collect())
+        }
+    }
+
+    fn ids(list: &Value) -> Vec<&str> {
+        let mut ids: Vec<_> = list.as_array().unwrap().iter().map(|n| n["id"].as_str().unwrap()).collect();
+        ids.sort();
+        ids
+    }
+
+    /// `node(id: "T1110")` with `hops` nested `neighbors` selections.
+    fn nested_query(hops: usize) -> String {
+        format!(
+            "{{ node(id: \"T1110\") {{ {} id {} }} }}",
+            "neighbors(limit: 1) { ".repeat(hops),
+            "}".repeat(hops)
+        )
+    }
+
+    #[tokio::test]
+    async fn test_node_filters() {
+        let dir = tempdir().unwrap();
+        let state = state(dir.path());
+
+        let data = execute(&state, "{ nodes(filter: { nodeType: VERIS }) { id nodeType degree } }").await.unwrap();
+        assert_eq!(ids(&data["nodes"]), vec!["V1", "V2"]);
+        assert_eq!(data["nodes"][1]["degree"], 2);
+
+        let data = execute(&state, r#"{ nodes(filter: { idPrefix: "T14" }) { id } }"#).await.unwrap();
+        assert_eq!(ids(&data["nodes"]), vec!["T1486"]);
+
+        let data = execute(&state, r#"{ nodes(filter: { technologyDomain: "mobile" }) { id } }"#).await.unwrap();
+        assert_eq!(ids(&data["nodes"]), vec!["T1110", "V2"]);
+
+        let data = execute(&state, "{ nodes(offset: 1, limit: 2) { id } }").await.unwrap();
+        assert_eq!(data["nodes"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_edge_filters_and_traversal() {
+        let dir = tempdir().unwrap();
+        let state = state(dir.path());
+
+        let data = execute(&state, "{ edges(filter: { minStrength: 0.5 }) { mappingType } }").await.unwrap();
+        assert_eq!(data["edges"], json!([{ "mappingType": "Strong" }, { "mappingType": "Moderate" }]));
+
+        let data = execute(&state, r#"{ edges(filter: { technologyDomain: "mobile" }) { strength technologyDomains source { id } target { id } } }"#)
+            .await
+            .unwrap();
+        assert_eq!(
+            data["edges"],
+            json!([{ "strength": 0.4000000059604645, "technologyDomains": ["mobile"], "source": { "id": "V2" }, "target": { "id": "T1110" } }])
+        );
+
+        let query = r#"{ node(id: "T1110") {
+            strong: neighbors(edgeFilter: { mappingType: "Strong" }) { id }
+            weak: edges(filter: { maxStrength: 0.5 }) { mappingType }
+            veris: neighbors(filter: { nodeType: VERIS }) { id neighbors(filter: { idPrefix: "T14" }) { id } }
+        } }"#;
+        let data = execute(&state, query).await.unwrap();
+        assert_eq!(ids(&data["node"]["strong"]), vec!["V1"]);
+        assert_eq!(data["node"]["weak"], json!([{ "mappingType": "Weak" }]));
+        assert_eq!(ids(&data["node"]["veris"]), vec!["V1", "V2"]);
+        let v2 = data["node"]["veris"].as_array().unwrap().iter().find(|n| n["id"] == "V2").unwrap();
+        assert_eq!(ids(&v2["neighbors"]), vec!["T1486"]);
+
+        let data = execute(&state, r#"{ node(id: "T9999") { id } }"#).await.unwrap();
+        assert_eq!(data["node"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_depth_limit() {
+        let dir = tempdir().unwrap();
+        let state = state(dir.path());
+        assert_eq!(MAX_QUERY_DEPTH, 8);
+
+        // `node` and the innermost `id` count as one level each.
+        assert!(execute(&state, &nested_query(MAX_QUERY_DEPTH - 2)).await.is_ok());
+        let errors = execute(&state, &nested_query(MAX_QUERY_DEPTH - 1)).await.unwrap_err();
+        assert_eq!(errors, vec!["Query is nested too deep."]);
+    }
+
+    #[tokio::test]
+    async fn test_complexity_limit() {
+        let dir = tempdir().unwrap();
+        let state = state(dir.path());
+        assert_eq!(MAX_QUERY_COMPLEXITY, 10_000);
+
+        // Lists cost `limit` times their children: 100 * 50 * 2 = 10,000.
+        let query = "{ nodes(limit: 100) { neighbors(limit: 50) { id degree } } }";
+        assert!(execute(&state, query).await.is_ok());
+
+        // The default list limit is 50, and `source { id }` costs 2: 50 * 50 * 6 = 15,000.
+        let query = "{ nodes { edges { mappingType strength source { id } target { id } } } }";
+        let errors = execute(&state, query).await.unwrap_err();
+        assert_eq!(errors, vec!["Query is too complex."]);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_route() {
+        let dir = tempdir().unwrap();
+        let state = Arc::try_unwrap(state(dir.path())).ok().unwrap();
+        let app = AppState::with_cache(state, AnalysisCache::new(Box::new(MemoryCache::new())));
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/graphql")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "query": r#"{ node(id: "T1486") { degree } }"# }).to_string()))
+            .unwrap();
+        let response = router(app).oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"]["node"]["degree"], 1);
+    }
+}
@@ -0,0 +1,193 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::catalog::{AttackCatalog, VerisCatalog};
+    use mighty_graph_rs::coverage::CoverageOptions;
+    use mighty_graph_rs::incremental::*;
+    use mighty_graph_rs::petgraph_full_0x0::perform_node_degree_analysis;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::utils::create_graph;
+    use petgraph::visit::EdgeRef;
+    use serde_json::json;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn mappings() -> Vec<Mapping> {
+        vec![
+            mapping("action.hacking.variety.Brute force", "T1110"),
+            mapping("action.hacking.variety.Brute force", "T1110"),
+            mapping("action.malware.variety.Ransomware", "T1486"),
+            mapping("action.hacking.variety.Use of stolen creds", "T1078"),
+            mapping("action.hacking.variety.Use of stolen creds", "T1110"),
+        ]
+    }
+
+    /// Every lookup still points at the right node and edge.
+    fn assert_consistent(graph: &IncrementalGraph) {
+        assert_eq!(graph.node_indices().len(), graph.graph().node_count());
+        for (id, &node) in graph.node_indices() {
+            assert_eq!(&graph.graph()[node].id, id);
+        }
+        let feed_edges = graph.graph().edge_references().filter(|e| graph.graph()[e.source()].node_type != NodeType::Veris).count();
+        assert_eq!(graph.graph().edge_count(), graph.mappings().len() + feed_edges);
+        for (key, m) in graph.keyed_mappings() {
+            let (source, target) = graph.graph().edge_endpoints(graph.mapping_edge(key).unwrap()).unwrap();
+            assert_eq!(graph.graph()[source].id, m.capability_id);
+            assert_eq!(graph.graph()[target].id, m.attack_object_id);
+        }
+    }
+
+    #[test]
+    fn test_matches_full_rebuild() {
+        let (graph, _) = create_graph(&mappings()).unwrap();
+        let incremental = IncrementalGraph::from_mappings(mappings());
+        assert_eq!(incremental.graph().node_count(), graph.node_count());
+        assert_eq!(incremental.graph().edge_count(), graph.edge_count());
+        assert_eq!(incremental.node_degree_analysis(), perform_node_degree_analysis(&graph));
+        assert_eq!(incremental.components(), 2);
+        assert!(incremental.changes().is_empty());
+        assert!(incremental.mapping("action.hacking.variety.Brute force|T1110#2").is_some());
+        assert_consistent(&incremental);
+    }
+
+    #[test]
+    fn test_removal_fixes_up_indices_and_logs() {
+        let mut graph = IncrementalGraph::from_mappings(mappings());
+
+        // T1486 and its VERIS node are not the last nodes, so other nodes move into their slots.
+        let removed = graph.remove_mapping("action.malware.variety.Ransomware|T1486").unwrap();
+        assert_eq!(removed.attack_object_id, "T1486");
+        assert!(!graph.node_indices().contains_key("T1486"));
+        assert!(!graph.node_indices().contains_key("action.malware.variety.Ransomware"));
+        assert_consistent(&graph);
+        let kinds: Vec<_> = graph.changes().iter().map(|r| (r.sequence, r.change.clone())).collect();
+        assert_eq!(kinds[0], (1, Change::MappingRemoved { key: "action.malware.variety.Ransomware|T1486".to_string() }));
+        assert_eq!(kinds.len(), 3);
+
+        let refresh = graph.refresh();
+        assert_eq!(refresh, Refresh { degrees: 2, components: true, coverage: false });
+        assert_eq!(graph.components(), 1);
+        assert!(!graph.node_degrees().contains_key("T1486"));
+
+        // Removing a node takes its mappings with it.
+        assert!(graph.remove_node("T1110"));
+        assert_consistent(&graph);
+        assert_eq!(graph.mappings().len(), 1);
+        assert_eq!(graph.graph().node_count(), 2);
+        graph.refresh();
+        assert_eq!(graph.node_degrees()["T1078"], 1);
+        assert_eq!(graph.changes_since(3).first().map(|r| r.sequence), Some(4));
+        assert!(!graph.remove_node("T1110"));
+    }
+
+    #[test]
+    fn test_updates_only_refresh_what_changed() {
+        let mut graph = IncrementalGraph::from_mappings(mappings());
+        let key = "action.hacking.variety.Use of stolen creds|T1078";
+
+        assert!(!graph.upsert_mapping(key, mapping("action.hacking.variety.Use of stolen creds", "T1078").with_type("Strong")));
+        assert_eq!(graph.refresh(), Refresh::default());
+        let edge = graph.mapping_edge(key).unwrap();
+        assert_eq!(graph.graph()[edge].strength, 1.0);
+
+        // Moving the mapping to another technique prunes the old one.
+        graph.upsert_mapping(key, mapping("action.hacking.variety.Use of stolen creds", "T1566").with_type("Strong"));
+        assert!(!graph.node_indices().contains_key("T1078"));
+        assert_consistent(&graph);
+        assert!(graph.is_stale());
+        assert_eq!(graph.refresh().degrees, 3);
+        assert_eq!(graph.node_degrees()["T1566"], 1);
+
+        let mut metadata = HashMap::new();
+        metadata.insert("name".to_string(), "Phishing".to_string());
+        graph.upsert_node("T1566", NodeType::Mitre, metadata.clone());
+        graph.upsert_node("T1566", NodeType::Mitre, metadata);
+        assert_eq!(graph.changes().last().unwrap().change, Change::NodeUpdated { id: "T1566".to_string() });
+        assert_eq!(graph.refresh(), Refresh::default());
+
+        assert!(graph.upsert_edge("mapping:x", "T1566", "T1110", EdgeData { mapping_type: "uses".into(), strength: 1.0 }).is_err());
+        assert!(graph.upsert_edge("r1", "T1566", "nowhere", EdgeData { mapping_type: "uses".into(), strength: 1.0 }).is_err());
+    }
+
+    fn technique(id: &str, stix_id: &str, modified: &str) -> Value {
+        json!({
+            "type": "attack-pattern",
+            "id": stix_id,
+            "name": format!("Technique {}", id),
+            "modified": modified,
+            "kill_chain_phases": [{ "kill_chain_name": "mitre-attack", "phase_name": "credential-access" }],
+            "external_references": [{ "source_name": "mitre-attack", "external_id": id }],
+        })
+    }
+
+    #[test]
+    fn test_stix_bundle_applies_as_delta() {
+        let mut graph = IncrementalGraph::from_mappings(mappings());
+        let group = json!({
+            "type": "intrusion-set",
+            "id": "intrusion-set--1",
+            "name": "APT28",
+            "modified": "2024-01-01T00:00:00.000Z",
+            "external_references": [{ "source_name": "mitre-attack", "external_id": "G0007" }],
+        });
+        let uses = json!({
+            "type": "relationship",
+            "id": "relationship--1",
+            "relationship_type": "uses",
+            "source_ref": "intrusion-set--1",
+            "target_ref": "attack-pattern--1",
+            "confidence": 80,
+        });
+        let bundle = json!({
+            "type": "bundle",
+            "objects": [uses, group, technique("T1110", "attack-pattern--1", "2024-01-01T00:00:00.000Z"), { "type": "identity", "id": "identity--1" }],
+        });
+
+        let delta = graph.apply_stix_bundle(&bundle);
+        assert_eq!(delta, StixDelta { nodes: 2, edges: 1, removed: 0, unchanged: 0, skipped: 1 });
+        let edge = graph.edge("relationship--1").unwrap();
+        assert_eq!(&*graph.graph()[edge].mapping_type, "uses");
+        assert!((graph.graph()[edge].strength - 0.8).abs() < 1e-6);
+        assert_eq!(graph.graph()[graph.node_indices()["T1110"]].metadata["name"], "Technique T1110");
+        assert_eq!(graph.graph()[graph.node_indices()["G0007"]].node_type, NodeType::Group);
+
+        // Re-applying the same bundle changes nothing.
+        let logged = graph.changes().len();
+        let again = graph.apply_stix_bundle(&bundle);
+        assert_eq!((again.unchanged, again.edges), (2, 1));
+        assert_eq!(graph.changes().len(), logged);
+
+        // The group is revoked: it goes with its edge; the mapped technique only gets marked.
+        let revoked = json!({ "objects": [
+            { "type": "intrusion-set", "id": "intrusion-set--1", "modified": "2024-02-01T00:00:00.000Z", "revoked": true,
+              "external_references": [{ "source_name": "mitre-attack", "external_id": "G0007" }] },
+            { "type": "attack-pattern", "id": "attack-pattern--1", "modified": "2024-02-01T00:00:00.000Z", "revoked": true,
+              "external_references": [{ "source_name": "mitre-attack", "external_id": "T1110" }] },
+        ]});
+        let delta = graph.apply_stix_bundle(&revoked);
+        assert_eq!(delta.removed, 1);
+        assert!(!graph.node_indices().contains_key("G0007"));
+        assert!(graph.edge("relationship--1").is_none());
+        assert_eq!(graph.graph()[graph.node_indices()["T1110"]].metadata["revoked"], "true");
+        assert_consistent(&graph);
+    }
+
+    #[test]
+    fn test_coverage_follows_mapping_changes() {
+        let attack = AttackCatalog::from_stix_value(&json!({ "objects": [
+            technique("T1110", "attack-pattern--1", "2024-01-01T00:00:00.000Z"),
+            technique("T1078", "attack-pattern--2", "2024-01-01T00:00:00.000Z"),
+        ]}));
+        let veris = VerisCatalog::from_mappings(&mappings());
+        let mut graph = IncrementalGraph::from_mappings(mappings()).with_coverage(attack, veris, CoverageOptions::default());
+        assert_eq!(graph.coverage().unwrap().techniques.mapped, 2);
+
+        graph.remove_mapping("action.hacking.variety.Use of stolen creds|T1078");
+        assert!(graph.refresh().coverage);
+        let coverage = graph.coverage().unwrap();
+        assert_eq!(coverage.techniques.mapped, 1);
+        assert_eq!(coverage.unmapped_techniques, vec!["T1078"]);
+        assert_eq!(coverage.by_tactic["credential-access"].total, 2);
+    }
+}
@@ -0,0 +1,134 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use mighty_graph_rs::csr::GraphView;
+    use mighty_graph_rs::ingest::*;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::petgraph_full_0x0::{perform_edge_strength_analysis, perform_node_degree_analysis};
+    use mighty_graph_rs::utils::create_graph;
+    use petgraph::graph::EdgeIndex;
+    use serde_json::json;
+
+    use crate::common::{mapping, MappingFixture};
+
+    #[global_allocator]
+    static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+    fn mappings() -> Vec<Mapping> {
+        vec![
+            mapping("action.hacking.variety.Brute force", "T1110").with_type("Strong").with_comments("Needs \"review\", see ticket"),
+            mapping("action.malware.variety.Ransomware", "T1486").with_type("Weak").with_comments("Needs \"review\", see ticket"),
+            mapping("action.hacking.variety.Use of stolen creds", "T1110").with_type("Strong").with_comments("Needs \"review\", see ticket"),
+        ]
+    }
+
+    fn csv_bytes(mappings: &[Mapping]) -> Vec<u8> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for m in mappings {
+            writer.serialize(m).unwrap();
+        }
+        writer.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_csv_stream_matches_create_graph() {
+        let mut ingestor = Ingestor::new(IngestOptions::default());
+        assert_eq!(ingestor.read_csv(Cursor::new(csv_bytes(&mappings()))).unwrap(), 3);
+        let ingested = ingestor.finish();
+
+        let (graph, _) = create_graph(&mappings()).unwrap();
+        let strong = |i: usize| &graph[EdgeIndex::new(i)].mapping_type;
+        assert!(std::sync::Arc::ptr_eq(strong(0), strong(2)));
+        assert_eq!(perform_node_degree_analysis(&ingested.graph), perform_node_degree_analysis(&graph));
+        assert_eq!(perform_edge_strength_analysis(&ingested.graph), perform_edge_strength_analysis(&graph));
+        assert_eq!((ingested.report.nodes, ingested.report.edges, ingested.report.mapping_rows), (5, 3, 3));
+
+        // The rows come back unchanged, quoting included.
+        let restored: Vec<Mapping> = ingested.mappings().collect();
+        assert_eq!(serde_json::to_value(&restored).unwrap(), serde_json::to_value(mappings()).unwrap());
+
+        // Repeated values share one symbol.
+        let strings = ingested.graph.strings();
+        assert_eq!(ingested.records[0].organization, ingested.records[2].organization);
+        assert_eq!(ingested.records[0].attack_object_id, strings.get("T1110").unwrap());
+        assert_eq!(strings.iter().filter(|(_, s)| *s == "Acme").count(), 1);
+        assert_eq!(ingested.report.strings, strings.len());
+        assert!(ingested.report.graph_bytes > 0);
+    }
+
+    #[test]
+    fn test_graph_only_and_owned_rows() {
+        let mut ingestor = Ingestor::new(IngestOptions { keep_mappings: false });
+        for m in &mappings() {
+            ingestor.add_mapping_row(&MappingRow::from(m));
+        }
+        let ingested = ingestor.finish();
+        assert!(ingested.records.is_empty());
+        assert_eq!(ingested.graph.edge_count(), 3);
+        assert_eq!(ingested.graph.mapping_type(EdgeIndex::new(1)), "Weak");
+        assert!((ingested.graph.strength(EdgeIndex::new(1)) - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_stix_stream_resolves_relationships_after_objects() {
+        let bundle = json!({
+            "type": "bundle",
+            "id": "bundle--1",
+            "objects": [
+                { "type": "relationship", "id": "relationship--1", "relationship_type": "uses",
+                  "source_ref": "intrusion-set--1", "target_ref": "attack-pattern--1", "confidence": 80 },
+                { "type": "relationship", "id": "relationship--2", "relationship_type": "uses",
+                  "source_ref": "intrusion-set--1", "target_ref": "attack-pattern--9" },
+                { "type": "intrusion-set", "id": "intrusion-set--1", "name": "APT28",
+                  "external_references": [{ "source_name": "mitre-attack", "external_id": "G0007" }] },
+                { "type": "attack-pattern", "id": "attack-pattern--1", "name": "Brute Force",
+                  "modified": "2024-01-01T00:00:00.000Z",
+                  "external_references": [{ "source_name": "mitre-attack", "external_id": "T1110" }] },
+                { "type": "identity", "id": "identity--1" },
+                { "type": "attack-pattern", "id": "attack-pattern--2", "revoked": true,
+                  "external_references": [{ "source_name": "mitre-attack", "external_id": "T1078" }] },
+            ],
+        });
+
+        let mut ingestor = Ingestor::new(IngestOptions::default());
+        ingestor.read_csv(Cursor::new(csv_bytes(&mappings()))).unwrap();
+        let objects = ingestor.read_stix(Cursor::new(serde_json::to_vec(&bundle).unwrap())).unwrap();
+        assert_eq!(objects, 6);
+        let ingested = ingestor.finish();
+        let graph = &ingested.graph;
+
+        assert_eq!((ingested.report.stix_objects, ingested.report.skipped), (6, 3));
+        assert_eq!((graph.node_count(), graph.edge_count()), (6, 4));
+        // The technique merges with the mapped node and keeps its type.
+        let t1110 = graph.node_index("T1110").unwrap();
+        assert_eq!(graph.node_type(t1110), NodeType::Mitre);
+        assert_eq!(graph.metadata(t1110, "name"), Some("Brute Force"));
+        assert_eq!(graph.metadata(t1110, "stix_id"), Some("attack-pattern--1"));
+        assert_eq!(graph.node_index("T1078"), None);
+
+        let group = graph.node_index("G0007").unwrap();
+        assert_eq!(graph.node_type(group), NodeType::Group);
+        let edge = EdgeIndex::new(3);
+        assert_eq!(graph.edge_endpoints(edge), (group, t1110));
+        assert_eq!(graph.mapping_type(edge), "uses");
+        assert!((graph.strength(edge) - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_run_report_has_peak_memory() {
+        reset_peak();
+        let ingested = {
+            let mut ingestor = Ingestor::new(IngestOptions::default());
+            ingestor.read_csv(Cursor::new(csv_bytes(&mappings()))).unwrap();
+            ingestor.finish()
+        };
+        let report = RunReport::new(vec!["mappings.csv".to_string()], chrono::Utc::now(), ingested.report);
+        // The graph is still alive, so the peak is at least its size.
+        assert!(allocated_bytes() >= report.ingest.graph_bytes);
+        assert!(report.peak_memory_bytes.unwrap() >= report.ingest.graph_bytes);
+        assert_eq!(serde_json::to_value(&report).unwrap()["ingest"]["mapping_rows"], 3);
+    }
+}
@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::link_prediction::*;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::utils::add_node_if_not_exists;
+
+    // V1 - T1 - V2 - T3, plus V1 - T2.
+    fn graph() -> MappingGraph {
+        let mut graph = MappingGraph::new();
+        let mut indices = HashMap::new();
+        for (veris, mitre) in [("V1", "T1"), ("V1", "T2"), ("V2", "T1"), ("V2", "T3")] {
+            let v = add_node_if_not_exists(&mut graph, &mut indices, veris, NodeType::Veris);
+            let t = add_node_if_not_exists(&mut graph, &mut indices, mitre, NodeType::Mitre);
+            graph.add_edge(v, t, EdgeData { mapping_type: "related-to".into(), strength: 1.0 });
+        }
+        graph
+    }
+
+    #[test]
+    fn test_candidates_are_explained_by_shared_neighbors() {
+        let candidates = predict_links(&graph(), &LinkPredictionOptions::default());
+
+        let pairs: Vec<(&str, &str)> =
+            candidates.iter().map(|c| (c.veris_id.as_str(), c.mitre_id.as_str())).collect();
+        assert_eq!(pairs, vec![("V1", "T3"), ("V2", "T2")]);
+
+        let best = &candidates[0];
+        assert_eq!(best.common_neighbors, 1);
+        assert_eq!(best.shared_neighbors, vec!["T1"]);
+        assert_eq!(best.bridging_veris, vec!["V2"]);
+        assert_eq!(best.jaccard, 0.5);
+        assert_eq!(best.resource_allocation, 0.5);
+        assert!((best.adamic_adar - 1.0 / 2f64.ln()).abs() < 1e-9);
+        assert!((best.katz - 0.05f64.powi(3) - 4.0 * 0.05f64.powi(5)).abs() < 1e-12);
+    }
+}
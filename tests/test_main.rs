@@ -47,7 +47,7 @@ mod tests {
             file.read_to_end(&mut buffer).unwrap();
             assert!(!buffer.is_empty());
             
-            let mut file = File::open("./analysed/ds/combined_analysis.csv").unwrap();
+            let mut file = File::open("./analysed/data/combined_analysis.csv").unwrap();
             let mut buffer = String::new();
             file.read_to_string(&mut buffer).unwrap();
             assert!(!buffer.is_empty());
@@ -72,7 +72,7 @@ mod tests {
             file.read_to_end(&mut buffer).unwrap();
             assert!(buffer.is_empty());
             
-            let mut file = File::open("./analysed/ds/combined_analysis.csv").unwrap();
+            let mut file = File::open("./analysed/data/combined_analysis.csv").unwrap();
             let mut buffer = String::new();
             file.read_to_string(&mut buffer).unwrap();
             assert!(buffer.is_empty());
@@ -0,0 +1,82 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::metrics::*;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::utils::create_graph;
+
+    use crate::common::{mapping, MappingFixture};
+
+    #[test]
+    fn test_render_exposition_format() {
+        let metrics = Metrics::new();
+        metrics.inc_counter(LOADER_ERRORS, "Inputs that failed to load.", &[("source", "csv")], 1.0);
+        metrics.inc_counter(LOADER_ERRORS, "Inputs that failed to load.", &[("source", "csv")], 1.0);
+        metrics.set_gauge(NOVELTY_SCORE, "Novelty.", &[("node", "T1110"), ("name", "Say \"hi\"\n")], 0.5);
+        metrics.observe(ANALYSIS_DURATION, "Time.", &[("analysis", "basic_stats")], 0.25);
+        metrics.time_analysis("basic_stats", || ());
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE mightygraph_loader_errors_total counter\nmightygraph_loader_errors_total{source=\"csv\"} 2\n"));
+        // Labels are sorted by name and their values escaped.
+        assert!(text.contains("mightygraph_novelty_score{name=\"Say \\\"hi\\\"\\n\",node=\"T1110\"} 0.5\n"));
+        assert!(text.contains("# TYPE mightygraph_analysis_duration_seconds summary\n"));
+        assert!(text.contains("mightygraph_analysis_duration_seconds_count{analysis=\"basic_stats\"} 2\n"));
+        assert!(text.contains("mightygraph_analysis_duration_seconds_sum{analysis=\"basic_stats\"} "));
+        assert_eq!(metrics.value(LOADER_ERRORS, &[("source", "csv")]), Some(2.0));
+    }
+
+    #[test]
+    fn test_record_graph_and_novelty() {
+        let mappings = vec![
+            mapping("action.hacking.variety.Brute force", "T1110"),
+            mapping("action.malware.variety.Ransomware", "T1486").with_created("sometime"),
+        ];
+        let (graph, _) = create_graph(&mappings).unwrap();
+        let metrics = Metrics::new();
+        record_graph(&metrics, &graph, &mappings);
+        assert_eq!(metrics.value(GRAPH_NODES, &[("node_type", "Mitre")]), Some(2.0));
+        assert_eq!(metrics.value(GRAPH_EDGES, &[]), Some(2.0));
+        assert_eq!(metrics.value(UNPARSED_DATES, &[("field", "creation_date")]), Some(1.0));
+
+        let scores = vec![
+            ("T1110".to_string(), "Brute Force".to_string(), 0.4),
+            ("T1486".to_string(), "Data Encrypted for Impact".to_string(), 0.9),
+            ("T1566".to_string(), "Phishing".to_string(), 0.7),
+        ];
+        record_novelty(&metrics, &scores, 2);
+        record_novelty(&metrics, &scores[..2], 1);
+        let text = metrics.render();
+        assert!(text.contains("node=\"T1486\""));
+        assert!(!text.contains("node=\"T1566\""), "earlier top-N is replaced");
+        assert!(!text.contains("node=\"T1110\""));
+    }
+
+    #[test]
+    fn test_textfile_and_dashboard() {
+        let dir = tempfile::tempdir().unwrap();
+        let metrics = Metrics::new();
+        metrics.set_gauge(MAPPINGS, "Mapping rows loaded.", &[], 3.0);
+        let path = dir.path().join("collector").join("mightygraph.prom");
+        metrics.write_textfile(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), metrics.render());
+        assert_eq!(std::fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        let dashboard = grafana_dashboard();
+        let panels = dashboard["panels"].as_array().unwrap();
+        assert_eq!(panels.len(), 6);
+        let exprs: Vec<&str> = panels
+            .iter()
+            .flat_map(|p| p["targets"].as_array().unwrap())
+            .filter_map(|t| t["expr"].as_str())
+            .collect();
+        for name in [GRAPH_NODES, GRAPH_EDGES, ANALYSIS_DURATION, LOADER_ERRORS, TACTIC_COVERAGE, NOVELTY_SCORE] {
+            assert!(exprs.iter().any(|e| e.contains(name)), "no panel for {}", name);
+        }
+        for p in panels {
+            let refs: HashSet<&str> = p["targets"].as_array().unwrap().iter().map(|t| t["refId"].as_str().unwrap()).collect();
+            assert_eq!(refs.len(), p["targets"].as_array().unwrap().len());
+        }
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::query::*;
+    use mighty_graph_rs::utils::add_node_if_not_exists;
+    use serde_json::json;
+
+    // V1 -> T1 (persistence, privesc), V1 -> T2 (persistence), V2 -> T1 (weak).
+    fn graph() -> MappingGraph {
+        let mut graph = MappingGraph::new();
+        let mut indices = HashMap::new();
+        for (veris, mitre, strength) in [("V1", "T1", 1.0), ("V1", "T2", 0.7), ("V2", "T1", 0.4)] {
+            let v = add_node_if_not_exists(&mut graph, &mut indices, veris, NodeType::Veris);
+            let t = add_node_if_not_exists(&mut graph, &mut indices, mitre, NodeType::Mitre);
+            graph.add_edge(v, t, EdgeData { mapping_type: "related-to".into(), strength });
+        }
+        graph[indices["T1"]].metadata.insert("tactics".into(), "persistence,privilege-escalation".into());
+        graph[indices["T2"]].metadata.insert("tactics".into(), "persistence".into());
+        graph
+    }
+
+    #[test]
+    fn test_filters_and_aggregates() {
+        let graph = graph();
+
+        let result = run_query(
+            &graph,
+            "MATCH (v:Veris)-[m]->(t:Mitre) WHERE t.tactics CONTAINS 'persistence' AND m.strength > 0.5 \
+             RETURN v.id, count(t) AS techniques ORDER BY techniques DESC",
+        )
+        .unwrap();
+
+        assert_eq!(result.columns, vec!["v.id", "techniques"]);
+        assert_eq!(result.rows, vec![vec![json!("V1"), json!(2)]]);
+    }
+
+    #[test]
+    fn test_variable_length_paths_ignore_direction_and_reuse_no_edge() {
+        let graph = graph();
+
+        let result = run_query(
+            &graph,
+            "MATCH (a {id: 'T2'})-[p*1..3]-(b:Veris) RETURN b.id, p.length ORDER BY p.length",
+        )
+        .unwrap();
+
+        assert_eq!(
+            result.rows,
+            vec![vec![json!("V1"), json!(1)], vec![json!("V2"), json!(3)]]
+        );
+    }
+
+    #[test]
+    fn test_errors_report_position() {
+        let graph = graph();
+
+        let error = run_query(&graph, "MATCH (v:Planet) RETURN v").unwrap_err();
+        assert_eq!(error.position, Some(9));
+
+        let error = run_query(&graph, "MATCH (v) RETURN w").unwrap_err();
+        assert_eq!(error.position, None);
+    }
+}
@@ -0,0 +1,120 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::export::NodeMetrics;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::rag::*;
+    use mighty_graph_rs::utils::{add_node_if_not_exists, create_graph};
+    use serde_json::json;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn mappings() -> Vec<Mapping> {
+        vec![
+            mapping("V1", "T1110").with_type("Strong"),
+            mapping("V2", "T1110").with_type("Weak").with_domain("mobile"),
+            mapping("V2", "T1486").with_type("Moderate"),
+        ]
+    }
+
+    /// The mapping graph plus a report `R1` that mentions T1486.
+    fn graph(mappings: &[Mapping]) -> MappingGraph {
+        let (mut graph, mut indices) = create_graph(mappings).unwrap();
+        let report = add_node_if_not_exists(&mut graph, &mut indices, "R1", NodeType::Report);
+        graph.add_edge(report, indices["T1486"], EdgeData { mapping_type: "mentions".into(), strength: 1.0 });
+        graph
+    }
+
+    fn chunks(mappings: &[Mapping], options: &RagOptions) -> Vec<Chunk> {
+        build_chunks(&graph(mappings), mappings, &NodeMetrics::new(), options)
+    }
+
+    fn find<'a>(chunks: &'a [Chunk], kind: ChunkKind, node_id: &str) -> &'a Chunk {
+        chunks.iter().find(|c| c.kind == kind && c.node_id == node_id).unwrap()
+    }
+
+    #[test]
+    fn test_chunk_ids_are_stable_and_hashes_follow_text() {
+        let id = chunk_id(ChunkKind::Node, "T1110", 0);
+        assert_eq!(id.len(), 32);
+        assert_eq!(id, chunk_id(ChunkKind::Node, "T1110", 0));
+        assert_ne!(id, chunk_id(ChunkKind::Neighborhood, "T1110", 0));
+        assert_ne!(chunk_id(ChunkKind::Neighborhood, "T1110", 1), chunk_id(ChunkKind::Neighborhood, "T1110", 2));
+
+        let before = chunks(&mappings(), &RagOptions::default());
+        let mut changed = mappings();
+        changed[0].capability_description = "Brute force".to_string();
+        let after = chunks(&changed, &RagOptions::default());
+
+        let ids = |chunks: &[Chunk]| chunks.iter().map(|c| c.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&before), ids(&after));
+        assert_eq!(find(&before, ChunkKind::Node, "V1").id, chunk_id(ChunkKind::Node, "V1", 0));
+
+        let (old, new) = (find(&before, ChunkKind::Node, "V1"), find(&after, ChunkKind::Node, "V1"));
+        assert!(new.text.contains("(Brute force)"));
+        assert_ne!(old.content_hash, new.content_hash);
+        assert_eq!(new.content_hash, mighty_graph_rs::utils::sha256_hex(&new.text));
+
+        let (old, new) = (find(&before, ChunkKind::Node, "R1"), find(&after, ChunkKind::Node, "R1"));
+        assert_eq!(old.content_hash, new.content_hash);
+    }
+
+    #[test]
+    fn test_filters() {
+        let node_ids = |filter: ChunkFilter| {
+            let options = RagOptions { neighborhood_chunks: false, filter, ..RagOptions::default() };
+            chunks(&mappings(), &options).into_iter().map(|c| c.node_id).collect::<Vec<_>>()
+        };
+
+        assert_eq!(node_ids(ChunkFilter { node_types: Some(vec![NodeType::Veris]), ..Default::default() }), vec!["V1", "V2"]);
+        assert_eq!(node_ids(ChunkFilter { id_prefix: Some("T14".to_string()), ..Default::default() }), vec!["T1486"]);
+        assert_eq!(
+            node_ids(ChunkFilter { technology_domains: Some(vec!["mobile".to_string()]), ..Default::default() }),
+            vec!["T1110", "V2"]
+        );
+
+        let filter = ChunkFilter { id_prefix: Some("T1110".to_string()), min_strength: Some(0.5), ..Default::default() };
+        let options = RagOptions { neighborhood_chunks: false, filter, ..RagOptions::default() };
+        let chunks = chunks(&mappings(), &options);
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].text.contains("Mapped from 1 node(s)"));
+        assert!(chunks[0].text.contains("VERIS enumeration V1 [Strong"));
+        assert!(!chunks[0].text.contains("V2"));
+    }
+
+    #[test]
+    fn test_neighborhood_lists_every_edge_once() {
+        let chunks = chunks(&mappings(), &RagOptions::default());
+        let neighborhood = find(&chunks, ChunkKind::Neighborhood, "T1486");
+
+        assert_eq!(neighborhood.metadata["hops"], 2);
+        assert_eq!(neighborhood.metadata["neighbor_ids"], json!(["R1", "V2", "T1110"]));
+        let edges: Vec<&str> = neighborhood.text.lines().filter(|l| l.starts_with("- ")).collect();
+        assert_eq!(
+            edges,
+            vec![
+                "- Intelligence report R1 -[mentions, 1.00]-> ATT&CK technique T1486",
+                "- VERIS enumeration V2 -[Moderate, 0.70]-> ATT&CK technique T1486",
+                "- VERIS enumeration V2 -[Weak, 0.40]-> ATT&CK technique T1110",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_jsonl() {
+        let chunks = chunks(&mappings(), &RagOptions::default());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chunks.jsonl");
+        write_jsonl(&chunks, &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<Value> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), chunks.len());
+        assert_eq!(lines[0]["id"], chunks[0].id);
+        assert_eq!(lines[0]["kind"], "node");
+        assert_eq!(lines[1]["kind"], "neighborhood");
+        assert_eq!(lines[0]["content_hash"], chunks[0].content_hash);
+        assert_eq!(lines[0]["metadata"]["node_type"], "Veris");
+    }
+}
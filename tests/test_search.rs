@@ -0,0 +1,51 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::search::*;
+    use mighty_graph_rs::utils::{create_graph, tokenize};
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn mappings() -> Vec<Mapping> {
+        vec![
+            mapping("action.malware.variety.Capture stored data", "T1003.001").with_description("Capture data stored on the system").with_name("OS Credential Dumping: LSASS Memory"),
+            mapping("action.hacking.variety.Brute force", "T1110").with_description("Brute force attack").with_name("Brute Force"),
+        ]
+    }
+
+    #[test]
+    fn test_tokenize_drops_stop_words_and_punctuation() {
+        assert_eq!(tokenize("OS Credential Dumping: LSASS Memory of the host"), vec!["os", "credential", "dumping", "lsass", "memory", "host"]);
+    }
+
+    #[test]
+    fn test_search_resolves_hits_to_nodes_and_mappings() {
+        let mappings = mappings();
+        let (graph, node_indices) = create_graph(&mappings).unwrap();
+        let index = SearchIndex::build(&mappings, None);
+
+        let options = SearchOptions { node_type: Some(NodeType::Mitre), ..SearchOptions::default() };
+        let hits = index.search(&graph, &node_indices, "credential dumping lsass", &options);
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "T1003.001");
+        assert_eq!(hits[0].matched_terms, vec!["credential", "dumping", "lsass"]);
+        assert_eq!(hits[0].mappings[0].veris_id, "action.malware.variety.Capture stored data");
+    }
+
+    #[test]
+    fn test_fuzzy_matching() {
+        let mappings = mappings();
+        let (graph, node_indices) = create_graph(&mappings).unwrap();
+        let index = SearchIndex::build(&mappings, None);
+
+        let hits = index.search(&graph, &node_indices, "credental", &SearchOptions::default());
+        assert_eq!(hits.iter().map(|h| h.id.as_str()).collect::<Vec<_>>(), vec!["T1003.001", "action.malware.variety.Capture stored data"]);
+
+        let exact = SearchOptions { fuzzy: false, ..SearchOptions::default() };
+        assert!(index.search(&graph, &node_indices, "credental", &exact).is_empty());
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+}
@@ -0,0 +1,119 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use mighty_graph_rs::cache::{AnalysisCache, MemoryCache};
+    use mighty_graph_rs::metrics;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::server::*;
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    use crate::common::mapping;
+
+    fn write_csv(path: &Path, mappings: &[Mapping]) {
+        let mut writer = csv::Writer::from_path(path).unwrap();
+        for m in mappings {
+            writer.serialize(m).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+
+    fn app(dir: &Path) -> (ServerConfig, AppState) {
+        let path = dir.join("mappings.csv");
+        write_csv(
+            &path,
+            &[
+                mapping("action.hacking.variety.Brute force", "T1110"),
+                mapping("action.malware.variety.Ransomware", "T1486"),
+            ],
+        );
+        let config = ServerConfig::new(path);
+        let state = GraphState::load(&config).unwrap();
+        let app = AppState::with_cache(state, AnalysisCache::new(Box::new(MemoryCache::new())));
+        (config, app)
+    }
+
+    async fn get(app: &AppState, uri: &str) -> (StatusCode, Value) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = router(app.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_list_nodes_filters_by_node_type() {
+        let dir = tempdir().unwrap();
+        let (_, app) = app(dir.path());
+
+        let (status, body) = get(&app, "/nodes").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 4);
+
+        let (status, body) = get(&app, "/nodes?node_type=veris").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 2);
+
+        // Same labels as /search and the query language.
+        let (_, body) = get(&app, "/nodes?node_type=technique&limit=1").await;
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_node_type_is_bad_request() {
+        let dir = tempdir().unwrap();
+        let (_, app) = app(dir.path());
+
+        let (status, body) = get(&app, "/nodes?node_type=bogus").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "unknown node_type: bogus");
+
+        let (status, _) = get(&app, "/search?q=brute&node_type=bogus").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_node_is_not_found() {
+        let dir = tempdir().unwrap();
+        let (_, app) = app(dir.path());
+
+        let (status, body) = get(&app, "/nodes/T9999").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "node not found: T9999");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reloads_when_file_is_replaced_by_rename() {
+        let dir = tempdir().unwrap();
+        let (mut config, app) = app(dir.path());
+        config.reload_debounce = Duration::from_millis(50);
+        let _watcher = spawn_reloader(config.clone(), app.clone()).unwrap();
+
+        // Save the way many editors do: write a new file, rename it over the
+        // old one. The second save only reloads if the watch outlives the
+        // replaced file.
+        for count in [1, 3] {
+            let mappings: Vec<_> = (0..count).map(|i| mapping("V", &format!("T{}", 1000 + i))).collect();
+            let staged = dir.path().join(".mappings.csv.tmp");
+            write_csv(&staged, &mappings);
+            std::fs::rename(&staged, &config.mappings_path).unwrap();
+
+            for _ in 0..100 {
+                if app.snapshot().mappings.len() == count {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            assert_eq!(app.snapshot().mappings.len(), count);
+        }
+        let reloads = metrics::global().value(metrics::RELOADS, &[("outcome", "ok")]);
+        assert!(reloads >= Some(2.0));
+    }
+}
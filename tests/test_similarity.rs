@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::embeddings::Embeddings;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::similarity::*;
+    use mighty_graph_rs::utils::add_node_if_not_exists;
+
+    /// V1 and V2 map to the same techniques, V3 shares T1 with them and V4
+    /// shares nothing.
+    fn graph() -> MappingGraph {
+        let mut graph = MappingGraph::new();
+        let mut indices = HashMap::new();
+        for (veris, mitre) in [
+            ("V1", "T1"),
+            ("V1", "T2"),
+            ("V2", "T1"),
+            ("V2", "T2"),
+            ("V3", "T1"),
+            ("V3", "T3"),
+            ("V4", "T4"),
+        ] {
+            let v = add_node_if_not_exists(&mut graph, &mut indices, veris, NodeType::Veris);
+            let t = add_node_if_not_exists(&mut graph, &mut indices, mitre, NodeType::Mitre);
+            graph.add_edge(v, t, EdgeData { mapping_type: "related-to".into(), strength: 1.0 });
+        }
+        graph
+    }
+
+    fn scores(results: &[SimilarNode]) -> Vec<(&str, f32)> {
+        results.iter().map(|r| (r.id.as_str(), r.score)).collect()
+    }
+
+    #[test]
+    fn test_jaccard() {
+        let index = SimilarityIndex::new(&graph());
+
+        let results = index.most_similar("V1", SimilarityMetric::Jaccard, 10).unwrap();
+        assert_eq!(scores(&results), vec![("V2", 1.0), ("V3", 1.0 / 3.0)]);
+        assert!(results.iter().all(|r| r.node_type == NodeType::Veris));
+
+        let results = index.most_similar("V1", SimilarityMetric::Jaccard, 1).unwrap();
+        assert_eq!(scores(&results), vec![("V2", 1.0)]);
+
+        assert!(index.most_similar("V4", SimilarityMetric::Jaccard, 10).unwrap().is_empty());
+        assert!(index.most_similar("V9", SimilarityMetric::Jaccard, 10).is_err());
+    }
+
+    #[test]
+    fn test_simrank() {
+        let index = SimilarityIndex::new(&graph());
+        assert!(!index.supports(SimilarityMetric::SimRank));
+        assert!(index.most_similar("V1", SimilarityMetric::SimRank, 10).is_err());
+
+        // One iteration: C / (|N(a)| |N(b)|) times the shared neighbors.
+        let index = index.with_simrank(0.8, 1, 10);
+        let results = index.most_similar("V1", SimilarityMetric::SimRank, 10).unwrap();
+        assert_eq!(scores(&results), vec![("V2", 0.4), ("V3", 0.2)]);
+
+        let index = SimilarityIndex::new(&graph()).with_simrank(0.8, 5, 10);
+        let v1 = index.most_similar("V1", SimilarityMetric::SimRank, 10).unwrap();
+        let v2 = index.most_similar("V2", SimilarityMetric::SimRank, 10).unwrap();
+        assert_eq!(v1.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["V2", "V3"]);
+        assert_eq!(v1[0].score, v2[0].score);
+        assert!(v1[0].score > v1[1].score);
+        assert!(index.most_similar("V4", SimilarityMetric::SimRank, 10).unwrap().is_empty());
+
+        let results = index.most_similar("T1", SimilarityMetric::SimRank, 10).unwrap();
+        assert!(results.iter().all(|r| r.node_type == NodeType::Mitre));
+    }
+
+    #[test]
+    fn test_simrank_keeps_top_k_per_node() {
+        let index = SimilarityIndex::new(&graph()).with_simrank(0.8, 5, 1);
+
+        let results = index.most_similar("V1", SimilarityMetric::SimRank, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "V2");
+    }
+
+    #[test]
+    fn test_embedding_top_k() {
+        let embeddings = Embeddings {
+            ids: vec!["V1", "V2", "V3", "T1"].into_iter().map(String::from).collect(),
+            node_types: vec![NodeType::Veris, NodeType::Veris, NodeType::Veris, NodeType::Mitre],
+            dimensions: 2,
+            vectors: vec![1.0, 0.0, 3.0, 1.0, 0.0, 2.0, 1.0, 0.0],
+        };
+        let mut index = SimilarityIndex::new(&graph()).with_embeddings(&embeddings);
+
+        // Cosine ignores vector length; V3 is orthogonal and scores 0.
+        let results = index.most_similar("V1", SimilarityMetric::Embedding, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "V2");
+        assert!((results[0].score - 3.0 / 10f32.sqrt()).abs() < 1e-6);
+
+        index.same_type_only = false;
+        let results = index.most_similar("V1", SimilarityMetric::Embedding, 1).unwrap();
+        assert_eq!(scores(&results), vec![("T1", 1.0)]);
+
+        // V4 has no vector.
+        assert!(index.most_similar("V4", SimilarityMetric::Embedding, 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::utils::{create_graph, graph_hash};
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn mappings() -> Vec<Mapping> {
+        vec![
+            mapping("action.hacking.variety.Brute force", "T1110").with_references("https://example.org"),
+            mapping("action.hacking.variety.Brute force", "T1110").with_references("https://example.org"),
+            mapping("action.malware.variety.Ransomware", "T1486").with_references("https://example.org"),
+        ]
+    }
+
+    #[test]
+    fn test_graph_hash_ignores_insertion_order() {
+        let mut mappings = mappings();
+        let (graph, node_indices) = create_graph(&mappings).unwrap();
+        mappings.reverse();
+        let (mut reversed, reversed_indices) = create_graph(&mappings).unwrap();
+        assert_ne!(node_indices["T1110"], reversed_indices["T1110"]);
+        assert_eq!(graph_hash(&graph), graph_hash(&reversed));
+
+        reversed[reversed_indices["T1110"]].metadata.insert("name".to_string(), "Brute Force".to_string());
+        assert_ne!(graph_hash(&graph), graph_hash(&reversed));
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn test_run_metadata_hashes_input_bytes() {
+        use mighty_graph_rs::storage::RunMetadata;
+        use mighty_graph_rs::utils::sha256_hex;
+
+        // Latin-1 export: not valid UTF-8.
+        let bytes = b"capability_id,attack_object_id\nCaf\xe9,T1110\n";
+        let input = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(input.path(), bytes).unwrap();
+        let mappings = mappings();
+        let (graph, _) = create_graph(&mappings).unwrap();
+
+        let metadata = RunMetadata::new(input.path().to_str().unwrap(), &graph, &mappings).unwrap();
+
+        assert_eq!(metadata.input_sha256, sha256_hex(bytes));
+    }
+
+    /// Needs a database: run with `DATABASE_URL` set and `--ignored`.
+    #[cfg(feature = "postgres")]
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn test_run_round_trip() {
+        use mighty_graph_rs::storage::*;
+        use serde_json::json;
+        use std::io::Write;
+
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must point at a test database");
+        let mut store = PgStore::connect(&url).unwrap();
+        assert!(store.migrate().unwrap().is_empty());
+
+        let mappings = mappings();
+        let (mut graph, node_indices) = create_graph(&mappings).unwrap();
+        graph[node_indices["T1110"]].metadata.insert("name".to_string(), "Brute Force".to_string());
+        let analyses = AnalysisResults {
+            basic_stats: json!({ "node_count": 4 }),
+            mapping_type_analysis: json!({}),
+            node_degree_analysis: json!({}),
+            connected_components_analysis: json!({}),
+            shortest_path_analysis: json!({}),
+            edge_strength_analysis: json!({}),
+            node_type_distribution: json!({}),
+            temporal_analysis: json!({}),
+            tech_domain_analysis: json!({}),
+            closeness_centrality: json!({}),
+            path_length_analysis: json!({}),
+        };
+        let mut input = tempfile::NamedTempFile::new().unwrap();
+        writeln!(input, "capability_id,attack_object_id").unwrap();
+        let metadata = RunMetadata::new(input.path().to_str().unwrap(), &graph, &mappings).unwrap();
+        assert_eq!(metadata.attack_versions, vec!["12.1"]);
+
+        let run_id = store.save_run(&metadata, &graph, &mappings, &analyses).unwrap();
+        let run = store.run(run_id).unwrap().unwrap();
+        assert_eq!(run.metadata.graph_hash, metadata.graph_hash);
+        assert_eq!(run.metadata.edge_count, 3);
+
+        let (loaded, loaded_indices) = store.load_graph(run_id).unwrap();
+        assert_eq!(graph_hash(&loaded), metadata.graph_hash);
+        assert_eq!(loaded_indices, node_indices);
+        assert_eq!(store.load_mappings(run_id).unwrap().len(), 3);
+        assert_eq!(store.load_analyses(run_id).unwrap()["basic_stats"], json!({ "node_count": 4 }));
+        let history = store.analysis_history("basic_stats").unwrap();
+        assert_eq!(history.last().unwrap().0, run_id);
+
+        assert!(store.delete_run(run_id).unwrap());
+        assert!(store.run(run_id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,107 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::subgraph_optimized::*;
+    use mighty_graph_rs::utils::{create_graph, with_threads};
+
+    use crate::common::{mapping, MappingFixture};
+
+    /// `V1 - T1 - V2 - T2` and a separate `V3 - T3` pair.
+    fn graph() -> NoveltyGraph {
+        let mappings = vec![
+            mapping("V1", "T1").with_type("Strong"),
+            mapping("V2", "T1").with_type("Weak"),
+            mapping("V2", "T2").with_type("Strong"),
+            mapping("V3", "T3").with_type("Strong"),
+        ];
+        from_mapping_graph(&create_graph(&mappings).unwrap().0)
+    }
+
+    fn ids(graph: &NoveltyGraph, nodes: impl IntoIterator<Item = NodeIndex>) -> Vec<&str> {
+        nodes.into_iter().map(|n| graph[n].id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_from_mapping_graph() {
+        let graph = graph();
+        assert_eq!(graph.node_count(), 6);
+        assert_eq!(graph.edge_count(), 8);
+        assert_eq!(graph[NodeIndex::new(1)].object_type, "attack-pattern");
+        assert_eq!(graph[NodeIndex::new(0)].object_type, "veris");
+    }
+
+    #[test]
+    fn test_novelty_scores() {
+        let graph = graph();
+        let scores = novelty_scores(&graph);
+
+        // Small components and short distances score high; ties keep node order.
+        assert_eq!(ids(&graph, scores.iter().map(|s| s.0)), vec!["V3", "T3", "T1", "V2", "V1", "T2"]);
+        assert!((scores[1].1 - 7.0 / 9.0).abs() < 1e-9);
+        assert!((scores[2].1 - 11.0 / 18.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_scores_do_not_depend_on_thread_count() {
+        let mappings: Vec<Mapping> = (0..60)
+            .map(|i| {
+                let mapping_type = ["Strong", "Moderate", "Weak"][i % 3];
+                mapping(&format!("V{}", i % 17), &format!("T{}", i % 11)).with_type(mapping_type)
+            })
+            .collect();
+        let graph = from_mapping_graph(&create_graph(&mappings).unwrap().0);
+        let run = || (novelty_scores(&graph), subsample_novel_techniques(&graph, 0.3, 5, 42));
+
+        let single = with_threads(1, run).unwrap();
+        let parallel = with_threads(4, run).unwrap();
+        assert_eq!(single, parallel);
+        assert_eq!(single.0.len(), graph.node_count());
+    }
+
+    #[test]
+    fn test_subsample_is_seeded() {
+        let graph = graph();
+        let sample = subsample_novel_techniques(&graph, 0.6, 2, 7);
+        assert_eq!(sample.len(), 2);
+        assert_eq!(sample, subsample_novel_techniques(&graph, 0.6, 2, 7));
+        assert!(sample.iter().all(|&n| ["V3", "T3", "T1", "V2"].contains(&graph[n].id.as_str())));
+
+        assert_eq!(subsample_novel_techniques(&graph, 0.6, 10, 7).len(), 4);
+    }
+
+    #[test]
+    fn test_extract_subgraph() {
+        let graph = graph();
+        let subgraph = extract_subgraph(&graph, &[NodeIndex::new(5)], 1);
+        assert_eq!(ids(&subgraph, subgraph.node_indices()), vec!["T3", "V3"]);
+        assert_eq!(subgraph.edge_count(), 2);
+
+        // V2 is two hops out; its edge to T2 is kept.
+        let subgraph = extract_subgraph(&graph, &[NodeIndex::new(0)], 2);
+        assert_eq!(ids(&subgraph, subgraph.node_indices()), vec!["V1", "T1", "V2", "T2"]);
+    }
+
+    #[test]
+    fn test_load_mitre_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("objects.json");
+        let data = serde_json::json!({
+            "objects": [
+                { "id": "T1110", "name": "Brute Force", "object_type": "attack-pattern" },
+                { "id": "S0001", "name": "Tool", "object_type": "tool" },
+            ],
+            "relationships": [
+                { "source_ref": "S0001", "target_ref": "T1110", "relationship_type": "uses" },
+                { "source_ref": "S0001", "target_ref": "T9999", "relationship_type": "uses" },
+            ],
+        });
+        std::fs::write(&path, data.to_string()).unwrap();
+
+        let (graph, node_map) = build_graph(&load_mitre_data(&path).unwrap());
+        assert_eq!((graph.node_count(), graph.edge_count()), (2, 1));
+        assert_eq!(graph[node_map["T1110"]].name, "Brute Force");
+        assert!(load_mitre_data(dir.path().join("missing.json")).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use mighty_graph_rs::export::NodeMetrics;
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::summarize::*;
+    use mighty_graph_rs::utils::create_graph;
+
+    use crate::common::{mapping, MappingFixture};
+
+    /// Answers every chat completion with the same reply and counts requests.
+    fn mock_endpoint(reply: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                assert_eq!(request["model"], "mock");
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let response = serde_json::json!({
+                    "choices": [{ "message": { "role": "assistant", "content": reply } }]
+                })
+                .to_string();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+
+        (format!("http://{}/v1", address), requests)
+    }
+
+    fn config(endpoint: String) -> SummarizerConfig {
+        SummarizerConfig { endpoint, model: "mock".to_string(), requests_per_minute: 6000, ..SummarizerConfig::default() }
+    }
+
+    #[test]
+    fn test_summaries_are_stored_and_cached() {
+        let mappings = vec![mapping("action.hacking.variety.Brute force", "T1110").with_name("Brute Force").with_description("Brute force attack")];
+        let (mut graph, node_indices) = create_graph(&mappings).unwrap();
+        let (endpoint, requests) = mock_endpoint("```json\n{\"summary\": \"Password guessing.\", \"tags\": [\"Credential Access\"]}\n```");
+        let config = config(endpoint);
+        let mut cache = SummaryCache::in_memory();
+
+        let report = summarize_graph(&mut graph, &mappings, &NodeMetrics::new(), &config, &mut cache).unwrap();
+        assert_eq!((report.summarized, report.cached), (2, 0));
+        assert!(report.failed.is_empty());
+
+        let technique = &graph[node_indices["T1110"]].metadata;
+        assert_eq!(technique["summary"], "Password guessing.");
+        assert_eq!(technique["tags"], "credential access");
+        assert_eq!(technique["summary_model"], "mock");
+
+        let report = summarize_graph(&mut graph, &mappings, &NodeMetrics::new(), &config, &mut cache).unwrap();
+        assert_eq!((report.summarized, report.cached), (0, 2));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_dry_run_sends_nothing() {
+        let mappings = vec![mapping("action.hacking.variety.Brute force", "T1110").with_name("Brute Force").with_description("Brute force attack")];
+        let (mut graph, node_indices) = create_graph(&mappings).unwrap();
+        let (endpoint, requests) = mock_endpoint("unused");
+        let config = SummarizerConfig { dry_run: true, node_types: Some(vec![NodeType::Mitre]), ..config(endpoint) };
+
+        let report = summarize_graph(&mut graph, &mappings, &NodeMetrics::new(), &config, &mut SummaryCache::in_memory()).unwrap();
+        assert_eq!(report.prompts.len(), 1);
+        assert_eq!(report.prompts[0].0, "T1110");
+        assert!(report.prompts[0].1.contains("Brute Force"));
+        assert!(graph[node_indices["T1110"]].metadata.is_empty());
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_plain_text_reply_falls_back_to_summary() {
+        let summary = parse_reply("Just a sentence.", "mock");
+        assert_eq!(summary.summary, "Just a sentence.");
+        assert!(summary.tags.is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use mighty_graph_rs::petgraph_full_0x0::perform_temporal_analysis;
+    use mighty_graph_rs::temporal::*;
+    use mighty_graph_rs::utils::parse_mapping_date;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_mapping_date_formats() {
+        assert_eq!(parse_mapping_date("21/03/2023"), Some(date(2023, 3, 21)));
+        assert_eq!(parse_mapping_date("2023-03-21"), Some(date(2023, 3, 21)));
+        assert_eq!(parse_mapping_date("2023-03-21T14:38:25.798Z"), Some(date(2023, 3, 21)));
+        assert_eq!(parse_mapping_date("March 21, 2023"), Some(date(2023, 3, 21)));
+        assert_eq!(parse_mapping_date("not a date"), None);
+        assert_eq!(parse_mapping_date(""), None);
+    }
+
+    #[test]
+    fn test_temporal_analysis_does_not_panic_on_bad_dates() {
+        let mappings = vec![
+            mapping("V1", "T1003").with_dates("15/01/2023", "2023-05-01"),
+            mapping("V2", "T1059").with_dates("garbage", ""),
+        ];
+
+        let result = perform_temporal_analysis(&mappings);
+
+        assert_eq!(result["min_date"], "2023-01-15");
+        assert_eq!(result["unparsed_creation_dates"], 1);
+        assert_eq!(result["creation_by_quarter"]["2023-Q1"], 1);
+        assert_eq!(result["update_by_month"]["2023-05"], 1);
+    }
+
+    #[test]
+    fn test_snapshots_grow_over_time() {
+        let mappings = vec![
+            mapping("V1", "T1003").with_dates("15/01/2023", ""),
+            mapping("V2", "T1059").with_dates("20/04/2023", ""),
+        ];
+        let dates = bucket_end_dates(&mappings, TimeBucket::Quarter);
+        assert_eq!(dates, vec![date(2023, 3, 31), date(2023, 6, 30)]);
+
+        let snapshots = perform_temporal_snapshots(&mappings, &dates, 3).unwrap();
+
+        assert_eq!(snapshots[0]["total_edges"], 1);
+        assert_eq!(snapshots[1]["total_edges"], 2);
+        assert_eq!(snapshots[1]["mapped_mitre_nodes"], 2);
+    }
+}
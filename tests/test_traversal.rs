@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::petgraph_full_0x0::prelude::*;
+    use mighty_graph_rs::traversal::*;
+    use mighty_graph_rs::utils::add_node_if_not_exists;
+
+    // V1 - T1 - V2 - T2, with a weak shortcut V1 - T2.
+    fn graph() -> (MappingGraph, HashMap<String, NodeIndex>) {
+        let mut graph = MappingGraph::new();
+        let mut indices = HashMap::new();
+        for (veris, mitre, strength) in [("V1", "T1", 1.0), ("V2", "T1", 1.0), ("V2", "T2", 1.0), ("V1", "T2", 0.1)] {
+            let v = add_node_if_not_exists(&mut graph, &mut indices, veris, NodeType::Veris);
+            let t = add_node_if_not_exists(&mut graph, &mut indices, mitre, NodeType::Mitre);
+            graph.add_edge(v, t, EdgeData { mapping_type: "related-to".into(), strength });
+        }
+        (graph, indices)
+    }
+
+    #[test]
+    fn test_shortest_path_ignores_direction_and_prefers_strong_edges() {
+        let (graph, indices) = graph();
+
+        let (cost, path) = shortest_path(&graph, indices["T1"], indices["T2"]).unwrap();
+
+        let ids: Vec<&str> = path.iter().map(|&n| graph[n].id.as_str()).collect();
+        assert_eq!(ids, vec!["T1", "V2", "T2"]);
+        assert_eq!(cost, 2.0);
+    }
+
+    #[test]
+    fn test_k_hop_nodes() {
+        let (graph, indices) = graph();
+
+        assert_eq!(k_hop_nodes(&graph, indices["T1"], 0).len(), 1);
+        assert_eq!(k_hop_nodes(&graph, indices["T1"], 1).len(), 3);
+        assert_eq!(k_hop_nodes(&graph, indices["T1"], 2).len(), 4);
+    }
+}
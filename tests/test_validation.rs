@@ -0,0 +1,91 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use mighty_graph_rs::catalog::AttackCatalog;
+    use mighty_graph_rs::validation::*;
+    use serde_json::json;
+
+    use crate::common::{mapping, MappingFixture};
+
+    fn catalog() -> AttackCatalog {
+        AttackCatalog::from_stix_value(&json!({
+            "objects": [
+                {
+                    "type": "attack-pattern", "id": "attack-pattern--1", "name": "Brute Force",
+                    "external_references": [{ "source_name": "mitre-attack", "external_id": "T1110" }]
+                },
+                {
+                    "type": "attack-pattern", "id": "attack-pattern--2", "name": "Old", "revoked": true,
+                    "external_references": [{ "source_name": "mitre-attack", "external_id": "T1086" }]
+                }
+            ]
+        }))
+    }
+
+    fn findings(report: &ValidationReport) -> Vec<(Option<usize>, &str, Severity)> {
+        report.findings.iter().map(|f| (f.row, f.rule.as_str(), f.severity)).collect()
+    }
+
+    #[test]
+    fn test_attack_id_suggestions() {
+        assert!(is_attack_id("T1003.001"));
+        assert!(!is_attack_id("T1003.1"));
+        assert_eq!(suggest_attack_id(" t1003.1"), Some("T1003.001".to_string()));
+        assert_eq!(suggest_attack_id("T1003-001"), Some("T1003.001".to_string()));
+        assert_eq!(suggest_attack_id("TA0001"), None);
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let attack = catalog();
+        let mut unreferenced = mapping("action.hacking.variety.Brute force", "T1110").with_references("https://example.org");
+        unreferenced.references = "".to_string();
+        let mappings = vec![
+            mapping("action.hacking.variety.Brute force", "T1110").with_references("https://example.org"),
+            mapping("action.hacking.variety.Brute force", "T1110").with_references("https://example.org"),
+            mapping("action.hacking.variety.Use of backdoor", "t1110").with_references("https://example.org"),
+            mapping("action.hacking.variety.SQLi", "T1086").with_type("Related-To").with_references("https://example.org"),
+            mapping("malware.variety.Ransomware", "T1110").with_references("https://example.org"),
+            unreferenced,
+        ];
+        let context = ValidationContext { mappings: &mappings, attack: Some(&attack), veris: None };
+        let report = Validator::default().validate(&context);
+
+        assert_eq!(
+            findings(&report),
+            vec![
+                (Some(1), "duplicate-mapping", Severity::Error),
+                (Some(2), "attack-catalog", Severity::Error),
+                (Some(2), "attack-id-format", Severity::Error),
+                (Some(3), "attack-catalog", Severity::Error),
+                (Some(3), "mapping-type", Severity::Warning),
+                (Some(4), "veris-id-format", Severity::Error),
+                (Some(5), "duplicate-mapping", Severity::Error),
+                (Some(5), "empty-references", Severity::Info),
+            ]
+        );
+        assert_eq!(report.findings[1].suggestion.as_deref(), Some("T1110"));
+        assert_eq!(report.findings[4].suggestion.as_deref(), Some("related-to"));
+        assert!(report.fails(Severity::Error));
+        assert_eq!(report.to_json()["counts"]["error"], 6);
+    }
+
+    #[test]
+    fn test_rules_can_be_disabled_and_downgraded() {
+        let mappings = vec![
+            mapping("action.hacking.variety.Brute force", "T1110").with_references("https://example.org"),
+            mapping("action.hacking.variety.Brute force", "T1110").with_references("https://example.org"),
+        ];
+        let context = ValidationContext { mappings: &mappings, attack: None, veris: None };
+
+        let validator = Validator::default().with_severity("duplicate-mapping", Severity::Warning);
+        let report = validator.validate(&context);
+        assert_eq!(findings(&report), vec![(Some(1), "duplicate-mapping", Severity::Warning)]);
+        assert!(!report.fails(Severity::Error));
+        assert!(report.fails(Severity::Warning));
+
+        let report = Validator::default().without_rule("duplicate-mapping").validate(&context);
+        assert!(report.findings.is_empty());
+    }
+}